@@ -358,11 +358,10 @@ impl ShaderManager for GaborShader {
                 Some("Display Pass"),
             );
 
-            render_pass.set_pipeline(&self.base.renderer.render_pipeline);
-            render_pass.set_vertex_buffer(0, self.base.renderer.vertex_buffer.slice(..));
-            render_pass.set_bind_group(0, &self.compute_shader.output_texture.bind_group, &[]);
-
-            render_pass.draw(0..4, 0..1);
+            self.base.draw_output(
+                &mut render_pass,
+                &self.compute_shader.output_texture.bind_group,
+            );
         }
 
         self.base
@@ -372,6 +371,10 @@ impl ShaderManager for GaborShader {
         Ok(())
     }
 
+    fn render_kit_mut(&mut self) -> Option<&mut RenderKit> {
+        Some(&mut self.base)
+    }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
         if self
             .base
@@ -224,6 +224,7 @@ impl ShaderManager for MandelbulbShader {
                 click_position: self.stored_mouse_position,
                 wheel: [0.0, 0.0],
                 buttons: [0, 0],
+                ..Default::default()
             };
             self.compute_shader
                 .update_mouse_uniform(&static_mouse, &core.queue);
@@ -599,11 +600,10 @@ impl ShaderManager for MandelbulbShader {
                 Some("Display Pass"),
             );
 
-            render_pass.set_pipeline(&self.base.renderer.render_pipeline);
-            render_pass.set_vertex_buffer(0, self.base.renderer.vertex_buffer.slice(..));
-            render_pass.set_bind_group(0, &self.compute_shader.output_texture.bind_group, &[]);
-
-            render_pass.draw(0..4, 0..1);
+            self.base.draw_output(
+                &mut render_pass,
+                &self.compute_shader.output_texture.bind_group,
+            );
         }
 
         self.base
@@ -618,6 +618,10 @@ impl ShaderManager for MandelbulbShader {
         Ok(())
     }
 
+    fn render_kit_mut(&mut self) -> Option<&mut RenderKit> {
+        Some(&mut self.base)
+    }
+
     fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
         if self
             .base
@@ -0,0 +1,32 @@
+// Fixed-point atomic sums to color, used by compute::AtomicAccumulator
+struct ResolveParams {
+    width: u32,
+    height: u32,
+    scale: f32,
+    _padding: u32,
+};
+
+// Planar layout: plane p of pixel i is at p * width * height + i. Planes are r, g, b, count.
+@group(0) @binding(0) var<storage, read> accum: array<u32>;
+@group(1) @binding(0) var<uniform> params: ResolveParams;
+@group(2) @binding(0) var output: texture_storage_2d<rgba16float, write>;
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+
+    let plane = params.width * params.height;
+    let idx = id.y * params.width + id.x;
+    let count = accum[idx + 3u * plane];
+    var color = vec3<f32>(
+        f32(accum[idx]),
+        f32(accum[idx + plane]),
+        f32(accum[idx + 2u * plane]),
+    ) / params.scale;
+    if (count > 0u) {
+        color /= f32(count);
+    }
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(color, select(0.0, 1.0, count > 0u)));
+}
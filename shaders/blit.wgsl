@@ -17,4 +17,28 @@ fn vs_main(@location(0) position: vec2<f32>) -> VertexOutput {
 @fragment
 fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
     return textureSample(texture, tex_sampler, tex_coords);
-}
\ No newline at end of file
+}
+
+// Global color grading, see `RenderKit::set_color_grading`. Only `fs_graded` uses it,
+// pipelines without a group 1 keep using `fs_main`.
+struct ColorGrading {
+    exposure: f32,
+    gamma: f32,
+    contrast: f32,
+    saturation: f32,
+};
+
+@group(1) @binding(0) var<uniform> grading: ColorGrading;
+
+@fragment
+fn fs_graded(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(texture, tex_sampler, tex_coords);
+    var rgb = max(color.rgb * grading.exposure, vec3<f32>(0.0));
+    // Contrast pivots around linear mid-grey so it doesn't shift overall brightness.
+    // pow(0, 0) is undefined in WGSL, keep the base above zero for contrast 0.
+    rgb = pow(max(rgb / 0.18, vec3<f32>(1e-6)), vec3<f32>(grading.contrast)) * 0.18;
+    let luma = dot(rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    rgb = max(mix(vec3<f32>(luma), rgb, grading.saturation), vec3<f32>(0.0));
+    rgb = pow(rgb, vec3<f32>(1.0 / max(grading.gamma, 1e-3)));
+    return vec4<f32>(rgb, color.a);
+}
@@ -0,0 +1,37 @@
+// Separable gaussian blur, used by compute::Blur (one dispatch per direction)
+struct BlurParams {
+    direction: vec2<f32>,
+    radius: i32,
+    sigma: f32,
+};
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var input_sampler: sampler;
+@group(0) @binding(2) var<uniform> params: BlurParams;
+
+@group(1) @binding(0) var output: texture_storage_2d<rgba16float, write>;
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let dims = textureDimensions(output);
+    if (id.x >= dims.x || id.y >= dims.y) {
+        return;
+    }
+
+    let texel = 1.0 / vec2<f32>(dims);
+    let uv = (vec2<f32>(id.xy) + 0.5) * texel;
+    let two_sigma_sq = 2.0 * params.sigma * params.sigma;
+
+    var color = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var i = -params.radius; i <= params.radius; i++) {
+        let x = f32(i);
+        let w = exp(-(x * x) / two_sigma_sq);
+        // sampler is clamp-to-edge, so border pixels are repeated
+        let offset = params.direction * x * texel;
+        color += textureSampleLevel(input_texture, input_sampler, uv + offset, 0.0) * w;
+        total += w;
+    }
+
+    textureStore(output, vec2<i32>(id.xy), color / total);
+}
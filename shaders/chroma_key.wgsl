@@ -0,0 +1,35 @@
+// Chroma key, used by compute::ChromaKey
+struct KeyParams {
+    color: vec3<f32>,
+    tolerance: f32,
+    softness: f32,
+    _padding: vec3<f32>,
+};
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var input_sampler: sampler;
+@group(0) @binding(2) var<uniform> params: KeyParams;
+
+@group(1) @binding(0) var output: texture_storage_2d<rgba16float, write>;
+
+// BT.601 chroma, so the key ignores brightness (shadows on the screen stay keyed)
+fn chroma(c: vec3<f32>) -> vec2<f32> {
+    return vec2<f32>(
+        -0.168736 * c.r - 0.331264 * c.g + 0.5 * c.b,
+        0.5 * c.r - 0.418688 * c.g - 0.081312 * c.b,
+    );
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let dims = textureDimensions(output);
+    if (id.x >= dims.x || id.y >= dims.y) {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(dims);
+    let color = textureSampleLevel(input_texture, input_sampler, uv, 0.0);
+    let d = distance(chroma(color.rgb), chroma(params.color));
+    let alpha = smoothstep(params.tolerance, params.tolerance + params.softness, d);
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(color.rgb, color.a * alpha));
+}
@@ -0,0 +1,64 @@
+// Transition between two textures, used by compute::Crossfade
+struct TransitionParams {
+    progress: f32,
+    kind: u32,
+    softness: f32,
+    _padding: f32,
+};
+
+@group(0) @binding(0) var from_texture: texture_2d<f32>;
+@group(0) @binding(1) var to_texture: texture_2d<f32>;
+@group(0) @binding(2) var<uniform> params: TransitionParams;
+
+@group(1) @binding(0) var output: texture_storage_2d<rgba16float, write>;
+
+const KIND_FADE: u32 = 0u;
+const KIND_WIPE: u32 = 1u;
+
+fn hash(p: vec2<u32>) -> f32 {
+    var h = p.x * 1664525u + p.y * 22695477u + 1013904223u;
+    h = (h ^ (h >> 16u)) * 2246822519u;
+    h = h ^ (h >> 13u);
+    return f32(h & 0xffffffu) / 16777216.0;
+}
+
+// Bilinear filtering by hand: the inputs can be any float format, including Rgba32Float
+// which isn't filterable on most devices
+fn sample_bilinear(source: texture_2d<f32>, uv: vec2<f32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(source));
+    let pos = uv * vec2<f32>(size) - 0.5;
+    let base = vec2<i32>(floor(pos));
+    let f = pos - floor(pos);
+    let last = size - 1;
+    let a = textureLoad(source, clamp(base, vec2<i32>(0), last), 0);
+    let b = textureLoad(source, clamp(base + vec2<i32>(1, 0), vec2<i32>(0), last), 0);
+    let c = textureLoad(source, clamp(base + vec2<i32>(0, 1), vec2<i32>(0), last), 0);
+    let d = textureLoad(source, clamp(base + vec2<i32>(1, 1), vec2<i32>(0), last), 0);
+    return mix(mix(a, b, f.x), mix(c, d, f.x), f.y);
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let dims = textureDimensions(output);
+    if (id.x >= dims.x || id.y >= dims.y) {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(dims);
+    let from_color = sample_bilinear(from_texture, uv);
+    let to_color = sample_bilinear(to_texture, uv);
+
+    // Wipe and dissolve reveal a pixel once progress passes its threshold, over `softness`
+    var threshold = 0.0;
+    if (params.kind == KIND_WIPE) {
+        threshold = uv.x;
+    } else if (params.kind != KIND_FADE) {
+        threshold = hash(id.xy);
+    }
+    var amount = params.progress;
+    if (params.kind != KIND_FADE) {
+        let s = max(params.softness, 1e-4);
+        amount = clamp((params.progress * (1.0 + s) - threshold) / s, 0.0, 1.0);
+    }
+    textureStore(output, vec2<i32>(id.xy), mix(from_color, to_color, amount));
+}
@@ -0,0 +1,14 @@
+// Copies an R32Float depth field into a depth attachment, used by compute::DepthResolve
+@group(0) @binding(0) var depth_field: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    // Fullscreen triangle
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @builtin(frag_depth) f32 {
+    return clamp(textureLoad(depth_field, vec2<i32>(position.xy), 0).r, 0.0, 1.0);
+}
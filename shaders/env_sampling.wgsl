@@ -0,0 +1,75 @@
+// Environment importance sampling over a `hdri::ImportanceMap` buffer.
+//
+// Append after a shader that declares the map's data (e.g. as a group 3 storage buffer):
+//     @group(3) @binding(0) var<storage, read> env_importance: array<f32>;
+// and is built `with_stdlib()` for envDirection / envSolidAnglePdf.
+//
+// Buffer layout in f32: [width, height, total, 0], the marginal CDF over rows (height + 1),
+// then one conditional CDF per row (width + 1 each). All CDFs run from 0 to 1.
+
+const ENV_HEADER: u32 = 4u;
+
+fn envMapSize() -> vec2<u32> {
+    return vec2<u32>(u32(env_importance[0]), u32(env_importance[1]));
+}
+
+// Largest i in [0, count) with cdf[start + i] <= u
+fn envSearch(start: u32, count: u32, u: f32) -> u32 {
+    var lo = 0u;
+    var hi = count;
+    while (lo + 1u < hi) {
+        let mid = (lo + hi) / 2u;
+        if (env_importance[start + mid] <= u) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    return lo;
+}
+
+struct EnvSample {
+    dir: vec3<f32>,
+    uv: vec2<f32>,
+    // Density over solid angle, 0 if the map is black
+    pdf: f32,
+};
+
+// Pick a direction with probability proportional to radiance * sin(theta), from two
+// uniform random numbers in [0, 1)
+fn sampleEnv(rnd: vec2<f32>) -> EnvSample {
+    let size = envMapSize();
+    let marginal = ENV_HEADER;
+    let y = envSearch(marginal, size.y, rnd.y);
+    let y0 = env_importance[marginal + y];
+    let y1 = env_importance[marginal + y + 1u];
+    let dv = (rnd.y - y0) / max(y1 - y0, 1e-8);
+
+    let row = ENV_HEADER + size.y + 1u + y * (size.x + 1u);
+    let x = envSearch(row, size.x, rnd.x);
+    let x0 = env_importance[row + x];
+    let x1 = env_importance[row + x + 1u];
+    let du = (rnd.x - x0) / max(x1 - x0, 1e-8);
+
+    let uv = vec2<f32>((f32(x) + du) / f32(size.x), (f32(y) + dv) / f32(size.y));
+    let pdf_uv = (y1 - y0) * f32(size.y) * (x1 - x0) * f32(size.x);
+
+    var s: EnvSample;
+    s.dir = envDirection(uv);
+    s.uv = uv;
+    s.pdf = envSolidAnglePdf(pdf_uv, uv);
+    return s;
+}
+
+// Solid-angle density `sampleEnv` would give `dir`, for multiple importance sampling
+fn envPdf(dir: vec3<f32>) -> f32 {
+    let size = envMapSize();
+    let uv = envUv(dir);
+    let x = min(u32(uv.x * f32(size.x)), size.x - 1u);
+    let y = min(u32(uv.y * f32(size.y)), size.y - 1u);
+    let marginal = ENV_HEADER;
+    let row = ENV_HEADER + size.y + 1u + y * (size.x + 1u);
+    let pdf_uv = (env_importance[marginal + y + 1u] - env_importance[marginal + y]) * f32(size.y)
+        * (env_importance[row + x + 1u] - env_importance[row + x]) * f32(size.x);
+    return envSolidAnglePdf(pdf_uv, uv);
+}
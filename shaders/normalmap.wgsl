@@ -0,0 +1,49 @@
+// Normal map from a heightmap (red channel) using a Sobel filter.
+// OUTPUT_FORMAT is replaced with the storage format before compiling.
+struct NormalMapParams {
+    strength: f32,
+    tangent_space: u32,
+    _padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var height_map: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> params: NormalMapParams;
+@group(0) @binding(2) var output: texture_storage_2d<OUTPUT_FORMAT, write>;
+
+fn height_at(p: vec2<i32>, dims: vec2<i32>) -> f32 {
+    // clamp to edge
+    return textureLoad(height_map, clamp(p, vec2<i32>(0), dims - 1), 0).r;
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let dims = vec2<i32>(textureDimensions(height_map));
+    let p = vec2<i32>(id.xy);
+    if (p.x >= dims.x || p.y >= dims.y) {
+        return;
+    }
+
+    let tl = height_at(p + vec2<i32>(-1, -1), dims);
+    let t = height_at(p + vec2<i32>(0, -1), dims);
+    let tr = height_at(p + vec2<i32>(1, -1), dims);
+    let l = height_at(p + vec2<i32>(-1, 0), dims);
+    let r = height_at(p + vec2<i32>(1, 0), dims);
+    let bl = height_at(p + vec2<i32>(-1, 1), dims);
+    let b = height_at(p + vec2<i32>(0, 1), dims);
+    let br = height_at(p + vec2<i32>(1, 1), dims);
+
+    // Gradient along +x (right) and +y (up, texture rows go down)
+    let dx = (tr + 2.0 * r + br) - (tl + 2.0 * l + bl);
+    let dy = (tl + 2.0 * t + tr) - (bl + 2.0 * b + br);
+
+    var n: vec3<f32>;
+    if (params.tangent_space != 0u) {
+        // Z-up, OpenGL convention (green = up)
+        n = normalize(vec3<f32>(-dx * params.strength, -dy * params.strength, 1.0));
+    } else {
+        // Y-up heightfield normal, for terrain in world space
+        n = normalize(vec3<f32>(-dx * params.strength, 1.0, dy * params.strength));
+    }
+
+    textureStore(output, p, vec4<f32>(n * 0.5 + 0.5, 1.0));
+}
@@ -0,0 +1,27 @@
+// Order-independent hash of a texture, used by ComputeShader::output_hash
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> result: array<atomic<u32>, 2>;
+
+fn pcg(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(source);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    let c = bitcast<vec4<u32>>(textureLoad(source, vec2<i32>(id.xy), 0));
+    // Seeding with the position makes moved pixels change the hash
+    var h = pcg(id.y * size.x + id.x);
+    h = pcg(h ^ c.r);
+    h = pcg(h ^ c.g);
+    h = pcg(h ^ c.b);
+    h = pcg(h ^ c.a);
+    // Two independent 32-bit halves, combined into a u64 on the CPU
+    atomicAdd(&result[0], h);
+    atomicXor(&result[1], pcg(h ^ 0x9e3779b9u));
+}
@@ -0,0 +1,80 @@
+// cuneus drawing helpers, appended to compute shaders built with `with_stdlib()`.
+//
+// Coordinates are in pixels, the same space as `global_invocation_id.xy` and `textureStore`:
+// origin at the top-left corner, +y down, pixel centers at integer + 0.5.
+// Distances are signed and in pixels (negative inside a shape), and anti-aliasing fades
+// over one pixel. Sample at `vec2<f32>(id.xy) + 0.5` for correct coverage.
+
+// Distance to the segment a-b
+fn sdLine(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = clamp(dot(pa, ba) / max(dot(ba, ba), 1e-8), 0.0, 1.0);
+    return length(pa - ba * h);
+}
+
+fn sdCircle(p: vec2<f32>, center: vec2<f32>, radius: f32) -> f32 {
+    return length(p - center) - radius;
+}
+
+// Axis-aligned box given by its center and half extents
+fn sdBox(p: vec2<f32>, center: vec2<f32>, half_size: vec2<f32>) -> f32 {
+    let d = abs(p - center) - half_size;
+    return length(max(d, vec2<f32>(0.0))) + min(max(d.x, d.y), 0.0);
+}
+
+// Coverage (0..1) of the inside of a shape from its signed distance
+fn aaFill(d: f32) -> f32 {
+    return clamp(0.5 - d, 0.0, 1.0);
+}
+
+// Coverage of an outline of `width` pixels centered on the shape's edge
+fn aaStroke(d: f32, width: f32) -> f32 {
+    return aaFill(abs(d) - width * 0.5);
+}
+
+// Composite `color` over `dst` with `coverage` scaled by the color's alpha
+fn blendOver(dst: vec4<f32>, color: vec4<f32>, coverage: f32) -> vec4<f32> {
+    let a = color.a * coverage;
+    return vec4<f32>(mix(dst.rgb, color.rgb, a), a + dst.a * (1.0 - a));
+}
+
+fn drawLine(dst: vec4<f32>, p: vec2<f32>, a: vec2<f32>, b: vec2<f32>, width: f32, color: vec4<f32>) -> vec4<f32> {
+    return blendOver(dst, color, aaFill(sdLine(p, a, b) - width * 0.5));
+}
+
+fn drawCircle(dst: vec4<f32>, p: vec2<f32>, center: vec2<f32>, radius: f32, color: vec4<f32>) -> vec4<f32> {
+    return blendOver(dst, color, aaFill(sdCircle(p, center, radius)));
+}
+
+fn drawRing(dst: vec4<f32>, p: vec2<f32>, center: vec2<f32>, radius: f32, width: f32, color: vec4<f32>) -> vec4<f32> {
+    return blendOver(dst, color, aaStroke(sdCircle(p, center, radius), width));
+}
+
+fn drawBox(dst: vec4<f32>, p: vec2<f32>, center: vec2<f32>, half_size: vec2<f32>, color: vec4<f32>) -> vec4<f32> {
+    return blendOver(dst, color, aaFill(sdBox(p, center, half_size)));
+}
+
+// Equirectangular environment mapping, matching `hdri::ImportanceMap`: u follows
+// atan2(dir.z, dir.x) from -pi to pi, v = 0 is straight up (+y) and v = 1 straight down.
+fn envUv(dir: vec3<f32>) -> vec2<f32> {
+    let phi = atan2(dir.z, dir.x);
+    let elevation = asin(clamp(dir.y, -1.0, 1.0));
+    return vec2<f32>((phi + 3.14159265) / 6.2831853, 0.5 - elevation / 3.14159265);
+}
+
+fn envDirection(uv: vec2<f32>) -> vec3<f32> {
+    let phi = uv.x * 6.2831853 - 3.14159265;
+    let elevation = (0.5 - uv.y) * 3.14159265;
+    return vec3<f32>(cos(elevation) * cos(phi), sin(elevation), cos(elevation) * sin(phi));
+}
+
+// Convert a density over the unit uv square to one over solid angle. Rows near the poles
+// cover less of the sphere, hence the sin(theta) term (theta = pi * v from the top).
+fn envSolidAnglePdf(pdf_uv: f32, uv: vec2<f32>) -> f32 {
+    let sin_theta = sin(uv.y * 3.14159265);
+    if (sin_theta <= 0.0) {
+        return 0.0;
+    }
+    return pdf_uv / (2.0 * 3.14159265 * 3.14159265 * sin_theta);
+}
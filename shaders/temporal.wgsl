@@ -0,0 +1,33 @@
+// Temporal accumulation, used by compute::TemporalAccumulator
+struct TemporalParams {
+    feedback: f32,
+    frame_count: u32,
+    _padding: vec2<u32>,
+};
+
+@group(0) @binding(0) var current_texture: texture_2d<f32>;
+@group(0) @binding(1) var current_sampler: sampler;
+@group(0) @binding(2) var history_texture: texture_2d<f32>;
+@group(0) @binding(3) var history_sampler: sampler;
+
+@group(1) @binding(0) var<uniform> params: TemporalParams;
+
+@group(2) @binding(0) var output: texture_storage_2d<rgba16float, write>;
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let dims = textureDimensions(output);
+    if (id.x >= dims.x || id.y >= dims.y) {
+        return;
+    }
+
+    let coord = vec2<i32>(id.xy);
+    let current = textureLoad(current_texture, coord, 0);
+    let history = textureLoad(history_texture, coord, 0);
+
+    // Plain running average until the history is long enough, then an exponential blend.
+    // frame_count == 0 means the history is empty.
+    let average_weight = 1.0 / f32(params.frame_count + 1u);
+    let weight = max(average_weight, 1.0 - params.feedback);
+    textureStore(output, coord, mix(history, current, weight));
+}
@@ -0,0 +1,165 @@
+use crate::TextureManager;
+use image::AnimationDecoder;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// GIF frames without a delay (or a tiny one) play at this rate, like browsers do
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// Looping GIF/APNG texture decoded with the `image` crate, advanced per frame like a video
+/// but without GStreamer. All frames are decoded up front.
+pub struct AnimatedTexture {
+    texture_manager: TextureManager,
+    frames: Vec<(image::RgbaImage, Duration)>,
+    current_frame: usize,
+    frame_started: Instant,
+    is_playing: bool,
+    is_looping: bool,
+}
+
+impl AnimatedTexture {
+    /// Decode `path` if it's an animated GIF/APNG. Returns `Ok(None)` for single frame images.
+    pub fn load(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        path: &Path,
+    ) -> anyhow::Result<Option<Self>> {
+        let frames = Self::decode_frames(path)?;
+        if frames.len() < 2 {
+            return Ok(None);
+        }
+        log::info!("Loaded animation {:?} with {} frames", path, frames.len());
+
+        let texture_manager = TextureManager::new(device, queue, &frames[0].0, layout);
+        Ok(Some(Self {
+            texture_manager,
+            frames,
+            current_frame: 0,
+            frame_started: Instant::now(),
+            is_playing: true,
+            is_looping: true,
+        }))
+    }
+
+    fn decode_frames(path: &Path) -> anyhow::Result<Vec<(image::RgbaImage, Duration)>> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+        let reader = BufReader::new(File::open(path)?);
+
+        let frames = match extension.as_deref() {
+            Some("gif") => image::codecs::gif::GifDecoder::new(reader)?
+                .into_frames()
+                .collect_frames()?,
+            Some("png") | Some("apng") => {
+                let decoder = image::codecs::png::PngDecoder::new(reader)?;
+                if !decoder.is_apng()? {
+                    return Ok(Vec::new());
+                }
+                decoder.apng()?.into_frames().collect_frames()?
+            }
+            _ => return Ok(Vec::new()),
+        };
+
+        Ok(frames
+            .into_iter()
+            .map(|frame| {
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                let delay = if denom == 0 || numer == 0 {
+                    DEFAULT_FRAME_DELAY
+                } else {
+                    Duration::from_secs_f64(numer as f64 / denom as f64 / 1000.0)
+                        .max(MIN_FRAME_DELAY)
+                };
+                (frame.into_buffer(), delay)
+            })
+            .collect())
+    }
+
+    /// Advance to the frame due at the current time. Returns true if the texture changed.
+    pub fn update_texture(&mut self, queue: &wgpu::Queue) -> bool {
+        if !self.is_playing {
+            return false;
+        }
+
+        let mut elapsed = self.frame_started.elapsed();
+        let mut next_frame = self.current_frame;
+        // Catch up on long frames (window hidden, slow shader) without replaying each step
+        while elapsed >= self.frames[next_frame].1 {
+            elapsed -= self.frames[next_frame].1;
+            if next_frame + 1 < self.frames.len() {
+                next_frame += 1;
+            } else if self.is_looping {
+                next_frame = 0;
+            } else {
+                self.is_playing = false;
+                break;
+            }
+        }
+
+        if next_frame == self.current_frame {
+            return false;
+        }
+        self.current_frame = next_frame;
+        self.frame_started = Instant::now() - elapsed;
+        self.texture_manager
+            .update(queue, &self.frames[self.current_frame].0);
+        true
+    }
+
+    pub fn texture_manager(&self) -> &TextureManager {
+        &self.texture_manager
+    }
+
+    pub fn play(&mut self) {
+        if !self.is_playing {
+            self.is_playing = true;
+            self.frame_started = Instant::now();
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.is_playing = false;
+    }
+
+    pub fn restart(&mut self, queue: &wgpu::Queue) {
+        self.current_frame = 0;
+        self.frame_started = Instant::now();
+        self.is_playing = true;
+        self.texture_manager.update(queue, &self.frames[0].0);
+    }
+
+    pub fn set_loop(&mut self, should_loop: bool) {
+        self.is_looping = should_loop;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.is_playing
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.is_looping
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn current_frame(&self) -> usize {
+        self.current_frame
+    }
+
+    /// Total length of one loop
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|(_, delay)| *delay).sum()
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.frames[0].0.dimensions()
+    }
+}
@@ -1,18 +1,45 @@
-use crate::{Core, ShaderManager};
+use crate::fps::FpsTracker;
+use crate::{Core, FrameLimiter, InputRecorder, ShaderManager};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use winit::{
     application::ApplicationHandler,
     dpi::LogicalSize,
     event::*,
-    event_loop::{ActiveEventLoop, EventLoop},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    keyboard::{Key, NamedKey},
     window::WindowAttributes,
 };
 
+/// When `ShaderApp` draws frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedrawMode {
+    /// Draw every frame (the default)
+    #[default]
+    Continuous,
+    /// Wait for events instead of spinning: draw after input or window events, and keep
+    /// drawing only while `ShaderManager::needs_redraw` returns true. Saves power for static
+    /// scenes and UI-only interaction, e.g. a shader can stop once
+    /// `ComputeShader::output_hash` reports the output stopped changing.
+    Reactive,
+}
+
 pub struct ShaderApp {
     window_title: String,
     window_size: (u32, u32),
     core: Option<Core>,
+    pause_on_occlusion: bool,
+    input_recorder: InputRecorder,
+    record_path: Option<PathBuf>,
+    frame_limiter: FrameLimiter,
+    fps_in_title: bool,
+    fps_tracker: FpsTracker,
+    last_title_update: Instant,
+    redraw_mode: RedrawMode,
 }
 
+const TITLE_FPS_INTERVAL: Duration = Duration::from_secs(1);
+
 impl ShaderApp {
     pub fn new(window_title: &str, width: u32, height: u32) -> (Self, EventLoop<()>) {
         let event_loop = EventLoop::builder()
@@ -20,15 +47,105 @@ impl ShaderApp {
             .expect("Failed to create event loop");
 
         //note: No window creation here - will happen in resumed event
-        let app = Self {
+        let mut app = Self {
             window_title: String::from(window_title),
             window_size: (width, height),
             core: None,
+            pause_on_occlusion: true,
+            input_recorder: InputRecorder::new(),
+            record_path: None,
+            frame_limiter: FrameLimiter::default(),
+            fps_in_title: false,
+            fps_tracker: FpsTracker::new(),
+            last_title_update: Instant::now(),
+            redraw_mode: RedrawMode::default(),
         };
 
+        if let Ok(path) = std::env::var("CUNEUS_REPLAY_INPUT") {
+            if let Err(e) = app.replay_input(&path) {
+                log::error!("Failed to load input recording {path}: {e}");
+            }
+        } else if let Ok(path) = std::env::var("CUNEUS_RECORD_INPUT") {
+            app.record_input(path);
+        }
+
         (app, event_loop)
     }
 
+    /// Pause rendering and media playback while the window is minimized/occluded.
+    /// Enabled by default.
+    pub fn set_pause_on_occlusion(&mut self, enabled: bool) {
+        self.pause_on_occlusion = enabled;
+    }
+
+    /// Cap the frame rate, `None` for unlimited (the default). Works with any present mode;
+    /// it's a cooperative cap, see `FrameLimiter`.
+    pub fn set_fps_limit(&mut self, limit: Option<u32>) {
+        self.frame_limiter.set_limit(limit);
+    }
+
+    pub fn fps_limit(&self) -> Option<u32> {
+        self.frame_limiter.limit()
+    }
+
+    /// Draw continuously or only on demand, see `RedrawMode`. Input replay always draws
+    /// continuously.
+    pub fn set_redraw_mode(&mut self, mode: RedrawMode) {
+        self.redraw_mode = mode;
+    }
+
+    pub fn redraw_mode(&self) -> RedrawMode {
+        self.redraw_mode
+    }
+
+    /// Whether frames are only drawn on demand right now
+    fn is_reactive(&self) -> bool {
+        self.redraw_mode == RedrawMode::Reactive && !self.input_recorder.is_replaying()
+    }
+
+    /// Append the frame rate to the window title ("Title - 60 FPS"), refreshed every second.
+    /// Turning it off restores the original title.
+    pub fn show_fps_in_title(&mut self, enabled: bool) {
+        self.fps_in_title = enabled;
+        if !enabled {
+            if let Some(core) = &self.core {
+                core.set_title(&self.window_title);
+            }
+        }
+    }
+
+    fn update_title_fps(&mut self) {
+        if !self.fps_in_title {
+            return;
+        }
+        self.fps_tracker.update();
+        if self.last_title_update.elapsed() < TITLE_FPS_INTERVAL {
+            return;
+        }
+        self.last_title_update = Instant::now();
+        if let Some(core) = &self.core {
+            core.set_title(&format!(
+                "{} - {:.0} FPS",
+                self.window_title,
+                self.fps_tracker.fps()
+            ));
+        }
+    }
+
+    /// Record mouse/keyboard input and frame times to `path` (see `InputRecorder` for the format).
+    /// F9 stops/restarts the recording, the file is written when it stops or the window closes.
+    /// Also enabled by the `CUNEUS_RECORD_INPUT=<path>` env var.
+    pub fn record_input(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        self.input_recorder.start_recording(path.clone());
+        self.record_path = Some(path);
+    }
+
+    /// Replay a recording instead of live input. Also enabled by `CUNEUS_REPLAY_INPUT=<path>`.
+    pub fn replay_input(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        self.input_recorder.start_replay(path)
+    }
+
     pub fn run<S: ShaderManager + 'static>(
         self,
         event_loop: EventLoop<()>,
@@ -39,6 +156,7 @@ impl ShaderApp {
             shader_creator: Some(Box::new(shader_creator)),
             shader: None,
             first_render: true,
+            occluded: false,
         };
 
         Ok(event_loop.run_app(&mut handler)?)
@@ -49,12 +167,40 @@ impl ShaderApp {
     }
 }
 
+impl<S: ShaderManager> ShaderAppHandler<S> {
+    fn is_record_toggle(event: &WindowEvent) -> bool {
+        matches!(
+            event,
+            WindowEvent::KeyboardInput { event, .. }
+                if event.state == ElementState::Pressed
+                    && !event.repeat
+                    && event.logical_key == Key::Named(NamedKey::F9)
+        )
+    }
+
+    fn deliver_replayed_inputs(shader: &mut S, core: &Core, inputs: &[crate::RecordedInput]) {
+        for input in inputs {
+            match input {
+                crate::RecordedInput::Key { key, pressed } => {
+                    shader.handle_replayed_key(core, key, InputRecorder::element_state(*pressed));
+                }
+                _ => {
+                    if let Some(event) = InputRecorder::to_window_event(input) {
+                        shader.handle_input(core, &event);
+                    }
+                }
+            }
+        }
+    }
+}
+
 // This struct implements ApplicationHandler to handle winit events
 struct ShaderAppHandler<S: ShaderManager> {
     app: ShaderApp,
     shader_creator: Option<Box<dyn FnOnce(&Core) -> S + 'static>>,
     shader: Option<S>,
     first_render: bool,
+    occluded: bool,
 }
 
 impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
@@ -88,9 +234,34 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
     ) {
         // Only process events if core and shader are initialized
         if let (Some(core), Some(shader)) = (&self.app.core, &mut self.shader) {
-            if window_id == core.window().id() && !shader.handle_input(core, &event) {
+            if window_id != core.window().id() {
+                return;
+            }
+            if self.app.is_reactive() && !matches!(event, WindowEvent::RedrawRequested) {
+                core.window().request_redraw();
+            }
+            let recorder = &mut self.app.input_recorder;
+            if InputRecorder::is_input_event(&event) {
+                // Live input would make the replay non-deterministic
+                if recorder.is_replaying() {
+                    return;
+                }
+                if let Some(path) = &self.app.record_path {
+                    if Self::is_record_toggle(&event) {
+                        if let Err(e) = recorder.toggle_recording(path.clone()) {
+                            log::error!("Failed to save input recording: {e}");
+                        }
+                        return;
+                    }
+                }
+                recorder.record_event(&event);
+            }
+            if !shader.handle_input(core, &event) {
                 match event {
                     WindowEvent::CloseRequested => {
+                        if let Err(e) = recorder.stop_recording() {
+                            log::error!("Failed to save input recording: {e}");
+                        }
                         event_loop.exit();
                     }
                     WindowEvent::Resized(size) => {
@@ -102,7 +273,27 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
                             shader.resize(core);
                         }
                     }
+                    WindowEvent::Occluded(occluded)
+                        if self.app.pause_on_occlusion && self.occluded != occluded =>
+                    {
+                        self.occluded = occluded;
+                        shader.occlusion_changed(core, occluded);
+                        if !occluded {
+                            core.window().request_redraw();
+                        }
+                    }
                     WindowEvent::RedrawRequested => {
+                        if recorder.is_replaying() {
+                            match recorder.next_replay_frame() {
+                                Some((inputs, time)) => {
+                                    Self::deliver_replayed_inputs(shader, core, &inputs);
+                                    shader.set_replay_time(core, Some(time));
+                                }
+                                None => shader.set_replay_time(core, None),
+                            }
+                        }
+                        recorder.record_frame();
+                        self.app.frame_limiter.frame_started();
                         shader.update(core);
                         match shader.render(core) {
                             Ok(_) => {
@@ -118,6 +309,11 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
                             Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
                             Err(e) => eprintln!("Render error: {e:?}"),
                         }
+                        // Mouse deltas are per frame, whichever path uploaded the uniform
+                        if let Some(kit) = shader.render_kit_mut() {
+                            kit.mouse_tracker.end_frame();
+                        }
+                        self.app.update_title_fps();
                     }
                     _ => {}
                 }
@@ -125,7 +321,54 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        if self.app.input_recorder.is_replaying() {
+            return;
+        }
+        if let (Some(core), Some(shader)) = (&self.app.core, &mut self.shader) {
+            shader.handle_device_event(core, &event);
+            if self.app.is_reactive() {
+                core.window().request_redraw();
+            }
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // `Core::set_window_size` applied without a `Resized` event
+        if let (Some(core), Some(shader)) = (&mut self.app.core, &mut self.shader) {
+            if let Some(size) = core.take_pending_resize() {
+                if core.size != size {
+                    core.resize(size);
+                    shader.resize(core);
+                    core.window().request_redraw();
+                }
+            }
+            // Frame latency picked in the controls panel
+            if let Some(kit) = shader.render_kit_mut() {
+                if let Some(latency) = kit.controls.take_frame_latency_request() {
+                    core.set_max_frame_latency(latency);
+                }
+                kit.controls.sync_frame_latency(core.max_frame_latency());
+            }
+        }
+        // No need to keep the GPU busy while nobody can see the window
+        if self.occluded {
+            return;
+        }
+        if self.app.is_reactive() && !self.shader.as_ref().is_some_and(|s| s.needs_redraw()) {
+            event_loop.set_control_flow(ControlFlow::Wait);
+            return;
+        }
+        if let Some(deadline) = self.app.frame_limiter.wait_until() {
+            event_loop.set_control_flow(ControlFlow::WaitUntil(deadline));
+            return;
+        }
+        event_loop.set_control_flow(ControlFlow::Wait);
         if let Some(core) = &self.app.core {
             core.window().request_redraw();
         }
@@ -134,4 +377,12 @@ impl<S: ShaderManager> ApplicationHandler for ShaderAppHandler<S> {
     fn new_events(&mut self, _event_loop: &ActiveEventLoop, _cause: StartCause) {
         // No special handling needed for new events
     }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(core) = &self.app.core {
+            if let Err(e) = core.save_pipeline_cache() {
+                log::warn!("Failed to save pipeline cache: {e}");
+            }
+        }
+    }
 }
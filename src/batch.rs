@@ -0,0 +1,188 @@
+use crate::compute::ComputeShader;
+use crate::{save_frame, Core, ExportError, RenderKit};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Saved file name and variant label, one line of manifest.csv
+type ManifestRow = (String, String);
+
+/// A single parameter set to render in a batch
+#[derive(Debug, Clone)]
+pub struct BatchVariant {
+    /// Human readable description of the parameters, written to the manifest
+    pub label: String,
+    /// Raw bytes written to the custom uniform buffer (group 1, binding 1)
+    pub params: Vec<u8>,
+}
+
+/// Renders the same compute shader across a list of parameter variants and saves
+/// one PNG per variant. This is the unattended counterpart of the export UI:
+/// it goes through `capture_export_frame` and `save_frame` just like a normal export.
+pub struct BatchRenderer {
+    output_dir: PathBuf,
+    variants: Vec<BatchVariant>,
+    width: u32,
+    height: u32,
+    time: f32,
+    frames_per_variant: u32,
+    write_manifest: bool,
+}
+
+impl BatchRenderer {
+    pub fn new(output_dir: impl Into<PathBuf>, width: u32, height: u32) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            variants: Vec::new(),
+            width,
+            height,
+            time: 0.0,
+            frames_per_variant: 1,
+            write_manifest: true,
+        }
+    }
+
+    /// Add a variant from any Pod params struct. The Debug output is used as manifest label.
+    pub fn add_variant<T: bytemuck::Pod + std::fmt::Debug>(&mut self, params: T) -> &mut Self {
+        self.variants.push(BatchVariant {
+            label: format!("{params:?}"),
+            params: bytemuck::bytes_of(&params).to_vec(),
+        });
+        self
+    }
+
+    /// Add a variant from raw uniform bytes
+    pub fn add_variant_bytes(&mut self, label: impl Into<String>, params: Vec<u8>) -> &mut Self {
+        self.variants.push(BatchVariant {
+            label: label.into(),
+            params,
+        });
+        self
+    }
+
+    /// Shader time used for every capture
+    pub fn with_time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Number of frames to run before capturing (useful for accumulating shaders)
+    pub fn with_frames_per_variant(mut self, frames: u32) -> Self {
+        self.frames_per_variant = frames.max(1);
+        self
+    }
+
+    /// Enable/disable writing manifest.csv next to the frames
+    pub fn with_manifest(mut self, enabled: bool) -> Self {
+        self.write_manifest = enabled;
+        self
+    }
+
+    pub fn variants(&self) -> &[BatchVariant] {
+        &self.variants
+    }
+
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    /// Render every variant to disk. Returns the written file paths in variant order.
+    /// Variants that fail to capture, or whose params aren't `custom_uniform_size` bytes,
+    /// are logged and skipped. The export settings are restored on every path.
+    pub fn run(
+        &self,
+        core: &Core,
+        shader: &mut ComputeShader,
+        render_kit: &mut RenderKit,
+    ) -> Result<Vec<PathBuf>, ExportError> {
+        std::fs::create_dir_all(&self.output_dir)?;
+
+        // Borrow the export settings for the duration of the batch
+        let previous_settings = render_kit.export_manager.settings().clone();
+        {
+            let settings = render_kit.export_manager.settings_mut();
+            settings.export_path = self.output_dir.clone();
+            settings.width = self.width;
+            settings.height = self.height;
+        }
+
+        // Put the settings back even when a frame fails to save
+        let result = self.render_variants(core, shader, render_kit);
+        *render_kit.export_manager.settings_mut() = previous_settings;
+        let (written, manifest_rows) = result?;
+
+        if self.write_manifest {
+            self.write_manifest_csv(&manifest_rows)?;
+        }
+
+        Ok(written)
+    }
+
+    /// Render and save every variant with the batch export settings already applied.
+    /// Returns the written paths and the manifest rows.
+    fn render_variants(
+        &self,
+        core: &Core,
+        shader: &mut ComputeShader,
+        render_kit: &mut RenderKit,
+    ) -> Result<(Vec<PathBuf>, Vec<ManifestRow>), ExportError> {
+        let mut written = Vec::with_capacity(self.variants.len());
+        let mut manifest_rows = Vec::with_capacity(self.variants.len());
+
+        for (index, variant) in self.variants.iter().enumerate() {
+            let index = index as u32;
+            if !shader.set_custom_params_bytes(&variant.params, &core.queue) {
+                log::error!("Batch variant {index} skipped, its params don't fit the uniform");
+                continue;
+            }
+
+            // Start every variant from a clean state
+            shader.clear_all_buffers(core);
+
+            // Warm-up frames, the last one is dispatched by the capture itself
+            for frame in 1..self.frames_per_variant {
+                let mut encoder =
+                    core.device
+                        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                            label: Some("Batch Warmup Encoder"),
+                        });
+                shader.set_time(self.time, 0.0, &core.queue);
+                shader.dispatch(&mut encoder, core);
+                core.queue.submit(Some(encoder.finish()));
+                log::debug!("Batch variant {index}: warmup frame {frame}");
+            }
+
+            match shader.capture_export_frame(
+                core,
+                self.time,
+                render_kit,
+                None::<fn(&mut ComputeShader, &mut wgpu::CommandEncoder, &Core)>,
+            ) {
+                Ok(data) => {
+                    let path = save_frame(data, index, render_kit.export_manager.settings())?;
+                    let file_name = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    log::info!("Batch variant {index} saved to {file_name}");
+                    written.push(path);
+                    manifest_rows.push((file_name, variant.label.clone()));
+                }
+                Err(e) => {
+                    log::error!("Batch variant {index} failed to capture: {e:?}");
+                }
+            }
+        }
+
+        Ok((written, manifest_rows))
+    }
+
+    fn write_manifest_csv(&self, rows: &[ManifestRow]) -> Result<(), ExportError> {
+        let mut file = std::fs::File::create(self.output_dir.join("manifest.csv"))?;
+        writeln!(file, "file,params")?;
+        for (file_name, label) in rows {
+            // Quote the label, the Debug output contains commas
+            writeln!(file, "{},\"{}\"", file_name, label.replace('"', "\"\""))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{MouseTracker, UniformProvider};
+use winit::event::{ElementState, KeyEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.001;
+const WORLD_UP: [f32; 3] = [0.0, 1.0, 0.0];
+/// Movement speed multiplier while Shift is held
+const FAST_FACTOR: f32 = 4.0;
+
+/// Camera data for 3D shaders, see `Camera::as_uniform`.
+///
+/// Right-handed world with y up; the camera looks down its local -z, like the usual
+/// `lookAt`. Matrices are column-major, ready for WGSL:
+///
+/// ```wgsl
+/// struct CameraUniform {
+///     view: mat4x4<f32>,     // world -> camera
+///     inv_view: mat4x4<f32>, // camera -> world
+///     position: vec3<f32>,
+///     fov_y: f32,            // vertical field of view, radians
+///     forward: vec3<f32>,
+///     near: f32,
+///     right: vec3<f32>,
+///     far: f32,
+///     up: vec3<f32>,
+///     _padding: f32,
+/// }
+///
+/// // Primary ray for a pixel, uv in -1..1 with y up
+/// let h = tan(camera.fov_y * 0.5);
+/// let dir = normalize(camera.forward + uv.x * h * aspect * camera.right + uv.y * h * camera.up);
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CameraUniform {
+    pub view: [[f32; 4]; 4],
+    pub inv_view: [[f32; 4]; 4],
+    pub position: [f32; 3],
+    pub fov_y: f32,
+    pub forward: [f32; 3],
+    pub near: f32,
+    pub right: [f32; 3],
+    pub far: f32,
+    pub up: [f32; 3],
+    pub _padding: f32,
+}
+
+impl UniformProvider for CameraUniform {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+/// How `Camera::update` turns input into motion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CameraMode {
+    /// Rotate around `target` at `distance`: drag to orbit, wheel to zoom, keys pan
+    /// the target
+    #[default]
+    Orbit,
+    /// Free flight from `position`: drag (or pointer lock) to look, keys move, wheel
+    /// changes `move_speed`
+    Fly,
+}
+
+/// Movement keys held for `Camera::update`, fed from keyboard events.
+///
+/// Physical keys, so the layout is the same on AZERTY: W/S or Up/Down forward and back,
+/// A/D or Left/Right sideways, E/Space up, Q/Ctrl down, Shift for faster movement.
+#[derive(Debug, Clone, Default)]
+pub struct CameraInput {
+    forward: bool,
+    back: bool,
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+    fast: bool,
+    /// Wheel total at the last update, `MouseUniform::wheel` only ever accumulates
+    last_wheel: Option<f32>,
+}
+
+impl CameraInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a movement key. Returns true if the key is one of the camera keys.
+    pub fn handle_keyboard_input(&mut self, event: &KeyEvent) -> bool {
+        let PhysicalKey::Code(code) = event.physical_key else {
+            return false;
+        };
+        let pressed = event.state == ElementState::Pressed;
+        let key = match code {
+            KeyCode::KeyW | KeyCode::ArrowUp => &mut self.forward,
+            KeyCode::KeyS | KeyCode::ArrowDown => &mut self.back,
+            KeyCode::KeyA | KeyCode::ArrowLeft => &mut self.left,
+            KeyCode::KeyD | KeyCode::ArrowRight => &mut self.right,
+            KeyCode::KeyE | KeyCode::Space => &mut self.up,
+            KeyCode::KeyQ | KeyCode::ControlLeft => &mut self.down,
+            KeyCode::ShiftLeft | KeyCode::ShiftRight => &mut self.fast,
+            _ => return false,
+        };
+        *key = pressed;
+        true
+    }
+
+    /// Forget held keys, e.g. on focus loss when the key-up events go elsewhere
+    pub fn release_all(&mut self) {
+        *self = Self {
+            last_wheel: self.last_wheel,
+            ..Self::default()
+        };
+    }
+
+    /// Right, up, forward movement in -1..1
+    fn axis(&self) -> [f32; 3] {
+        let axis = |pos: bool, neg: bool| (pos as i32 - neg as i32) as f32;
+        [
+            axis(self.right, self.left),
+            axis(self.up, self.down),
+            axis(self.forward, self.back),
+        ]
+    }
+}
+
+/// Interactive camera for raymarching and other 3D shaders.
+///
+/// Call `update` once per frame before `RenderKit::update_mouse_uniform`, which clears the
+/// mouse delta it reads, then upload `as_uniform()`, either as a field of your params
+/// struct or in its own `UniformBinding<CameraUniform>`. Looking follows the mouse while
+/// the left button is held or the pointer is locked (`RenderKit::set_mouse_capture`).
+///
+/// Orientation is `yaw` (radians, 0 looks down -z, positive turns right) and `pitch`
+/// (positive looks up, clamped short of the poles). In `Orbit` mode `position` is derived
+/// from `target`, the angles and `distance`.
+#[derive(Debug, Clone)]
+pub struct Camera {
+    pub mode: CameraMode,
+    pub position: [f32; 3],
+    pub target: [f32; 3],
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Vertical field of view in radians
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+    /// Fly speed in units per second; orbit panning moves `distance` per second instead
+    pub move_speed: f32,
+    /// Radians of rotation per pixel of mouse movement
+    pub look_sensitivity: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::orbit([0.0, 0.0, 0.0], 5.0)
+    }
+}
+
+impl Camera {
+    /// Orbit camera looking at `target` from `distance` along +z
+    pub fn orbit(target: [f32; 3], distance: f32) -> Self {
+        let mut camera = Self {
+            mode: CameraMode::Orbit,
+            position: [0.0; 3],
+            target,
+            distance: distance.max(1e-3),
+            yaw: 0.0,
+            pitch: 0.0,
+            fov_y: 60f32.to_radians(),
+            near: 0.01,
+            far: 1000.0,
+            move_speed: 2.0,
+            look_sensitivity: 0.005,
+        };
+        camera.sync_position();
+        camera
+    }
+
+    /// Fly camera at `position` looking at `target`
+    pub fn fly(position: [f32; 3], target: [f32; 3]) -> Self {
+        let mut camera = Self::orbit(target, 1.0);
+        camera.mode = CameraMode::Fly;
+        camera.position = position;
+        camera.distance = length(sub(target, position)).max(1e-3);
+        camera.look_at(target);
+        camera
+    }
+
+    /// Turn towards `point`. Orbit cameras also make it their target.
+    pub fn look_at(&mut self, point: [f32; 3]) {
+        let to = sub(point, self.position);
+        let len = length(to);
+        if len < 1e-6 {
+            return;
+        }
+        self.yaw = to[0].atan2(-to[2]);
+        self.pitch = (to[1] / len).asin().clamp(-MAX_PITCH, MAX_PITCH);
+        if self.mode == CameraMode::Orbit {
+            self.target = point;
+            self.distance = len;
+            self.sync_position();
+        }
+    }
+
+    /// Switch modes without moving the view: a new orbit target is placed `distance`
+    /// ahead of the camera
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == CameraMode::Orbit && self.mode == CameraMode::Fly {
+            self.target = add(self.position, scale(self.forward(), self.distance));
+        }
+        self.mode = mode;
+        self.sync_position();
+    }
+
+    /// Apply this frame's mouse and key input, `dt` in seconds
+    pub fn update(&mut self, input: &mut CameraInput, mouse: &MouseTracker, dt: f32) {
+        let looking = mouse.is_captured() || mouse.uniform.buttons[0] & 1 != 0;
+        if looking {
+            let [dx, dy] = mouse.uniform.delta;
+            self.yaw += dx * self.look_sensitivity;
+            self.pitch = (self.pitch - dy * self.look_sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+
+        let wheel = mouse.uniform.wheel[1];
+        let scroll = wheel - input.last_wheel.unwrap_or(wheel);
+        input.last_wheel = Some(wheel);
+
+        let [x, y, z] = input.axis();
+        let fast = if input.fast { FAST_FACTOR } else { 1.0 };
+        match self.mode {
+            CameraMode::Orbit => {
+                self.distance = (self.distance * 0.9f32.powf(scroll)).max(1e-3);
+                let step = self.distance * dt * fast;
+                let pan = add(
+                    add(scale(self.right(), x), scale(self.up(), y)),
+                    scale(self.forward(), z),
+                );
+                self.target = add(self.target, scale(pan, step));
+            }
+            CameraMode::Fly => {
+                self.move_speed = (self.move_speed * 1.1f32.powf(scroll)).max(1e-3);
+                let step = self.move_speed * dt * fast;
+                let movement = add(
+                    add(scale(self.right(), x), scale(WORLD_UP, y)),
+                    scale(self.forward(), z),
+                );
+                self.position = add(self.position, scale(movement, step));
+            }
+        }
+        self.sync_position();
+    }
+
+    pub fn forward(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        [sin_yaw * cos_pitch, sin_pitch, -cos_yaw * cos_pitch]
+    }
+
+    pub fn right(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        [cos_yaw, 0.0, sin_yaw]
+    }
+
+    pub fn up(&self) -> [f32; 3] {
+        cross(self.right(), self.forward())
+    }
+
+    pub fn as_uniform(&self) -> CameraUniform {
+        let (f, r, u, p) = (self.forward(), self.right(), self.up(), self.position);
+        CameraUniform {
+            view: [
+                [r[0], u[0], -f[0], 0.0],
+                [r[1], u[1], -f[1], 0.0],
+                [r[2], u[2], -f[2], 0.0],
+                [-dot(r, p), -dot(u, p), dot(f, p), 1.0],
+            ],
+            inv_view: [
+                [r[0], r[1], r[2], 0.0],
+                [u[0], u[1], u[2], 0.0],
+                [-f[0], -f[1], -f[2], 0.0],
+                [p[0], p[1], p[2], 1.0],
+            ],
+            position: p,
+            fov_y: self.fov_y,
+            forward: f,
+            near: self.near,
+            right: r,
+            far: self.far,
+            up: u,
+            _padding: 0.0,
+        }
+    }
+
+    fn sync_position(&mut self) {
+        if self.mode == CameraMode::Orbit {
+            self.position = sub(self.target, scale(self.forward(), self.distance));
+        }
+    }
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn length(a: [f32; 3]) -> f32 {
+    dot(a, a).sqrt()
+}
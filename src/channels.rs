@@ -0,0 +1,229 @@
+use crate::animated::AnimatedTexture;
+use crate::compute::ComputeShader;
+#[cfg(feature = "media")]
+use crate::gst::video::VideoTextureManager;
+#[cfg(feature = "media")]
+use crate::gst::webcam::WebcamTextureManager;
+use crate::{Core, TextureManager};
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+
+const VIDEO_EXTENSIONS: [&str; 6] = ["mp4", "avi", "mkv", "webm", "mov", "m4v"];
+
+/// What a `// @channelN: ...` line asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelSource {
+    /// Image, GIF/APNG, KTX2 or (with the media feature) video file
+    File(PathBuf),
+    /// Default webcam, needs the media feature
+    Webcam,
+    /// Fed by the host code (e.g. a pass output); the loader leaves the channel alone
+    Buffer,
+}
+
+/// Parse channel declarations from a shader's comments:
+///
+/// ```wgsl
+/// // @channel0: "textures/noise.png"
+/// // @channel1: webcam
+/// // @channel2: buffer
+/// ```
+///
+/// Quoted paths are kept verbatim, relative paths are resolved by `ChannelInputs::load`.
+pub fn parse_channel_directives(source: &str) -> Vec<(u32, ChannelSource)> {
+    let mut directives = Vec::new();
+    for line in source.lines() {
+        let Some(comment) = line.trim().strip_prefix("//") else {
+            continue;
+        };
+        let Some(rest) = comment.trim().strip_prefix("@channel") else {
+            continue;
+        };
+        let Some((index, value)) = rest.split_once(':') else {
+            continue;
+        };
+        let Ok(index) = index.trim().parse::<u32>() else {
+            warn!("Ignoring channel directive with invalid index: {line}");
+            continue;
+        };
+
+        let value = value.trim();
+        let channel = match value {
+            "webcam" => ChannelSource::Webcam,
+            "buffer" => ChannelSource::Buffer,
+            _ => {
+                let path = value.trim_matches('"');
+                if path.is_empty() {
+                    warn!("Ignoring empty @channel{index} directive");
+                    continue;
+                }
+                ChannelSource::File(PathBuf::from(path))
+            }
+        };
+        directives.push((index, channel));
+    }
+    directives
+}
+
+enum ChannelMedia {
+    Static(TextureManager),
+    Animated(AnimatedTexture),
+    #[cfg(feature = "media")]
+    Video(VideoTextureManager),
+    #[cfg(feature = "media")]
+    Webcam(WebcamTextureManager),
+}
+
+impl ChannelMedia {
+    fn texture_manager(&self) -> &TextureManager {
+        match self {
+            ChannelMedia::Static(texture) => texture,
+            ChannelMedia::Animated(animation) => animation.texture_manager(),
+            #[cfg(feature = "media")]
+            ChannelMedia::Video(video) => video.texture_manager(),
+            #[cfg(feature = "media")]
+            ChannelMedia::Webcam(webcam) => webcam.texture_manager(),
+        }
+    }
+}
+
+/// Media loaded from a shader's `@channelN` directives and kept bound to its channels.
+///
+/// Missing or unsupported inputs log a warning and leave the channel on the default texture.
+/// Call `update` every frame so videos, webcams and animations advance.
+pub struct ChannelInputs {
+    channels: Vec<(u32, ChannelMedia)>,
+}
+
+impl ChannelInputs {
+    /// Load every directive in `shader_source`, resolving relative paths against `base_dir`
+    /// (usually the shader file's directory), and bind them to `shader`'s channels.
+    pub fn load(
+        core: &Core,
+        shader: &mut ComputeShader,
+        shader_source: &str,
+        base_dir: &Path,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let mut channels = Vec::new();
+        for (index, source) in parse_channel_directives(shader_source) {
+            let media = match source {
+                ChannelSource::Buffer => {
+                    info!("@channel{index} is a buffer, leaving it to the host");
+                    continue;
+                }
+                ChannelSource::Webcam => Self::load_webcam(core, layout),
+                ChannelSource::File(path) => Self::load_file(core, &base_dir.join(path), layout),
+            };
+            match media {
+                Ok(media) => {
+                    let texture = media.texture_manager();
+                    shader.update_channel_texture(
+                        index,
+                        &texture.view,
+                        &texture.sampler,
+                        &core.device,
+                        &core.queue,
+                    );
+                    channels.push((index, media));
+                }
+                Err(e) => warn!("@channel{index}: {e}, using the default texture"),
+            }
+        }
+        Self { channels }
+    }
+
+    fn load_file(
+        core: &Core,
+        path: &Path,
+        layout: &wgpu::BindGroupLayout,
+    ) -> anyhow::Result<ChannelMedia> {
+        if !path.exists() {
+            anyhow::bail!("{path:?} not found");
+        }
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_default();
+
+        if VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+            #[cfg(feature = "media")]
+            {
+                let mut video = VideoTextureManager::new(&core.device, &core.queue, layout, path)?;
+                video.set_loop(true);
+                video.play()?;
+                return Ok(ChannelMedia::Video(video));
+            }
+            #[cfg(not(feature = "media"))]
+            anyhow::bail!("{path:?} is a video, which needs the media feature");
+        }
+        if extension == "ktx2" {
+            let bytes = std::fs::read(path)?;
+            let texture = TextureManager::from_ktx2(&core.device, &core.queue, &bytes, layout)?;
+            return Ok(ChannelMedia::Static(texture));
+        }
+        if extension == "gif" || extension == "png" || extension == "apng" {
+            if let Some(animation) = AnimatedTexture::load(&core.device, &core.queue, layout, path)?
+            {
+                return Ok(ChannelMedia::Animated(animation));
+            }
+        }
+
+        let image = image::open(path)?;
+        let texture = TextureManager::new(&core.device, &core.queue, &image.to_rgba8(), layout);
+        Ok(ChannelMedia::Static(texture))
+    }
+
+    #[allow(unused_variables)]
+    fn load_webcam(core: &Core, layout: &wgpu::BindGroupLayout) -> anyhow::Result<ChannelMedia> {
+        #[cfg(feature = "media")]
+        {
+            let mut webcam = WebcamTextureManager::new(&core.device, &core.queue, layout, None)?;
+            webcam.start()?;
+            return Ok(ChannelMedia::Webcam(webcam));
+        }
+        #[cfg(not(feature = "media"))]
+        anyhow::bail!("webcam input needs the media feature");
+    }
+
+    /// Advance animated inputs and rebind the channels whose texture changed
+    #[allow(unused_variables)]
+    pub fn update(
+        &mut self,
+        core: &Core,
+        shader: &mut ComputeShader,
+        layout: &wgpu::BindGroupLayout,
+    ) {
+        for (index, media) in &mut self.channels {
+            let changed = match media {
+                ChannelMedia::Static(_) => false,
+                ChannelMedia::Animated(animation) => animation.update_texture(&core.queue),
+                #[cfg(feature = "media")]
+                ChannelMedia::Video(video) => video
+                    .update_texture(&core.device, &core.queue, layout)
+                    .unwrap_or(false),
+                #[cfg(feature = "media")]
+                ChannelMedia::Webcam(webcam) => webcam
+                    .update_texture(&core.device, &core.queue, layout)
+                    .unwrap_or(false),
+            };
+            if changed {
+                // Video/webcam textures are recreated when the frame size changes
+                let texture = media.texture_manager();
+                shader.update_channel_texture(
+                    *index,
+                    &texture.view,
+                    &texture.sampler,
+                    &core.device,
+                    &core.queue,
+                );
+            }
+        }
+    }
+
+    /// Channel indices that got media from the directives
+    pub fn loaded_channels(&self) -> Vec<u32> {
+        self.channels.iter().map(|(index, _)| *index).collect()
+    }
+}
@@ -0,0 +1,247 @@
+use super::{create_bind_group_layout, BindGroupLayoutType};
+use crate::{Core, TextureManager};
+use wgpu::util::DeviceExt;
+
+const ATOMIC_RESOLVE_SHADER: &str = include_str!("../../shaders/atomic_resolve.wgsl");
+
+/// u32 planes per pixel in an `AtomicAccumulator` buffer: r, g, b sums and a hit count
+pub const ATOMIC_PLANES: u32 = 4;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ResolveParams {
+    width: u32,
+    height: u32,
+    scale: f32,
+    _padding: u32,
+}
+
+/// Double-buffered fixed-point atomic accumulation for splatting, plus a resolve pass.
+///
+/// Splat shaders bind `bind_group()` (layout `storage_layout()`) as
+/// `var<storage, read_write> accum: array<atomic<u32>>` and add
+/// `u32(value * scale)` to plane `p` of pixel `i` at `p * width * height + i`, with
+/// planes r, g, b and a hit count (`ATOMIC_PLANES`). `resolve_to` writes the average
+/// color per pixel (alpha 0 where nothing landed) into an `Rgba16Float` output texture.
+///
+/// Per frame: `clear`, dispatch the splat shader, `resolve_to`, then `swap`. The previous
+/// frame's sums stay readable through `previous_buffer` for feedback effects.
+pub struct AtomicAccumulator {
+    buffers: [wgpu::Buffer; 2],
+    bind_groups: [wgpu::BindGroup; 2],
+    current: usize,
+    storage_layout: wgpu::BindGroupLayout,
+    resolve_pipeline: wgpu::ComputePipeline,
+    resolve_input_layout: wgpu::BindGroupLayout,
+    output_layout: wgpu::BindGroupLayout,
+    params_buffer: wgpu::Buffer,
+    params_bind_group: wgpu::BindGroup,
+    scale: f32,
+    width: u32,
+    height: u32,
+}
+
+impl AtomicAccumulator {
+    /// `scale` is the fixed-point factor splat shaders multiply values by, e.g. 256.0.
+    /// Higher keeps more precision but overflows sooner (u32 sums).
+    pub fn new(core: &Core, scale: f32) -> Self {
+        let device = &core.device;
+        let storage_layout = create_bind_group_layout(
+            device,
+            BindGroupLayoutType::StorageBuffer { read_only: false },
+            "Atomic Accumulator Storage",
+        );
+        let resolve_input_layout = create_bind_group_layout(
+            device,
+            BindGroupLayoutType::StorageBuffer { read_only: true },
+            "Atomic Resolve Input",
+        );
+        let params_layout = create_bind_group_layout(
+            device,
+            BindGroupLayoutType::Uniform,
+            "Atomic Resolve Params",
+        );
+        let output_layout = create_bind_group_layout(
+            device,
+            BindGroupLayoutType::StorageTexture,
+            "Atomic Resolve Output",
+        );
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Atomic Resolve Pipeline Layout"),
+            bind_group_layouts: &[&resolve_input_layout, &params_layout, &output_layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Atomic Resolve Shader"),
+            source: wgpu::ShaderSource::Wgsl(ATOMIC_RESOLVE_SHADER.into()),
+        });
+        let resolve_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Atomic Resolve Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: core.pipeline_cache(),
+        });
+
+        let (width, height) = (core.size.width, core.size.height);
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Atomic Resolve Params"),
+            contents: bytemuck::bytes_of(&ResolveParams {
+                width,
+                height,
+                scale,
+                _padding: 0,
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let params_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Atomic Resolve Params"),
+            layout: &params_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params_buffer.as_entire_binding(),
+            }],
+        });
+
+        let (buffers, bind_groups) = Self::create_buffers(device, &storage_layout, width, height);
+        Self {
+            buffers,
+            bind_groups,
+            current: 0,
+            storage_layout,
+            resolve_pipeline,
+            resolve_input_layout,
+            output_layout,
+            params_buffer,
+            params_bind_group,
+            scale,
+            width,
+            height,
+        }
+    }
+
+    fn create_buffers(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        width: u32,
+        height: u32,
+    ) -> ([wgpu::Buffer; 2], [wgpu::BindGroup; 2]) {
+        let size = width as u64 * height as u64 * ATOMIC_PLANES as u64 * 4;
+        let create = |i: usize| {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("Atomic Accumulator Buffer {i}")),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&format!("Atomic Accumulator Bind Group {i}")),
+                layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+            (buffer, bind_group)
+        };
+        let (buffer0, bind_group0) = create(0);
+        let (buffer1, bind_group1) = create(1);
+        ([buffer0, buffer1], [bind_group0, bind_group1])
+    }
+
+    /// Layout of `bind_group`, for the splat shader's pipeline layout
+    pub fn storage_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.storage_layout
+    }
+
+    /// Storage binding of the buffer being accumulated this frame
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_groups[self.current]
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffers[self.current]
+    }
+
+    /// Last frame's sums, valid after `swap`
+    pub fn previous_buffer(&self) -> &wgpu::Buffer {
+        &self.buffers[1 - self.current]
+    }
+
+    /// u32 values per plane, i.e. the offset between a pixel's r, g, b and count
+    pub fn plane_stride(&self) -> u32 {
+        self.width * self.height
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Zero the current buffer with `clear_buffer`, no upload involved
+    pub fn clear(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.clear_buffer(&self.buffers[self.current], 0, None);
+    }
+
+    /// Average the accumulated sums into `output` (`Rgba16Float`, the accumulator's size)
+    pub fn resolve_to(
+        &self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &TextureManager,
+    ) {
+        let input_bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Atomic Resolve Input"),
+            layout: &self.resolve_input_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: self.buffers[self.current].as_entire_binding(),
+            }],
+        });
+        let output_bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Atomic Resolve Output"),
+            layout: &self.output_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&output.view),
+            }],
+        });
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Atomic Resolve Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.resolve_pipeline);
+        pass.set_bind_group(0, &input_bind_group, &[]);
+        pass.set_bind_group(1, &self.params_bind_group, &[]);
+        pass.set_bind_group(2, &output_bind_group, &[]);
+        pass.dispatch_workgroups(self.width.div_ceil(16), self.height.div_ceil(16), 1);
+    }
+
+    /// Make the next frame accumulate into the other buffer
+    pub fn swap(&mut self) {
+        self.current = 1 - self.current;
+    }
+
+    /// Recreate both buffers at the new size, the contents are lost
+    pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+        if width == 0 || height == 0 || (width, height) == (self.width, self.height) {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        (self.buffers, self.bind_groups) =
+            Self::create_buffers(&core.device, &self.storage_layout, width, height);
+        core.queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&ResolveParams {
+                width,
+                height,
+                scale: self.scale,
+                _padding: 0,
+            }),
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use super::{ComputeShader, MultiPassManager, COMPUTE_TEXTURE_FORMAT_RGBA16};
+use crate::{Core, TextureManager};
+use wgpu::util::DeviceExt;
+
+const BLUR_SHADER: &str = include_str!("../../shaders/blur.wgsl");
+const BLUR_BUFFER: &str = "blur_horizontal";
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BlurParams {
+    direction: [f32; 2],
+    radius: i32,
+    sigma: f32,
+}
+
+/// Separable gaussian blur: a horizontal pass into an intermediate texture
+/// (owned by a `MultiPassManager`) followed by a vertical pass into the output.
+/// Works on `Rgba16Float`, edges are clamped.
+pub struct Blur {
+    pipeline: wgpu::ComputePipeline,
+    input_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    horizontal_params: wgpu::Buffer,
+    vertical_params: wgpu::Buffer,
+    intermediate: MultiPassManager,
+    output: TextureManager,
+    output_bind_group: wgpu::BindGroup,
+    radius: u32,
+    sigma: f32,
+    width: u32,
+    height: u32,
+}
+
+impl Blur {
+    /// Smallest sigma used, a zero sigma would divide by zero in the gaussian weights
+    const MIN_SIGMA: f32 = 0.001;
+
+    pub fn new(core: &Core, radius: u32, sigma: f32) -> Self {
+        let device = &core.device;
+        let sigma = sigma.max(Self::MIN_SIGMA);
+
+        let input_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Blur Input Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let placeholder_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Blur Placeholder Layout"),
+                entries: &[],
+            });
+        let intermediate = MultiPassManager::new(
+            core,
+            &[BLUR_BUFFER.to_string()],
+            COMPUTE_TEXTURE_FORMAT_RGBA16,
+            placeholder_layout,
+        );
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blur Pipeline Layout"),
+            bind_group_layouts: &[&input_layout, intermediate.get_storage_layout()],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Blur Shader"),
+            source: wgpu::ShaderSource::Wgsl(BLUR_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Blur Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: core.pipeline_cache(),
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Blur Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let make_params = |label: &str, direction: [f32; 2]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::bytes_of(&BlurParams {
+                    direction,
+                    radius: radius as i32,
+                    sigma,
+                }),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            })
+        };
+        let horizontal_params = make_params("Blur Horizontal Params", [1.0, 0.0]);
+        let vertical_params = make_params("Blur Vertical Params", [0.0, 1.0]);
+
+        let (output, output_bind_group) =
+            Self::create_output(core, &intermediate, core.size.width, core.size.height);
+
+        Self {
+            pipeline,
+            input_layout,
+            sampler,
+            horizontal_params,
+            vertical_params,
+            intermediate,
+            output,
+            output_bind_group,
+            radius,
+            sigma,
+            width: core.size.width,
+            height: core.size.height,
+        }
+    }
+
+    fn create_output(
+        core: &Core,
+        intermediate: &MultiPassManager,
+        width: u32,
+        height: u32,
+    ) -> (TextureManager, wgpu::BindGroup) {
+        let output = ComputeShader::create_output_texture(
+            &core.device,
+            width,
+            height,
+            COMPUTE_TEXTURE_FORMAT_RGBA16,
+            "Blur Output Texture",
+        );
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blur Output Storage"),
+            layout: intermediate.get_storage_layout(),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&output.view),
+            }],
+        });
+        (output, bind_group)
+    }
+
+    fn create_input_bind_group(
+        &self,
+        device: &wgpu::Device,
+        view: &wgpu::TextureView,
+        params: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blur Input"),
+            layout: &self.input_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Set the kernel radius (in pixels) and gaussian sigma
+    pub fn set_kernel(&mut self, radius: u32, sigma: f32, queue: &wgpu::Queue) {
+        self.radius = radius;
+        self.sigma = sigma.max(Self::MIN_SIGMA);
+        for (buffer, direction) in [
+            (&self.horizontal_params, [1.0, 0.0]),
+            (&self.vertical_params, [0.0, 1.0]),
+        ] {
+            let params = BlurParams {
+                direction,
+                radius: self.radius as i32,
+                sigma: self.sigma,
+            };
+            queue.write_buffer(buffer, 0, bytemuck::bytes_of(&params));
+        }
+    }
+
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    pub fn sigma(&self) -> f32 {
+        self.sigma
+    }
+
+    /// Record both blur passes into `encoder` and return the blurred texture
+    pub fn apply(
+        &self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        input: &TextureManager,
+    ) -> &TextureManager {
+        let workgroups = [self.width.div_ceil(16), self.height.div_ceil(16)];
+
+        let intermediate_view = self
+            .intermediate
+            .get_write_texture(BLUR_BUFFER)
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let horizontal_input =
+            self.create_input_bind_group(&core.device, &input.view, &self.horizontal_params);
+        let vertical_input =
+            self.create_input_bind_group(&core.device, &intermediate_view, &self.vertical_params);
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Blur Horizontal Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &horizontal_input, &[]);
+            pass.set_bind_group(1, self.intermediate.get_write_bind_group(BLUR_BUFFER), &[]);
+            pass.dispatch_workgroups(workgroups[0], workgroups[1], 1);
+        }
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Blur Vertical Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &vertical_input, &[]);
+            pass.set_bind_group(1, &self.output_bind_group, &[]);
+            pass.dispatch_workgroups(workgroups[0], workgroups[1], 1);
+        }
+
+        &self.output
+    }
+
+    pub fn output(&self) -> &TextureManager {
+        &self.output
+    }
+
+    pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        self.intermediate.resize(core, width, height);
+        let (output, output_bind_group) =
+            Self::create_output(core, &self.intermediate, width, height);
+        self.output = output;
+        self.output_bind_group = output_bind_group;
+    }
+}
@@ -1,12 +1,26 @@
-use crate::UniformProvider;
+use crate::{SharedUniform, UniformProvider};
 use wgpu;
 
 /// Pass description for multi-pass shaders
+///
+/// Pass buffers start zeroed (wgpu zero-initializes new textures). A `persistent` pass keeps
+/// what it wrote across frames (feedback/accumulation), a non-persistent pass has its buffer
+/// cleared to zero at the start of every frame.
+///
+/// A pass writes the buffer named after it unless `with_output` picks another one, e.g. a
+/// scratch buffer from `ComputeShaderBuilder::with_scratch_buffers`. `main_image` always
+/// writes the shader output. `inputs` name any buffers, pass or scratch; inputs naming no
+/// buffer fail `ComputeShaderBuilder::validate` and are left unbound otherwise.
+///
+/// A pass writes exactly one buffer, a pass writing several isn't supported: split it into
+/// one pass per buffer, or use storage buffers for data that doesn't fit one texture.
 #[derive(Debug, Clone)]
 pub struct PassDescription {
     pub name: String,
     pub inputs: Vec<String>,
     pub workgroup_size: Option<[u32; 3]>,
+    pub persistent: bool,
+    pub output: Option<String>,
 }
 
 impl PassDescription {
@@ -15,13 +29,32 @@ impl PassDescription {
             name: name.to_string(),
             inputs: inputs.iter().map(|s| s.to_string()).collect(),
             workgroup_size: None,
+            persistent: true,
+            output: None,
         }
     }
 
+    /// Keep the buffer across frames (default) or clear it every frame
+    pub fn with_persistent(mut self, persistent: bool) -> Self {
+        self.persistent = persistent;
+        self
+    }
+
     pub fn with_workgroup_size(mut self, size: [u32; 3]) -> Self {
         self.workgroup_size = Some(size);
         self
     }
+
+    /// Write `buffer` instead of the buffer named after the pass
+    pub fn with_output(mut self, buffer: &str) -> Self {
+        self.output = Some(buffer.to_string());
+        self
+    }
+
+    /// Buffer the pass writes (ignored for `main_image`)
+    pub fn output_buffer(&self) -> &str {
+        self.output.as_deref().unwrap_or(&self.name)
+    }
 }
 
 /// User-defined storage buffer specification
@@ -29,6 +62,8 @@ impl PassDescription {
 pub struct StorageBufferSpec {
     pub name: String,
     pub size_bytes: u64,
+    /// Bound as `var<storage, read>` instead of `read_write`
+    pub read_only: bool,
 }
 
 impl StorageBufferSpec {
@@ -36,19 +71,52 @@ impl StorageBufferSpec {
         Self {
             name: name.to_string(),
             size_bytes,
+            read_only: false,
         }
     }
+
+    /// Bind as `var<storage, read>`, for data the shader only reads (lookup tables,
+    /// uploaded meshes). The buffer can still be written with `queue.write_buffer`.
+    pub fn with_read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+}
+
+/// Language of the shader source.
+///
+/// Only WGSL gets the engine's source handling: `OUTPUT_FORMAT` substitution, the stdlib,
+/// binding checks and hot reload. Other languages are compiled as given and must follow
+/// the 4-group convention themselves (`layout(set = N, binding = M)` in GLSL).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShaderLanguage {
+    #[default]
+    Wgsl,
+    /// GLSL 450 compute shader, needs the `glsl` feature. naga's GLSL frontend names the
+    /// entry point `main`, takes one entry point per source and covers less than WGSL
+    /// (no subgroup ops, limited extensions). Storage image formats are written out,
+    /// e.g. `layout(rgba16f)`.
+    Glsl,
+    /// SPIR-V binary, needs the `spirv` feature and `ComputeShader::from_spirv`
+    SpirV,
 }
 
 /// Configuration built by the builder
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ComputeConfiguration {
     pub entry_points: Vec<String>,
     pub passes: Option<Vec<PassDescription>>,
+    /// Extra multi-pass buffers not tied to a pass, see `with_scratch_buffers`
+    pub scratch_buffers: Vec<String>,
     pub custom_uniform_size: Option<u64>,
+    /// Time uniform buffer owned by a `SharedUniform`, see `with_shared_time`
+    pub shared_time: Option<wgpu::Buffer>,
+    /// Params buffer owned by a `SharedUniform`, see `with_shared_params`
+    pub shared_params: Option<wgpu::Buffer>,
     pub has_input_texture: bool,
     pub has_mouse: bool,
     pub has_fonts: bool,
+    pub sdf_font_spread: Option<u32>,
     pub has_audio: bool,
     pub has_atomic_buffer: bool,
     pub audio_buffer_size: usize,
@@ -57,9 +125,64 @@ pub struct ComputeConfiguration {
     pub storage_buffers: Vec<StorageBufferSpec>,
     pub workgroup_size: [u32; 3],
     pub dispatch_once: bool,
+    pub init_entry_point: Option<String>,
+    pub has_stdlib: bool,
     pub texture_format: wgpu::TextureFormat,
+    pub direct_present: bool,
+    pub has_depth_output: bool,
+    pub readwrite_output: bool,
+    pub seed: Option<u64>,
     pub label: String,
     pub num_channels: Option<u32>,
+    pub has_blue_noise: bool,
+    /// Color of unbound channels, see `with_default_channel_color`
+    pub default_channel_color: wgpu::Color,
+    pub source_language: ShaderLanguage,
+}
+
+impl ComputeConfiguration {
+    /// Ping-pong buffers a texture multi-pass shader gets: one per pass, then the scratch
+    /// buffers and any other buffer a pass writes. Empty for single-pass shaders and
+    /// multi-pass shaders on storage buffers.
+    pub fn multi_pass_buffers(&self) -> Vec<String> {
+        let Some(passes) = self
+            .passes
+            .as_ref()
+            .filter(|_| self.storage_buffers.is_empty())
+        else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = passes.iter().map(|p| p.name.clone()).collect();
+        let extra = self
+            .scratch_buffers
+            .iter()
+            .cloned()
+            .chain(passes.iter().map(|p| p.output_buffer().to_string()));
+        for name in extra {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// `(pass, input)` for every pass input that names none of the `multi_pass_buffers`
+    pub fn unknown_pass_inputs(&self) -> Vec<(String, String)> {
+        let buffers = self.multi_pass_buffers();
+        if buffers.is_empty() {
+            return Vec::new();
+        }
+        self.passes
+            .iter()
+            .flatten()
+            .flat_map(|pass| {
+                pass.inputs
+                    .iter()
+                    .filter(|input| !buffers.contains(input))
+                    .map(|input| (pass.name.clone(), input.clone()))
+            })
+            .collect()
+    }
 }
 
 /// Builder for compute shader configurations
@@ -77,10 +200,14 @@ impl ComputeShaderBuilder {
             config: ComputeConfiguration {
                 entry_points: vec!["main".to_string()],
                 passes: None,
+                scratch_buffers: Vec::new(),
                 custom_uniform_size: None,
+                shared_time: None,
+                shared_params: None,
                 has_input_texture: false,
                 has_mouse: false,
                 has_fonts: false,
+                sdf_font_spread: None,
                 has_audio: false,
                 has_atomic_buffer: false,
                 audio_buffer_size: 1024,
@@ -89,9 +216,23 @@ impl ComputeShaderBuilder {
                 storage_buffers: Vec::new(),
                 workgroup_size: [16, 16, 1],
                 dispatch_once: false,
+                init_entry_point: None,
+                has_stdlib: false,
                 texture_format: wgpu::TextureFormat::Rgba16Float,
+                direct_present: false,
+                has_depth_output: false,
+                readwrite_output: false,
+                seed: None,
                 label: "Compute Shader".to_string(),
                 num_channels: None,
+                has_blue_noise: false,
+                default_channel_color: wgpu::Color {
+                    r: 1.0,
+                    g: 0.0,
+                    b: 1.0,
+                    a: 1.0,
+                },
+                source_language: ShaderLanguage::Wgsl,
             },
         }
     }
@@ -102,6 +243,26 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Use every `@compute` entry point of `source`, in declaration order. Single-pass
+    /// shaders run them one after another each frame. Keeps the current entry points
+    /// if the source has none.
+    pub fn with_all_entry_points(mut self, source: &str) -> Self {
+        let entry_points: Vec<String> = super::list_entry_points(source)
+            .into_iter()
+            .filter(|ep| ep.stage == wgpu::ShaderStages::COMPUTE)
+            .map(|ep| ep.name)
+            .collect();
+        if entry_points.is_empty() {
+            log::warn!(
+                "No @compute entry points found, keeping {:?}",
+                self.config.entry_points
+            );
+        } else {
+            self.config.entry_points = entry_points;
+        }
+        self
+    }
+
     /// Configure multi-pass execution with ping-pong buffers
     pub fn with_multi_pass(mut self, passes: &[PassDescription]) -> Self {
         self.config.passes = Some(passes.to_vec());
@@ -109,12 +270,39 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Allocate extra ping-pong buffers for multi-pass shaders, beyond the one per pass.
+    /// Passes write them with `PassDescription::with_output` and read them by name in
+    /// `inputs`, like pass buffers (including the ping-pong timing).
+    pub fn with_scratch_buffers(mut self, names: &[&str]) -> Self {
+        self.config.scratch_buffers = names.iter().map(|n| n.to_string()).collect();
+        self
+    }
+
     /// Add custom uniform parameters (goes to @group(1))
     pub fn with_custom_uniforms<T: UniformProvider>(mut self) -> Self {
         self.config.custom_uniform_size = Some(std::mem::size_of::<T>() as u64);
         self
     }
 
+    /// Bind `@group(1) @binding(1)` to a buffer shared with other shaders.
+    ///
+    /// Writes through `ComputeShader::set_custom_params` land in the shared buffer too,
+    /// so normally only the `SharedUniform` owner updates it.
+    pub fn with_shared_params<T: UniformProvider>(mut self, params: &SharedUniform<T>) -> Self {
+        self.config.custom_uniform_size = Some(std::mem::size_of::<T>() as u64);
+        self.config.shared_params = Some(params.buffer().clone());
+        self
+    }
+
+    /// Bind the `@group(0)` time uniform to a buffer shared with other shaders.
+    ///
+    /// `ComputeShader::set_time` then leaves the buffer alone, update the `SharedUniform`
+    /// once per frame instead. Frame export still writes its own timing into it.
+    pub fn with_shared_time(mut self, time: &SharedUniform<super::ComputeTimeUniform>) -> Self {
+        self.config.shared_time = Some(time.buffer().clone());
+        self
+    }
+
     /// Enable input texture support (goes to @group(1))
     pub fn with_input_texture(mut self) -> Self {
         self.config.has_input_texture = true;
@@ -127,6 +315,31 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Bind the built-in 256x256 blue-noise texture after the channels (goes to @group(2)):
+    ///
+    /// ```wgsl
+    /// @group(2) @binding(N) var blue_noise: texture_2d<f32>;
+    /// @group(2) @binding(N+1) var blue_noise_sampler: sampler;
+    /// ```
+    ///
+    /// It's the classic void-and-cluster pattern with every value equally common, in the
+    /// red channel. The texture tiles seamlessly and the sampler repeats with nearest
+    /// filtering, so `textureLoad(blue_noise, vec2<i32>(id.xy) % 256, 0).r` gives one value
+    /// per pixel for dithering or sample offsets. Offset the lookup per frame (e.g. by
+    /// `time_data.frame * vec2(71, 37)`) to decorrelate accumulated frames.
+    pub fn with_blue_noise(mut self) -> Self {
+        self.config.has_blue_noise = true;
+        self
+    }
+
+    /// Color channels sample while nothing is bound to them. Magenta by default so a
+    /// missing binding stands out; use `wgpu::Color::TRANSPARENT` or `BLACK` for shaders
+    /// that treat unbound channels as empty. Linear values, like clear colors.
+    pub fn with_default_channel_color(mut self, color: wgpu::Color) -> Self {
+        self.config.default_channel_color = color;
+        self
+    }
+
     /// Enable mouse input (goes to @group(2))
     pub fn with_mouse(mut self) -> Self {
         self.config.has_mouse = true;
@@ -139,6 +352,13 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Enable font rendering with a signed distance field atlas (see `FontSystem::new_sdf`)
+    pub fn with_sdf_fonts(mut self, spread: u32) -> Self {
+        self.config.has_fonts = true;
+        self.config.sdf_font_spread = Some(spread);
+        self
+    }
+
     /// Enable audio buffer (goes to @group(2))
     pub fn with_audio(mut self, buffer_size: usize) -> Self {
         self.config.has_audio = true;
@@ -153,6 +373,23 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Enable the audio buffer with room for `samples` f32 values. `read_audio_buffer`
+    /// returns exactly this many values. Clamped to the device's
+    /// `max_storage_buffer_binding_size` (128 MiB by default, 32M samples) at build time.
+    pub fn with_audio_buffer(mut self, samples: usize) -> Self {
+        self.config.has_audio = true;
+        self.config.audio_buffer_size = samples;
+        self
+    }
+
+    /// Enable the spectrum buffer with `bins` f32 values. `update_audio_spectrum` fills
+    /// the first 64 bins and writes BPM at index 64, so use at least 65.
+    pub fn with_audio_spectrum_size(mut self, bins: usize) -> Self {
+        self.config.has_audio_spectrum = true;
+        self.config.audio_spectrum_size = bins;
+        self
+    }
+
     /// Enable atomic buffer for particle systems (goes to @group(2))
     pub fn with_atomic_buffer(mut self) -> Self {
         self.config.has_atomic_buffer = true;
@@ -171,6 +408,48 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Let `ComputeShader::dispatch_to_surface` write straight into the swapchain texture,
+    /// skipping the output texture + blit. Needs a surface that supports storage binding
+    /// (see `Core::direct_present_format`); otherwise the shader keeps the normal path.
+    /// Declare the output as `texture_storage_2d<OUTPUT_FORMAT, write>`, the placeholder is
+    /// replaced with the format actually used. Values are written without sRGB encoding.
+    pub fn direct_present(mut self) -> Self {
+        self.config.direct_present = true;
+        self
+    }
+
+    /// Add an `R32Float` depth field at `@group(1) @binding(4)`, declared as
+    /// `texture_storage_2d<r32float, write>`. Depth formats can't be storage textures, so
+    /// the shader writes R32Float and `DepthResolve` (or `RenderKit::resolve_compute_depth`)
+    /// copies it into a `Depth32Float` attachment for later render passes.
+    /// Write depth in 0..1 (0 = near with the default `Less` compare).
+    pub fn with_depth_output(mut self) -> Self {
+        self.config.has_depth_output = true;
+        self
+    }
+
+    /// Bind the output texture as `texture_storage_2d<FORMAT, read_write>`, so a shader can
+    /// `textureLoad` and `textureStore` the same texel for in-place iterative updates
+    /// without ping-pong buffers. Reads see what earlier dispatches stored; within one
+    /// dispatch only the texel an invocation writes itself is safe to read back.
+    ///
+    /// Read-write access is format-restricted: `R32Float`/`R32Uint`/`R32Sint` always work,
+    /// `Rgba16Float`, `Rgba32Float` and 8-bit formats only where
+    /// `Core::supports_read_write_storage` says so. Otherwise an error is logged and the
+    /// output stays write-only, so the shader's `read_write` declaration fails to validate.
+    /// Applies to the single-pass output, not multi-pass buffers.
+    pub fn with_readwrite_output(mut self) -> Self {
+        self.config.readwrite_output = true;
+        self
+    }
+
+    /// Fix the seed behind `ComputeTimeUniform::random`/`seed`, so the same frame number
+    /// always gets the same values (reproducible exports). Random per run by default.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
     /// Set workgroup size
     pub fn with_workgroup_size(mut self, size: [u32; 3]) -> Self {
         self.config.workgroup_size = size;
@@ -183,12 +462,38 @@ impl ComputeShaderBuilder {
         self
     }
 
+    /// Run `entry_point` once before the regular passes on the first frame and after
+    /// `clear_all_buffers`, for seeding buffers and textures. It writes the main output
+    /// texture, not a ping-pong buffer; with `with_multi_pass` it reads the inputs of the
+    /// first pass.
+    pub fn with_init_pass(mut self, entry_point: &str) -> Self {
+        self.config.init_entry_point = Some(entry_point.to_string());
+        self
+    }
+
+    /// Append the `STDLIB_WGSL` drawing helpers to the shader source. They are added after
+    /// the user code so error line numbers still match the file; don't redefine their names.
+    pub fn with_stdlib(mut self) -> Self {
+        self.config.has_stdlib = true;
+        self
+    }
+
     /// Set output texture format
+    ///
+    /// With the linear variant of `CAPTURE_FORMAT` (`Rgba8Unorm`, `Bgra8Unorm` on macOS)
+    /// export copies the output bytes as written instead of blitting them, so a shader
+    /// that writes sRGB-encoded values exports them unchanged.
     pub fn with_texture_format(mut self, format: wgpu::TextureFormat) -> Self {
         self.config.texture_format = format;
         self
     }
 
+    /// Compile the source as another language than WGSL, see `ShaderLanguage`
+    pub fn with_source_language(mut self, language: ShaderLanguage) -> Self {
+        self.config.source_language = language;
+        self
+    }
+
     /// Set debug label
     pub fn with_label(mut self, label: &str) -> Self {
         self.config.label = label.to_string();
@@ -199,6 +504,26 @@ impl ComputeShaderBuilder {
     pub fn build(self) -> ComputeConfiguration {
         self.config
     }
+
+    /// Dry run: compile `source` and check the configured entry points exist and every
+    /// pass input names a buffer, without allocating textures, buffers or pipelines.
+    pub fn validate(
+        &self,
+        core: &crate::Core,
+        source: &str,
+    ) -> Result<super::ValidationReport, super::ShaderBuildError> {
+        if let Some((pass, input)) = self.config.unknown_pass_inputs().into_iter().next() {
+            return Err(super::ShaderBuildError::UnknownPassInput { pass, input });
+        }
+        let mut required = self.config.entry_points.clone();
+        required.extend(self.config.init_entry_point.iter().cloned());
+        let source = if self.config.has_stdlib {
+            format!("{source}\n{}", super::STDLIB_WGSL)
+        } else {
+            source.to_string()
+        };
+        super::validate::validate_source(core, &source, &required, &self.config.label)
+    }
 }
 
 impl Default for ComputeShaderBuilder {
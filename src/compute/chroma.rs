@@ -0,0 +1,252 @@
+use super::{ComputeShader, COMPUTE_TEXTURE_FORMAT_RGBA16};
+use crate::{Core, TextureManager};
+use bytemuck::Zeroable;
+use wgpu::util::DeviceExt;
+
+const CHROMA_KEY_SHADER: &str = include_str!("../../shaders/chroma_key.wgsl");
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct KeyParams {
+    color: [f32; 3],
+    tolerance: f32,
+    softness: f32,
+    _padding: [f32; 7],
+}
+
+/// Chroma key (green screen): pixels close to `color` in chroma become transparent.
+///
+/// The distance ignores luma, so shadows on the screen are keyed too. Pixels closer than
+/// `tolerance` are fully transparent and alpha ramps up over the next `softness`.
+/// The output is straight-alpha `Rgba16Float`, bind it as a channel of another shader
+/// (`update_channel_texture`) to composite. Settings can be edited live with `ui`.
+pub struct ChromaKey {
+    /// Key color, linear RGB
+    pub color: [f32; 3],
+    /// Chroma distance below which pixels are fully keyed out (~0..0.5)
+    pub tolerance: f32,
+    /// Width of the transparent-to-opaque ramp after `tolerance`
+    pub softness: f32,
+    pipeline: wgpu::ComputePipeline,
+    input_layout: wgpu::BindGroupLayout,
+    output_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    params_buffer: wgpu::Buffer,
+    output: TextureManager,
+    output_bind_group: wgpu::BindGroup,
+    width: u32,
+    height: u32,
+}
+
+impl ChromaKey {
+    /// Green key with a moderate tolerance, sized to the window
+    pub fn new(core: &Core) -> Self {
+        let device = &core.device;
+
+        let input_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Chroma Key Input Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let output_layout = super::create_bind_group_layout(
+            device,
+            super::BindGroupLayoutType::StorageTexture,
+            "Chroma Key Output Layout",
+        );
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Chroma Key Pipeline Layout"),
+            bind_group_layouts: &[&input_layout, &output_layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Chroma Key Shader"),
+            source: wgpu::ShaderSource::Wgsl(CHROMA_KEY_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Chroma Key Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: core.pipeline_cache(),
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Chroma Key Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Chroma Key Params"),
+            contents: bytemuck::bytes_of(&KeyParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let (width, height) = (core.size.width, core.size.height);
+        let (output, output_bind_group) = Self::create_output(core, &output_layout, width, height);
+
+        Self {
+            color: [0.0, 1.0, 0.0],
+            tolerance: 0.15,
+            softness: 0.1,
+            pipeline,
+            input_layout,
+            output_layout,
+            sampler,
+            params_buffer,
+            output,
+            output_bind_group,
+            width,
+            height,
+        }
+    }
+
+    fn create_output(
+        core: &Core,
+        layout: &wgpu::BindGroupLayout,
+        width: u32,
+        height: u32,
+    ) -> (TextureManager, wgpu::BindGroup) {
+        let output = ComputeShader::create_output_texture(
+            &core.device,
+            width,
+            height,
+            COMPUTE_TEXTURE_FORMAT_RGBA16,
+            "Chroma Key Output Texture",
+        );
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Chroma Key Output Storage"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&output.view),
+            }],
+        });
+        (output, bind_group)
+    }
+
+    /// Builder-style key color
+    pub fn with_color(mut self, color: [f32; 3]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Builder-style tolerance and softness
+    pub fn with_tolerance(mut self, tolerance: f32, softness: f32) -> Self {
+        self.tolerance = tolerance;
+        self.softness = softness;
+        self
+    }
+
+    /// Key `input` into the output texture and return it. Picks up the current settings.
+    pub fn apply(
+        &self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        input: &TextureManager,
+    ) -> &TextureManager {
+        core.queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&KeyParams {
+                color: self.color,
+                tolerance: self.tolerance.max(0.0),
+                softness: self.softness.max(1e-4),
+                _padding: [0.0; 7],
+            }),
+        );
+        let input_bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Chroma Key Input"),
+            layout: &self.input_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&input.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Chroma Key Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &input_bind_group, &[]);
+        pass.set_bind_group(1, &self.output_bind_group, &[]);
+        pass.dispatch_workgroups(self.width.div_ceil(16), self.height.div_ceil(16), 1);
+
+        &self.output
+    }
+
+    pub fn output(&self) -> &TextureManager {
+        &self.output
+    }
+
+    /// Key color picker plus tolerance/softness sliders. Returns true if anything changed.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.collapsing("Chroma Key", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Key Color");
+                changed |= ui.color_edit_button_rgb(&mut self.color).changed();
+            });
+            changed |= ui
+                .add(egui::Slider::new(&mut self.tolerance, 0.0..=0.5).text("Tolerance"))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(&mut self.softness, 0.0..=0.5).text("Softness"))
+                .changed();
+        });
+        changed
+    }
+
+    pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+        if width == 0 || height == 0 || (width, height) == (self.width, self.height) {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        let (output, output_bind_group) =
+            Self::create_output(core, &self.output_layout, width, height);
+        self.output = output;
+        self.output_bind_group = output_bind_group;
+    }
+}
@@ -1,14 +1,36 @@
 use log::info;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use wgpu;
 
-use super::builder::ComputeConfiguration;
+use super::builder::{ComputeConfiguration, ShaderLanguage, StorageBufferSpec};
+use super::hash::OutputHasher;
 use super::multipass::MultiPassManager;
-use super::resource::ResourceLayout;
-use crate::{Core, FontSystem, ShaderHotReload, TextureManager, UniformBinding, UniformProvider};
-
+use super::noise::create_blue_noise;
+use super::readback::PendingReadback;
+use super::resource::{create_storage_buffers, ChannelConfig, ResourceLayout};
+use super::validate::{layout_changed, reflect_bindings, BindingSignature};
+use crate::{
+    Core, ExportOutput, FontSystem, HotReloadError, OutputPixels, ShaderHotReload, TextureManager,
+    UniformBinding, UniformProvider,
+};
+
+/// `@group(0) @binding(0)` of every compute shader.
+///
+/// `random` and `seed` were appended after the original 16 bytes, so shaders declaring only
+/// `time, delta, frame, _padding` keep working. The full WGSL struct is:
+/// ```wgsl
+/// struct TimeUniform {
+///     time: f32,
+///     delta: f32,
+///     frame: u32,
+///     _padding: u32,
+///     random: f32, // uniform in [0, 1), new every frame
+///     seed: u32,   // random bits of the same frame, for seeding a shader PRNG
+///     _padding2: vec2<u32>,
+/// }
+/// ```
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct ComputeTimeUniform {
@@ -16,6 +38,17 @@ pub struct ComputeTimeUniform {
     pub delta: f32,
     pub frame: u32,
     pub _padding: u32,
+    pub random: f32,
+    pub seed: u32,
+    pub _padding2: [u32; 2],
+}
+
+/// splitmix64, enough to decorrelate consecutive frames
+fn frame_hash(seed: u64, frame: u32) -> u64 {
+    let mut z = seed.wrapping_add((frame as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
 
 impl UniformProvider for ComputeTimeUniform {
@@ -32,6 +65,9 @@ pub struct ComputeShader {
     pub workgroup_size: [u32; 3],
     pub dispatch_once: bool,
     pub current_frame: u32,
+    /// Entry point dispatched once on frame 0, see `ComputeShaderBuilder::with_init_pass`
+    pub init_entry_point: Option<String>,
+    init_pipeline: Option<wgpu::ComputePipeline>,
 
     // Layouts following the 4-group convention
     pub bind_group_layouts: HashMap<u32, wgpu::BindGroupLayout>,
@@ -65,7 +101,11 @@ pub struct ComputeShader {
 
     // Channel system for external textures (Group 2)
     pub channel_textures: HashMap<u32, Option<(wgpu::TextureView, wgpu::Sampler)>>,
+    pub channel_configs: HashMap<u32, ChannelConfig>,
+    channel_memory: HashMap<u32, u64>,
     pub num_channels: u32,
+    /// Built-in blue-noise texture and sampler, see `ComputeShaderBuilder::with_blue_noise`
+    pub blue_noise: Option<(wgpu::TextureView, wgpu::Sampler)>,
 
     // User storage buffers (Group 3)
     pub storage_buffers: Vec<wgpu::Buffer>,
@@ -78,11 +118,235 @@ pub struct ComputeShader {
     pub hot_reload: Option<ShaderHotReload>,
     pub label: String,
     pub has_input_texture: bool,
+    /// `STDLIB_WGSL` is appended to the source, also on hot reload
+    pub has_stdlib: bool,
+
+    // Output format and whether `dispatch_to_surface` is available
+    pub output_format: wgpu::TextureFormat,
+    pub direct_present: bool,
+    current_input: Option<(wgpu::TextureView, wgpu::Sampler)>,
+    /// Base of the per-frame `random`/`seed`, fixed with `ComputeShaderBuilder::with_seed`
+    random_seed: u64,
+
+    /// R32Float depth field written at `@group(1) @binding(4)`, see `with_depth_output`
+    pub depth_output: Option<TextureManager>,
+
+    /// Configuration the shader was built with (after limit clamping), reused on resize
+    config: ComputeConfiguration,
+
+    /// Entry points skipped by `dispatch`, see `set_pass_enabled`
+    disabled_passes: HashSet<String>,
+
+    /// Bindings the current source declares, compared by `check_hot_reload_full`
+    binding_signature: Vec<BindingSignature>,
+
+    /// Export frame whose readbacks are still in flight, see `handle_export`
+    pending_export: Option<PendingExport>,
+
+    /// Created by the first `output_hash` call
+    output_hasher: Option<OutputHasher>,
+
+    /// `Core::pipeline_cache` at build time, reused when hot reload recreates pipelines
+    pipeline_cache: Option<wgpu::PipelineCache>,
+}
+
+/// Outputs of one export frame, read back over several event loop iterations
+struct PendingExport {
+    frame: u32,
+    /// Still on the GPU
+    readbacks: Vec<PendingOutput>,
+    finished: Vec<FinishedOutput>,
+}
+
+/// An export output whose readback is in flight
+struct PendingOutput {
+    output: ExportOutput,
+    /// Size of float outputs, `None` for the RGBA8 color capture
+    float_size: Option<(u32, u32)>,
+    readback: PendingReadback,
+}
+
+/// An export output read back and waiting for the rest of its frame
+struct FinishedOutput {
+    output: ExportOutput,
+    pixels: OutputPixels,
+}
+
+/// Optional group 1 bindings, each bound only when the configuration uses it
+#[derive(Clone, Copy)]
+struct Group1Resources<'a> {
+    /// Binding 1
+    custom_uniform: Option<&'a wgpu::Buffer>,
+    /// Bindings 2 and 3, texture and sampler
+    input_texture: Option<&'a TextureManager>,
+    /// Binding 4
+    depth_output: Option<&'a TextureManager>,
+}
+
+/// Shader code for `ComputeShader::build`
+enum ModuleSource<'a> {
+    /// WGSL, or GLSL per `ComputeConfiguration::source_language`
+    Text(&'a str),
+    #[cfg(feature = "spirv")]
+    SpirV(&'a [u8]),
+}
+
+fn is_8bit_unorm(format: wgpu::TextureFormat) -> bool {
+    matches!(
+        format.remove_srgb_suffix(),
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Bgra8Unorm
+    )
+}
+
+/// Thumbnail pixel from an output texel, sRGB-encoding linear float values
+fn thumbnail_pixel(rgba: [f32; 4], srgb_encode: bool) -> image::Rgba<u8> {
+    let encode = |c: f32| {
+        let c = c.clamp(0.0, 1.0);
+        if !srgb_encode {
+            c
+        } else if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    };
+    let to_u8 = |c: f32| (c * 255.0).round() as u8;
+    image::Rgba([
+        to_u8(encode(rgba[0])),
+        to_u8(encode(rgba[1])),
+        to_u8(encode(rgba[2])),
+        to_u8(rgba[3].clamp(0.0, 1.0)),
+    ])
+}
+
+/// WGSL name of a storage texture format, for the `OUTPUT_FORMAT` placeholder
+fn wgsl_storage_format(format: wgpu::TextureFormat) -> Option<&'static str> {
+    match format {
+        wgpu::TextureFormat::Rgba16Float => Some("rgba16float"),
+        wgpu::TextureFormat::Rgba32Float => Some("rgba32float"),
+        wgpu::TextureFormat::Rgba8Unorm => Some("rgba8unorm"),
+        wgpu::TextureFormat::Bgra8Unorm => Some("bgra8unorm"),
+        _ => None,
+    }
+}
+
+/// Replace the `OUTPUT_FORMAT` placeholder with the WGSL name of `format`. Only the whole
+/// word is replaced, identifiers containing it (`MY_OUTPUT_FORMAT`) are left alone.
+fn substitute_output_format(source: &str, format: wgpu::TextureFormat) -> String {
+    const PLACEHOLDER: &str = "OUTPUT_FORMAT";
+    let Some(name) = wgsl_storage_format(format) else {
+        return source.to_string();
+    };
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(index) = rest.find(PLACEHOLDER) {
+        let (before, after) = (&rest[..index], &rest[index + PLACEHOLDER.len()..]);
+        let preceded = before
+            .chars()
+            .next_back()
+            .or_else(|| result.chars().next_back())
+            .is_some_and(is_ident);
+        let followed = after.chars().next().is_some_and(is_ident);
+        let replacement = if preceded || followed {
+            PLACEHOLDER
+        } else {
+            name
+        };
+        result.push_str(before);
+        result.push_str(replacement);
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Copy `old` into `new` when both exist with the same size
+fn copy_if_same_size(
+    encoder: &mut wgpu::CommandEncoder,
+    old: &Option<wgpu::Buffer>,
+    new: &Option<wgpu::Buffer>,
+) {
+    if let (Some(old), Some(new)) = (old, new) {
+        if old.size() == new.size() {
+            encoder.copy_buffer_to_buffer(old, 0, new, 0, old.size());
+        }
+    }
 }
 
 impl ComputeShader {
     /// Create a compute shader from builder configuration
     pub fn from_builder(core: &Core, shader_source: &str, config: ComputeConfiguration) -> Self {
+        Self::build(core, ModuleSource::Text(shader_source), config)
+    }
+
+    /// Create a compute shader from a SPIR-V binary (e.g. `include_bytes!("shader.spv")`).
+    /// Bindings must follow the 4-group convention, entry points are named as in the binary.
+    #[cfg(feature = "spirv")]
+    pub fn from_spirv(core: &Core, spirv: &[u8], mut config: ComputeConfiguration) -> Self {
+        config.source_language = ShaderLanguage::SpirV;
+        Self::build(core, ModuleSource::SpirV(spirv), config)
+    }
+
+    fn build(core: &Core, source: ModuleSource, config: ComputeConfiguration) -> Self {
+        let mut config = config;
+        if config.direct_present {
+            match core.direct_present_format() {
+                Some(format) => config.texture_format = format,
+                None => {
+                    log::warn!(
+                        "{}: surface doesn't support storage binding, using the blit path",
+                        config.label
+                    );
+                    config.direct_present = false;
+                }
+            }
+        }
+        if config.readwrite_output && !core.supports_read_write_storage(config.texture_format) {
+            log::error!(
+                "{}: {:?} can't be a read-write storage texture on this device, keeping the output write-only",
+                config.label,
+                config.texture_format
+            );
+            config.readwrite_output = false;
+        }
+        // Oversized audio buffers would fail buffer/bind group creation, clamp them instead
+        let max_floats = core.device.limits().max_storage_buffer_binding_size as usize
+            / std::mem::size_of::<f32>();
+        if config.has_audio && config.audio_buffer_size > max_floats {
+            log::warn!(
+                "{}: audio buffer of {} samples exceeds the device limit, clamping to {max_floats}",
+                config.label,
+                config.audio_buffer_size
+            );
+            config.audio_buffer_size = max_floats;
+        }
+        if config.has_audio_spectrum && config.audio_spectrum_size > max_floats {
+            log::warn!(
+                "{}: audio spectrum of {} bins exceeds the device limit, clamping to {max_floats}",
+                config.label,
+                config.audio_spectrum_size
+            );
+            config.audio_spectrum_size = max_floats;
+        }
+
+        // Only WGSL is preprocessed and checked, other languages are compiled as given
+        let wgsl_source = match source {
+            ModuleSource::Text(text) => match config.source_language {
+                ShaderLanguage::Glsl if cfg!(feature = "glsl") => None,
+                ShaderLanguage::Wgsl => Some(Self::prepare_source(text, &config)),
+                language => {
+                    log::error!(
+                        "{}: {language:?} source needs the `glsl` feature or, for SPIR-V, the `spirv` feature and `from_spirv`; compiling as WGSL",
+                        config.label
+                    );
+                    Some(Self::prepare_source(text, &config))
+                }
+            },
+            #[cfg(feature = "spirv")]
+            ModuleSource::SpirV(_) => None,
+        };
+
         // Step 1: Create resource layout following 4-group convention
         let mut resource_layout = ResourceLayout::new();
 
@@ -90,13 +354,21 @@ impl ComputeShader {
         resource_layout.add_time_uniform();
 
         // Group 1: Primary I/O & Parameters
-        resource_layout.add_output_texture(config.texture_format);
+        let output_access = if config.readwrite_output {
+            wgpu::StorageTextureAccess::ReadWrite
+        } else {
+            wgpu::StorageTextureAccess::WriteOnly
+        };
+        resource_layout.add_output_texture_with_access(config.texture_format, output_access);
         if let Some(uniform_size) = config.custom_uniform_size {
             resource_layout.add_custom_uniform("params", uniform_size);
         }
         if config.has_input_texture {
             resource_layout.add_input_texture();
         }
+        if config.has_depth_output {
+            resource_layout.add_depth_output();
+        }
 
         // Group 2: Engine Resources
         if config.has_mouse {
@@ -120,40 +392,47 @@ impl ComputeShader {
         if let Some(num_channels) = config.num_channels {
             resource_layout.add_channel_textures(num_channels);
         }
+        if config.has_blue_noise {
+            resource_layout.add_blue_noise();
+        }
 
         // Group 3: User-defined storage buffers with optional multi-pass input textures
         if !config.storage_buffers.is_empty() {
             // User storage buffers
             for buffer_spec in &config.storage_buffers {
-                resource_layout.add_storage_buffer(&buffer_spec.name, buffer_spec.size_bytes);
+                resource_layout.add_storage_buffer_with_access(
+                    &buffer_spec.name,
+                    buffer_spec.size_bytes,
+                    buffer_spec.read_only,
+                );
             }
         } else if config.passes.is_some() {
             // Fallback: Multi-pass input textures only if no storage buffers requested
             resource_layout.add_multipass_input_textures();
         }
 
-        // Step 2: Create bind group layouts
-        let bind_group_layouts = resource_layout.create_bind_group_layouts(&core.device);
-
-        // Step 3: Create pipeline layout - WebGPU requires contiguous bind group indices
-        // I need to ensure all groups 0-3 are present, creating empty layouts if needed
-        let mut layouts_vec: Vec<wgpu::BindGroupLayout> = Vec::new();
-
-        for i in 0..4 {
-            if let Some(layout) = bind_group_layouts.get(&i) {
-                layouts_vec.push(layout.clone()); // Clone the existing layout
-            } else {
-                // Create an empty bind group layout for missing groups
-                let empty_layout =
-                    core.device
-                        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                            label: Some(&format!("Empty Group {i} Layout")),
-                            entries: &[],
-                        });
-                layouts_vec.push(empty_layout);
+        // Catch setup mistakes (e.g. an @group(2) binding with nothing enabled) before wgpu
+        // reports them as an opaque pipeline error. Parse errors surface at module creation.
+        if let Some(Ok(issues)) = wgsl_source
+            .as_deref()
+            .map(|wgsl| super::validate::check_bindings(wgsl, &resource_layout))
+        {
+            for issue in issues {
+                log::warn!("{}: {issue}", config.label);
             }
         }
 
+        // Step 2-3: Create bind group layouts. WebGPU requires contiguous bind group
+        // indices, unused groups get empty layouts (and empty bind groups, set at dispatch)
+        let (layouts_vec, empty_bind_groups) = resource_layout.finalize_contiguous(&core.device);
+        let bind_group_layouts: HashMap<u32, wgpu::BindGroupLayout> = layouts_vec
+            .iter()
+            .enumerate()
+            .map(|(group, layout)| (group as u32, layout))
+            .filter(|(group, _)| !empty_bind_groups.contains_key(group))
+            .map(|(group, layout)| (group, layout.clone()))
+            .collect();
+
         let layout_refs: Vec<&wgpu::BindGroupLayout> = layouts_vec.iter().collect();
 
         let pipeline_layout = core
@@ -166,18 +445,33 @@ impl ComputeShader {
 
         // Step 4: Create time uniform (Group 0)
         let time_bind_group_layout = bind_group_layouts.get(&0).unwrap();
-        let time_uniform = UniformBinding::new(
-            &core.device,
-            &format!("{} Time Uniform", config.label),
-            ComputeTimeUniform {
-                time: 0.0,
-                delta: 0.0,
-                frame: 0,
-                _padding: 0,
-            },
-            time_bind_group_layout,
-            0,
-        );
+        let time_data = ComputeTimeUniform {
+            time: 0.0,
+            delta: 0.0,
+            frame: 0,
+            _padding: 0,
+            random: 0.0,
+            seed: 0,
+            _padding2: [0; 2],
+        };
+        let time_label = format!("{} Time Uniform", config.label);
+        let time_uniform = match &config.shared_time {
+            Some(buffer) => UniformBinding::from_buffer(
+                &core.device,
+                &time_label,
+                buffer.clone(),
+                time_data,
+                time_bind_group_layout,
+                0,
+            ),
+            None => UniformBinding::new(
+                &core.device,
+                &time_label,
+                time_data,
+                time_bind_group_layout,
+                0,
+            ),
+        };
         let group0_bind_group = time_uniform.bind_group.clone();
 
         // Step 5: Create output texture
@@ -190,11 +484,15 @@ impl ComputeShader {
         );
 
         // Step 5.5: Create custom uniform buffer if needed
-        let custom_uniform = if let Some(uniform_size) = config.custom_uniform_size {
+        let custom_uniform = if let Some(shared) = &config.shared_params {
+            Some(shared.clone())
+        } else if let Some(uniform_size) = config.custom_uniform_size {
             Some(core.device.create_buffer(&wgpu::BufferDescriptor {
                 label: Some(&format!("{} Custom Uniform Buffer", config.label)),
                 size: uniform_size,
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                usage: wgpu::BufferUsages::UNIFORM
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
                 mapped_at_creation: false,
             }))
         } else {
@@ -211,17 +509,33 @@ impl ComputeShader {
             None
         };
 
+        let placeholder_input_texture_binding = placeholder_input_texture
+            .as_ref()
+            .map(|t| (t.view.clone(), t.sampler.clone()));
+
+        let depth_output = config.has_depth_output.then(|| {
+            Self::create_depth_output(
+                &core.device,
+                core.size.width,
+                core.size.height,
+                &config.label,
+            )
+        });
+
         let group1_bind_group = Self::create_group1_bind_group(
             &core.device,
             bind_group_layouts.get(&1).unwrap(),
             &output_texture,
             &config,
-            custom_uniform.as_ref(),
-            placeholder_input_texture.as_ref().map(|t| &t.view),
-            placeholder_input_texture.as_ref().map(|t| &t.sampler),
+            Group1Resources {
+                custom_uniform: custom_uniform.as_ref(),
+                input_texture: placeholder_input_texture.as_ref(),
+                depth_output: depth_output.as_ref(),
+            },
         );
 
         // Step 6: Create engine resources (Group 2) if needed
+        let blue_noise = config.has_blue_noise.then(|| create_blue_noise(core));
         let (
             font_system,
             atomic_buffer_raw,
@@ -230,7 +544,7 @@ impl ComputeShader {
             audio_spectrum_buffer,
             mouse_uniform,
             group2_bind_group,
-        ) = Self::create_engine_resources(core, &bind_group_layouts, &config);
+        ) = Self::create_engine_resources(core, &bind_group_layouts, &config, &blue_noise);
 
         // Step 7: Create user storage buffers (Group 3) if needed
         let (storage_buffers, group3_bind_group) = if !config.storage_buffers.is_empty() {
@@ -244,29 +558,26 @@ impl ComputeShader {
             (Vec::new(), None)
         };
 
-        // Step 7.5: Create empty bind groups for empty layouts (needed when we create contiguous layouts)
-        let mut empty_bind_groups: std::collections::HashMap<u32, wgpu::BindGroup> =
-            std::collections::HashMap::new();
-        for i in 0..4 {
-            if !bind_group_layouts.contains_key(&i) {
-                // This group was missing and got an empty layout, create an empty bind group
-                let empty_bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    label: Some(&format!("Empty Group {i} Bind Group")),
-                    layout: &layouts_vec[i as usize],
-                    entries: &[],
-                });
-                empty_bind_groups.insert(i, empty_bind_group);
-            }
-        }
-
         // Step 8: Create multi-pass manager if needed (only for texture ping-pong, not storage buffers)
         let (multipass_manager, pass_dependencies) = if let Some(passes) = &config.passes {
             if config.storage_buffers.is_empty() {
                 // Pure multi-pass mode with texture ping-pong: Group 3 managed by MultiPassManager
-                let buffer_names: Vec<String> = passes.iter().map(|p| p.name.clone()).collect();
+                let buffer_names = config.multi_pass_buffers();
+                for (pass, input) in config.unknown_pass_inputs() {
+                    log::error!("Pass {pass} reads unknown buffer {input}, leaving it unbound");
+                }
+                // Unknown inputs are dropped, the manager has no texture to bind for them
                 let dependencies: HashMap<String, Vec<String>> = passes
                     .iter()
-                    .map(|p| (p.name.clone(), p.inputs.clone()))
+                    .map(|p| {
+                        let inputs = p
+                            .inputs
+                            .iter()
+                            .filter(|input| buffer_names.contains(input))
+                            .cloned()
+                            .collect();
+                        (p.name.clone(), inputs)
+                    })
                     .collect();
 
                 let manager = MultiPassManager::new(
@@ -291,11 +602,24 @@ impl ComputeShader {
         };
 
         // Step 9: Create compute pipelines
+        let module_source = match (&source, wgsl_source.as_deref()) {
+            (_, Some(wgsl)) => wgpu::ShaderSource::Wgsl(wgsl.into()),
+            #[cfg(feature = "glsl")]
+            (ModuleSource::Text(text), None) => wgpu::ShaderSource::Glsl {
+                shader: (*text).into(),
+                stage: wgpu::naga::ShaderStage::Compute,
+                defines: &[],
+            },
+            #[cfg(feature = "spirv")]
+            (ModuleSource::SpirV(bytes), None) => wgpu::util::make_spirv(bytes),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!("text sources without the glsl feature are compiled as WGSL"),
+        };
         let shader_module = core
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: Some(&format!("{} Module", config.label)),
-                source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+                source: module_source,
             });
 
         let mut pipelines = Vec::new();
@@ -308,11 +632,23 @@ impl ComputeShader {
                     module: &shader_module,
                     entry_point: Some(entry_point),
                     compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    cache: None,
+                    cache: core.pipeline_cache(),
                 });
             pipelines.push(pipeline);
         }
+        let init_pipeline = config.init_entry_point.as_ref().map(|entry_point| {
+            core.device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(&format!("{} Init Pipeline - {}", config.label, entry_point)),
+                    layout: Some(&pipeline_layout),
+                    module: &shader_module,
+                    entry_point: Some(entry_point),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    cache: core.pipeline_cache(),
+                })
+        });
 
+        let stored_config = config.clone();
         Self {
             pipelines,
             output_texture,
@@ -320,6 +656,8 @@ impl ComputeShader {
             workgroup_size: config.workgroup_size,
             dispatch_once: config.dispatch_once,
             current_frame: 0,
+            init_entry_point: config.init_entry_point,
+            init_pipeline,
             bind_group_layouts,
             pipeline_layout,
             group0_bind_group,
@@ -341,15 +679,156 @@ impl ComputeShader {
             custom_uniform_size: config.custom_uniform_size,
             placeholder_input_texture,
             channel_textures: Self::initialize_channel_textures(config.num_channels.unwrap_or(0)),
+            channel_configs: HashMap::new(),
+            channel_memory: HashMap::new(),
             num_channels: config.num_channels.unwrap_or(0),
+            blue_noise,
             entry_points: config.entry_points,
             hot_reload: None,
             label: config.label,
             has_input_texture: config.has_input_texture,
+            has_stdlib: config.has_stdlib,
+            output_format: config.texture_format,
+            direct_present: config.direct_present,
+            current_input: placeholder_input_texture_binding,
+            random_seed: config.seed.unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_nanos() as u64)
+                    .unwrap_or(0)
+            }),
+            depth_output,
+            config: stored_config,
+            disabled_passes: HashSet::new(),
+            binding_signature: wgsl_source
+                .as_deref()
+                .and_then(|wgsl| reflect_bindings(wgsl).ok())
+                .unwrap_or_default(),
+            pending_export: None,
+            output_hasher: None,
+            pipeline_cache: core.pipeline_cache().cloned(),
+        }
+    }
+
+    /// Source as compiled: `OUTPUT_FORMAT` substituted and the stdlib appended if enabled
+    fn prepare_source(shader_source: &str, config: &ComputeConfiguration) -> String {
+        let shader_source = substitute_output_format(shader_source, config.texture_format);
+        if config.has_stdlib {
+            format!("{shader_source}\n{}", super::STDLIB_WGSL)
+        } else {
+            shader_source
+        }
+    }
+
+    /// Configuration the shader was built with
+    pub fn config(&self) -> &ComputeConfiguration {
+        &self.config
+    }
+
+    /// Skip a pass at runtime, for isolating which pass produces an artifact.
+    ///
+    /// A disabled pass isn't dispatched, but multi-pass buffers still flip after it, so the
+    /// other passes keep their ping-pong order. Passes reading a disabled pass see its last
+    /// output if it's persistent, or zeros if it's cleared every frame. Disabling a pass that
+    /// an enabled pass depends on is allowed and logs a warning. The init pass always runs.
+    pub fn set_pass_enabled(&mut self, entry_point: &str, enabled: bool) {
+        if !self.entry_points.iter().any(|e| e == entry_point) {
+            log::warn!("set_pass_enabled: no pass named {entry_point}");
+            return;
+        }
+        if enabled {
+            self.disabled_passes.remove(entry_point);
+            return;
+        }
+        self.disabled_passes.insert(entry_point.to_string());
+        if let Some(dependencies) = &self.pass_dependencies {
+            for (pass, inputs) in dependencies {
+                if self.is_pass_enabled(pass) && inputs.iter().any(|i| i == entry_point) {
+                    log::warn!("Pass {pass} reads disabled pass {entry_point}");
+                }
+            }
+        }
+    }
+
+    pub fn is_pass_enabled(&self, entry_point: &str) -> bool {
+        !self.disabled_passes.contains(entry_point)
+    }
+
+    /// R32Float isn't filterable, so unlike `create_output_texture` the display bind group
+    /// uses a non-filtering sampler. Read it with `textureLoad` or a nearest sampler.
+    fn create_depth_output(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        label: &str,
+    ) -> TextureManager {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&format!("{label} Depth Output")),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Depth Output Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some(&format!("{label} Depth Output Bind Group")),
+        });
+
+        TextureManager {
+            texture,
+            view,
+            sampler,
+            bind_group,
+            view_formats: Vec::new(),
         }
     }
 
-    fn create_output_texture(
+    pub(crate) fn create_output_texture(
         device: &wgpu::Device,
         width: u32,
         height: u32,
@@ -367,7 +846,9 @@ impl ComputeShader {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
 
@@ -417,6 +898,7 @@ impl ComputeShader {
             view,
             sampler,
             bind_group,
+            view_formats: Vec::new(),
         }
     }
 
@@ -487,6 +969,7 @@ impl ComputeShader {
             view,
             sampler,
             bind_group,
+            view_formats: Vec::new(),
         }
     }
 
@@ -495,10 +978,13 @@ impl ComputeShader {
         layout: &wgpu::BindGroupLayout,
         output_texture: &TextureManager,
         config: &ComputeConfiguration,
-        custom_uniform_buffer: Option<&wgpu::Buffer>,
-        input_texture_view: Option<&wgpu::TextureView>,
-        input_sampler: Option<&wgpu::Sampler>,
+        optional: Group1Resources,
     ) -> wgpu::BindGroup {
+        let Group1Resources {
+            custom_uniform: custom_uniform_buffer,
+            input_texture,
+            depth_output,
+        } = optional;
         // Create a storage view for the compute shader
         let storage_view = output_texture
             .texture
@@ -520,14 +1006,14 @@ impl ComputeShader {
         // Add input texture and sampler if present (for shaders like FFT): again, this still not "perfect" and generic but let me think more
         if config.has_input_texture {
             // Input textures should always be provided - if not, there's an architecture issue
-            if let (Some(view), Some(sampler)) = (input_texture_view, input_sampler) {
+            if let Some(input) = input_texture {
                 entries.push(wgpu::BindGroupEntry {
                     binding: 2, // Input texture goes to binding 2
-                    resource: wgpu::BindingResource::TextureView(view),
+                    resource: wgpu::BindingResource::TextureView(&input.view),
                 });
                 entries.push(wgpu::BindGroupEntry {
                     binding: 3, // Input sampler goes to binding 3
-                    resource: wgpu::BindingResource::Sampler(sampler),
+                    resource: wgpu::BindingResource::Sampler(&input.sampler),
                 });
             } else {
                 // This indicates an architecture problem - input texture support needs placeholder handling
@@ -535,6 +1021,13 @@ impl ComputeShader {
             }
         }
 
+        if let Some(depth) = depth_output {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(&depth.view),
+            });
+        }
+
         device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout,
             entries: &entries,
@@ -546,6 +1039,7 @@ impl ComputeShader {
         core: &Core,
         layouts: &HashMap<u32, wgpu::BindGroupLayout>,
         config: &ComputeConfiguration,
+        blue_noise: &Option<(wgpu::TextureView, wgpu::Sampler)>,
     ) -> (
         Option<FontSystem>,
         Option<wgpu::Buffer>,
@@ -563,7 +1057,10 @@ impl ComputeShader {
 
         // Create font system if needed
         let font_system = if config.has_fonts {
-            Some(FontSystem::new(core))
+            Some(match config.sdf_font_spread {
+                Some(spread) => FontSystem::new_sdf(core, spread),
+                None => FontSystem::new(core),
+            })
         } else {
             None
         };
@@ -575,7 +1072,9 @@ impl ComputeShader {
             Some(core.device.create_buffer(&wgpu::BufferDescriptor {
                 label: Some("Atomic Storage Buffer"),
                 size: buffer_size,
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
                 mapped_at_creation: false,
             }))
         } else {
@@ -669,6 +1168,8 @@ impl ComputeShader {
             &mouse_uniform,
             &empty_channels,
             num_channels,
+            blue_noise,
+            config.default_channel_color,
         );
 
         (
@@ -693,6 +1194,8 @@ impl ComputeShader {
         mouse_uniform: &Option<UniformBinding<crate::MouseUniform>>,
         channel_textures: &HashMap<u32, Option<(wgpu::TextureView, wgpu::Sampler)>>,
         num_channels: u32,
+        blue_noise: &Option<(wgpu::TextureView, wgpu::Sampler)>,
+        default_channel_color: wgpu::Color,
     ) -> Option<wgpu::BindGroup> {
         // Create entries based on expected layout from ResourceLayout
         // Order must match ResourceLayout creation order:
@@ -702,8 +1205,9 @@ impl ComputeShader {
         // 4. audio_spectrum (if has_audio_spectrum) -> binding N+1
         // 5. atomic_buffer (if has_atomic_buffer) -> binding N+2
         // 6. channels (if num_channels > 0) -> bindings N+3 onwards (texture + sampler pairs)
+        // 7. blue noise (if has_blue_noise) -> texture + sampler after the channels
 
-        // Create a default 1x1 magenta texture for unassigned channels
+        // Create a default 1x1 texture for unassigned channels
         let default_texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Default Channel Texture"),
             size: wgpu::Extent3d {
@@ -714,13 +1218,15 @@ impl ComputeShader {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
+            // sRGB like the placeholder input and `TextureManager::new` images
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         });
 
-        // Fill with magenta color so we can see when default texture is used
-        let magenta_data: [u8; 4] = [255, 0, 255, 255];
+        // Magenta unless configured otherwise, so we can see when default texture is used
+        let wgpu::Color { r, g, b, a } = default_channel_color;
+        let color_data = thumbnail_pixel([r as f32, g as f32, b as f32, a as f32], true).0;
         queue.write_texture(
             wgpu::TexelCopyTextureInfo {
                 texture: &default_texture,
@@ -728,7 +1234,7 @@ impl ComputeShader {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &magenta_data,
+            &color_data,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
                 bytes_per_row: Some(4),
@@ -823,6 +1329,20 @@ impl ComputeShader {
             binding_counter += 1;
         }
 
+        // Add blue noise texture and sampler
+        if let Some((view, sampler)) = blue_noise {
+            entries.extend_from_slice(&[
+                wgpu::BindGroupEntry {
+                    binding: binding_counter,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: binding_counter + 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ]);
+        }
+
         if entries.is_empty() {
             return None;
         }
@@ -849,34 +1369,12 @@ impl ComputeShader {
         }
         let layout = layout.unwrap();
 
-        // Create storage buffers and entries in one pass
-        let mut storage_buffers = Vec::new();
-        let mut entries = Vec::new();
-
-        for buffer_spec in config.storage_buffers.iter() {
-            let buffer = core.device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some(&buffer_spec.name),
-                size: buffer_spec.size_bytes,
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
-
-            storage_buffers.push(buffer);
-        }
-
-        // Create entries using references to stored buffers
-        for (i, buffer) in storage_buffers.iter().enumerate() {
-            entries.push(wgpu::BindGroupEntry {
-                binding: i as u32,
-                resource: buffer.as_entire_binding(),
-            });
-        }
-
-        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        let (storage_buffers, bind_group) = create_storage_buffers(
+            &core.device,
             layout,
-            entries: &entries,
-            label: Some("Group 3 Bind Group"),
-        });
+            &config.storage_buffers,
+            "Group 3 Bind Group",
+        );
 
         (storage_buffers, Some(bind_group))
     }
@@ -954,16 +1452,32 @@ impl ComputeShader {
         self.dispatch_stage_with_workgroups(encoder, stage_index, workgroup_count);
     }
 
+    /// Dispatch one screen-sized frame: `core.size` divided by the workgroup size, Z = 1
     pub fn dispatch(&mut self, encoder: &mut wgpu::CommandEncoder, core: &Core) {
-        if self.dispatch_once && self.current_frame > 0 {
-            return;
-        }
-
         let workgroup_count = [
             core.size.width.div_ceil(self.workgroup_size[0]),
             core.size.height.div_ceil(self.workgroup_size[1]),
             1,
         ];
+        self.dispatch_with_count(encoder, core, workgroup_count);
+    }
+
+    /// Same as `dispatch` (single/multi-pass, `dispatch_once`, frame counter) but with an
+    /// explicit workgroup count, for 1D reductions, 3D grids and other non-image workloads.
+    /// Every pass uses the same count.
+    pub fn dispatch_with_count(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        core: &Core,
+        workgroup_count: [u32; 3],
+    ) {
+        if self.dispatch_once && self.current_frame > 0 {
+            return;
+        }
+
+        if self.current_frame == 0 {
+            self.dispatch_init_pass(encoder, core, workgroup_count);
+        }
 
         // Handle multi-pass execution
         if self.multipass_manager.is_some() {
@@ -975,20 +1489,130 @@ impl ComputeShader {
         self.current_frame += 1;
     }
 
-    /// Flip ping-pong buffers for multi-pass rendering (call after render)
-    pub fn flip_buffers(&mut self) {
-        if let Some(ref mut multipass) = self.multipass_manager {
-            multipass.flip_buffers();
-        }
-    }
+    /// Init pass from `with_init_pass`, recorded before the regular passes of frame 0.
+    ///
+    /// It writes the main output (the regular group 1), not a ping-pong buffer. Multi-pass
+    /// shaders get the inputs of their first pass as group 3.
+    fn dispatch_init_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        core: &Core,
+        workgroup_count: [u32; 3],
+    ) {
+        let (Some(pipeline), Some(entry_point)) = (&self.init_pipeline, &self.init_entry_point)
+        else {
+            return;
+        };
 
-    /// Update custom uniform parameters
-    pub fn set_custom_params<T: bytemuck::Pod>(&self, params: T, queue: &wgpu::Queue) {
-        if let Some(ref buffer) = self.custom_uniform {
-            queue.write_buffer(buffer, 0, bytemuck::bytes_of(&params));
-        } else {
-            log::warn!("Attempted to set custom params but no custom uniform buffer exists");
-        }
+        let multipass_inputs = match (&self.multipass_manager, &self.pass_dependencies) {
+            (Some(multipass), Some(dependencies)) => {
+                let sampler = core
+                    .device
+                    .create_sampler(&wgpu::SamplerDescriptor::default());
+                let empty_deps = Vec::new();
+                let first_pass_inputs = self
+                    .entry_points
+                    .first()
+                    .and_then(|first| dependencies.get(first))
+                    .unwrap_or(&empty_deps);
+                Some(multipass.create_input_bind_group(&core.device, &sampler, first_pass_inputs))
+            }
+            _ => None,
+        };
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(&format!("{} Init Pass - {}", self.label, entry_point)),
+            timestamp_writes: None,
+        });
+
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, &self.group0_bind_group, &[]);
+        compute_pass.set_bind_group(1, &self.group1_bind_group, &[]);
+
+        if let Some(ref group2) = self.group2_bind_group {
+            compute_pass.set_bind_group(2, group2, &[]);
+        } else if let Some(empty_group2) = self.empty_bind_groups.get(&2) {
+            compute_pass.set_bind_group(2, empty_group2, &[]);
+        }
+
+        if let Some(ref group3) = self.group3_bind_group {
+            compute_pass.set_bind_group(3, group3, &[]);
+        } else if let Some(ref inputs) = multipass_inputs {
+            compute_pass.set_bind_group(3, inputs, &[]);
+        } else if let Some(empty_group3) = self.empty_bind_groups.get(&3) {
+            compute_pass.set_bind_group(3, empty_group3, &[]);
+        }
+
+        compute_pass.dispatch_workgroups(
+            workgroup_count[0],
+            workgroup_count[1],
+            workgroup_count[2],
+        );
+    }
+
+    /// Run every pipeline once with a single workgroup while loading, so drivers that
+    /// finish compiling on first use do it now instead of on the first visible frame, and
+    /// save the pipeline cache (`Core::save_pipeline_cache`).
+    ///
+    /// The throwaway dispatch writes real data, so it's undone afterwards: multi-pass and
+    /// atomic buffers are cleared, user storage buffers zeroed and the frame counter reset.
+    /// Call it right after `from_builder`, before uploading initial buffer contents. After a
+    /// hot reload (`check_hot_reload` returning true) it can be called again when starting
+    /// from a clean state is acceptable.
+    pub fn prewarm(&mut self, core: &Core) {
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some(&format!("{} Prewarm Encoder", self.label)),
+            });
+        let dispatch_once = std::mem::replace(&mut self.dispatch_once, false);
+        self.dispatch_with_count(&mut encoder, core, [1, 1, 1]);
+        self.dispatch_once = dispatch_once;
+        for buffer in &self.storage_buffers {
+            encoder.clear_buffer(buffer, 0, None);
+        }
+        core.queue.submit(Some(encoder.finish()));
+        self.clear_all_buffers(core);
+
+        if let Err(e) = core.save_pipeline_cache() {
+            log::warn!("Failed to save the pipeline cache: {e}");
+        }
+    }
+
+    /// Flip ping-pong buffers for multi-pass rendering (call after render)
+    pub fn flip_buffers(&mut self) {
+        if let Some(ref mut multipass) = self.multipass_manager {
+            multipass.flip_buffers();
+        }
+    }
+
+    /// Update custom uniform parameters
+    pub fn set_custom_params<T: bytemuck::Pod>(&self, params: T, queue: &wgpu::Queue) {
+        if let Some(ref buffer) = self.custom_uniform {
+            queue.write_buffer(buffer, 0, bytemuck::bytes_of(&params));
+        } else {
+            log::warn!("Attempted to set custom params but no custom uniform buffer exists");
+        }
+    }
+
+    /// Update custom uniform parameters from raw bytes. Returns false and writes nothing
+    /// unless `bytes` is exactly `custom_uniform_size` long.
+    pub fn set_custom_params_bytes(&self, bytes: &[u8], queue: &wgpu::Queue) -> bool {
+        let Some(ref buffer) = self.custom_uniform else {
+            log::warn!("Attempted to set custom params but no custom uniform buffer exists");
+            return false;
+        };
+        if self.custom_uniform_size != Some(bytes.len() as u64) {
+            log::error!(
+                "{}: custom params are {} bytes, the uniform is {:?}",
+                self.label,
+                bytes.len(),
+                self.custom_uniform_size
+            );
+            return false;
+        }
+        queue.write_buffer(buffer, 0, bytes);
+        true
     }
 
     /// Get the custom uniform buffer size (if any)
@@ -1047,12 +1671,175 @@ impl ComputeShader {
             binding: 3,
             resource: wgpu::BindingResource::Sampler(sampler),
         });
+        if let Some(depth) = &self.depth_output {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(&depth.view),
+            });
+        }
 
         self.group1_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: group1_layout,
             entries: &entries,
             label: Some("Updated Group 1 Bind Group with Input Texture"),
         });
+        self.current_input = Some((texture_view.clone(), sampler.clone()));
+    }
+
+    /// Group 1 bind group writing to `output_view` instead of the output texture
+    fn create_group1_bind_group_for_view(
+        &self,
+        device: &wgpu::Device,
+        output_view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        let mut entries = vec![wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::TextureView(output_view),
+        }];
+        if let Some(buffer) = &self.custom_uniform {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 1,
+                resource: buffer.as_entire_binding(),
+            });
+        }
+        if self.has_input_texture {
+            if let Some((view, sampler)) = &self.current_input {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(view),
+                });
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                });
+            }
+        }
+        if let Some(depth) = &self.depth_output {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(&depth.view),
+            });
+        }
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: self.bind_group_layouts.get(&1).unwrap(),
+            entries: &entries,
+            label: Some("Group 1 Direct Present Bind Group"),
+        })
+    }
+
+    /// Render a small preview image at `time`, e.g. for a shader browser.
+    ///
+    /// Single-pass shaders are dispatched once at `size` into temporary output (and depth)
+    /// textures, so the live output is untouched and the frame counter and time uniform
+    /// are restored afterwards (a shared time uniform is left alone, `time` is ignored). Storage and atomic buffers the shader writes do see that
+    /// dispatch. Multi-pass shaders can't run at another size than their pass buffers and
+    /// `dispatch_once` shaders don't run again, so their thumbnail is the current output
+    /// scaled down and `time` is ignored.
+    ///
+    /// A single dispatch can't show what builds up over many frames (accumulation,
+    /// feedback, simulations), such shaders may give incomplete thumbnails. Float outputs
+    /// are sRGB-encoded like on screen, 8-bit outputs are taken as stored.
+    pub fn render_thumbnail(
+        &mut self,
+        core: &Core,
+        size: winit::dpi::PhysicalSize<u32>,
+        time: f32,
+    ) -> Result<image::RgbaImage, super::ReadbackError> {
+        let (width, height) = (size.width.max(1), size.height.max(1));
+        if self.multipass_manager.is_some() || self.dispatch_once {
+            let live = &self.output_texture.texture;
+            let texels = super::readback::read_texture(&core.device, &core.queue, live)?;
+            let (live_width, live_height) = (live.width(), live.height());
+            let srgb = !is_8bit_unorm(self.output_format);
+            return Ok(image::RgbaImage::from_fn(width, height, |x, y| {
+                // Nearest texel, plenty for a preview
+                let sx = (x as u64 * live_width as u64 / width as u64) as u32;
+                let sy = (y as u64 * live_height as u64 / height as u64) as u32;
+                thumbnail_pixel(texels[(sy * live_width + sx) as usize], srgb)
+            }));
+        }
+
+        let live_frame = self.current_frame;
+        let live_time = self.time_uniform.data;
+        let thumb_output = Self::create_output_texture(
+            &core.device,
+            width,
+            height,
+            self.output_format,
+            &format!("{} Thumbnail Output", self.label),
+        );
+        let thumb_depth = self
+            .depth_output
+            .as_ref()
+            .map(|_| Self::create_depth_output(&core.device, width, height, &self.label));
+        let live_depth = std::mem::replace(&mut self.depth_output, thumb_depth);
+        let group1 = self.create_group1_bind_group_for_view(&core.device, &thumb_output.view);
+        let live_group1 = std::mem::replace(&mut self.group1_bind_group, group1);
+
+        if self.owns_time() {
+            self.write_time(time, 1.0 / 60.0, live_frame, &core.queue);
+        }
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some(&format!("{} Thumbnail Encoder", self.label)),
+            });
+        self.dispatch_with_count(
+            &mut encoder,
+            core,
+            [
+                width.div_ceil(self.workgroup_size[0]),
+                height.div_ceil(self.workgroup_size[1]),
+                1,
+            ],
+        );
+        core.queue.submit(Some(encoder.finish()));
+
+        self.group1_bind_group = live_group1;
+        self.depth_output = live_depth;
+        self.current_frame = live_frame;
+        if self.owns_time() {
+            self.time_uniform.data = live_time;
+            self.time_uniform.update(&core.queue);
+        }
+
+        let texels =
+            super::readback::read_texture(&core.device, &core.queue, &thumb_output.texture)?;
+        let srgb = !is_8bit_unorm(self.output_format);
+        Ok(image::RgbaImage::from_fn(width, height, |x, y| {
+            thumbnail_pixel(texels[(y * width + x) as usize], srgb)
+        }))
+    }
+
+    /// Dispatch straight into the swapchain texture (see `ComputeShaderBuilder::direct_present`).
+    /// Returns false without dispatching when direct present isn't active, in that case
+    /// use `dispatch` and blit the output texture as usual:
+    ///
+    /// ```ignore
+    /// if !shader.dispatch_to_surface(&mut encoder, core, &frame.texture) {
+    ///     shader.dispatch(&mut encoder, core);
+    ///     // ... render pass drawing shader.output_texture
+    /// }
+    /// ```
+    pub fn dispatch_to_surface(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        core: &Core,
+        surface_texture: &wgpu::Texture,
+    ) -> bool {
+        if !self.direct_present {
+            return false;
+        }
+        let view = surface_texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Direct Present Storage View"),
+            format: Some(self.output_format),
+            ..Default::default()
+        });
+        let direct = self.create_group1_bind_group_for_view(&core.device, &view);
+        let regular = std::mem::replace(&mut self.group1_bind_group, direct);
+        self.dispatch(encoder, core);
+        self.group1_bind_group = regular;
+        true
     }
 
     /// Update a specific channel texture (channel0, channel1, etc.)
@@ -1076,11 +1863,196 @@ impl ComputeShader {
         // Store the channel texture
         self.channel_textures
             .insert(channel_index, Some((texture_view.clone(), sampler.clone())));
+        self.channel_configs.remove(&channel_index);
+        self.channel_memory.remove(&channel_index);
 
         // Recreate Group 2 bind group with updated channel
         self.recreate_group2_bind_group(device, queue);
     }
 
+    /// Update a channel from a `TextureManager`, creating the view and sampler from `config`.
+    /// The view is reinterpreted only into formats listed in `texture.view_formats`, so
+    /// `TextureManager::new` images can be sampled as linear; other textures (normal maps,
+    /// KTX2, R8 data) are viewed in their own format and `config.srgb` is ignored.
+    pub fn update_channel_texture_with_config(
+        &mut self,
+        channel_index: u32,
+        texture: &TextureManager,
+        config: ChannelConfig,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        let format = texture.texture.format();
+        let mut config = config;
+        let view_format = config.view_format(format);
+        let view = if view_format == format {
+            texture.view.clone()
+        } else if texture.view_formats.contains(&view_format) {
+            texture.texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Channel View"),
+                format: Some(view_format),
+                ..Default::default()
+            })
+        } else {
+            // Viewing as a format the texture wasn't created with is a validation error
+            log::debug!("Channel {channel_index}: viewing {format:?} as-is, sRGB setting ignored");
+            config = config.with_srgb(format.is_srgb());
+            texture.view.clone()
+        };
+        let sampler = if config == ChannelConfig::default() {
+            texture.sampler.clone()
+        } else {
+            config.create_sampler(device)
+        };
+
+        self.update_channel_texture(channel_index, &view, &sampler, device, queue);
+        if channel_index < self.num_channels {
+            self.channel_configs.insert(channel_index, config);
+            self.channel_memory
+                .insert(channel_index, texture.memory_bytes());
+        }
+    }
+
+    /// Load an image file into a channel, choosing the texture format from `config.srgb`:
+    ///
+    /// - `srgb: true` (color, the default): `Rgba8UnormSrgb` like `TextureManager::new`, so
+    ///   the shader samples linear values exactly as on the main texture path
+    /// - `srgb: false` (data: heightmaps, normal/flow maps, LUTs): `TextureManager::from_image`
+    ///   without conversion, so 16-bit and grayscale images keep their precision
+    ///   (`Rgba16Unorm`/`R16Unorm`/`R8Unorm`) and 8-bit RGBA is viewed as `Rgba8Unorm`
+    pub fn load_channel_image(
+        &mut self,
+        core: &Core,
+        channel_index: u32,
+        path: impl AsRef<std::path::Path>,
+        config: ChannelConfig,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Result<(), image::ImageError> {
+        let image = image::open(path)?;
+        let texture = if config.srgb {
+            TextureManager::new(&core.device, &core.queue, &image.to_rgba8(), layout)
+        } else {
+            TextureManager::from_image(&core.device, &core.queue, &image, layout, false)
+        };
+        self.update_channel_texture_with_config(
+            channel_index,
+            &texture,
+            config,
+            &core.device,
+            &core.queue,
+        );
+        Ok(())
+    }
+
+    /// Bind a texture made elsewhere (another library, a render target) to a channel. The
+    /// view and sampler are created here from `config`, and the channel keeps the texture
+    /// alive and bound across resizes until the channel is replaced.
+    ///
+    /// The texture needs `TEXTURE_BINDING` usage. It's viewed in its own format, since the
+    /// view formats it was created with aren't known, so `config.srgb` only follows the
+    /// texture's format. Use `update_channel_texture` with a custom view to reinterpret it.
+    pub fn set_channel_from_texture(
+        &mut self,
+        channel_index: u32,
+        texture: &wgpu::Texture,
+        config: ChannelConfig,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        if !texture
+            .usage()
+            .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+        {
+            log::warn!(
+                "Channel {channel_index}: texture lacks TEXTURE_BINDING usage, not binding it"
+            );
+            return;
+        }
+        let format = texture.format();
+        if config.view_format(format) != format {
+            log::debug!("Channel {channel_index}: viewing {format:?} as-is, sRGB setting ignored");
+        }
+        let config = config.with_srgb(format.is_srgb());
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Channel View"),
+            ..Default::default()
+        });
+        let sampler = config.create_sampler(device);
+
+        self.update_channel_texture(channel_index, &view, &sampler, device, queue);
+        if channel_index < self.num_channels {
+            self.channel_configs.insert(channel_index, config);
+            self.channel_memory
+                .insert(channel_index, crate::texture_memory_bytes(texture));
+        }
+    }
+
+    /// Views currently bound to each channel, `None` for channels still on the placeholder
+    pub fn channel_views(&self) -> Vec<(u32, Option<&wgpu::TextureView>)> {
+        (0..self.num_channels)
+            .map(|i| {
+                let view = self
+                    .channel_textures
+                    .get(&i)
+                    .and_then(|c| c.as_ref())
+                    .map(|(view, _)| view);
+                (i, view)
+            })
+            .collect()
+    }
+
+    /// Rough VRAM footprint of everything this shader owns or binds: output texture,
+    /// multi-pass buffers (double-buffered, see `MultiPassManager::memory_bytes`), atomic,
+    /// audio and storage buffers, uniforms, the font atlas and channel textures.
+    ///
+    /// Computed from descriptors, not reported by the driver. Channels bound with a raw
+    /// view (`update_channel_texture`) aren't counted since their texture size is unknown.
+    pub fn estimated_memory_bytes(&self) -> u64 {
+        let mut total = self.output_texture.memory_bytes();
+        total += self.time_uniform.buffer.size();
+
+        if let Some(manager) = &self.multipass_manager {
+            total += manager.memory_bytes();
+        }
+        if let Some(placeholder) = &self.placeholder_input_texture {
+            total += placeholder.memory_bytes();
+        }
+        if let Some(depth) = &self.depth_output {
+            total += depth.memory_bytes();
+        }
+        if let Some(font_system) = &self.font_system {
+            total += font_system.atlas_texture.memory_bytes();
+            total += font_system.font_uniforms.buffer.size();
+        }
+        if let Some(mouse) = &self.mouse_uniform {
+            total += mouse.buffer.size();
+        }
+
+        let buffers = [
+            &self.custom_uniform,
+            &self.atomic_buffer_raw,
+            &self.audio_buffer,
+            &self.audio_staging_buffer,
+            &self.audio_spectrum_buffer,
+        ];
+        total += buffers
+            .iter()
+            .filter_map(|b| b.as_ref())
+            .map(|b| b.size())
+            .sum::<u64>();
+        total += self.storage_buffers.iter().map(|b| b.size()).sum::<u64>();
+        total += self.channel_memory.values().sum::<u64>();
+        total
+    }
+
+    /// Sampling config of a channel, default if it was set with a raw view/sampler
+    pub fn channel_config(&self, channel_index: u32) -> ChannelConfig {
+        self.channel_configs
+            .get(&channel_index)
+            .copied()
+            .unwrap_or_default()
+    }
+
     fn initialize_channel_textures(
         num_channels: u32,
     ) -> HashMap<u32, Option<(wgpu::TextureView, wgpu::Sampler)>> {
@@ -1104,6 +2076,8 @@ impl ComputeShader {
                 &self.mouse_uniform,
                 &self.channel_textures,
                 self.num_channels,
+                &self.blue_noise,
+                self.config.default_channel_color,
             );
         }
     }
@@ -1115,6 +2089,9 @@ impl ComputeShader {
         workgroup_count: [u32; 3],
     ) {
         for (i, pipeline) in self.pipelines.iter().enumerate() {
+            if !self.is_pass_enabled(&self.entry_points[i]) {
+                continue;
+            }
             // Get workgroup count for this specific pass
             let pass_workgroup_count = if let Some(ref pass_descriptions) = self.pass_descriptions {
                 if let Some(pass_desc) = pass_descriptions.get(i) {
@@ -1174,6 +2151,15 @@ impl ComputeShader {
     ) {
         let num_passes = self.pipelines.len();
 
+        // Non-persistent passes start every frame from zero
+        if let (Some(multipass), Some(pass_descriptions)) =
+            (&self.multipass_manager, &self.pass_descriptions)
+        {
+            for pass in pass_descriptions.iter().filter(|p| !p.persistent) {
+                multipass.clear_buffer(encoder, pass.output_buffer());
+            }
+        }
+
         let sampler = core
             .device
             .create_sampler(&wgpu::SamplerDescriptor::default());
@@ -1183,6 +2169,16 @@ impl ComputeShader {
             let pipeline = &self.pipelines[pass_idx];
             let entry_point = &self.entry_points[pass_idx];
 
+            // Disabled passes still flip so later passes read the same buffers as usual
+            if !self.is_pass_enabled(entry_point) {
+                if pass_idx < num_passes - 1 {
+                    if let Some(ref mut multipass_mut) = self.multipass_manager {
+                        multipass_mut.flip_buffers();
+                    }
+                }
+                continue;
+            }
+
             // Get workgroup count for this specific pass
             let pass_workgroup_count = if let Some(ref pass_descriptions) = self.pass_descriptions {
                 if let Some(pass_desc) = pass_descriptions.get(pass_idx) {
@@ -1223,33 +2219,19 @@ impl ComputeShader {
                 // main_image writes to the final output texture - use main Group 1
                 compute_pass.set_bind_group(1, &self.group1_bind_group, &[]);
             } else {
-                // Intermediate passes write to their ping-pong buffers
-                // Create a Group 1 compatible bind group with both storage texture and custom uniform
+                // Intermediate passes write to their ping-pong buffers, through a bind
+                // group with the rest of group 1 (custom uniform, input, depth) unchanged
                 if let Some(multipass) = &self.multipass_manager {
-                    let write_texture = multipass.get_write_texture(entry_point);
+                    let output = self
+                        .pass_descriptions
+                        .as_ref()
+                        .and_then(|passes| passes.get(pass_idx))
+                        .map_or(entry_point.as_str(), |pass| pass.output_buffer());
+                    let write_texture = multipass.get_write_texture(output);
                     let write_view =
                         write_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-                    let mut entries = vec![wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&write_view),
-                    }];
-
-                    // Add custom uniform if present (to match Group 1 layout)
-                    if let Some(ref uniform_buffer) = self.custom_uniform {
-                        entries.push(wgpu::BindGroupEntry {
-                            binding: 1,
-                            resource: uniform_buffer.as_entire_binding(),
-                        });
-                    }
-
-                    let group1_layout = self.bind_group_layouts.get(&1).unwrap();
                     let intermediate_bind_group =
-                        core.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                            label: Some(&format!("{entry_point} Intermediate Group 1")),
-                            layout: group1_layout,
-                            entries: &entries,
-                        });
+                        self.create_group1_bind_group_for_view(&core.device, &write_view);
 
                     compute_pass.set_bind_group(1, &intermediate_bind_group, &[]);
                 } else {
@@ -1289,99 +2271,345 @@ impl ComputeShader {
         }
     }
 
+    /// Zero the buffers of a single pass, e.g. to restart a feedback pass
+    pub fn clear_pass(&self, name: &str, core: &Core) {
+        let Some(multipass) = &self.multipass_manager else {
+            log::warn!("clear_pass({name}): shader has no multi-pass buffers");
+            return;
+        };
+        if !multipass.has_buffer(name) {
+            log::warn!("clear_pass: no pass named {name}");
+            return;
+        }
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Clear Pass Encoder"),
+            });
+        multipass.clear_buffer(&mut encoder, name);
+        core.queue.submit(Some(encoder.finish()));
+    }
+
     /// Enable hot reload for the shader
     pub fn enable_hot_reload(
         &mut self,
         device: Arc<wgpu::Device>,
         shader_path: PathBuf,
         shader_module: wgpu::ShaderModule,
-    ) -> Result<(), notify::Error> {
+    ) -> Result<(), HotReloadError> {
         let entry_point = self
             .entry_points
             .first()
             .cloned()
             .unwrap_or_else(|| "main".to_string());
-        let hot_reload =
-            ShaderHotReload::new_compute(device, shader_path, shader_module, &entry_point)?;
+        let suffix = if self.has_stdlib {
+            super::STDLIB_WGSL
+        } else {
+            ""
+        };
+        let output_format = self.config.texture_format;
+        let hot_reload = ShaderHotReload::new_compute_with_preprocessor(
+            device,
+            shader_path,
+            shader_module,
+            &entry_point,
+            suffix,
+            Some(Box::new(move |source| {
+                substitute_output_format(source, output_format)
+            })),
+        )?;
 
         self.hot_reload = Some(hot_reload);
         Ok(())
     }
 
-    /// Check for hot reload updates
+    /// Check for hot reload updates. Only the code is swapped, the pipeline layout is kept,
+    /// so edits to bindings or uniform sizes need `check_hot_reload_full`.
     pub fn check_hot_reload(&mut self, device: &wgpu::Device) -> bool {
-        if let Some(hot_reload) = &mut self.hot_reload {
-            if let Some(new_module) = hot_reload.reload_compute_shader() {
-                // Recreate pipelines with updated shader
-                let mut new_pipelines = Vec::new();
-                for entry_point in &self.entry_points {
-                    let new_pipeline =
-                        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                            label: Some(&format!(
-                                "Updated {} Pipeline - {}",
-                                self.label, entry_point
-                            )),
-                            layout: Some(&self.pipeline_layout),
-                            module: new_module,
-                            entry_point: Some(entry_point),
-                            compilation_options: wgpu::PipelineCompilationOptions::default(),
-                            cache: None,
-                        });
-                    new_pipelines.push(new_pipeline);
-                }
+        let Some(new_module) = self
+            .hot_reload
+            .as_mut()
+            .and_then(|hot_reload| hot_reload.reload_compute_shader())
+            .cloned()
+        else {
+            return false;
+        };
+        self.recreate_pipelines(device, &new_module);
+        info!(
+            "{} shader hot-reloaded at frame: {}",
+            self.label, self.current_frame
+        );
+        true
+    }
 
-                self.pipelines = new_pipelines;
+    /// Like `check_hot_reload`, but when the edit changes the declared bindings (a binding
+    /// added, removed or retyped, or a uniform/storage struct resized) the whole shader is
+    /// rebuilt with `rebuild` instead of only swapping pipelines.
+    ///
+    /// The new configuration is the current one with sizes taken from the source: the
+    /// `@group(1) @binding(1)` uniform sets `custom_uniform_size`, and `@group(3)` storage
+    /// buffers set the storage buffer sizes (new bindings are appended under their WGSL name,
+    /// runtime-sized arrays keep their previous size). Engine resources (mouse, fonts, audio,
+    /// channels...) aren't inferred; a new `@group(2)` binding still needs the builder flag
+    /// and a restart. After resizing the params struct in WGSL, update the Rust struct too,
+    /// `set_custom_params` writes its size in bytes.
+    pub fn check_hot_reload_full(&mut self, core: &Core) -> bool {
+        let Some(hot_reload) = self.hot_reload.as_mut() else {
+            return false;
+        };
+        let Some(new_module) = hot_reload.reload_compute_shader().cloned() else {
+            return false;
+        };
+        let source = hot_reload.compute_source().unwrap_or_default().to_string();
+
+        let bindings = reflect_bindings(&Self::prepare_source(&source, &self.config));
+        match bindings {
+            Ok(bindings) if layout_changed(&self.binding_signature, &bindings) => {
+                let config = self.config_for_bindings(&bindings);
+                if !self.rebuild(core, &source, config) {
+                    return false;
+                }
+                info!(
+                    "{} shader rebuilt after a layout change at frame: {}",
+                    self.label, self.current_frame
+                );
+            }
+            _ => {
+                self.recreate_pipelines(&core.device, &new_module);
                 info!(
                     "{} shader hot-reloaded at frame: {}",
                     self.label, self.current_frame
                 );
-                return true;
             }
         }
-        false
+        true
     }
 
-    /// Set time uniform data
-    pub fn set_time(&mut self, elapsed: f32, delta: f32, queue: &wgpu::Queue) {
-        self.time_uniform.data.time = elapsed;
-        self.time_uniform.data.delta = delta;
-        self.time_uniform.data.frame = self.current_frame;
-        self.time_uniform.update(queue);
+    fn recreate_pipelines(&mut self, device: &wgpu::Device, new_module: &wgpu::ShaderModule) {
+        let mut new_pipelines = Vec::new();
+        for entry_point in &self.entry_points {
+            let new_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(&format!(
+                    "Updated {} Pipeline - {}",
+                    self.label, entry_point
+                )),
+                layout: Some(&self.pipeline_layout),
+                module: new_module,
+                entry_point: Some(entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: self.pipeline_cache.as_ref(),
+            });
+            new_pipelines.push(new_pipeline);
+        }
+
+        self.pipelines = new_pipelines;
+        if let Some(entry_point) = &self.init_entry_point {
+            self.init_pipeline = Some(device.create_compute_pipeline(
+                &wgpu::ComputePipelineDescriptor {
+                    label: Some(&format!(
+                        "Updated {} Init Pipeline - {}",
+                        self.label, entry_point
+                    )),
+                    layout: Some(&self.pipeline_layout),
+                    module: new_module,
+                    entry_point: Some(entry_point),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    cache: self.pipeline_cache.as_ref(),
+                },
+            ));
+        }
     }
 
-    /// Update audio spectrum buffer with data from ResolutionUniform
-    pub fn update_audio_spectrum(
-        &mut self,
-        resolution_uniform: &crate::ResolutionUniform,
-        queue: &wgpu::Queue,
-    ) {
-        if let Some(ref buffer) = self.audio_spectrum_buffer {
-            // Convert audio_data from [[f32; 4]; 32] to [f32; 65] format
-            // 64 spectrum values + 1 BPM value at the end
-            let mut spectrum_data = vec![0.0f32; 65];
-            for i in 0..64 {
-                let vec_idx = i / 4;
-                let comp_idx = i % 4;
-                if vec_idx < 32 {
-                    spectrum_data[i] = resolution_uniform.audio_data[vec_idx][comp_idx];
-                }
-            }
+    /// Current configuration with buffer sizes updated from reflected bindings
+    fn config_for_bindings(&self, bindings: &[BindingSignature]) -> ComputeConfiguration {
+        let mut config = self.config.clone();
 
-            // Add BPM at index 64
+        if let Some(size) = bindings
+            .iter()
+            .find(|b| b.group == 1 && b.binding == 1 && b.is_uniform())
+            .and_then(|b| b.size)
+        {
+            config.custom_uniform_size = Some(size);
+        }
+
+        // In pure multi-pass mode group 3 holds the pass inputs, not storage buffers
+        if config.passes.is_none() || !config.storage_buffers.is_empty() {
+            let storage = bindings.iter().filter(|b| b.group == 3 && b.is_storage());
+            for binding in storage {
+                let index = binding.binding as usize;
+                let len = config.storage_buffers.len();
+                match (config.storage_buffers.get_mut(index), binding.size) {
+                    (Some(spec), Some(size)) => spec.size_bytes = size,
+                    (Some(_), None) => {}
+                    (None, Some(size)) if index == len => {
+                        config
+                            .storage_buffers
+                            .push(StorageBufferSpec::new(&binding.name, size));
+                    }
+                    (None, _) => log::warn!(
+                        "{}: can't size new storage buffer '{}' at @group(3) @binding({index}), declare it with a fixed size right after the existing ones",
+                        self.label,
+                        binding.name
+                    ),
+                }
+            }
+        }
+
+        config
+    }
+
+    /// Rebuild everything from `shader_source` (as passed to `from_builder`) and `config`,
+    /// keeping the state a live edit shouldn't lose:
+    ///
+    /// - GPU contents of the custom uniform, atomic buffer, storage buffers (matched by
+    ///   index) and multi-pass buffers (matched by name) are copied when the old and new
+    ///   sizes are equal, otherwise the new resource starts zeroed
+    /// - frame counter, random seed, hot reload watcher, disabled passes that still exist,
+    ///   bound channel textures and the input texture carry over
+    /// - the output and depth textures keep their size but not their contents
+    ///
+    /// Returns false and keeps the current shader if wgpu rejects the new one.
+    pub fn rebuild(
+        &mut self,
+        core: &Core,
+        shader_source: &str,
+        config: ComputeConfiguration,
+    ) -> bool {
+        core.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let mut rebuilt = Self::from_builder(core, shader_source, config);
+        if let Some(error) = pollster::block_on(core.device.pop_error_scope()) {
+            log::error!(
+                "{}: rebuild failed, keeping the previous shader: {error}",
+                self.label
+            );
+            return false;
+        }
+
+        let size = self.output_texture.texture.size();
+        if rebuilt.output_texture.texture.size() != size {
+            rebuilt.resize(core, size.width, size.height);
+        }
+
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some(&format!("{} Rebuild Encoder", self.label)),
+            });
+        if rebuilt.config.shared_params.is_none() {
+            copy_if_same_size(&mut encoder, &self.custom_uniform, &rebuilt.custom_uniform);
+        }
+        copy_if_same_size(
+            &mut encoder,
+            &self.atomic_buffer_raw,
+            &rebuilt.atomic_buffer_raw,
+        );
+        for (old, new) in self.storage_buffers.iter().zip(&rebuilt.storage_buffers) {
+            if old.size() == new.size() {
+                encoder.copy_buffer_to_buffer(old, 0, new, 0, old.size());
+            }
+        }
+        if let (Some(old), Some(new)) = (&self.multipass_manager, &mut rebuilt.multipass_manager) {
+            new.copy_buffers_from(&mut encoder, old);
+        }
+        core.queue.submit(Some(encoder.finish()));
+
+        rebuilt.hot_reload = self.hot_reload.take();
+        rebuilt.pending_export = self.pending_export.take();
+        rebuilt.current_frame = self.current_frame;
+        rebuilt.random_seed = self.random_seed;
+        rebuilt.disabled_passes = self
+            .disabled_passes
+            .iter()
+            .filter(|pass| rebuilt.entry_points.contains(pass))
+            .cloned()
+            .collect();
+
+        for index in 0..rebuilt.num_channels.min(self.num_channels) {
+            if let Some(binding) = self.channel_textures.get(&index) {
+                rebuilt.channel_textures.insert(index, binding.clone());
+            }
+            if let Some(config) = self.channel_configs.get(&index) {
+                rebuilt.channel_configs.insert(index, *config);
+            }
+            if let Some(memory) = self.channel_memory.get(&index) {
+                rebuilt.channel_memory.insert(index, *memory);
+            }
+        }
+        rebuilt.recreate_group2_bind_group(&core.device, &core.queue);
+        if rebuilt.has_input_texture {
+            if let Some((view, sampler)) = &self.current_input {
+                rebuilt.update_input_texture(view, sampler, &core.device);
+            }
+        }
+
+        *self = rebuilt;
+        true
+    }
+
+    /// Set time uniform data
+    ///
+    /// Does nothing when the time uniform is shared (`with_shared_time`), the
+    /// `SharedUniform` owner updates it.
+    pub fn set_time(&mut self, elapsed: f32, delta: f32, queue: &wgpu::Queue) {
+        if !self.owns_time() {
+            return;
+        }
+        self.write_time(elapsed, delta, self.current_frame, queue);
+    }
+
+    /// False with `with_shared_time`: the buffer belongs to the `SharedUniform` owner and
+    /// `time_uniform.data` is never updated here, so it's neither written nor restored
+    fn owns_time(&self) -> bool {
+        self.config.shared_time.is_none()
+    }
+
+    fn write_time(&mut self, elapsed: f32, delta: f32, frame: u32, queue: &wgpu::Queue) {
+        self.time_uniform.data.time = elapsed;
+        self.time_uniform.data.delta = delta;
+        self.time_uniform.data.frame = frame;
+        // Derived from the frame number, so a fixed seed replays the same sequence
+        let hash = frame_hash(self.random_seed, frame);
+        self.time_uniform.data.random = (hash >> 40) as f32 / (1u64 << 24) as f32;
+        self.time_uniform.data.seed = hash as u32;
+        self.time_uniform.update(queue);
+    }
+
+    /// Update audio spectrum buffer with data from ResolutionUniform
+    pub fn update_audio_spectrum(
+        &mut self,
+        resolution_uniform: &crate::ResolutionUniform,
+        queue: &wgpu::Queue,
+    ) {
+        if let Some(ref buffer) = self.audio_spectrum_buffer {
+            // Convert audio_data from [[f32; 4]; 32] to [f32; 65] format
+            // 64 spectrum values + 1 BPM value at the end
+            let mut spectrum_data = vec![0.0f32; 65];
+            for i in 0..64 {
+                let vec_idx = i / 4;
+                let comp_idx = i % 4;
+                if vec_idx < 32 {
+                    spectrum_data[i] = resolution_uniform.audio_data[vec_idx][comp_idx];
+                }
+            }
+
+            // Add BPM at index 64
             spectrum_data[64] = resolution_uniform.bpm;
 
-            // Debug: to see audio spectrum data flow
-            let total_energy: f32 = spectrum_data[..64].iter().sum();
-            if total_energy > 0.01 {
-                log::info!(
+            // Debug: to see audio spectrum data flow (RUST_LOG=trace), this runs every frame
+            if log::log_enabled!(log::Level::Trace) {
+                let total_energy: f32 = spectrum_data[..64].iter().sum();
+                log::trace!(
                     "Audio spectrum: energy={:.3}, BPM={:.1}",
                     total_energy,
                     spectrum_data[64]
                 );
             }
 
-            // Write the spectrum data to the buffer (including BPM)
-            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&spectrum_data));
+            // Write the spectrum data to the buffer (including BPM), truncated for small buffers
+            let len = spectrum_data
+                .len()
+                .min(buffer.size() as usize / std::mem::size_of::<f32>());
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&spectrum_data[..len]));
         }
     }
 
@@ -1392,43 +2620,41 @@ impl ComputeShader {
 
     /// Resize resources
     pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+        // Minimized window: keep the current textures, the next non-zero resize recreates them
+        if width == 0 || height == 0 {
+            return;
+        }
+
         // Recreate output texture
         self.output_texture = Self::create_output_texture(
             &core.device,
             width,
             height,
-            wgpu::TextureFormat::Rgba16Float,
+            self.output_format,
             &format!("{} Output Texture", self.label),
         );
 
+        if self.depth_output.is_some() {
+            self.depth_output = Some(Self::create_depth_output(
+                &core.device,
+                width,
+                height,
+                &self.label,
+            ));
+        }
+
         // CRITICAL: Recreate Group 1 bind group with new texture!
         let group1_layout = self.bind_group_layouts.get(&1).unwrap();
         self.group1_bind_group = Self::create_group1_bind_group(
             &core.device,
             group1_layout,
             &self.output_texture,
-            &ComputeConfiguration {
-                entry_points: self.entry_points.clone(),
-                passes: None,
-                custom_uniform_size: self.custom_uniform_size,
-                has_input_texture: self.has_input_texture,
-                has_mouse: true,
-                has_fonts: true,
-                has_audio: true,
-                has_atomic_buffer: false,
-                audio_buffer_size: 1024,
-                has_audio_spectrum: false,
-                audio_spectrum_size: 128,
-                storage_buffers: Vec::new(),
-                workgroup_size: self.workgroup_size,
-                dispatch_once: self.dispatch_once,
-                texture_format: wgpu::TextureFormat::Rgba16Float,
-                label: self.label.clone(),
-                num_channels: Some(self.num_channels),
+            &self.config,
+            Group1Resources {
+                custom_uniform: self.custom_uniform.as_ref(),
+                input_texture: self.placeholder_input_texture.as_ref(),
+                depth_output: self.depth_output.as_ref(),
             },
-            self.custom_uniform.as_ref(),
-            self.placeholder_input_texture.as_ref().map(|t| &t.view),
-            self.placeholder_input_texture.as_ref().map(|t| &t.sampler),
         );
 
         // Resize multi-pass buffers if present
@@ -1447,20 +2673,7 @@ impl ComputeShader {
             });
 
             // Recreate group2 bind group with the new buffer
-            if let Some(layout) = self.bind_group_layouts.get(&2) {
-                self.group2_bind_group = Self::create_group2_bind_group(
-                    &core.device,
-                    &core.queue,
-                    layout,
-                    &self.font_system,
-                    &self.atomic_buffer_raw,
-                    &self.audio_buffer,
-                    &self.audio_spectrum_buffer,
-                    &self.mouse_uniform,
-                    &self.channel_textures,
-                    self.num_channels,
-                );
-            }
+            self.recreate_group2_bind_group(&core.device, &core.queue);
         }
 
         // Reset frame counter on resize to start fresh
@@ -1477,7 +2690,7 @@ impl ComputeShader {
         // Clear atomic buffer if present
         self.clear_atomic_buffer(core);
 
-        // Reset frame counter
+        // Reset frame counter, which also re-runs the init pass
         self.current_frame = 0;
     }
 
@@ -1494,24 +2707,12 @@ impl ComputeShader {
             }));
 
             // Recreate group2 bind group with the new buffer
-            if let Some(layout) = self.bind_group_layouts.get(&2) {
-                self.group2_bind_group = Self::create_group2_bind_group(
-                    &core.device,
-                    &core.queue,
-                    layout,
-                    &self.font_system,
-                    &self.atomic_buffer_raw,
-                    &self.audio_buffer,
-                    &self.audio_spectrum_buffer,
-                    &self.mouse_uniform,
-                    &self.channel_textures,
-                    self.num_channels,
-                );
-            }
+            self.recreate_group2_bind_group(&core.device, &core.queue);
         }
     }
 
-    /// Update mouse uniform with data from RenderKit
+    /// Update mouse uniform with data from RenderKit. `delta` is reset by the app loop
+    /// after each frame (see `MouseTracker::end_frame`).
     pub fn update_mouse_uniform(
         &mut self,
         mouse_uniform_data: &crate::MouseUniform,
@@ -1531,105 +2732,295 @@ impl ComputeShader {
     /// This method copies audio data from the GPU compute shader's audio buffer
     /// to CPU memory for processing or playback. The GPU shader writes audio
     /// parameters (frequencies, amplitudes, waveforms, etc.) to the buffer,
-    /// and this method retrieves them asynchronously.
+    /// and this method retrieves them.
     ///
-    /// Returns a Vec<f32> containing the audio buffer data, or an empty vector if no audio buffer exists.
+    /// Despite being `async` it waits on the device internally, which stalls the event loop
+    /// while the GPU is busy. Call it from a background thread, or use `PendingReadback`
+    /// to poll a readback without blocking.
+    ///
+    /// The result has one value per sample configured with `with_audio`/`with_audio_buffer`.
+    /// Returns `ReadbackError::NoBuffer` if the shader was built without `with_audio`.
     pub async fn read_audio_buffer(
         &self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
-        if let (Some(audio_buffer), Some(staging_buffer)) =
+    ) -> Result<Vec<f32>, super::ReadbackError> {
+        let (Some(audio_buffer), Some(staging_buffer)) =
             (&self.audio_buffer, &self.audio_staging_buffer)
-        {
-            // Get buffer size directly from the wgpu buffer itself
-            let buffer_size = audio_buffer.size();
-
-            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Audio Buffer Copy"),
-            });
-
-            encoder.copy_buffer_to_buffer(audio_buffer, 0, staging_buffer, 0, buffer_size);
-
-            queue.submit(std::iter::once(encoder.finish()));
-
-            let buffer_slice = staging_buffer.slice(..);
-            let (tx, rx) = std::sync::mpsc::channel();
-            buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-                let _ = tx.send(result);
-            });
-
-            let _ = device.poll(wgpu::PollType::wait_indefinitely());
+        else {
+            return Err(super::ReadbackError::NoBuffer);
+        };
 
-            match rx.recv() {
-                Ok(Ok(())) => {}
-                Ok(Err(e)) => return Err(e.into()),
-                Err(_) => return Err("Buffer mapping failed".into()),
-            }
+        // Get buffer size directly from the wgpu buffer itself
+        let bytes = super::readback::read_buffer_bytes(
+            device,
+            queue,
+            audio_buffer,
+            staging_buffer,
+            audio_buffer.size(),
+        )?;
+        Ok(bytemuck::cast_slice(&bytes).to_vec())
+    }
 
-            let samples = {
-                let data = buffer_slice.get_mapped_range();
-                let samples: Vec<f32> = bytemuck::cast_slice(&data).to_vec();
-                samples
-            };
+    /// Reads a user storage buffer (group 3) back to CPU as raw bytes.
+    /// `index` follows the order of `with_storage_buffer` calls.
+    pub fn read_storage_buffer(
+        &self,
+        index: usize,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Vec<u8>, super::ReadbackError> {
+        let buffer = self
+            .storage_buffers
+            .get(index)
+            .ok_or(super::ReadbackError::NoBuffer)?;
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Storage Readback Staging Buffer"),
+            size: buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        super::readback::read_buffer_bytes(device, queue, buffer, &staging_buffer, buffer.size())
+    }
 
-            staging_buffer.unmap();
+    /// Reads the output texture value at pixel (x, y), e.g. under the mouse for picking.
+    /// Blocks until the GPU has finished, like `read_audio_buffer`.
+    /// 8-bit formats are returned as stored (0..1), without sRGB decoding.
+    pub fn read_pixel(
+        &self,
+        core: &Core,
+        x: u32,
+        y: u32,
+    ) -> Result<[f32; 4], super::ReadbackError> {
+        super::readback::read_texel(
+            &core.device,
+            &core.queue,
+            &self.output_texture.texture,
+            x,
+            y,
+        )
+    }
 
-            Ok(samples)
-        } else {
-            Ok(Vec::new())
-        }
+    /// 64-bit hash of the output texture, for skipping work when a frame didn't change
+    /// (render on demand, see `RedrawMode::Reactive`). Call it after submitting the frame.
+    ///
+    /// Opt-in and not free: the first call builds a pipeline, every call runs one extra
+    /// dispatch over all output pixels and then blocks on an 8 byte readback, which drains
+    /// the GPU queue like `read_pixel`. Equal outputs always hash equal; different ones
+    /// differ except for rare collisions.
+    pub fn output_hash(&mut self, core: &Core) -> Result<u64, super::ReadbackError> {
+        let hasher = self
+            .output_hasher
+            .get_or_insert_with(|| OutputHasher::new(core));
+        hasher.hash(core, &self.output_texture.texture)
     }
 
-    /// Automatic export - call from shader update() method
+    /// Automatic export - call from shader update() method.
+    /// Runs even while the controls are paused, see `capture_export_frame`.
+    ///
+    /// Never blocks: a frame is submitted on one call and saved on a later one once its
+    /// readback finished, so the window stays responsive during long exports. Also keeps
+    /// the controls panel up to date with the shader, see `RenderKit::sync_shader_controls`.
     pub fn handle_export(&mut self, core: &Core, render_kit: &mut crate::RenderKit) {
-        if let Some((frame, time)) = render_kit.export_manager.try_get_next_frame() {
-            match self.capture_export_frame(
-                core,
-                time,
-                render_kit,
-                None::<fn(&mut Self, &mut wgpu::CommandEncoder, &Core)>,
-            ) {
-                Ok(data) => {
-                    let settings = render_kit.export_manager.settings();
-                    if let Err(e) = crate::save_frame(data, frame, settings) {
-                        eprintln!("Error saving frame: {e:?}");
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Error capturing export frame: {e:?}");
+        self.step_export(
+            core,
+            render_kit,
+            None::<fn(&mut Self, &mut wgpu::CommandEncoder, &Core)>,
+        );
+    }
+
+    /// Time to capture an export frame at. For video-synced exports this steps the kit's
+    /// video, binds it as the input texture and returns its timestamp; `None` when it ended.
+    #[allow(unused_variables)]
+    fn synced_video_time(
+        &mut self,
+        core: &Core,
+        render_kit: &mut crate::RenderKit,
+        scheduled: f32,
+    ) -> Option<f32> {
+        if !render_kit.export_manager.is_video_synced() {
+            return Some(scheduled);
+        }
+        #[cfg(feature = "media")]
+        {
+            let time = render_kit.step_synced_video(core)?;
+            if self.has_input_texture {
+                if let Some(video) = &render_kit.video_texture_manager {
+                    let texture = video.texture_manager();
+                    self.update_input_texture(&texture.view, &texture.sampler, &core.device);
                 }
             }
-        } else {
-            render_kit.export_manager.complete_export();
+            return Some(time);
         }
+        #[cfg(not(feature = "media"))]
+        Some(scheduled)
     }
 
-    /// Automatic export with custom dispatch
+    /// Automatic export with custom dispatch, non-blocking like `handle_export`
     pub fn handle_export_dispatch(
         &mut self,
         core: &Core,
         render_kit: &mut crate::RenderKit,
         custom_dispatch: impl FnOnce(&mut Self, &mut wgpu::CommandEncoder, &Core),
     ) {
-        if let Some((frame, time)) = render_kit.export_manager.try_get_next_frame() {
-            match self.capture_export_frame(core, time, render_kit, Some(custom_dispatch)) {
-                Ok(data) => {
-                    let settings = render_kit.export_manager.settings();
-                    if let Err(e) = crate::save_frame(data, frame, settings) {
-                        eprintln!("Error saving frame: {e:?}");
+        self.step_export(core, render_kit, Some(custom_dispatch));
+    }
+
+    /// Save the in-flight export frame once all its outputs (`ExportSettings::outputs`) are
+    /// read back, then submit the next one. Returns early while the GPU is still busy, one
+    /// frame is in flight at a time.
+    fn step_export<F>(
+        &mut self,
+        core: &Core,
+        render_kit: &mut crate::RenderKit,
+        custom_dispatch: Option<F>,
+    ) where
+        F: FnOnce(&mut Self, &mut wgpu::CommandEncoder, &Core),
+    {
+        render_kit.sync_shader_controls(core, self);
+        if let Some(pending) = &mut self.pending_export {
+            let mut i = 0;
+            while i < pending.readbacks.len() {
+                let Some(result) = pending.readbacks[i].readback.try_take(&core.device) else {
+                    i += 1;
+                    continue;
+                };
+                let PendingOutput {
+                    output, float_size, ..
+                } = pending.readbacks.remove(i);
+                let pixels = match (result, float_size) {
+                    (Ok(bytes), None) => OutputPixels::Rgba8(bytes),
+                    (Ok(bytes), Some((width, height))) => {
+                        let data = bytes
+                            .chunks_exact(4)
+                            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                            .collect();
+                        OutputPixels::Float {
+                            width,
+                            height,
+                            data,
+                        }
+                    }
+                    (Err(e), _) => {
+                        eprintln!("Error capturing export output {}: {e}", output.index);
+                        continue;
                     }
+                };
+                pending.finished.push(FinishedOutput { output, pixels });
+            }
+            if !pending.readbacks.is_empty() {
+                return;
+            }
+            let frame = pending.frame;
+            for FinishedOutput { output, pixels } in std::mem::take(&mut pending.finished) {
+                if let Err(e) = render_kit
+                    .export_manager
+                    .save_export_output(&output, pixels, frame)
+                {
+                    eprintln!("Error saving frame: {e:?}");
                 }
-                Err(e) => {
-                    eprintln!("Error capturing export frame: {e:?}");
+            }
+            self.pending_export = None;
+        }
+
+        if let Some((frame, time)) = render_kit.export_manager.try_get_next_frame() {
+            let Some(time) = self.synced_video_time(core, render_kit, time) else {
+                render_kit.export_manager.complete_export();
+                return;
+            };
+            let outputs = render_kit.export_manager.settings().outputs.clone();
+            let mut color =
+                Some(self.begin_export_capture(core, time, render_kit, custom_dispatch));
+            let mut readbacks = Vec::new();
+            for output in outputs {
+                if output.index == ExportOutput::COLOR {
+                    // Only the first color output gets the capture
+                    if let Some(readback) = color.take() {
+                        readbacks.push(PendingOutput {
+                            output,
+                            float_size: None,
+                            readback,
+                        });
+                    }
+                } else if let Some((size, readback)) = self.begin_output_capture(core, output.index)
+                {
+                    readbacks.push(PendingOutput {
+                        output,
+                        float_size: Some(size),
+                        readback,
+                    });
+                } else if frame == 0 {
+                    log::warn!(
+                        "{}: no export output {}, skipping it",
+                        self.label,
+                        output.index
+                    );
                 }
             }
+            self.pending_export = Some(PendingExport {
+                frame,
+                readbacks,
+                finished: Vec::new(),
+            });
         } else {
             render_kit.export_manager.complete_export();
         }
     }
 
-    /// Captures current frame with format conversion and optional custom dispatch
+    /// Read back a non-color export output (`ExportOutput::DEPTH`) at its own size
+    fn begin_output_capture(
+        &self,
+        core: &Core,
+        index: u32,
+    ) -> Option<((u32, u32), PendingReadback)> {
+        let texture = match index {
+            ExportOutput::DEPTH => &self.depth_output.as_ref()?.texture,
+            _ => return None,
+        };
+        let size = texture.size();
+        let unpadded_bytes_per_row = size.width * 4;
+        let padded_bytes_per_row = unpadded_bytes_per_row
+            .div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer_size = (padded_bytes_per_row * size.height) as u64;
+        let buffer = core.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Export Output Buffer"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Export Output Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        core.queue.submit(Some(encoder.finish()));
+        let readback = PendingReadback::new(buffer, buffer_size)
+            .with_row_padding(padded_bytes_per_row, unpadded_bytes_per_row);
+        Some(((size.width, size.height), readback))
+    }
+
+    /// Captures current frame with format conversion and optional custom dispatch.
+    /// Only the output texture is drawn into the capture, egui never is, so exports look
+    /// like the live view with `RenderKit::set_ui_enabled(false)`.
+    ///
+    /// Exports ignore the live pause state: time, delta (`1 / fps`) and frame all come from
+    /// the export schedule, so a paused view still exports a moving sequence. The live time
+    /// uniform is restored afterwards, so the paused view stays frozen.
+    ///
+    /// Blocks until the frame is read back; `begin_export_capture` is the non-blocking variant.
     pub fn capture_export_frame<F>(
         &mut self,
         core: &Core,
@@ -1640,11 +3031,57 @@ impl ComputeShader {
     where
         F: FnOnce(&mut Self, &mut wgpu::CommandEncoder, &Core),
     {
-        let settings = render_kit.export_manager.settings();
-        let (capture_texture, output_buffer) =
-            render_kit.create_capture_texture(&core.device, settings.width, settings.height);
+        self.begin_export_capture(core, time, render_kit, custom_dispatch)
+            .wait(&core.device)
+            .map_err(|e| {
+                log::error!("Export frame readback failed: {e}");
+                wgpu::SurfaceError::Other
+            })
+    }
 
-        let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    /// Whether export can copy the output texture straight into the capture buffer.
+    ///
+    /// True for 8-bit outputs with the byte layout of `CAPTURE_FORMAT` at the export size.
+    /// Their values are saved as written, like `direct_present`; going through the blit
+    /// would sRGB-encode values the shader already encoded.
+    fn export_copies_output(&self, width: u32, height: u32) -> bool {
+        let texture = &self.output_texture.texture;
+        texture.format().remove_srgb_suffix() == crate::CAPTURE_FORMAT.remove_srgb_suffix()
+            && texture.width() == width
+            && texture.height() == height
+    }
+
+    /// Record and submit an export frame like `capture_export_frame`, without waiting.
+    /// Poll the result with `PendingReadback::try_take` from the event loop, or await
+    /// `compute::poll_until_ready` off it. A shader using `with_shared_time` renders at the
+    /// time its `SharedUniform` holds, set that to the export time first.
+    pub fn begin_export_capture<F>(
+        &mut self,
+        core: &Core,
+        time: f32,
+        render_kit: &crate::RenderKit,
+        custom_dispatch: Option<F>,
+    ) -> PendingReadback
+    where
+        F: FnOnce(&mut Self, &mut wgpu::CommandEncoder, &Core),
+    {
+        let settings = render_kit.export_manager.settings();
+        let direct_copy = render_kit.color_grading().is_neutral()
+            && self.export_copies_output(settings.width, settings.height);
+        let (capture_texture, output_buffer) = if direct_copy {
+            (
+                None,
+                crate::RenderKit::create_capture_buffer(
+                    &core.device,
+                    settings.width,
+                    settings.height,
+                ),
+            )
+        } else {
+            let (texture, buffer) =
+                render_kit.create_capture_texture(&core.device, settings.width, settings.height);
+            (Some(texture), buffer)
+        };
 
         let mut encoder = core
             .device
@@ -1652,7 +3089,12 @@ impl ComputeShader {
                 label: Some("Export Encoder"),
             });
 
-        self.set_time(time, 0.0, &core.queue);
+        let live_time = self.time_uniform.data;
+        let fps = settings.fps.max(1) as f32;
+        let export_frame = ((time - settings.start_time) * fps).round().max(0.0) as u32;
+        if self.owns_time() {
+            self.write_time(time, 1.0 / fps, export_frame, &core.queue);
+        }
 
         // Use custom dispatch if provided, otherwise use default
         if let Some(custom_dispatch) = custom_dispatch {
@@ -1661,18 +3103,23 @@ impl ComputeShader {
             self.dispatch(&mut encoder, core);
         }
 
-        {
+        // Transparent clear keeps the shader alpha untouched when exporting with alpha
+        let clear_color = if settings.preserve_alpha {
+            wgpu::Color::TRANSPARENT
+        } else {
+            wgpu::Color::BLACK
+        };
+
+        if let Some(capture_texture) = &capture_texture {
+            let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
             let mut render_pass = crate::Renderer::begin_render_pass(
                 &mut encoder,
                 &capture_view,
-                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                wgpu::LoadOp::Clear(clear_color),
                 Some("Export Capture Pass"),
             );
 
-            render_pass.set_pipeline(&render_kit.renderer.render_pipeline);
-            render_pass.set_vertex_buffer(0, render_kit.renderer.vertex_buffer.slice(..));
-            render_pass.set_bind_group(0, &self.output_texture.bind_group, &[]);
-            render_pass.draw(0..4, 0..1);
+            render_kit.draw_output(&mut render_pass, &self.output_texture.bind_group);
         }
 
         let align = 256;
@@ -1682,7 +3129,9 @@ impl ComputeShader {
 
         encoder.copy_texture_to_buffer(
             wgpu::TexelCopyTextureInfo {
-                texture: &capture_texture,
+                texture: capture_texture
+                    .as_ref()
+                    .unwrap_or(&self.output_texture.texture),
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
@@ -1704,24 +3153,460 @@ impl ComputeShader {
 
         core.queue.submit(Some(encoder.finish()));
 
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
+        // Back to the live (possibly paused) values for the next regular frame
+        if self.owns_time() {
+            self.time_uniform.data = live_time;
+            self.time_uniform.update(&core.queue);
+        }
+
+        let size = output_buffer.size();
+        PendingReadback::new(output_buffer, size)
+            .with_row_padding(padded_bytes_per_row, unpadded_bytes_per_row)
+    }
+}
 
-        let _ = core
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::{
+        PassDescription, COMPUTE_TEXTURE_FORMAT_RGBA16, COMPUTE_TEXTURE_FORMAT_RGBA8,
+    };
+    use crate::testing::test_core;
+
+    /// `accumulate` adds 0.125 to what it read from its own buffer, `main_image` shows it
+    const ACCUMULATE_WGSL: &str = r#"
+@group(1) @binding(0) var output: texture_storage_2d<rgba16float, write>;
+@group(3) @binding(0) var input_texture0: texture_2d<f32>;
+
+@compute @workgroup_size(16, 16, 1)
+fn accumulate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let previous = textureLoad(input_texture0, vec2<i32>(id.xy), 0);
+    textureStore(output, vec2<i32>(id.xy), previous + vec4<f32>(0.125));
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main_image(@builtin(global_invocation_id) id: vec3<u32>) {
+    textureStore(output, vec2<i32>(id.xy), textureLoad(input_texture0, vec2<i32>(id.xy), 0));
+}
+"#;
+
+    fn accumulated_after(core: &Core, frames: u32, persistent: bool) -> f32 {
+        let passes = [
+            PassDescription::new("accumulate", &["accumulate"]).with_persistent(persistent),
+            PassDescription::new("main_image", &["accumulate"]),
+        ];
+        let config = ComputeShader::builder()
+            .with_multi_pass(&passes)
+            .with_workgroup_size([16, 16, 1])
+            .with_texture_format(COMPUTE_TEXTURE_FORMAT_RGBA16)
+            .with_label("Accumulate Test")
+            .build();
+        let mut shader = ComputeShader::from_builder(core, ACCUMULATE_WGSL, config);
+        for _ in 0..frames {
+            let mut encoder = core
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            shader.dispatch(&mut encoder, core);
+            core.queue.submit(Some(encoder.finish()));
+        }
+        shader.read_pixel(core, 0, 0).unwrap()[0]
+    }
+
+    #[test]
+    fn output_format_is_replaced_as_a_whole_word() {
+        let source = "var output: texture_storage_2d<OUTPUT_FORMAT, write>;\n\
+                      const MY_OUTPUT_FORMAT = 1; let OUTPUT_FORMAT_2 = OUTPUT_FORMAT;";
+        assert_eq!(
+            substitute_output_format(source, COMPUTE_TEXTURE_FORMAT_RGBA16),
+            "var output: texture_storage_2d<rgba16float, write>;\n\
+             const MY_OUTPUT_FORMAT = 1; let OUTPUT_FORMAT_2 = rgba16float;"
+        );
+        assert_eq!(
+            substitute_output_format("OUTPUT_FORMAT", wgpu::TextureFormat::R32Float),
+            "OUTPUT_FORMAT"
+        );
+    }
+
+    #[test]
+    fn hot_reload_compiles_the_substituted_source() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let source = MINIMAL_WGSL.replace("rgba16float", "OUTPUT_FORMAT");
+        let path = std::env::temp_dir().join(format!(
+            "cuneus-hot-reload-test-{}.wgsl",
+            std::process::id()
+        ));
+        std::fs::write(&path, &source).unwrap();
+
+        let config = ComputeShader::builder()
+            .with_texture_format(COMPUTE_TEXTURE_FORMAT_RGBA16)
+            .with_label("Hot Reload Format Test")
+            .build();
+        let module = core
             .device
-            .poll(wgpu::PollType::wait_indefinitely())
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(
+                    ComputeShader::prepare_source(&source, &config).into(),
+                ),
+            });
+        let mut shader = ComputeShader::from_builder(core, &source, config);
+        let result = shader.enable_hot_reload(core.device.clone(), path.clone(), module);
+        std::fs::remove_file(&path).ok();
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[test]
+    fn export_leaves_a_shared_time_uniform_alone() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let shared_time = crate::SharedUniform::new(
+            &core.device,
+            "Shared Time Test",
+            ComputeTimeUniform {
+                time: 2.5,
+                ..bytemuck::Zeroable::zeroed()
+            },
+        );
+        let config = ComputeShader::builder()
+            .with_shared_time(&shared_time)
+            .with_texture_format(COMPUTE_TEXTURE_FORMAT_RGBA16)
+            .with_label("Shared Time Export Test")
+            .build();
+        let mut shader = ComputeShader::from_builder(core, MINIMAL_WGSL, config);
+        let render_kit = crate::RenderKit::new_with_standard_layout(core);
+        shader
+            .capture_export_frame(
+                core,
+                7.0,
+                &render_kit,
+                None::<fn(&mut ComputeShader, &mut wgpu::CommandEncoder, &Core)>,
+            )
             .unwrap();
-        rx.recv().unwrap().unwrap();
 
-        let padded_data = buffer_slice.get_mapped_range().to_vec();
-        let mut unpadded_data = Vec::with_capacity((settings.width * settings.height * 4) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
-            unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
+        let size = std::mem::size_of::<ComputeTimeUniform>() as u64;
+        let readback = core.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(shared_time.buffer(), 0, &readback, 0, size);
+        core.queue.submit(Some(encoder.finish()));
+        let bytes = PendingReadback::new(readback, size)
+            .wait(&core.device)
+            .unwrap();
+        let time: &ComputeTimeUniform = bytemuck::from_bytes(&bytes);
+        assert_eq!(time.time, 2.5);
+    }
+
+    #[test]
+    fn init_pass_binds_the_multi_pass_inputs() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let source = format!(
+            "{ACCUMULATE_WGSL}
+@compute @workgroup_size(16, 16, 1)
+fn seed(@builtin(global_invocation_id) id: vec3<u32>) {{
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(1.0));
+}}
+"
+        );
+        let passes = [
+            PassDescription::new("accumulate", &["accumulate"]),
+            PassDescription::new("main_image", &["accumulate"]),
+        ];
+        let config = ComputeShader::builder()
+            .with_multi_pass(&passes)
+            .with_init_pass("seed")
+            .with_workgroup_size([16, 16, 1])
+            .with_texture_format(COMPUTE_TEXTURE_FORMAT_RGBA16)
+            .with_label("Init Pass Test")
+            .build();
+        let mut shader = ComputeShader::from_builder(core, &source, config);
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        shader.dispatch(&mut encoder, core);
+        core.queue.submit(Some(encoder.finish()));
+        // The seed went to the main output, main_image then overwrote it
+        assert!((shader.read_pixel(core, 0, 0).unwrap()[0] - 0.125).abs() < 1e-3);
+    }
+
+    #[test]
+    fn intermediate_passes_bind_the_depth_output() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let passes = [
+            PassDescription::new("accumulate", &["accumulate"]),
+            PassDescription::new("main_image", &["accumulate"]),
+        ];
+        let config = ComputeShader::builder()
+            .with_multi_pass(&passes)
+            .with_depth_output()
+            .with_workgroup_size([16, 16, 1])
+            .with_texture_format(COMPUTE_TEXTURE_FORMAT_RGBA16)
+            .with_label("Multi-Pass Depth Test")
+            .build();
+        let mut shader = ComputeShader::from_builder(core, ACCUMULATE_WGSL, config);
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        shader.dispatch(&mut encoder, core);
+        core.queue.submit(Some(encoder.finish()));
+        assert!((shader.read_pixel(core, 0, 0).unwrap()[0] - 0.125).abs() < 1e-3);
+    }
+
+    #[test]
+    fn persistent_pass_keeps_its_data_across_frames() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        assert!((accumulated_after(core, 3, true) - 0.375).abs() < 1e-3);
+        assert!((accumulated_after(core, 3, false) - 0.125).abs() < 1e-3);
+    }
+
+    /// Writes sRGB-encoded mid gray (byte 128), as an 8-bit shader would
+    const ENCODED_GRAY_WGSL: &str = r#"
+@group(1) @binding(0) var output: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(vec3<f32>(128.0 / 255.0), 1.0));
+}
+"#;
+
+    /// The export blit into `CAPTURE_FORMAT`, as taken when the output isn't copied
+    fn blit_capture(core: &Core, shader: &ComputeShader, render_kit: &crate::RenderKit) -> Vec<u8> {
+        let (width, height) = (core.size.width, core.size.height);
+        let (texture, buffer) = render_kit.create_capture_texture(&core.device, width, height);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut render_pass = crate::Renderer::begin_render_pass(
+                &mut encoder,
+                &view,
+                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                None,
+            );
+            render_kit.draw_output(&mut render_pass, &shader.output_texture.bind_group);
         }
+        let padded_bytes_per_row = (width * 4).div_ceil(256) * 256;
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        core.queue.submit(Some(encoder.finish()));
+        let size = buffer.size();
+        PendingReadback::new(buffer, size)
+            .with_row_padding(padded_bytes_per_row, width * 4)
+            .wait(&core.device)
+            .unwrap()
+    }
+
+    #[test]
+    fn export_copies_8bit_output_without_reencoding() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let config = ComputeShader::builder()
+            .with_texture_format(COMPUTE_TEXTURE_FORMAT_RGBA8)
+            .with_label("Direct Copy Test")
+            .build();
+        let mut shader = ComputeShader::from_builder(core, ENCODED_GRAY_WGSL, config);
+        let mut render_kit = crate::RenderKit::new_with_standard_layout(core);
+        {
+            let settings = render_kit.export_manager.settings_mut();
+            settings.width = core.size.width;
+            settings.height = core.size.height;
+        }
+        if !shader.export_copies_output(core.size.width, core.size.height) {
+            // Only RGBA capture formats can be copied from an rgba8unorm output
+            return;
+        }
+
+        let direct = shader
+            .capture_export_frame(
+                core,
+                0.0,
+                &render_kit,
+                None::<fn(&mut ComputeShader, &mut wgpu::CommandEncoder, &Core)>,
+            )
+            .unwrap();
+        let blit = blit_capture(core, &shader, &render_kit);
+        assert_eq!(direct.len(), blit.len());
+
+        // The copy keeps the bytes as written, the blit encodes them to sRGB once more
+        let reencoded = (((128.0f32 / 255.0).powf(1.0 / 2.4) * 1.055 - 0.055) * 255.0).round();
+        for (direct, blit) in direct.chunks_exact(4).zip(blit.chunks_exact(4)) {
+            assert_eq!(direct, [128, 128, 128, 255]);
+            assert!(blit[..3].iter().all(|&c| c.abs_diff(reencoded as u8) <= 1));
+            assert_eq!(blit[3], 255);
+        }
+    }
 
-        Ok(unpadded_data)
+    /// Only the output texture: no custom uniform, no engine resources in group 2
+    const MINIMAL_WGSL: &str = r#"
+@group(1) @binding(0) var output: texture_storage_2d<rgba16float, write>;
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(0.25, 0.5, 0.75, 1.0));
+}
+"#;
+
+    #[test]
+    fn resize_keeps_a_minimal_shader_working() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let config = ComputeShader::builder()
+            .with_texture_format(COMPUTE_TEXTURE_FORMAT_RGBA16)
+            .with_label("Minimal Resize Test")
+            .build();
+        let mut shader = ComputeShader::from_builder(core, MINIMAL_WGSL, config);
+        shader.resize(core, 48, 24);
+        assert_eq!(shader.output_texture.texture.width(), 48);
+        assert_eq!(shader.output_texture.texture.height(), 24);
+
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        shader.dispatch(&mut encoder, core);
+        core.queue.submit(Some(encoder.finish()));
+        let pixel = shader.read_pixel(core, 47, 23).unwrap();
+        assert!((pixel[2] - 0.75).abs() < 1e-3);
+    }
+
+    #[test]
+    fn zero_sized_resize_keeps_the_textures() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let config = ComputeShader::builder()
+            .with_texture_format(COMPUTE_TEXTURE_FORMAT_RGBA16)
+            .with_label("Zero Resize Test")
+            .build();
+        let mut shader = ComputeShader::from_builder(core, MINIMAL_WGSL, config);
+        let before = shader.output_texture.texture.clone();
+        shader.resize(core, 0, 0);
+        shader.resize(core, 0, 32);
+        assert!(shader.output_texture.texture == before);
+
+        shader.resize(core, 32, 16);
+        assert_eq!(shader.output_texture.texture.width(), 32);
+        assert_eq!(shader.output_texture.texture.height(), 16);
+    }
+
+    /// `fill` writes scratch `tmp1`, `blend` reads it and the never-written `tmp3` into `tmp2`
+    const SCRATCH_WGSL: &str = r#"
+@group(1) @binding(0) var output: texture_storage_2d<rgba16float, write>;
+@group(3) @binding(0) var input_texture0: texture_2d<f32>;
+@group(3) @binding(2) var input_texture1: texture_2d<f32>;
+
+@compute @workgroup_size(16, 16, 1)
+fn fill(@builtin(global_invocation_id) id: vec3<u32>) {
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(0.25));
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn blend(@builtin(global_invocation_id) id: vec3<u32>) {
+    let tmp1 = textureLoad(input_texture0, vec2<i32>(id.xy), 0);
+    let tmp3 = textureLoad(input_texture1, vec2<i32>(id.xy), 0);
+    textureStore(output, vec2<i32>(id.xy), tmp1 + tmp3 + vec4<f32>(0.5));
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn main_image(@builtin(global_invocation_id) id: vec3<u32>) {
+    textureStore(output, vec2<i32>(id.xy), textureLoad(input_texture0, vec2<i32>(id.xy), 0));
+}
+"#;
+
+    #[test]
+    fn passes_write_scratch_buffers_by_name() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let passes = [
+            PassDescription::new("fill", &[]).with_output("tmp1"),
+            PassDescription::new("blend", &["tmp1", "tmp3"]).with_output("tmp2"),
+            PassDescription::new("main_image", &["tmp2"]),
+        ];
+        let config = ComputeShader::builder()
+            .with_multi_pass(&passes)
+            .with_scratch_buffers(&["tmp1", "tmp2", "tmp3"])
+            .with_workgroup_size([16, 16, 1])
+            .with_texture_format(COMPUTE_TEXTURE_FORMAT_RGBA16)
+            .with_label("Scratch Buffer Test")
+            .build();
+        let mut shader = ComputeShader::from_builder(core, SCRATCH_WGSL, config);
+        let manager = shader.multipass_manager.as_ref().unwrap();
+        assert!(["tmp1", "tmp2", "tmp3"]
+            .iter()
+            .all(|name| manager.has_buffer(name)));
+
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        shader.dispatch(&mut encoder, core);
+        core.queue.submit(Some(encoder.finish()));
+        // tmp3 is never written and reads as zero
+        assert!((shader.read_pixel(core, 0, 0).unwrap()[0] - 0.75).abs() < 1e-3);
+    }
+
+    #[test]
+    fn unknown_pass_inputs_are_reported_and_skipped() {
+        let passes = [
+            PassDescription::new("fill", &[]).with_output("tmp1"),
+            PassDescription::new("blend", &["tmp1", "typo"]).with_output("tmp2"),
+            PassDescription::new("main_image", &["tmp2"]),
+        ];
+        let builder = || {
+            ComputeShader::builder()
+                .with_multi_pass(&passes)
+                .with_scratch_buffers(&["tmp1", "tmp3"])
+                .with_workgroup_size([16, 16, 1])
+                .with_texture_format(COMPUTE_TEXTURE_FORMAT_RGBA16)
+        };
+        let config = builder().build();
+        assert_eq!(
+            config.multi_pass_buffers(),
+            ["fill", "blend", "main_image", "tmp1", "tmp3", "tmp2"]
+        );
+        assert_eq!(
+            config.unknown_pass_inputs(),
+            [("blend".to_string(), "typo".to_string())]
+        );
+
+        let Some(core) = test_core() else {
+            return;
+        };
+        assert!(matches!(
+            builder().validate(core, SCRATCH_WGSL),
+            Err(crate::compute::ShaderBuildError::UnknownPassInput { .. })
+        ));
+        let mut shader = ComputeShader::from_builder(core, SCRATCH_WGSL, config);
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        shader.dispatch(&mut encoder, core);
+        core.queue.submit(Some(encoder.finish()));
     }
 }
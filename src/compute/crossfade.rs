@@ -0,0 +1,389 @@
+use super::{ComputeShader, COMPUTE_TEXTURE_FORMAT_RGBA16};
+use crate::{Core, RenderKit, Renderer, RendererDescriptor, TextureManager};
+use bytemuck::Zeroable;
+use std::time::{Duration, Instant};
+use wgpu::util::DeviceExt;
+
+const CROSSFADE_SHADER: &str = include_str!("../../shaders/crossfade.wgsl");
+const BLIT_SHADER: &str = include_str!("../../shaders/blit.wgsl");
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TransitionParams {
+    progress: f32,
+    kind: u32,
+    softness: f32,
+    _padding: f32,
+}
+
+/// How `Crossfade` goes from one image to the other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransitionKind {
+    /// Blend the whole image at once
+    #[default]
+    Fade,
+    /// Reveal the incoming image left to right
+    Wipe,
+    /// Reveal the incoming image pixel by pixel in random order
+    Dissolve,
+}
+
+impl TransitionKind {
+    fn index(self) -> u32 {
+        match self {
+            TransitionKind::Fade => 0,
+            TransitionKind::Wipe => 1,
+            TransitionKind::Dissolve => 2,
+        }
+    }
+}
+
+/// Timed transition between two shader outputs, e.g. when a VJ setup switches between
+/// `ComputeShader`s.
+///
+/// Keep dispatching both shaders while `is_active`, then present the texture `blend`
+/// returns instead of either output (`present` draws it onto the surface). Once the
+/// transition is over present the incoming shader's output directly. Inputs can be any
+/// float format and size, the output is `Rgba16Float`, sized to the window.
+///
+/// `ShaderSwitcher::with_transition` uses it to crossfade between shaders.
+pub struct Crossfade {
+    /// Edge width of `Wipe` and `Dissolve`, as a fraction of the transition
+    pub softness: f32,
+    kind: TransitionKind,
+    duration: Duration,
+    started: Option<Instant>,
+    pipeline: wgpu::ComputePipeline,
+    input_layout: wgpu::BindGroupLayout,
+    output_layout: wgpu::BindGroupLayout,
+    blit: Renderer,
+    params_buffer: wgpu::Buffer,
+    output: TextureManager,
+    output_bind_group: wgpu::BindGroup,
+    width: u32,
+    height: u32,
+}
+
+impl Crossfade {
+    pub fn new(core: &Core) -> Self {
+        let device = &core.device;
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                // Filtered in the shader, so unfilterable formats like Rgba32Float work too
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+            count: None,
+        };
+        let input_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Crossfade Input Layout"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let output_layout = super::create_bind_group_layout(
+            device,
+            super::BindGroupLayoutType::StorageTexture,
+            "Crossfade Output Layout",
+        );
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Crossfade Pipeline Layout"),
+            bind_group_layouts: &[&input_layout, &output_layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Crossfade Shader"),
+            source: wgpu::ShaderSource::Wgsl(CROSSFADE_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Crossfade Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: core.pipeline_cache(),
+        });
+
+        let blit_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Crossfade Blit Shader"),
+            source: wgpu::ShaderSource::Wgsl(BLIT_SHADER.into()),
+        });
+        let blit_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Crossfade Blit Layout"),
+            bind_group_layouts: &[&RenderKit::create_standard_texture_layout(device)],
+            push_constant_ranges: &[],
+        });
+        let blit = Renderer::new_with_blend(
+            device,
+            &RendererDescriptor {
+                vs_module: &blit_module,
+                fs_module: &blit_module,
+                format: core.config.format,
+                layout: &blit_layout,
+                fragment_entry: None,
+                blend: None,
+                cache: core.pipeline_cache(),
+            },
+        );
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Crossfade Params"),
+            contents: bytemuck::bytes_of(&TransitionParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let (width, height) = (core.size.width, core.size.height);
+        let (output, output_bind_group) = Self::create_output(core, &output_layout, width, height);
+
+        Self {
+            softness: 0.1,
+            kind: TransitionKind::Fade,
+            duration: Duration::ZERO,
+            started: None,
+            pipeline,
+            input_layout,
+            output_layout,
+            blit,
+            params_buffer,
+            output,
+            output_bind_group,
+            width,
+            height,
+        }
+    }
+
+    fn create_output(
+        core: &Core,
+        layout: &wgpu::BindGroupLayout,
+        width: u32,
+        height: u32,
+    ) -> (TextureManager, wgpu::BindGroup) {
+        let output = ComputeShader::create_output_texture(
+            &core.device,
+            width,
+            height,
+            COMPUTE_TEXTURE_FORMAT_RGBA16,
+            "Crossfade Output Texture",
+        );
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Crossfade Output Storage"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&output.view),
+            }],
+        });
+        (output, bind_group)
+    }
+
+    /// Start going from the outgoing to the incoming image over `duration`, restarting
+    /// any transition in progress
+    pub fn start_transition(&mut self, duration: Duration, kind: TransitionKind) {
+        self.kind = kind;
+        self.duration = duration;
+        self.started = Some(Instant::now());
+    }
+
+    /// 0 at the start of the transition, 1 once it's over (or none was started)
+    pub fn progress(&self) -> f32 {
+        match self.started {
+            Some(started) if !self.duration.is_zero() => {
+                (started.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Whether a transition is running, both shaders need to be dispatched meanwhile
+    pub fn is_active(&self) -> bool {
+        self.progress() < 1.0
+    }
+
+    pub fn kind(&self) -> TransitionKind {
+        self.kind
+    }
+
+    /// Blend `from` (outgoing) and `to` (incoming) at the current progress into the
+    /// output texture and return it
+    pub fn blend(
+        &self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        from: &TextureManager,
+        to: &TextureManager,
+    ) -> &TextureManager {
+        core.queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&TransitionParams {
+                progress: self.progress(),
+                kind: self.kind.index(),
+                softness: self.softness.clamp(1e-4, 1.0),
+                _padding: 0.0,
+            }),
+        );
+        let input_bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Crossfade Input"),
+            layout: &self.input_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&from.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&to.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Crossfade Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &input_bind_group, &[]);
+        pass.set_bind_group(1, &self.output_bind_group, &[]);
+        pass.dispatch_workgroups(self.width.div_ceil(16), self.height.div_ceil(16), 1);
+
+        &self.output
+    }
+
+    pub fn output(&self) -> &TextureManager {
+        &self.output
+    }
+
+    /// Draw the output of the last `blend` over all of `view`, a surface view
+    pub fn present(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut render_pass = Renderer::begin_render_pass(
+            encoder,
+            view,
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            Some("Crossfade Present Pass"),
+        );
+        render_pass.set_pipeline(&self.blit.render_pipeline);
+        render_pass.set_vertex_buffer(0, self.blit.vertex_buffer.slice(..));
+        render_pass.set_bind_group(0, &self.output.bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+    }
+
+    pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+        if width == 0 || height == 0 || (width, height) == (self.width, self.height) {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        let (output, output_bind_group) =
+            Self::create_output(core, &self.output_layout, width, height);
+        self.output = output;
+        self.output_bind_group = output_bind_group;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_core;
+
+    fn float32_input(core: &Core) -> TextureManager {
+        let device = &core.device;
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Rgba32Float Input"),
+            size: wgpu::Extent3d {
+                width: 8,
+                height: 8,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+        TextureManager {
+            texture,
+            view,
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor::default()),
+            bind_group,
+            view_formats: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn shader_validates() {
+        let module = naga::front::wgsl::parse_str(CROSSFADE_SHADER).unwrap();
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap();
+    }
+
+    #[test]
+    fn blends_unfilterable_inputs() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let crossfade = Crossfade::new(core);
+        let from = float32_input(core);
+        let to = ComputeShader::create_output_texture(
+            &core.device,
+            16,
+            16,
+            COMPUTE_TEXTURE_FORMAT_RGBA16,
+            "Crossfade Test Input",
+        );
+
+        core.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        crossfade.blend(core, &mut encoder, &from, &to);
+        core.queue.submit(Some(encoder.finish()));
+        let error = pollster::block_on(core.device.pop_error_scope());
+        assert!(error.is_none(), "{error:?}");
+    }
+}
@@ -0,0 +1,159 @@
+use super::ComputeShader;
+use crate::Core;
+
+const DEPTH_RESOLVE_SHADER: &str = include_str!("../../shaders/depth_resolve.wgsl");
+
+/// Format of the attachment `DepthResolve` produces
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Turns the R32Float depth field of a `with_depth_output` shader into a `Depth32Float`
+/// texture usable as a render pass depth attachment.
+///
+/// Depth formats can't be storage textures and texture copies need matching formats,
+/// so this is a fullscreen draw writing `frag_depth`. Values are clamped to 0..1.
+pub struct DepthResolve {
+    pipeline: wgpu::RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    depth: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+}
+
+impl DepthResolve {
+    pub fn new(core: &Core) -> Self {
+        let device = &core.device;
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Depth Resolve Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Depth Resolve Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Depth Resolve Shader"),
+            source: wgpu::ShaderSource::Wgsl(DEPTH_RESOLVE_SHADER.into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Depth Resolve Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: Some("fs_main"),
+                targets: &[],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: core.pipeline_cache(),
+        });
+
+        let (depth, depth_view) = Self::create_depth(device, core.size.width, core.size.height);
+        Self {
+            pipeline,
+            layout,
+            depth,
+            depth_view,
+        }
+    }
+
+    fn create_depth(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let depth = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Resolved Depth"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = depth.create_view(&wgpu::TextureViewDescriptor::default());
+        (depth, view)
+    }
+
+    /// Copy `shader`'s depth field into the depth texture, following its size.
+    /// Returns `None` if the shader wasn't built `with_depth_output`.
+    pub fn resolve(
+        &mut self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        shader: &ComputeShader,
+    ) -> Option<&wgpu::TextureView> {
+        let source = shader.depth_output.as_ref()?;
+        let size = source.texture.size();
+        if self.depth.size() != size {
+            (self.depth, self.depth_view) =
+                Self::create_depth(&core.device, size.width, size.height);
+        }
+
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Depth Resolve Bind Group"),
+            layout: &self.layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&source.view),
+            }],
+        });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Depth Resolve Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        Some(&self.depth_view)
+    }
+
+    /// The resolved depth, valid after `resolve`
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.depth_view
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.depth
+    }
+}
@@ -0,0 +1,125 @@
+use super::readback::{PendingReadback, ReadbackError};
+use crate::Core;
+
+const OUTPUT_HASH_SHADER: &str = include_str!("../../shaders/output_hash.wgsl");
+
+/// GPU reduction of a texture to a 64-bit hash, see `ComputeShader::output_hash`.
+///
+/// Every texel's raw bits and position are hashed and folded with atomics, so any changed
+/// texel changes the result (up to hash collisions). Not a cryptographic hash.
+pub(crate) struct OutputHasher {
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    result: wgpu::Buffer,
+}
+
+impl OutputHasher {
+    pub(crate) fn new(core: &Core) -> Self {
+        let device = &core.device;
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Output Hash Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(8),
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Output Hash Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Output Hash Shader"),
+            source: wgpu::ShaderSource::Wgsl(OUTPUT_HASH_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Output Hash Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: core.pipeline_cache(),
+        });
+        let result = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Output Hash Result"),
+            size: 8,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            pipeline,
+            layout,
+            result,
+        }
+    }
+
+    /// Hash `texture` as of the work already submitted. Blocks until the GPU is done.
+    pub(crate) fn hash(&self, core: &Core, texture: &wgpu::Texture) -> Result<u64, ReadbackError> {
+        let device = &core.device;
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Output Hash Bind Group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.result.as_entire_binding(),
+                },
+            ],
+        });
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Output Hash Readback"),
+            size: 8,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Output Hash Encoder"),
+        });
+        encoder.clear_buffer(&self.result, 0, None);
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Output Hash Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(
+                texture.width().div_ceil(16),
+                texture.height().div_ceil(16),
+                1,
+            );
+        }
+        encoder.copy_buffer_to_buffer(&self.result, 0, &staging, 0, 8);
+        core.queue.submit(Some(encoder.finish()));
+
+        let bytes = PendingReadback::new(staging, 8).wait(device)?;
+        let words: [u32; 2] = bytemuck::pod_read_unaligned(&bytes[..8]);
+        Ok((words[0] as u64) << 32 | words[1] as u64)
+    }
+}
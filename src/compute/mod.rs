@@ -3,20 +3,45 @@
 // @group(2): Global Engine Resources (fonts, audio, atomics, mouse)
 // @group(3): User-Defined Data Buffers (custom storage buffers)
 
+pub mod atomic;
+pub mod blur;
 pub mod builder;
+pub mod chroma;
 pub mod core;
+pub mod crossfade;
+pub mod depth;
+mod hash;
 pub mod multipass;
+mod noise;
+pub mod readback;
 pub mod resource;
+pub mod temporal;
+pub mod validate;
 
+pub use atomic::{AtomicAccumulator, ATOMIC_PLANES};
+pub use blur::Blur;
 pub use builder::*;
+pub use chroma::ChromaKey;
 pub use core::*;
+pub use crossfade::{Crossfade, TransitionKind};
+pub use depth::{DepthResolve, DEPTH_FORMAT};
 pub use multipass::*;
+pub use noise::BLUE_NOISE_SIZE;
+pub use readback::{poll_until_ready, PendingReadback, ReadbackError};
 pub use resource::*;
+pub use temporal::TemporalAccumulator;
+pub use validate::{
+    check_bindings, list_entry_points, EntryPointInfo, ShaderBuildError, ValidationReport,
+};
 
 // Texture format constants
 pub const COMPUTE_TEXTURE_FORMAT_RGBA16: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
 pub const COMPUTE_TEXTURE_FORMAT_RGBA8: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
 
+/// WGSL drawing helpers (`sdLine`, `sdCircle`, `sdBox`, `aaFill`, `drawLine`, ...) in pixel
+/// coordinates, origin top-left. Appended automatically by `ComputeShaderBuilder::with_stdlib`.
+pub const STDLIB_WGSL: &str = include_str!("../../shaders/stdlib.wgsl");
+
 use crate::Core;
 
 /// Main entry point for creating compute shaders
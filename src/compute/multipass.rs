@@ -1,4 +1,4 @@
-use crate::Core;
+use crate::{texture_memory_bytes, Core};
 use std::collections::HashMap;
 use wgpu;
 
@@ -14,6 +14,8 @@ pub struct MultiPassManager {
     width: u32,
     height: u32,
     texture_format: wgpu::TextureFormat,
+    /// Zeroed upload source for `clear_buffer` when `Features::CLEAR_TEXTURE` is missing
+    zero_buffer: Option<wgpu::Buffer>,
 }
 
 /// Note: storage layout currently un-used. I try to create our own storage-only layout
@@ -110,9 +112,35 @@ impl MultiPassManager {
             width,
             height,
             texture_format,
+            zero_buffer: Self::create_zero_buffer(&core.device, width, height, texture_format),
         }
     }
 
+    /// Row pitch of a buffer copy into one of the ping-pong textures
+    fn padded_bytes_per_row(width: u32, format: wgpu::TextureFormat) -> u32 {
+        let texel_bytes = format.block_copy_size(None).unwrap_or(4);
+        (width * texel_bytes).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+    }
+
+    /// `None` when the device can `clear_texture`. wgpu zero-initializes buffers, so the
+    /// buffer never needs to be written.
+    fn create_zero_buffer(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Option<wgpu::Buffer> {
+        if device.features().contains(wgpu::Features::CLEAR_TEXTURE) {
+            return None;
+        }
+        Some(device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Multi-Pass Zero Buffer"),
+            size: Self::padded_bytes_per_row(width, format) as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        }))
+    }
+
     fn create_storage_texture(
         device: &wgpu::Device,
         width: u32,
@@ -157,59 +185,11 @@ impl MultiPassManager {
     }
 
     fn create_input_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 4,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 5,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-            label: Some("Multi-Pass Input Layout"),
-        })
+        super::create_bind_group_layout(
+            device,
+            super::BindGroupLayoutType::MultiTexture { count: 3 },
+            "Multi-Pass Input Layout",
+        )
     }
 
     /// Get the write bind group for current frame
@@ -312,11 +292,79 @@ impl MultiPassManager {
         &self.output_texture
     }
 
+    /// Estimated VRAM of all buffers plus the output texture. Every buffer is a ping-pong
+    /// pair, so each one costs twice a screen-sized texture.
+    pub fn memory_bytes(&self) -> u64 {
+        let buffers: u64 = self
+            .buffers
+            .values()
+            .map(|(a, b)| texture_memory_bytes(a) + texture_memory_bytes(b))
+            .sum();
+        buffers + texture_memory_bytes(&self.output_texture)
+    }
+
     /// Flip ping-pong buffers
     pub fn flip_buffers(&mut self) {
         self.frame_flip = !self.frame_flip;
     }
 
+    /// Has a ping-pong buffer with this name
+    pub fn has_buffer(&self, buffer_name: &str) -> bool {
+        self.buffers.contains_key(buffer_name)
+    }
+
+    /// Copy the contents of buffers `previous` also has, with the same size and format, and
+    /// take over its ping-pong phase. Used when a shader is rebuilt to keep feedback state.
+    pub fn copy_buffers_from(&mut self, encoder: &mut wgpu::CommandEncoder, previous: &Self) {
+        self.frame_flip = previous.frame_flip;
+        for (name, (texture0, texture1)) in &self.buffers {
+            let Some((old0, old1)) = previous.buffers.get(name) else {
+                continue;
+            };
+            if old0.size() != texture0.size() || old0.format() != texture0.format() {
+                continue;
+            }
+            encoder.copy_texture_to_texture(
+                old0.as_image_copy(),
+                texture0.as_image_copy(),
+                texture0.size(),
+            );
+            encoder.copy_texture_to_texture(
+                old1.as_image_copy(),
+                texture1.as_image_copy(),
+                texture1.size(),
+            );
+        }
+    }
+
+    /// Zero both textures of one buffer, with `clear_texture` when the device supports it and
+    /// a copy from a zeroed buffer otherwise
+    pub fn clear_buffer(&self, encoder: &mut wgpu::CommandEncoder, buffer_name: &str) {
+        let Some((texture0, texture1)) = self.buffers.get(buffer_name) else {
+            return;
+        };
+        for texture in [texture0, texture1] {
+            match &self.zero_buffer {
+                None => encoder.clear_texture(texture, &wgpu::ImageSubresourceRange::default()),
+                Some(zeros) => encoder.copy_buffer_to_texture(
+                    wgpu::TexelCopyBufferInfo {
+                        buffer: zeros,
+                        layout: wgpu::TexelCopyBufferLayout {
+                            offset: 0,
+                            bytes_per_row: Some(Self::padded_bytes_per_row(
+                                self.width,
+                                self.texture_format,
+                            )),
+                            rows_per_image: None,
+                        },
+                    },
+                    texture.as_image_copy(),
+                    texture.size(),
+                ),
+            }
+        }
+    }
+
     /// Clear all buffers
     pub fn clear_all(&mut self, core: &Core) {
         // Recreate all buffer textures
@@ -374,8 +422,16 @@ impl MultiPassManager {
 
     /// Resize all buffers
     pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+        // Zero sized textures are a validation error, keep the old ones until we get a real size
+        if width == 0 || height == 0 {
+            return;
+        }
         self.width = width;
         self.height = height;
+        if self.zero_buffer.is_some() {
+            self.zero_buffer =
+                Self::create_zero_buffer(&core.device, width, height, self.texture_format);
+        }
         self.clear_all(core);
     }
 
@@ -389,3 +445,36 @@ impl MultiPassManager {
         &self.storage_layout
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_core;
+
+    #[test]
+    fn zero_sized_resize_keeps_the_buffers() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let unused_layout =
+            core.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &[],
+                });
+        let names = ["buffer_a".to_string(), "main_image".to_string()];
+        let mut manager = MultiPassManager::new(
+            core,
+            &names,
+            crate::compute::COMPUTE_TEXTURE_FORMAT_RGBA16,
+            unused_layout,
+        );
+        let before = manager.get_write_texture("buffer_a").clone();
+        manager.resize(core, 0, 0);
+        assert!(*manager.get_write_texture("buffer_a") == before);
+        assert_eq!(
+            (manager.width, manager.height),
+            (core.size.width, core.size.height)
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use crate::Core;
+
+/// Side length of the built-in blue-noise texture, see `ComputeShaderBuilder::with_blue_noise`
+pub const BLUE_NOISE_SIZE: u32 = 256;
+
+/// Classic 256x256 void-and-cluster blue noise, one 8-bit rank per texel. Every value
+/// 0-255 appears exactly 256 times and the pattern tiles seamlessly.
+const BLUE_NOISE_PNG: &[u8] = include_bytes!("../../assets/noise/blue_noise_256.png");
+
+/// Upload the blue-noise texture as `R8Unorm` with a repeating nearest sampler, so
+/// `textureSampleLevel` with uvs past 1 tiles it texel for texel
+pub(crate) fn create_blue_noise(core: &Core) -> (wgpu::TextureView, wgpu::Sampler) {
+    let image = image::load_from_memory(BLUE_NOISE_PNG)
+        .expect("Failed to load blue noise texture")
+        .into_luma8();
+    let size = wgpu::Extent3d {
+        width: image.width(),
+        height: image.height(),
+        depth_or_array_layers: 1,
+    };
+    let texture = core.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Blue Noise Texture"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::R8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    core.queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        &image,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(image.width()),
+            rows_per_image: Some(image.height()),
+        },
+        size,
+    );
+    let sampler = core.device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Blue Noise Sampler"),
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::Repeat,
+        mag_filter: wgpu::FilterMode::Nearest,
+        min_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    });
+    (
+        texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        sampler,
+    )
+}
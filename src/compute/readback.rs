@@ -0,0 +1,339 @@
+use std::fmt;
+use std::sync::mpsc;
+use std::task::Poll;
+
+/// Errors that can happen while reading GPU buffers back to the CPU
+#[derive(Debug)]
+pub enum ReadbackError {
+    /// `map_async` reported a failure or the callback never fired
+    BufferMapFailed(Option<wgpu::BufferAsyncError>),
+    /// The requested buffer does not exist on this shader
+    NoBuffer,
+    /// Waiting for the device to finish the copy failed
+    PollTimeout(wgpu::PollError),
+    /// Requested pixel is outside the texture
+    OutOfBounds,
+    /// Texture format can't be converted to f32 on the CPU
+    UnsupportedFormat(wgpu::TextureFormat),
+}
+
+impl fmt::Display for ReadbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadbackError::BufferMapFailed(Some(e)) => write!(f, "Buffer mapping failed: {e}"),
+            ReadbackError::BufferMapFailed(None) => write!(f, "Buffer mapping failed"),
+            ReadbackError::NoBuffer => write!(f, "No buffer available for readback"),
+            ReadbackError::PollTimeout(e) => write!(f, "Device poll failed: {e}"),
+            ReadbackError::OutOfBounds => write!(f, "Pixel coordinates out of bounds"),
+            ReadbackError::UnsupportedFormat(format) => {
+                write!(f, "Unsupported texture format for readback: {format:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReadbackError {}
+
+impl From<wgpu::BufferAsyncError> for ReadbackError {
+    fn from(err: wgpu::BufferAsyncError) -> Self {
+        ReadbackError::BufferMapFailed(Some(err))
+    }
+}
+
+impl From<wgpu::PollError> for ReadbackError {
+    fn from(err: wgpu::PollError) -> Self {
+        ReadbackError::PollTimeout(err)
+    }
+}
+
+/// A GPU to CPU copy that's submitted but maybe not finished. Nothing blocks until `wait`:
+/// on the event loop call `try_take` once per frame, from a background task await
+/// `poll_until_ready`.
+pub struct PendingReadback {
+    buffer: wgpu::Buffer,
+    size: u64,
+    receiver: mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>,
+    /// Padded and unpadded bytes per row for texture copies, stripped from the result
+    rows: Option<(u32, u32)>,
+}
+
+impl PendingReadback {
+    /// Start mapping `buffer` (MAP_READ) once the already submitted copy into it is done
+    pub(crate) fn new(buffer: wgpu::Buffer, size: u64) -> Self {
+        let (tx, receiver) = mpsc::channel();
+        buffer
+            .slice(..size)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = tx.send(result);
+            });
+        Self {
+            buffer,
+            size,
+            receiver,
+            rows: None,
+        }
+    }
+
+    /// Rows of a texture copy are `padded` bytes apart, keep only the first `unpadded`
+    pub(crate) fn with_row_padding(mut self, padded: u32, unpadded: u32) -> Self {
+        self.rows = Some((padded, unpadded));
+        self
+    }
+
+    /// Poll the device without blocking. `None` while the GPU is still busy.
+    pub fn try_take(&mut self, device: &wgpu::Device) -> Option<Result<Vec<u8>, ReadbackError>> {
+        if let Err(e) = device.poll(wgpu::PollType::Poll) {
+            return Some(Err(e.into()));
+        }
+        match self.receiver.try_recv() {
+            Ok(Ok(())) => Some(Ok(self.take_bytes())),
+            Ok(Err(e)) => Some(Err(e.into())),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Some(Err(ReadbackError::BufferMapFailed(None)))
+            }
+        }
+    }
+
+    /// Block until the copy is done. Freezes the window if called on the event loop
+    /// while the GPU is busy, prefer `try_take` there.
+    pub fn wait(self, device: &wgpu::Device) -> Result<Vec<u8>, ReadbackError> {
+        device.poll(wgpu::PollType::wait_indefinitely())?;
+        match self.receiver.recv() {
+            Ok(Ok(())) => Ok(self.take_bytes()),
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => Err(ReadbackError::BufferMapFailed(None)),
+        }
+    }
+
+    fn take_bytes(&self) -> Vec<u8> {
+        let data = self.buffer.slice(..self.size).get_mapped_range();
+        let bytes = match self.rows {
+            Some((padded, unpadded)) => data
+                .chunks(padded as usize)
+                .flat_map(|row| &row[..unpadded as usize])
+                .copied()
+                .collect(),
+            None => data.to_vec(),
+        };
+        drop(data);
+        self.buffer.unmap();
+        bytes
+    }
+}
+
+/// Resolves with the bytes of `readback` once the GPU is done, without ever blocking.
+///
+/// Executor-agnostic: each poll checks the device and, if the copy isn't done, wakes itself
+/// to be polled again, so it needs no wgpu-aware reactor. Other tasks on the same executor
+/// keep running in between, but with nothing else to run the executor busy-spins one core
+/// on this future for the whole wait. Use it for short waits, or poll `try_take` on a timer
+/// for long ones.
+pub async fn poll_until_ready(
+    device: &wgpu::Device,
+    mut readback: PendingReadback,
+) -> Result<Vec<u8>, ReadbackError> {
+    std::future::poll_fn(|cx| match readback.try_take(device) {
+        Some(result) => Poll::Ready(result),
+        None => {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Copy `source` into `staging` and map it for reading. `staging` must be MAP_READ | COPY_DST.
+pub(crate) fn read_buffer_bytes(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    source: &wgpu::Buffer,
+    staging: &wgpu::Buffer,
+    size: u64,
+) -> Result<Vec<u8>, ReadbackError> {
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Buffer Readback Copy"),
+    });
+    encoder.copy_buffer_to_buffer(source, 0, staging, 0, size);
+    queue.submit(std::iter::once(encoder.finish()));
+
+    PendingReadback::new(staging.clone(), size).wait(device)
+}
+
+/// Copy a single texel of `texture` to the CPU and convert it to f32 RGBA
+pub(crate) fn read_texel(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    x: u32,
+    y: u32,
+) -> Result<[f32; 4], ReadbackError> {
+    let size = texture.size();
+    if x >= size.width || y >= size.height {
+        return Err(ReadbackError::OutOfBounds);
+    }
+    let format = texture.format();
+    let bytes_per_texel = bytes_per_texel(format)?;
+
+    // A single row still has to respect the 256 byte row alignment
+    let row_bytes = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Pixel Readback Buffer"),
+        size: row_bytes as u64,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Pixel Readback Copy"),
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x, y, z: 0 },
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &staging,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(row_bytes),
+                rows_per_image: Some(1),
+            },
+        },
+        wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let data = PendingReadback::new(staging, row_bytes as u64).wait(device)?;
+    Ok(texel_to_rgba(format, &data[..bytes_per_texel]))
+}
+
+/// Copy all of `texture` to the CPU as f32 RGBA, row-major. Blocks like `read_texel`.
+pub(crate) fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Vec<[f32; 4]>, ReadbackError> {
+    let format = texture.format();
+    let bytes_per_texel = bytes_per_texel(format)?;
+    let (width, height) = (texture.width(), texture.height());
+    let unpadded = width * bytes_per_texel as u32;
+    let padded = unpadded.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let size = padded as u64 * height as u64;
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Texture Readback Buffer"),
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Texture Readback Copy"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::TexelCopyBufferInfo {
+            buffer: &staging,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let data = PendingReadback::new(staging, size)
+        .with_row_padding(padded, unpadded)
+        .wait(device)?;
+    Ok(data
+        .chunks_exact(bytes_per_texel)
+        .map(|texel| texel_to_rgba(format, texel))
+        .collect())
+}
+
+fn bytes_per_texel(format: wgpu::TextureFormat) -> Result<usize, ReadbackError> {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm
+        | wgpu::TextureFormat::Rgba8UnormSrgb
+        | wgpu::TextureFormat::Bgra8Unorm
+        | wgpu::TextureFormat::Bgra8UnormSrgb => Ok(4),
+        wgpu::TextureFormat::Rgba16Float => Ok(8),
+        wgpu::TextureFormat::Rgba32Float => Ok(16),
+        other => Err(ReadbackError::UnsupportedFormat(other)),
+    }
+}
+
+/// One texel of a `bytes_per_texel` format as f32 RGBA
+fn texel_to_rgba(format: wgpu::TextureFormat, texel: &[u8]) -> [f32; 4] {
+    let mut rgba = [0.0f32; 4];
+    match format {
+        wgpu::TextureFormat::Rgba16Float => {
+            for (i, c) in texel.chunks_exact(2).enumerate() {
+                rgba[i] = f16_to_f32(u16::from_le_bytes([c[0], c[1]]));
+            }
+        }
+        wgpu::TextureFormat::Rgba32Float => {
+            for (i, c) in texel.chunks_exact(4).enumerate() {
+                rgba[i] = f32::from_le_bytes([c[0], c[1], c[2], c[3]]);
+            }
+        }
+        _ => {
+            // 8-bit formats: values as stored, no sRGB decoding
+            for (i, &b) in texel.iter().enumerate() {
+                rgba[i] = b as f32 / 255.0;
+            }
+            if matches!(
+                format,
+                wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+            ) {
+                rgba.swap(0, 2);
+            }
+        }
+    }
+    rgba
+}
+
+/// IEEE 754 half to f32
+pub(crate) fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2.0f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2.0f32.powi(exponent - 15),
+    }
+}
+
+/// f32 to IEEE 754 half, rounding to nearest (ties away from zero). Out of range values
+/// become infinity, values too small for a subnormal become zero.
+pub(crate) fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    if value.is_nan() {
+        return sign | 0x7e00;
+    }
+    let abs = value.abs();
+    if abs >= 65520.0 {
+        return sign | 0x7c00;
+    }
+    if abs < 2.0f32.powi(-14) {
+        // Subnormal: multiples of 2^-24
+        return sign | (abs * 2.0f32.powi(24)).round() as u16;
+    }
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+    let half = ((exponent as u32) << 10) | (mantissa >> 13);
+    // Round on the dropped bits, a mantissa carry correctly bumps the exponent
+    let rounded = half + ((mantissa >> 12) & 1);
+    sign | rounded as u16
+}
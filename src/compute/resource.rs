@@ -1,3 +1,4 @@
+use super::builder::StorageBufferSpec;
 use std::collections::HashMap;
 use wgpu;
 
@@ -19,6 +20,112 @@ pub enum ResourceType {
     Sampler,
 }
 
+/// How a channel texture is sampled.
+///
+/// `srgb: true` samples through an sRGB view so the shader gets linear values from
+/// photos/video. `srgb: false` reads the stored bytes as-is, which is what data
+/// textures (heightmaps, flow fields, LUTs) need. The default matches the old behavior.
+///
+/// Formats: color images are `Rgba8UnormSrgb` everywhere (`TextureManager::new`, the
+/// placeholder input and the default channel), so a photo samples the same on the
+/// main texture path and on a channel. `srgb: false` views 8-bit textures as `Rgba8Unorm`;
+/// float, 16-bit and single-channel formats have no sRGB twin and are always linear. See
+/// `ComputeShader::load_channel_image` for loading files with the right format.
+///
+/// `anisotropy_clamp` above 1 sharpens textures seen at grazing angles (ground planes in
+/// 3D shaders). It needs linear filtering and is clamped to 1..=16; on devices without
+/// `Core::supports_anisotropic_filtering` wgpu ignores it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelConfig {
+    pub srgb: bool,
+    pub filter: wgpu::FilterMode,
+    pub address_mode: wgpu::AddressMode,
+    pub anisotropy_clamp: u16,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            srgb: true,
+            filter: wgpu::FilterMode::Linear,
+            address_mode: wgpu::AddressMode::ClampToEdge,
+            anisotropy_clamp: 1,
+        }
+    }
+}
+
+impl ChannelConfig {
+    /// Linear data texture with nearest filtering, no colorspace conversion
+    pub fn data() -> Self {
+        Self {
+            srgb: false,
+            filter: wgpu::FilterMode::Nearest,
+            address_mode: wgpu::AddressMode::ClampToEdge,
+            anisotropy_clamp: 1,
+        }
+    }
+
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_address_mode(mut self, address_mode: wgpu::AddressMode) -> Self {
+        self.address_mode = address_mode;
+        self
+    }
+
+    /// Max anisotropy, 1 (off) to 16. Values above 1 also need `FilterMode::Linear`.
+    pub fn with_anisotropy(mut self, anisotropy_clamp: u16) -> Self {
+        self.anisotropy_clamp = anisotropy_clamp;
+        self
+    }
+
+    /// `anisotropy_clamp` as wgpu accepts it: within 1..=16, and 1 unless filtering is linear
+    pub fn effective_anisotropy(&self) -> u16 {
+        let clamp = self.anisotropy_clamp.clamp(1, 16);
+        if clamp != self.anisotropy_clamp {
+            log::warn!(
+                "Anisotropy {} out of range, using {clamp}",
+                self.anisotropy_clamp
+            );
+        }
+        if clamp > 1 && self.filter != wgpu::FilterMode::Linear {
+            log::warn!("Anisotropic filtering needs linear filtering, disabling it");
+            return 1;
+        }
+        clamp
+    }
+
+    /// Format to view a texture of `format` with. Formats without an sRGB twin are unchanged.
+    pub fn view_format(&self, format: wgpu::TextureFormat) -> wgpu::TextureFormat {
+        if self.srgb {
+            format.add_srgb_suffix()
+        } else {
+            format.remove_srgb_suffix()
+        }
+    }
+
+    pub fn create_sampler(&self, device: &wgpu::Device) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Channel Sampler"),
+            address_mode_u: self.address_mode,
+            address_mode_v: self.address_mode,
+            address_mode_w: self.address_mode,
+            mag_filter: self.filter,
+            min_filter: self.filter,
+            mipmap_filter: self.filter,
+            anisotropy_clamp: self.effective_anisotropy(),
+            ..Default::default()
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ResourceBinding {
     pub group: u32,
@@ -87,6 +194,49 @@ impl ResourceLayout {
             .collect()
     }
 
+    /// Layouts for every group up to `@group(3)` plus bind groups for the unused ones.
+    ///
+    /// WebGPU pipeline layouts can't skip group indices, so a shader using groups 0, 1 and 3
+    /// still needs a layout for group 2. Missing groups get an empty layout here, and the
+    /// returned map holds an empty bind group for each of them to set while dispatching.
+    /// The layouts are indexed by group and always cover 0-3, more if a binding uses a
+    /// higher group.
+    pub fn finalize_contiguous(
+        &self,
+        device: &wgpu::Device,
+    ) -> (Vec<wgpu::BindGroupLayout>, HashMap<u32, wgpu::BindGroup>) {
+        let mut layouts = self.create_bind_group_layouts(device);
+        let group_count = layouts
+            .keys()
+            .map(|&group| group + 1)
+            .max()
+            .unwrap_or(0)
+            .max(4);
+
+        let mut contiguous = Vec::with_capacity(group_count as usize);
+        let mut empty_bind_groups = HashMap::new();
+        for group in 0..group_count {
+            match layouts.remove(&group) {
+                Some(layout) => contiguous.push(layout),
+                None => {
+                    let layout =
+                        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                            label: Some(&format!("Empty Group {group} Layout")),
+                            entries: &[],
+                        });
+                    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some(&format!("Empty Group {group} Bind Group")),
+                        layout: &layout,
+                        entries: &[],
+                    });
+                    empty_bind_groups.insert(group, bind_group);
+                    contiguous.push(layout);
+                }
+            }
+        }
+        (contiguous, empty_bind_groups)
+    }
+
     fn create_layout_entry(&self, binding: &ResourceBinding) -> wgpu::BindGroupLayoutEntry {
         wgpu::BindGroupLayoutEntry {
             binding: binding.binding,
@@ -159,14 +309,29 @@ impl ResourceLayout {
 
     // GROUP 1: Primary Pass I/O & Parameters
     pub fn add_output_texture(&mut self, format: wgpu::TextureFormat) {
-        self.add_resource(
-            1,
-            "output",
-            ResourceType::StorageTexture {
-                format,
+        self.add_output_texture_with_access(format, wgpu::StorageTextureAccess::WriteOnly);
+    }
+
+    /// Output texture with `ReadWrite` access for in-place updates, see `with_readwrite_output`
+    pub fn add_output_texture_with_access(
+        &mut self,
+        format: wgpu::TextureFormat,
+        access: wgpu::StorageTextureAccess,
+    ) {
+        self.add_resource(1, "output", ResourceType::StorageTexture { format, access });
+    }
+
+    /// R32Float depth field at a fixed binding 4, after output/params/input texture
+    pub fn add_depth_output(&mut self) {
+        self.bindings.push(ResourceBinding {
+            group: 1,
+            binding: 4,
+            name: "depth_output".to_string(),
+            resource_type: ResourceType::StorageTexture {
+                format: wgpu::TextureFormat::R32Float,
                 access: wgpu::StorageTextureAccess::WriteOnly,
             },
-        );
+        });
     }
 
     pub fn add_input_texture(&mut self) {
@@ -187,6 +352,12 @@ impl ResourceLayout {
         }
     }
 
+    /// Built-in blue-noise texture after the channels, see `with_blue_noise`
+    pub fn add_blue_noise(&mut self) {
+        self.add_resource(2, "blue_noise", ResourceType::ChannelTexture);
+        self.add_resource(2, "blue_noise_sampler", ResourceType::Sampler);
+    }
+
     pub fn add_multipass_input_textures(&mut self) {
         // Add 3 input texture pairs for multi-pass dependencies
         for i in 0..3 {
@@ -256,13 +427,242 @@ impl ResourceLayout {
 
     // GROUP 3: User-Defined Data Buffers
     pub fn add_storage_buffer(&mut self, name: &str, size: u64) {
-        self.add_resource(
-            3,
-            name,
-            ResourceType::StorageBuffer {
-                size,
-                read_only: false,
+        self.add_storage_buffer_with_access(name, size, false);
+    }
+
+    /// Storage buffer bound as `var<storage, read>` when `read_only`
+    pub fn add_storage_buffer_with_access(&mut self, name: &str, size: u64, read_only: bool) {
+        self.add_resource(3, name, ResourceType::StorageBuffer { size, read_only });
+    }
+}
+
+/// Common bind group layouts, built by role with `create_bind_group_layout`.
+///
+/// Every variant starts at binding 0. Textures and samplers are visible to compute
+/// and fragment stages, storage resources to compute only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindGroupLayoutType {
+    /// 0: texture_2d<f32>, 1: filtering sampler
+    Texture,
+    /// 0: texture_storage_2d<rgba16float, write>
+    StorageTexture,
+    /// 0: uniform buffer
+    Uniform,
+    /// `count` texture/sampler pairs: 0/1, 2/3, 4/5... (the multi-pass input layout is `count: 3`)
+    MultiTexture { count: u32 },
+    /// 0: storage buffer
+    StorageBuffer { read_only: bool },
+    /// 0: texture_2d_array<f32>, 1: filtering sampler
+    ArrayTexture,
+}
+
+impl BindGroupLayoutType {
+    const SAMPLED: wgpu::ShaderStages =
+        wgpu::ShaderStages::COMPUTE.union(wgpu::ShaderStages::FRAGMENT);
+
+    fn texture_entry(
+        binding: u32,
+        view_dimension: wgpu::TextureViewDimension,
+    ) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: Self::SAMPLED,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension,
             },
-        );
+            count: None,
+        }
+    }
+
+    fn sampler_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: Self::SAMPLED,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        }
+    }
+
+    /// Layout entries for this role
+    pub fn entries(&self) -> Vec<wgpu::BindGroupLayoutEntry> {
+        match *self {
+            BindGroupLayoutType::Texture => vec![
+                Self::texture_entry(0, wgpu::TextureViewDimension::D2),
+                Self::sampler_entry(1),
+            ],
+            BindGroupLayoutType::StorageTexture => vec![wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: wgpu::TextureFormat::Rgba16Float,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            }],
+            BindGroupLayoutType::Uniform => vec![wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            BindGroupLayoutType::MultiTexture { count } => (0..count)
+                .flat_map(|i| {
+                    [
+                        Self::texture_entry(i * 2, wgpu::TextureViewDimension::D2),
+                        Self::sampler_entry(i * 2 + 1),
+                    ]
+                })
+                .collect(),
+            BindGroupLayoutType::StorageBuffer { read_only } => vec![wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            BindGroupLayoutType::ArrayTexture => vec![
+                Self::texture_entry(0, wgpu::TextureViewDimension::D2Array),
+                Self::sampler_entry(1),
+            ],
+        }
+    }
+}
+
+/// Build a bind group layout for a common role, see `BindGroupLayoutType`
+pub fn create_bind_group_layout(
+    device: &wgpu::Device,
+    layout_type: BindGroupLayoutType,
+    label: &str,
+) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(label),
+        entries: &layout_type.entries(),
+    })
+}
+
+/// Storage buffers for `specs` at bindings 0, 1, 2... with their layout and bind group,
+/// for `ShaderManager`s that build pipelines by hand. The same buffers and layout the
+/// builder creates for `with_storage_buffer`, so a manual shader can bind the bind group
+/// at `@group(3)` and declare the buffers in the same order:
+///
+/// ```wgsl
+/// @group(3) @binding(0) var<storage, read_write> particles: array<Particle>;
+/// @group(3) @binding(1) var<storage, read> palette: array<vec4<f32>>; // with_read_only
+/// ```
+///
+/// Buffers are zeroed and can be written (`COPY_DST`) and read back (`COPY_SRC`).
+pub fn create_storage_buffer_bind_group(
+    device: &wgpu::Device,
+    specs: &[StorageBufferSpec],
+) -> (Vec<wgpu::Buffer>, wgpu::BindGroup, wgpu::BindGroupLayout) {
+    let mut layout = ResourceLayout::new();
+    for spec in specs {
+        layout.add_storage_buffer_with_access(&spec.name, spec.size_bytes, spec.read_only);
+    }
+    let entries: Vec<wgpu::BindGroupLayoutEntry> = layout
+        .get_bindings_for_group(3)
+        .into_iter()
+        .map(|binding| layout.create_layout_entry(binding))
+        .collect();
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Storage Buffer Layout"),
+        entries: &entries,
+    });
+    let (buffers, bind_group) = create_storage_buffers(
+        device,
+        &bind_group_layout,
+        specs,
+        "Storage Buffer Bind Group",
+    );
+    (buffers, bind_group, bind_group_layout)
+}
+
+/// Buffers for `specs` and a bind group with buffer `i` at binding `i` of `layout`
+pub(crate) fn create_storage_buffers(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    specs: &[StorageBufferSpec],
+    label: &str,
+) -> (Vec<wgpu::Buffer>, wgpu::BindGroup) {
+    let buffers: Vec<wgpu::Buffer> = specs
+        .iter()
+        .map(|spec| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&spec.name),
+                size: spec.size_bytes,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        })
+        .collect();
+    let entries: Vec<wgpu::BindGroupEntry> = buffers
+        .iter()
+        .enumerate()
+        .map(|(i, buffer)| wgpu::BindGroupEntry {
+            binding: i as u32,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect();
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &entries,
+        label: Some(label),
+    });
+    (buffers, bind_group)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_core;
+
+    fn uniform() -> ResourceType {
+        ResourceType::UniformBuffer { size: 16 }
+    }
+
+    #[test]
+    fn finalize_contiguous_always_covers_groups_0_to_3() {
+        let Some(core) = test_core() else {
+            return;
+        };
+
+        let (layouts, empty) = ResourceLayout::new().finalize_contiguous(&core.device);
+        assert_eq!(layouts.len(), 4);
+        let mut missing: Vec<u32> = empty.keys().copied().collect();
+        missing.sort_unstable();
+        assert_eq!(missing, [0, 1, 2, 3]);
+
+        let mut layout = ResourceLayout::new();
+        layout.add_resource(0, "time", uniform());
+        layout.add_resource(1, "params", uniform());
+        layout.add_resource(3, "data", uniform());
+        let (layouts, empty) = layout.finalize_contiguous(&core.device);
+        assert_eq!(layouts.len(), 4);
+        assert_eq!(empty.keys().copied().collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn finalize_contiguous_extends_past_group_3() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        let mut layout = ResourceLayout::new();
+        layout.add_resource(5, "extra", uniform());
+        let (layouts, empty) = layout.finalize_contiguous(&core.device);
+        assert_eq!(layouts.len(), 6);
+        assert_eq!(empty.len(), 5);
+        assert!(!empty.contains_key(&5));
     }
 }
@@ -0,0 +1,284 @@
+use super::COMPUTE_TEXTURE_FORMAT_RGBA16;
+use super::{create_bind_group_layout, BindGroupLayoutType, ComputeShader};
+use crate::{Core, TextureManager};
+use wgpu::util::DeviceExt;
+
+const TEMPORAL_SHADER: &str = include_str!("../../shaders/temporal.wgsl");
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TemporalParams {
+    feedback: f32,
+    frame_count: u32,
+    _padding: [u32; 2],
+}
+
+/// Accumulates a (jittered) compute output across frames into a history texture.
+///
+/// Each `accumulate` blends the new frame into the history: a running average for the
+/// first frames, then an exponential blend where `feedback` is the weight of the history
+/// (0.9 keeps ~10 frames, 0.99 ~100). There is no reprojection, so call `reset` when
+/// `ControlsRequest::should_clear_buffers` is set or the camera moves, or let
+/// `reset_if_changed` watch the params. Works on `Rgba16Float`.
+pub struct TemporalAccumulator {
+    pipeline: wgpu::ComputePipeline,
+    input_layout: wgpu::BindGroupLayout,
+    output_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    params_buffer: wgpu::Buffer,
+    params_bind_group: wgpu::BindGroup,
+    history: wgpu::Texture,
+    history_view: wgpu::TextureView,
+    output: TextureManager,
+    output_bind_group: wgpu::BindGroup,
+    feedback: f32,
+    frame_count: u32,
+    last_params: Option<Vec<u8>>,
+    width: u32,
+    height: u32,
+}
+
+impl TemporalAccumulator {
+    pub fn new(core: &Core, feedback: f32) -> Self {
+        let device = &core.device;
+
+        let input_layout = create_bind_group_layout(
+            device,
+            BindGroupLayoutType::MultiTexture { count: 2 },
+            "Temporal Input Layout",
+        );
+        let params_layout =
+            create_bind_group_layout(device, BindGroupLayoutType::Uniform, "Temporal Params");
+        let output_layout = create_bind_group_layout(
+            device,
+            BindGroupLayoutType::StorageTexture,
+            "Temporal Output Layout",
+        );
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Temporal Pipeline Layout"),
+            bind_group_layouts: &[&input_layout, &params_layout, &output_layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Temporal Shader"),
+            source: wgpu::ShaderSource::Wgsl(TEMPORAL_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Temporal Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: core.pipeline_cache(),
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Temporal Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            ..Default::default()
+        });
+
+        let feedback = feedback.clamp(0.0, 1.0);
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Temporal Params"),
+            contents: bytemuck::bytes_of(&TemporalParams {
+                feedback,
+                frame_count: 0,
+                _padding: [0; 2],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let params_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Temporal Params"),
+            layout: &params_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params_buffer.as_entire_binding(),
+            }],
+        });
+
+        let (width, height) = (core.size.width, core.size.height);
+        let (history, history_view) = Self::create_history(device, width, height);
+        let (output, output_bind_group) =
+            Self::create_output(device, &output_layout, width, height);
+
+        Self {
+            pipeline,
+            input_layout,
+            output_layout,
+            sampler,
+            params_buffer,
+            params_bind_group,
+            history,
+            history_view,
+            output,
+            output_bind_group,
+            feedback,
+            frame_count: 0,
+            last_params: None,
+            width,
+            height,
+        }
+    }
+
+    fn create_history(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Temporal History Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: COMPUTE_TEXTURE_FORMAT_RGBA16,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view)
+    }
+
+    fn create_output(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        width: u32,
+        height: u32,
+    ) -> (TextureManager, wgpu::BindGroup) {
+        let output = ComputeShader::create_output_texture(
+            device,
+            width,
+            height,
+            COMPUTE_TEXTURE_FORMAT_RGBA16,
+            "Temporal Output Texture",
+        );
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Temporal Output Storage"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&output.view),
+            }],
+        });
+        (output, bind_group)
+    }
+
+    /// History weight of the exponential blend, 0 disables accumulation
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(0.0, 1.0);
+    }
+
+    pub fn feedback(&self) -> f32 {
+        self.feedback
+    }
+
+    /// Frames accumulated since the last reset
+    pub fn frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    /// Drop the history, the next frame starts a new accumulation
+    pub fn reset(&mut self) {
+        self.frame_count = 0;
+    }
+
+    /// Reset when `params` differ from the ones seen on the previous call
+    pub fn reset_if_changed<T: bytemuck::Pod>(&mut self, params: &T) -> bool {
+        let bytes = bytemuck::bytes_of(params);
+        let changed = self.last_params.as_deref() != Some(bytes);
+        if changed {
+            self.last_params = Some(bytes.to_vec());
+            self.reset();
+        }
+        changed
+    }
+
+    /// Blend `current` into the history and return the accumulated image.
+    /// `current` must be `Rgba16Float` and the accumulator's size.
+    pub fn accumulate(
+        &mut self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        current: &TextureManager,
+    ) -> &TextureManager {
+        let params = TemporalParams {
+            feedback: self.feedback,
+            frame_count: self.frame_count,
+            _padding: [0; 2],
+        };
+        core.queue
+            .write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+
+        let input_bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Temporal Input"),
+            layout: &self.input_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&current.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&self.history_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Temporal Accumulation Pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &input_bind_group, &[]);
+            pass.set_bind_group(1, &self.params_bind_group, &[]);
+            pass.set_bind_group(2, &self.output_bind_group, &[]);
+            pass.dispatch_workgroups(self.width.div_ceil(16), self.height.div_ceil(16), 1);
+        }
+
+        // The blended result is next frame's history
+        encoder.copy_texture_to_texture(
+            self.output.texture.as_image_copy(),
+            self.history.as_image_copy(),
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.frame_count = self.frame_count.saturating_add(1);
+
+        &self.output
+    }
+
+    pub fn output(&self) -> &TextureManager {
+        &self.output
+    }
+
+    pub fn resize(&mut self, core: &Core, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        (self.history, self.history_view) = Self::create_history(&core.device, width, height);
+        (self.output, self.output_bind_group) =
+            Self::create_output(&core.device, &self.output_layout, width, height);
+        self.reset();
+    }
+}
@@ -0,0 +1,325 @@
+use super::resource::{ResourceLayout, ResourceType};
+use crate::Core;
+use std::fmt;
+
+/// Why a compute shader can't be built
+#[derive(Debug, Clone)]
+pub enum ShaderBuildError {
+    /// WGSL failed to parse, message includes the source location
+    Parse(String),
+    /// wgpu rejected the module (type errors, invalid bindings...)
+    Validation(String),
+    /// A configured entry point isn't a `@compute` function in the source
+    MissingEntryPoint(String),
+    /// A multi-pass pass reads a buffer no pass writes and that isn't a scratch buffer
+    UnknownPassInput { pass: String, input: String },
+}
+
+impl fmt::Display for ShaderBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderBuildError::Parse(msg) => write!(f, "Shader parse error: {msg}"),
+            ShaderBuildError::Validation(msg) => write!(f, "Shader validation error: {msg}"),
+            ShaderBuildError::MissingEntryPoint(name) => {
+                write!(f, "Compute entry point '{name}' not found in shader")
+            }
+            ShaderBuildError::UnknownPassInput { pass, input } => {
+                write!(f, "Pass '{pass}' reads unknown buffer '{input}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderBuildError {}
+
+/// An entry point found in a shader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPointInfo {
+    pub name: String,
+    /// `COMPUTE`, `VERTEX` or `FRAGMENT`
+    pub stage: wgpu::ShaderStages,
+    /// `@workgroup_size`, `[0, 0, 0]` for non-compute stages
+    pub workgroup_size: [u32; 3],
+}
+
+/// Result of `ComputeShaderBuilder::validate`
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    /// All compute entry points in the source, in declaration order
+    pub entry_points: Vec<EntryPointInfo>,
+}
+
+impl ValidationReport {
+    pub fn entry_point(&self, name: &str) -> Option<&EntryPointInfo> {
+        self.entry_points.iter().find(|e| e.name == name)
+    }
+}
+
+/// Parse WGSL with naga, no GPU involved
+pub fn parse_wgsl(source: &str) -> Result<naga::Module, ShaderBuildError> {
+    naga::front::wgsl::parse_str(source)
+        .map_err(|e| ShaderBuildError::Parse(e.emit_to_string(source)))
+}
+
+/// List the `@compute` entry points of a WGSL source with their workgroup sizes
+pub fn reflect_entry_points(source: &str) -> Result<Vec<EntryPointInfo>, ShaderBuildError> {
+    Ok(reflect_all_entry_points(source)?
+        .into_iter()
+        .filter(|ep| ep.stage == wgpu::ShaderStages::COMPUTE)
+        .collect())
+}
+
+fn reflect_all_entry_points(source: &str) -> Result<Vec<EntryPointInfo>, ShaderBuildError> {
+    let module = parse_wgsl(source)?;
+    Ok(module
+        .entry_points
+        .iter()
+        .filter_map(|ep| {
+            let stage = match ep.stage {
+                naga::ShaderStage::Compute => wgpu::ShaderStages::COMPUTE,
+                naga::ShaderStage::Vertex => wgpu::ShaderStages::VERTEX,
+                naga::ShaderStage::Fragment => wgpu::ShaderStages::FRAGMENT,
+                _ => return None,
+            };
+            Some(EntryPointInfo {
+                name: ep.name.clone(),
+                stage,
+                workgroup_size: ep.workgroup_size,
+            })
+        })
+        .collect())
+}
+
+/// Every entry point of a WGSL source in declaration order, with its stage and workgroup
+/// size. Returns an empty list (and logs why) if the source doesn't parse.
+pub fn list_entry_points(source: &str) -> Vec<EntryPointInfo> {
+    reflect_all_entry_points(source).unwrap_or_else(|e| {
+        log::warn!("Can't list entry points: {e}");
+        Vec::new()
+    })
+}
+
+/// Compile `source` inside an error scope and check `required_entry_points` exist.
+/// Only a shader module is created, no pipelines, buffers or textures.
+pub(crate) fn validate_source(
+    core: &Core,
+    source: &str,
+    required_entry_points: &[String],
+    label: &str,
+) -> Result<ValidationReport, ShaderBuildError> {
+    let entry_points = reflect_entry_points(source)?;
+
+    core.device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let _module = core
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&format!("{label} Validation Module")),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+    if let Some(error) = pollster::block_on(core.device.pop_error_scope()) {
+        return Err(ShaderBuildError::Validation(error.to_string()));
+    }
+
+    for name in required_entry_points {
+        if !entry_points.iter().any(|e| &e.name == name) {
+            return Err(ShaderBuildError::MissingEntryPoint(name.clone()));
+        }
+    }
+
+    Ok(ValidationReport { entry_points })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindingKind {
+    Uniform,
+    Storage,
+    StorageTexture,
+    Texture,
+    Sampler,
+}
+
+impl BindingKind {
+    fn of_resource(resource_type: &ResourceType) -> Self {
+        match resource_type {
+            ResourceType::UniformBuffer { .. } => BindingKind::Uniform,
+            ResourceType::StorageBuffer { .. } => BindingKind::Storage,
+            ResourceType::StorageTexture { .. } => BindingKind::StorageTexture,
+            ResourceType::InputTexture | ResourceType::ChannelTexture => BindingKind::Texture,
+            ResourceType::Sampler => BindingKind::Sampler,
+        }
+    }
+
+    fn of_global(module: &naga::Module, var: &naga::GlobalVariable) -> Option<Self> {
+        match var.space {
+            naga::AddressSpace::Uniform => return Some(BindingKind::Uniform),
+            naga::AddressSpace::Storage { .. } => return Some(BindingKind::Storage),
+            _ => {}
+        }
+        let mut inner = &module.types[var.ty].inner;
+        if let naga::TypeInner::BindingArray { base, .. } = inner {
+            inner = &module.types[*base].inner;
+        }
+        match inner {
+            naga::TypeInner::Image {
+                class: naga::ImageClass::Storage { .. },
+                ..
+            } => Some(BindingKind::StorageTexture),
+            naga::TypeInner::Image { .. } => Some(BindingKind::Texture),
+            naga::TypeInner::Sampler { .. } => Some(BindingKind::Sampler),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            BindingKind::Uniform => "a uniform buffer",
+            BindingKind::Storage => "a storage buffer",
+            BindingKind::StorageTexture => "a storage texture",
+            BindingKind::Texture => "a texture",
+            BindingKind::Sampler => "a sampler",
+        }
+    }
+}
+
+/// Builder call that usually provides a binding, guessed from the WGSL variable name
+fn builder_hint(name: &str) -> Option<&'static str> {
+    let hint = match name {
+        "params" => "with_custom_uniforms::<T>()",
+        "mouse" => "with_mouse()",
+        "audio_buffer" => "with_audio(size)",
+        "audio_spectrum" => "with_audio_spectrum(size)",
+        "atomic_buffer" => "with_atomic_buffer()",
+        n if n.starts_with("font") => "with_fonts()",
+        n if n.starts_with("blue_noise") => "with_blue_noise()",
+        n if n.starts_with("channel") => "with_channels(n)",
+        n if n.starts_with("input_texture") || n.starts_with("input_sampler") => {
+            "with_input_texture() or with_multi_pass(..)"
+        }
+        _ => return None,
+    };
+    Some(hint)
+}
+
+/// Compare the `@group/@binding` declarations of a WGSL source against the layout the
+/// builder produced. Returns one message per mismatch, empty when everything lines up.
+///
+/// Bindings the layout has but the shader doesn't declare are fine and not reported.
+pub fn check_bindings(
+    source: &str,
+    layout: &ResourceLayout,
+) -> Result<Vec<String>, ShaderBuildError> {
+    let module = parse_wgsl(source)?;
+    let mut issues = Vec::new();
+
+    for (_, var) in module.global_variables.iter() {
+        let Some(binding) = &var.binding else {
+            continue;
+        };
+        let name = var.name.as_deref().unwrap_or("<unnamed>");
+        let location = format!("@group({}) @binding({})", binding.group, binding.binding);
+
+        let provided = layout
+            .bindings
+            .iter()
+            .find(|b| b.group == binding.group && b.binding == binding.binding);
+        let Some(provided) = provided else {
+            let mut message =
+                format!("shader uses {location} ('{name}') but the builder didn't enable it");
+            if let Some(hint) = builder_hint(name) {
+                message.push_str(&format!(", did you forget {hint}?"));
+            }
+            issues.push(message);
+            continue;
+        };
+
+        let expected = BindingKind::of_resource(&provided.resource_type);
+        if let Some(declared) = BindingKind::of_global(&module, var) {
+            if declared != expected {
+                issues.push(format!(
+                    "shader declares {location} ('{name}') as {} but the builder bound '{}' there, which is {}",
+                    declared.describe(),
+                    provided.name,
+                    expected.describe()
+                ));
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// One `@group/@binding` declaration of a shader, what hot reload compares to decide whether
+/// the pipeline layout is still valid
+#[derive(Debug, Clone)]
+pub(crate) struct BindingSignature {
+    pub group: u32,
+    pub binding: u32,
+    pub name: String,
+    kind: BindingKind,
+    /// Byte size of uniform and storage buffers, `None` for runtime-sized arrays and
+    /// non-buffer bindings
+    pub size: Option<u64>,
+}
+
+impl BindingSignature {
+    pub fn is_uniform(&self) -> bool {
+        self.kind == BindingKind::Uniform
+    }
+
+    pub fn is_storage(&self) -> bool {
+        self.kind == BindingKind::Storage
+    }
+
+    fn key(&self) -> (u32, u32, BindingKind, Option<u64>) {
+        (self.group, self.binding, self.kind, self.size)
+    }
+}
+
+/// The bindings a WGSL source declares, sorted by group then binding
+pub(crate) fn reflect_bindings(source: &str) -> Result<Vec<BindingSignature>, ShaderBuildError> {
+    let module = parse_wgsl(source)?;
+    let mut bindings: Vec<BindingSignature> = module
+        .global_variables
+        .iter()
+        .filter_map(|(_, var)| {
+            let binding = var.binding.as_ref()?;
+            let kind = BindingKind::of_global(&module, var)?;
+            let size = matches!(kind, BindingKind::Uniform | BindingKind::Storage)
+                .then(|| buffer_size(&module, var.ty))
+                .flatten();
+            Some(BindingSignature {
+                group: binding.group,
+                binding: binding.binding,
+                name: var.name.clone().unwrap_or_default(),
+                kind,
+                size,
+            })
+        })
+        .collect();
+    bindings.sort_by_key(|b| (b.group, b.binding));
+    Ok(bindings)
+}
+
+/// Whether two binding sets need different pipeline layouts. Renaming a binding doesn't count.
+pub(crate) fn layout_changed(old: &[BindingSignature], new: &[BindingSignature]) -> bool {
+    old.len() != new.len() || old.iter().zip(new).any(|(a, b)| a.key() != b.key())
+}
+
+/// Size of a buffer type, `None` if it ends in a runtime-sized array
+fn buffer_size(module: &naga::Module, ty: naga::Handle<naga::Type>) -> Option<u64> {
+    let inner = &module.types[ty].inner;
+    let last = match inner {
+        naga::TypeInner::Struct { members, .. } => {
+            members.last().map(|m| &module.types[m.ty].inner)
+        }
+        other => Some(other),
+    };
+    if let Some(naga::TypeInner::Array {
+        size: naga::ArraySize::Dynamic,
+        ..
+    }) = last
+    {
+        return None;
+    }
+    Some(inner.size(module.to_ctx()) as u64)
+}
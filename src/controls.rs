@@ -1,6 +1,7 @@
 #[cfg(feature = "media")]
 use crate::gst::video::VideoTextureManager;
 use crate::hdri::HdriMetadata;
+use crate::{ColorGrading, FrequencyScale, SpectrumWeighting};
 use std::path::PathBuf;
 #[derive(Clone)]
 pub struct ControlsRequest {
@@ -8,9 +9,17 @@ pub struct ControlsRequest {
     pub should_reset: bool,
     pub should_clear_buffers: bool,
     pub current_time: Option<f32>,
+    /// Shader time speed multiplier, see `ShaderControls::set_time_scale`
+    pub time_scale: f32,
     pub window_size: Option<(u32, u32)>,
 
     pub current_fps: Option<f32>,
+    /// `Core::max_frame_latency` as of the last frame
+    pub frame_latency: u32,
+    /// Frame latency picked in the advanced panel, the app applies it to the `Core`
+    pub max_frame_latency: Option<u32>,
+    /// `ComputeShader::estimated_memory_bytes` of the shader, see `RenderKit::sync_shader_controls`
+    pub gpu_memory_bytes: Option<u64>,
 
     // Video reqs
     pub load_media_path: Option<PathBuf>,
@@ -24,6 +33,17 @@ pub struct ControlsRequest {
     pub set_volume: Option<f64>,
     pub mute_audio: Option<bool>,
     pub toggle_mute: bool,
+    pub spectrum_scale: Option<FrequencyScale>,
+    pub spectrum_smoothing: Option<f32>,
+    pub spectrum_weighting: Option<SpectrumWeighting>,
+    /// Current spectrum mapping, `None` unless the shader analyzes audio
+    pub spectrum: Option<SpectrumSettings>,
+    /// Passes of a multi-pass shader, see `RenderKit::sync_shader_controls`
+    pub pass_graph: Option<PassGraph>,
+    /// Passes switched on or off with the pass toggles, `(entry point, enabled)`
+    pub set_pass_enabled: Vec<(String, bool)>,
+    /// Channel thumbnails shown in the media panel, see `ShaderControls::set_debug_channels`
+    pub channel_previews: Vec<(u32, Option<egui::TextureId>)>,
 
     // HDRI reqs
     pub hdri_exposure: Option<f32>,
@@ -33,7 +53,84 @@ pub struct ControlsRequest {
     pub start_webcam: bool,
     pub stop_webcam: bool,
     pub webcam_device_index: Option<u32>,
+
+    // Action buttons registered with `ShaderControls::register_button`
+    pub action_buttons: Vec<ActionButton>,
+    pub fired_actions: Vec<String>,
+
+    // Live-view composition overlay
+    pub guides: CompositionGuides,
+
+    // Display color grading: the current values and an edit from the grading panel
+    pub color_grading: ColorGrading,
+    pub set_color_grading: Option<ColorGrading>,
+}
+
+impl ControlsRequest {
+    /// Whether the action button `name` was clicked this frame
+    pub fn action_fired(&self, name: &str) -> bool {
+        self.fired_actions.iter().any(|action| action == name)
+    }
+}
+
+/// A named button shown in the controls widget, see `ShaderControls::register_button`
+#[derive(Clone, Debug)]
+pub struct ActionButton {
+    pub name: String,
+    pub tooltip: Option<String>,
+}
+
+/// Delivery aspect ratio outlined by `CompositionGuides`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GuideAspect {
+    Landscape16x9,
+    Square,
+    Portrait9x16,
 }
+
+impl GuideAspect {
+    pub const ALL: [GuideAspect; 3] = [
+        GuideAspect::Landscape16x9,
+        GuideAspect::Square,
+        GuideAspect::Portrait9x16,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GuideAspect::Landscape16x9 => "16:9",
+            GuideAspect::Square => "1:1",
+            GuideAspect::Portrait9x16 => "9:16",
+        }
+    }
+
+    /// Width over height
+    pub fn ratio(&self) -> f32 {
+        match self {
+            GuideAspect::Landscape16x9 => 16.0 / 9.0,
+            GuideAspect::Square => 1.0,
+            GuideAspect::Portrait9x16 => 9.0 / 16.0,
+        }
+    }
+}
+
+/// Composition aids drawn over the live view with egui, see `ShaderControls::draw_guides`.
+/// They're part of the UI, so exports never contain them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CompositionGuides {
+    pub center_cross: bool,
+    pub rule_of_thirds: bool,
+    /// 90% action-safe and 80% title-safe rectangles
+    pub safe_areas: bool,
+    /// Largest centered box of this aspect ratio, the frame a crop for that format keeps
+    pub aspect: Option<GuideAspect>,
+}
+
+impl CompositionGuides {
+    pub fn any_enabled(&self) -> bool {
+        self.center_cross || self.rule_of_thirds || self.safe_areas || self.aspect.is_some()
+    }
+}
+
 impl Default for ControlsRequest {
     fn default() -> Self {
         let mut default_media = None;
@@ -53,9 +150,13 @@ impl Default for ControlsRequest {
             should_reset: false,
             should_clear_buffers: false,
             current_time: None,
+            time_scale: 1.0,
             window_size: None,
 
             current_fps: None,
+            gpu_memory_bytes: None,
+            frame_latency: crate::Core::DEFAULT_FRAME_LATENCY,
+            max_frame_latency: None,
 
             // Video-related stuff
             load_media_path: default_media,
@@ -69,6 +170,13 @@ impl Default for ControlsRequest {
             set_volume: None,
             mute_audio: None,
             toggle_mute: false,
+            spectrum_scale: None,
+            spectrum_smoothing: None,
+            spectrum_weighting: None,
+            spectrum: None,
+            pass_graph: None,
+            set_pass_enabled: Vec::new(),
+            channel_previews: Vec::new(),
 
             // HDRI-related stuff
             hdri_exposure: None,
@@ -78,6 +186,142 @@ impl Default for ControlsRequest {
             start_webcam: false,
             stop_webcam: false,
             webcam_device_index: None,
+
+            action_buttons: Vec::new(),
+            fired_actions: Vec::new(),
+
+            guides: CompositionGuides::default(),
+
+            color_grading: ColorGrading::NEUTRAL,
+            set_color_grading: None,
+        }
+    }
+}
+
+/// Spectrum mapping shown by `ShaderControls::render_spectrum_controls`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectrumSettings {
+    pub scale: FrequencyScale,
+    pub smoothing: f32,
+    pub weighting: SpectrumWeighting,
+}
+
+/// One row of `PassGraph`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassSummary {
+    pub entry_point: String,
+    /// Entry point, with its output buffer and `(cleared)` for non-persistent ones
+    pub name: String,
+    pub workgroups: [u32; 3],
+    /// Buffers the pass reads, `-` for none
+    pub inputs: String,
+    /// See `ComputeShader::set_pass_enabled`
+    pub enabled: bool,
+}
+
+/// What a shader runs each frame: passes in dispatch order with their workgroup counts and
+/// inputs, plus the active resource groups. Shown by `ShaderControls::render_pass_graph`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PassGraph {
+    pub passes: Vec<PassSummary>,
+    pub dispatch_once: bool,
+    /// Contents of bind groups 1 to 3
+    pub groups: [String; 3],
+}
+
+impl PassGraph {
+    /// Snapshot of `shader` with workgroup counts at `screen_size`
+    pub fn of(shader: &crate::compute::ComputeShader, screen_size: (u32, u32)) -> Self {
+        let screen_count = [
+            screen_size.0.div_ceil(shader.workgroup_size[0]),
+            screen_size.1.div_ceil(shader.workgroup_size[1]),
+            1,
+        ];
+        let passes = shader
+            .entry_points
+            .iter()
+            .enumerate()
+            .map(|(i, entry_point)| {
+                let pass = shader
+                    .pass_descriptions
+                    .as_ref()
+                    .and_then(|passes| passes.get(i));
+                let inputs = shader
+                    .pass_dependencies
+                    .as_ref()
+                    .and_then(|deps| deps.get(entry_point))
+                    .filter(|deps| !deps.is_empty())
+                    .map(|deps| deps.join(", "))
+                    .unwrap_or_else(|| "-".to_string());
+
+                let mut name = entry_point.clone();
+                if let Some(output) = pass.and_then(|p| p.output.as_ref()) {
+                    name.push_str(&format!(" -> {output}"));
+                }
+                if pass.is_some_and(|p| !p.persistent) {
+                    name.push_str(" (cleared)");
+                }
+                PassSummary {
+                    entry_point: entry_point.clone(),
+                    name,
+                    workgroups: pass.and_then(|p| p.workgroup_size).unwrap_or(screen_count),
+                    inputs,
+                    enabled: shader.is_pass_enabled(entry_point),
+                }
+            })
+            .collect();
+
+        let mut group1 = vec!["output"];
+        if shader.custom_uniform.is_some() {
+            group1.push("params");
+        }
+        if shader.has_input_texture {
+            group1.push("input texture");
+        }
+        if shader.depth_output.is_some() {
+            group1.push("depth");
+        }
+
+        let mut group2 = Vec::new();
+        if shader.mouse_uniform.is_some() {
+            group2.push("mouse".to_string());
+        }
+        if shader.font_system.is_some() {
+            group2.push("fonts".to_string());
+        }
+        if shader.audio_buffer.is_some() {
+            group2.push("audio".to_string());
+        }
+        if shader.atomic_buffer_raw.is_some() {
+            group2.push("atomics".to_string());
+        }
+        if shader.audio_spectrum_buffer.is_some() {
+            group2.push("spectrum".to_string());
+        }
+        if shader.num_channels > 0 {
+            group2.push(format!("{} channels", shader.num_channels));
+        }
+
+        let group3 = if !shader.storage_buffers.is_empty() {
+            format!("{} storage buffers", shader.storage_buffers.len())
+        } else if shader.multipass_manager.is_some() {
+            "multi-pass inputs".to_string()
+        } else {
+            "-".to_string()
+        };
+
+        Self {
+            passes,
+            dispatch_once: shader.dispatch_once,
+            groups: [
+                group1.join(", "),
+                if group2.is_empty() {
+                    "-".to_string()
+                } else {
+                    group2.join(", ")
+                },
+                group3,
+            ],
         }
     }
 }
@@ -101,6 +345,27 @@ pub struct ShaderControls {
     total_pause_duration: f32,
     current_frame: u32,
     media_loaded_once: bool,
+    debug_channels: bool,
+    channel_previews: Vec<(u32, Option<egui::TextureId>)>,
+    time_override: Option<f32>,
+    action_buttons: Vec<ActionButton>,
+    time_scale: f32,
+    // Keeps shader time continuous across scale changes
+    time_scale_offset: f32,
+    time_origin: Option<std::time::Instant>,
+    guides: CompositionGuides,
+    /// Mirror of `RenderKit::color_grading` for the grading panel
+    color_grading: ColorGrading,
+    /// Mirror of `Core::max_frame_latency` for the advanced panel
+    frame_latency: u32,
+    /// Latency picked in the advanced panel, not yet applied to the `Core`
+    frame_latency_request: Option<u32>,
+    gpu_memory_bytes: Option<u64>,
+    /// Mirror of the `SpectrumAnalyzer` settings for the spectrum section
+    spectrum: Option<SpectrumSettings>,
+    pass_graph: Option<PassGraph>,
+    /// Pass toggles not yet applied to the shader
+    pass_toggles: Vec<(String, bool)>,
 }
 
 impl Default for ShaderControls {
@@ -111,6 +376,21 @@ impl Default for ShaderControls {
             total_pause_duration: 0.0,
             current_frame: 0,
             media_loaded_once: false,
+            debug_channels: std::env::var("CUNEUS_DEBUG_CHANNELS").is_ok(),
+            channel_previews: Vec::new(),
+            time_override: None,
+            action_buttons: Vec::new(),
+            time_scale: 1.0,
+            time_scale_offset: 0.0,
+            time_origin: None,
+            guides: CompositionGuides::default(),
+            color_grading: ColorGrading::NEUTRAL,
+            frame_latency: crate::Core::DEFAULT_FRAME_LATENCY,
+            frame_latency_request: None,
+            gpu_memory_bytes: None,
+            spectrum: None,
+            pass_graph: None,
+            pass_toggles: Vec::new(),
         }
     }
 }
@@ -120,6 +400,15 @@ impl ShaderControls {
         Self::default()
     }
 
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    /// Frame counter as of the last `get_frame`
+    pub fn current_frame(&self) -> u32 {
+        self.current_frame
+    }
+
     pub fn get_frame(&mut self) -> u32 {
         if !self.is_paused {
             self.current_frame = self.current_frame.wrapping_add(1);
@@ -127,7 +416,20 @@ impl ShaderControls {
         self.current_frame
     }
 
+    /// Drive `get_time` from an external clock (input replay), `None` to go back to wall time
+    pub fn set_time_override(&mut self, time: Option<f32>) {
+        self.time_override = time;
+    }
+
+    /// Shader time: wall time since `start_time` minus pauses, multiplied by the time scale
     pub fn get_time(&self, start_time: &std::time::Instant) -> f32 {
+        if let Some(time) = self.time_override {
+            return time;
+        }
+        self.unpaused_time(start_time) * self.time_scale + self.time_scale_offset
+    }
+
+    fn unpaused_time(&self, start_time: &std::time::Instant) -> f32 {
         let raw_time = start_time.elapsed().as_secs_f32();
         if self.is_paused {
             if let Some(pause_start) = self.pause_start {
@@ -140,11 +442,27 @@ impl ShaderControls {
         }
     }
 
+    /// Speed up (> 1) or slow down (< 1) shader time for the live view, 0 freezes it.
+    /// Independent from pause and from the export time settings; time stays continuous.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        let scale = scale.max(0.0);
+        if let Some(origin) = self.time_origin {
+            let unpaused = self.unpaused_time(&origin);
+            self.time_scale_offset += unpaused * (self.time_scale - scale);
+        }
+        self.time_scale = scale;
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
     pub fn get_ui_request(
         &mut self,
         start_time: &std::time::Instant,
         size: &winit::dpi::PhysicalSize<u32>,
     ) -> ControlsRequest {
+        self.time_origin = Some(*start_time);
         let mut load_media_path = None;
         let mut play_video = false;
         if !self.media_loaded_once {
@@ -165,8 +483,12 @@ impl ShaderControls {
             should_reset: false,
             should_clear_buffers: false,
             current_time: Some(self.get_time(start_time)),
+            time_scale: self.time_scale,
             window_size: Some((size.width, size.height)),
             current_fps: None,
+            gpu_memory_bytes: self.gpu_memory_bytes,
+            frame_latency: self.frame_latency,
+            max_frame_latency: None,
 
             load_media_path,
             play_video,
@@ -177,6 +499,13 @@ impl ShaderControls {
             set_volume: None,
             mute_audio: None,
             toggle_mute: false,
+            spectrum_scale: None,
+            spectrum_smoothing: None,
+            spectrum_weighting: None,
+            spectrum: self.spectrum,
+            pass_graph: self.pass_graph.clone(),
+            set_pass_enabled: Vec::new(),
+            channel_previews: self.channel_previews.clone(),
 
             hdri_exposure: None,
             hdri_gamma: None,
@@ -184,9 +513,50 @@ impl ShaderControls {
             start_webcam: false,
             stop_webcam: false,
             webcam_device_index: None,
+
+            action_buttons: self.action_buttons.clone(),
+            fired_actions: Vec::new(),
+
+            guides: self.guides,
+
+            color_grading: self.color_grading,
+            set_color_grading: None,
+        }
+    }
+
+    /// Add a button to the controls widget. Clicks show up in
+    /// `ControlsRequest::fired_actions` (check with `action_fired(name)`).
+    /// Registering the same name twice updates the tooltip.
+    pub fn register_button(&mut self, name: impl Into<String>, tooltip: Option<&str>) {
+        let name = name.into();
+        let tooltip = tooltip.map(str::to_string);
+        match self.action_buttons.iter_mut().find(|b| b.name == name) {
+            Some(button) => button.tooltip = tooltip,
+            None => self.action_buttons.push(ActionButton { name, tooltip }),
         }
     }
 
+    pub fn unregister_button(&mut self, name: &str) {
+        self.action_buttons.retain(|b| b.name != name);
+    }
+
+    pub fn render_action_buttons(ui: &mut egui::Ui, request: &mut ControlsRequest) {
+        if request.action_buttons.is_empty() {
+            return;
+        }
+        ui.horizontal_wrapped(|ui| {
+            for button in &request.action_buttons {
+                let mut response = ui.button(&button.name);
+                if let Some(tooltip) = &button.tooltip {
+                    response = response.on_hover_text(tooltip);
+                }
+                if response.clicked() {
+                    request.fired_actions.push(button.name.clone());
+                }
+            }
+        });
+    }
+
     pub fn apply_ui_request(&mut self, request: ControlsRequest) {
         if request.should_reset {
             self.is_paused = false;
@@ -194,6 +564,7 @@ impl ShaderControls {
             self.total_pause_duration = 0.0;
             self.current_frame = 0;
             self.media_loaded_once = false;
+            self.time_scale_offset = 0.0;
         } else if request.is_paused && !self.is_paused {
             self.pause_start = Some(std::time::Instant::now());
         } else if !request.is_paused && self.is_paused {
@@ -203,6 +574,161 @@ impl ShaderControls {
             self.pause_start = None;
         }
         self.is_paused = request.is_paused;
+        if request.time_scale != self.time_scale {
+            self.set_time_scale(request.time_scale);
+        }
+        self.guides = request.guides;
+        if let Some(latency) = request.max_frame_latency {
+            self.frame_latency_request = Some(latency);
+        }
+        self.pass_toggles.extend(request.set_pass_enabled);
+    }
+
+    /// Pass toggles clicked since the last call, `(entry point, enabled)`
+    pub(crate) fn take_pass_toggles(&mut self) -> Vec<(String, bool)> {
+        std::mem::take(&mut self.pass_toggles)
+    }
+
+    /// Frame latency picked in the advanced panel since the last call, the app passes it
+    /// to `Core::set_max_frame_latency`
+    pub fn take_frame_latency_request(&mut self) -> Option<u32> {
+        self.frame_latency_request.take()
+    }
+
+    /// Keep the advanced panel in sync with `Core::max_frame_latency`
+    pub(crate) fn sync_frame_latency(&mut self, latency: u32) {
+        self.frame_latency = latency;
+    }
+
+    /// Keep the spectrum section in sync with the `SpectrumAnalyzer`, see
+    /// `RenderKit::update_audio_spectrum`
+    #[cfg(feature = "media")]
+    pub(crate) fn sync_spectrum(&mut self, settings: SpectrumSettings) {
+        self.spectrum = Some(settings);
+    }
+
+    /// Passes shown in the controls widget, `None` for single-pass shaders
+    pub(crate) fn sync_pass_graph(&mut self, graph: Option<PassGraph>) {
+        self.pass_graph = graph;
+    }
+
+    /// Estimated GPU memory shown in the advanced panel, see `RenderKit::sync_shader_controls`
+    pub(crate) fn sync_gpu_memory(&mut self, bytes: u64) {
+        self.gpu_memory_bytes = Some(bytes);
+    }
+
+    /// Keep the grading panel in sync when `RenderKit::set_color_grading` is called
+    pub(crate) fn sync_color_grading(&mut self, grading: ColorGrading) {
+        self.color_grading = grading;
+    }
+
+    /// Sliders for the display color grading. Edits go to `ControlsRequest::set_color_grading`,
+    /// `RenderKit::apply_control_request` applies them.
+    pub fn render_color_grading(ui: &mut egui::Ui, request: &mut ControlsRequest) {
+        ui.collapsing("Color grading", |ui| {
+            let mut grading = request.set_color_grading.unwrap_or(request.color_grading);
+            if grading.ui(ui) {
+                request.set_color_grading = Some(grading);
+            }
+        });
+    }
+
+    pub fn guides(&self) -> CompositionGuides {
+        self.guides
+    }
+
+    pub fn set_guides(&mut self, guides: CompositionGuides) {
+        self.guides = guides;
+    }
+
+    /// Checkboxes for the `CompositionGuides` in `request`
+    pub fn render_guide_controls(ui: &mut egui::Ui, request: &mut ControlsRequest) {
+        ui.collapsing("Guides", |ui| {
+            let guides = &mut request.guides;
+            ui.checkbox(&mut guides.center_cross, "Center cross");
+            ui.checkbox(&mut guides.rule_of_thirds, "Rule of thirds");
+            ui.checkbox(&mut guides.safe_areas, "Action/title safe");
+            ui.horizontal(|ui| {
+                ui.label("Aspect:");
+                ui.selectable_value(&mut guides.aspect, None, "Off");
+                for aspect in GuideAspect::ALL {
+                    ui.selectable_value(&mut guides.aspect, Some(aspect), aspect.name());
+                }
+            });
+        });
+    }
+
+    /// Paint `guides` over a window of `window_size` physical pixels (`core.config` width
+    /// and height), behind all egui windows. Only affects the live view.
+    pub fn draw_guides(ctx: &egui::Context, guides: &CompositionGuides, window_size: (u32, u32)) {
+        if !guides.any_enabled() {
+            return;
+        }
+        let scale = ctx.pixels_per_point();
+        let screen = egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(window_size.0 as f32, window_size.1 as f32) / scale,
+        );
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Background,
+            egui::Id::new("composition_guides"),
+        ));
+        let stroke = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(140));
+        let faint = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(70));
+
+        if guides.rule_of_thirds {
+            for t in [1.0 / 3.0, 2.0 / 3.0] {
+                let x = screen.left() + screen.width() * t;
+                let y = screen.top() + screen.height() * t;
+                painter.line_segment(
+                    [egui::pos2(x, screen.top()), egui::pos2(x, screen.bottom())],
+                    faint,
+                );
+                painter.line_segment(
+                    [egui::pos2(screen.left(), y), egui::pos2(screen.right(), y)],
+                    faint,
+                );
+            }
+        }
+        if guides.safe_areas {
+            for fraction in [0.9, 0.8] {
+                painter.rect_stroke(
+                    egui::Rect::from_center_size(screen.center(), screen.size() * fraction),
+                    0.0,
+                    stroke,
+                    egui::StrokeKind::Inside,
+                );
+            }
+        }
+        if let Some(aspect) = guides.aspect {
+            let ratio = aspect.ratio();
+            let size = if screen.width() / screen.height() > ratio {
+                egui::vec2(screen.height() * ratio, screen.height())
+            } else {
+                egui::vec2(screen.width(), screen.width() / ratio)
+            };
+            let frame = egui::Rect::from_center_size(screen.center(), size);
+            painter.rect_stroke(frame, 0.0, stroke, egui::StrokeKind::Inside);
+            painter.text(
+                frame.left_top() + egui::vec2(4.0, 4.0),
+                egui::Align2::LEFT_TOP,
+                aspect.name(),
+                egui::FontId::proportional(12.0),
+                stroke.color,
+            );
+        }
+        if guides.center_cross {
+            let center = screen.center();
+            let arm = 12.0;
+            painter.line_segment(
+                [center - egui::vec2(arm, 0.0), center + egui::vec2(arm, 0.0)],
+                stroke,
+            );
+            painter.line_segment(
+                [center - egui::vec2(0.0, arm), center + egui::vec2(0.0, arm)],
+                stroke,
+            );
+        }
     }
 
     /// Extract video info from a video texture manager
@@ -405,9 +931,217 @@ impl ShaderControls {
                     }
                 });
             }
+            Self::render_channel_previews(ui, &request.channel_previews);
+        });
+    }
+
+    /// Spectrum mapping controls (frequency scale, smoothing and weighting)
+    pub fn render_spectrum_controls(
+        ui: &mut egui::Ui,
+        request: &mut ControlsRequest,
+        current_scale: FrequencyScale,
+        current_smoothing: f32,
+        current_weighting: SpectrumWeighting,
+    ) {
+        ui.collapsing("Spectrum", |ui| {
+            let mut scale = current_scale;
+            egui::ComboBox::from_label("Frequency Scale")
+                .selected_text(scale.name())
+                .show_ui(ui, |ui| {
+                    for option in FrequencyScale::ALL {
+                        ui.selectable_value(&mut scale, option, option.name());
+                    }
+                });
+            if scale != current_scale {
+                request.spectrum_scale = Some(scale);
+            }
+
+            let mut smoothing = current_smoothing;
+            if ui
+                .add(egui::Slider::new(&mut smoothing, 0.0..=2.0).text("Smoothing"))
+                .changed()
+            {
+                request.spectrum_smoothing = Some(smoothing);
+            }
+
+            let mut weighting = current_weighting;
+            egui::ComboBox::from_label("Weighting")
+                .selected_text(weighting.name())
+                .show_ui(ui, |ui| {
+                    for option in SpectrumWeighting::ALL {
+                        ui.selectable_value(&mut weighting, option, option.name());
+                    }
+                });
+            if weighting != current_weighting {
+                request.spectrum_weighting = Some(weighting);
+            }
         });
     }
 
+    /// Advanced/diagnostics settings (frame latency, estimated GPU memory)
+    pub fn render_advanced_panel(ui: &mut egui::Ui, request: &mut ControlsRequest) {
+        ui.collapsing("Advanced", |ui| {
+            let mut latency = request.max_frame_latency.unwrap_or(request.frame_latency);
+            if ui
+                .add(
+                    egui::Slider::new(
+                        &mut latency,
+                        crate::Core::MIN_FRAME_LATENCY..=crate::Core::MAX_FRAME_LATENCY,
+                    )
+                    .text("Max Frame Latency"),
+                )
+                .on_hover_text("1 = lowest input latency, 3 = smoother throughput")
+                .changed()
+            {
+                request.max_frame_latency = Some(latency);
+            }
+            if let Some(bytes) = request.gpu_memory_bytes {
+                ui.label(format!(
+                    "GPU memory (est.): {:.1} MB",
+                    bytes as f64 / (1024.0 * 1024.0)
+                ))
+                .on_hover_text(
+                    "Textures and buffers owned by the shader, ping-pong pairs count twice",
+                );
+            }
+        });
+    }
+
+    /// Enable channel thumbnails (also enabled by the `CUNEUS_DEBUG_CHANNELS` env var)
+    pub fn set_debug_channels(&mut self, enabled: bool) {
+        self.debug_channels = enabled;
+    }
+
+    pub fn debug_channels(&self) -> bool {
+        self.debug_channels
+    }
+
+    /// Register the views currently bound to `shader`'s channels with egui.
+    /// Call once per frame before building the UI; does nothing unless debug channels are on.
+    pub fn sync_channel_previews(
+        &mut self,
+        egui_renderer: &mut egui_wgpu::Renderer,
+        device: &wgpu::Device,
+        shader: &crate::compute::ComputeShader,
+    ) {
+        if !self.debug_channels {
+            for id in self.channel_previews.drain(..).filter_map(|(_, id)| id) {
+                egui_renderer.free_texture(&id);
+            }
+            return;
+        }
+
+        let mut previews = Vec::new();
+        for (index, view) in shader.channel_views() {
+            let existing = self
+                .channel_previews
+                .iter()
+                .find(|(i, _)| *i == index)
+                .and_then(|(_, id)| *id);
+            let id = match (view, existing) {
+                (Some(view), Some(id)) => {
+                    egui_renderer.update_egui_texture_from_wgpu_texture(
+                        device,
+                        view,
+                        wgpu::FilterMode::Linear,
+                        id,
+                    );
+                    Some(id)
+                }
+                (Some(view), None) => Some(egui_renderer.register_native_texture(
+                    device,
+                    view,
+                    wgpu::FilterMode::Linear,
+                )),
+                (None, Some(id)) => {
+                    egui_renderer.free_texture(&id);
+                    None
+                }
+                (None, None) => None,
+            };
+            previews.push((index, id));
+        }
+        self.channel_previews = previews;
+    }
+
+    /// Previews collected by `sync_channel_previews`, for `render_channel_previews`
+    pub fn channel_previews(&self) -> Vec<(u32, Option<egui::TextureId>)> {
+        self.channel_previews.clone()
+    }
+
+    pub fn render_channel_previews(ui: &mut egui::Ui, previews: &[(u32, Option<egui::TextureId>)]) {
+        if previews.is_empty() {
+            return;
+        }
+        ui.collapsing("Channels", |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (index, id) in previews {
+                    ui.vertical(|ui| {
+                        ui.label(format!("channel{index}"));
+                        match id {
+                            Some(id) => {
+                                ui.add(egui::Image::new(egui::load::SizedTexture::new(
+                                    *id,
+                                    [96.0, 96.0],
+                                )));
+                            }
+                            None => {
+                                ui.label("unbound");
+                            }
+                        }
+                    });
+                }
+            });
+        });
+    }
+
+    /// Read-only view of what a multi-pass shader runs each frame, see `PassGraph`
+    pub fn render_pass_graph(ui: &mut egui::Ui, graph: &PassGraph) {
+        ui.collapsing("Passes", |ui| {
+            egui::Grid::new("pass_graph").striped(true).show(ui, |ui| {
+                ui.strong("Pass");
+                ui.strong("Workgroups");
+                ui.strong("Inputs");
+                ui.end_row();
+                for pass in &graph.passes {
+                    let count = pass.workgroups;
+                    ui.label(&pass.name);
+                    ui.label(format!("{}x{}x{}", count[0], count[1], count[2]));
+                    ui.label(&pass.inputs);
+                    ui.end_row();
+                }
+            });
+            if graph.dispatch_once {
+                ui.label("Dispatch once");
+            }
+
+            ui.separator();
+            for (i, group) in graph.groups.iter().enumerate() {
+                ui.label(format!("Group {}: {group}", i + 1));
+            }
+        });
+    }
+
+    /// Checkbox per pass of `request.pass_graph`, clicks go to `set_pass_enabled` for
+    /// `ComputeShader::set_pass_enabled`. Returns true when one changed.
+    pub fn render_pass_toggles(ui: &mut egui::Ui, request: &mut ControlsRequest) -> bool {
+        let Some(graph) = &mut request.pass_graph else {
+            return false;
+        };
+        let mut changed = false;
+        ui.collapsing("Enabled Passes", |ui| {
+            for pass in &mut graph.passes {
+                if ui.checkbox(&mut pass.enabled, &pass.entry_point).changed() {
+                    request
+                        .set_pass_enabled
+                        .push((pass.entry_point.clone(), pass.enabled));
+                    changed = true;
+                }
+            }
+        });
+        changed
+    }
+
     pub fn render_controls_widget(ui: &mut egui::Ui, request: &mut ControlsRequest) {
         ui.vertical(|ui| {
             ui.horizontal(|ui| {
@@ -437,6 +1171,158 @@ impl ShaderControls {
                     ui.label(format!("Resolution: {width}x{height}"));
                 });
             }
+            ui.horizontal(|ui| {
+                ui.label("Speed:");
+                ui.add(
+                    egui::DragValue::new(&mut request.time_scale)
+                        .speed(0.01)
+                        .range(0.0..=8.0)
+                        .suffix("x"),
+                );
+                if request.time_scale != 1.0 && ui.small_button("1x").clicked() {
+                    request.time_scale = 1.0;
+                }
+            });
+            Self::render_action_buttons(ui, request);
+            Self::render_guide_controls(ui, request);
+            Self::render_color_grading(ui, request);
+            if let Some(spectrum) = request.spectrum {
+                Self::render_spectrum_controls(
+                    ui,
+                    request,
+                    spectrum.scale,
+                    spectrum.smoothing,
+                    spectrum.weighting,
+                );
+            }
+            if let Some(graph) = &request.pass_graph {
+                Self::render_pass_graph(ui, graph);
+            }
+            Self::render_pass_toggles(ui, request);
+            Self::render_advanced_panel(ui, request);
+        });
+        if let Some(window_size) = request.window_size {
+            Self::draw_guides(ui.ctx(), &request.guides, window_size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Texts drawn by `render_controls_widget`, after clicking the section header `open`
+    /// if it's there
+    fn widget_texts(request: &mut ControlsRequest, open: &str) -> Vec<String> {
+        let ctx = egui::Context::default();
+        ctx.style_mut(|style| style.animation_time = 0.0);
+        let mut frame = |events: Vec<egui::Event>| {
+            let input = egui::RawInput {
+                events,
+                ..Default::default()
+            };
+            let output = ctx.run(input, |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ShaderControls::render_controls_widget(ui, request);
+                });
+            });
+            output
+                .shapes
+                .into_iter()
+                .filter_map(|clipped| match clipped.shape {
+                    egui::Shape::Text(text) => Some((text.galley.text().to_string(), text.pos)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let texts = frame(Vec::new());
+        if let Some((_, pos)) = texts.iter().find(|(text, _)| text == open) {
+            let pos = *pos + egui::vec2(2.0, 2.0);
+            let click = |pressed| egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed,
+                modifiers: Default::default(),
+            };
+            frame(vec![
+                egui::Event::PointerMoved(pos),
+                click(true),
+                click(false),
+            ]);
+        }
+        frame(Vec::new())
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect()
+    }
+
+    #[test]
+    fn spectrum_section_offers_the_weighting() {
+        let mut request = ControlsRequest::default();
+        assert!(!widget_texts(&mut request, "Spectrum")
+            .iter()
+            .any(|t| t == "Spectrum"));
+
+        request.spectrum = Some(SpectrumSettings {
+            scale: FrequencyScale::default(),
+            smoothing: 1.0,
+            weighting: SpectrumWeighting::default(),
         });
+        let texts = widget_texts(&mut request, "Spectrum");
+        assert!(texts.iter().any(|t| t == "Spectrum"));
+        assert!(texts.iter().any(|t| t == "Weighting"), "{texts:?}");
+    }
+
+    #[test]
+    fn multi_pass_shaders_list_their_passes() {
+        let mut request = ControlsRequest::default();
+        assert!(!widget_texts(&mut request, "Passes")
+            .iter()
+            .any(|t| t == "Passes"));
+
+        request.pass_graph = Some(PassGraph {
+            passes: vec![
+                PassSummary {
+                    entry_point: "buffer_a".to_string(),
+                    name: "buffer_a -> buffer_a".to_string(),
+                    workgroups: [4, 4, 1],
+                    inputs: "buffer_a".to_string(),
+                    enabled: true,
+                },
+                PassSummary {
+                    entry_point: "main_image".to_string(),
+                    name: "main_image".to_string(),
+                    workgroups: [4, 4, 1],
+                    inputs: "buffer_a".to_string(),
+                    enabled: true,
+                },
+            ],
+            dispatch_once: false,
+            groups: ["output".to_string(), "-".to_string(), "-".to_string()],
+        });
+        let texts = widget_texts(&mut request, "Passes");
+        assert!(
+            texts.iter().any(|t| t == "buffer_a -> buffer_a"),
+            "{texts:?}"
+        );
+        assert!(texts.iter().any(|t| t == "main_image"));
+        assert!(texts.iter().any(|t| t == "Group 1: output"));
+    }
+
+    #[test]
+    fn pass_toggles_reach_the_shader_sync() {
+        let mut controls = ShaderControls::new();
+        let start = std::time::Instant::now();
+        let mut request = controls.get_ui_request(&start, &winit::dpi::PhysicalSize::new(64, 64));
+        request
+            .set_pass_enabled
+            .push(("buffer_a".to_string(), false));
+        controls.apply_ui_request(request);
+        assert_eq!(
+            controls.take_pass_toggles(),
+            [("buffer_a".to_string(), false)]
+        );
+        assert!(controls.take_pass_toggles().is_empty());
     }
 }
@@ -0,0 +1,153 @@
+use crate::{Core, Renderer, RendererDescriptor};
+
+/// Format the UI is drawn into on linear surfaces
+const UI_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// egui_wgpu writes gamma-encoded colors to every non-sRGB target. That's right for
+/// `*8Unorm` swapchains, which the display treats as sRGB, but float swapchains are linear
+/// and the UI comes out washed out. On those the UI is drawn into an sRGB texture instead,
+/// so egui outputs and blends linear values, then composited over the frame with
+/// premultiplied alpha.
+pub(crate) struct LinearUiTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    compositor: Renderer,
+}
+
+impl LinearUiTarget {
+    /// Surface formats whose values are linear light
+    pub fn is_linear_surface(format: wgpu::TextureFormat) -> bool {
+        matches!(
+            format,
+            wgpu::TextureFormat::Rgba16Float
+                | wgpu::TextureFormat::Rgba32Float
+                | wgpu::TextureFormat::Rg11b10Ufloat
+        )
+    }
+
+    /// Target format for `egui_wgpu::Renderer` on a surface of `surface_format`
+    pub fn egui_format(surface_format: wgpu::TextureFormat) -> wgpu::TextureFormat {
+        if Self::is_linear_surface(surface_format) {
+            UI_FORMAT
+        } else {
+            surface_format
+        }
+    }
+
+    pub fn new(
+        core: &Core,
+        vs_module: &wgpu::ShaderModule,
+        fs_module: &wgpu::ShaderModule,
+    ) -> Self {
+        let layout = crate::RenderKit::create_standard_texture_layout(&core.device);
+        let pipeline_layout = core
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("UI Composite Pipeline Layout"),
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[],
+            });
+        let compositor = Renderer::new_with_blend(
+            &core.device,
+            &RendererDescriptor {
+                vs_module,
+                fs_module,
+                format: core.config.format,
+                layout: &pipeline_layout,
+                fragment_entry: None,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                cache: core.pipeline_cache(),
+            },
+        );
+        let sampler = core
+            .device
+            .create_sampler(&wgpu::SamplerDescriptor::default());
+        let (texture, view, bind_group) = Self::create_target(
+            core,
+            &layout,
+            &sampler,
+            core.config.width,
+            core.config.height,
+        );
+
+        Self {
+            texture,
+            view,
+            bind_group,
+            layout,
+            sampler,
+            compositor,
+        }
+    }
+
+    fn create_target(
+        core: &Core,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::Texture, wgpu::TextureView, wgpu::BindGroup) {
+        let texture = core.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("UI Target"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: UI_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("UI Target Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+        (texture, view, bind_group)
+    }
+
+    /// View to draw the UI into, cleared to transparent by the caller. Follows the surface size.
+    pub fn view(&mut self, core: &Core) -> &wgpu::TextureView {
+        let size = self.texture.size();
+        if size.width != core.config.width || size.height != core.config.height {
+            (self.texture, self.view, self.bind_group) = Self::create_target(
+                core,
+                &self.layout,
+                &self.sampler,
+                core.config.width,
+                core.config.height,
+            );
+        }
+        &self.view
+    }
+
+    /// Blend the drawn UI over `target`
+    pub fn composite(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let mut render_pass = Renderer::begin_render_pass(
+            encoder,
+            target,
+            wgpu::LoadOp::Load,
+            Some("UI Composite Pass"),
+        );
+        render_pass.set_pipeline(&self.compositor.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.compositor.vertex_buffer.slice(..));
+        render_pass.draw(0..4, 0..1);
+    }
+}
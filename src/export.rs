@@ -19,6 +19,50 @@ impl From<ImageError> for ExportError {
         ExportError::ImageError(err)
     }
 }
+/// One image written per export frame, see `ExportSettings::outputs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportOutput {
+    /// `ExportOutput::COLOR` or `ExportOutput::DEPTH`
+    pub index: u32,
+    /// Appended to the file stem: `_depth` turns `frame_00042.png` into `frame_00042_depth.exr`
+    pub suffix: String,
+}
+
+impl ExportOutput {
+    /// The shader output as displayed, saved as 8-bit PNG
+    pub const COLOR: u32 = 0;
+    /// The R32Float field of `ComputeShaderBuilder::with_depth_output`, saved as EXR
+    pub const DEPTH: u32 = 1;
+
+    pub fn new(index: u32, suffix: &str) -> Self {
+        Self {
+            index,
+            suffix: suffix.to_string(),
+        }
+    }
+
+    pub fn color() -> Self {
+        Self::new(Self::COLOR, "")
+    }
+
+    pub fn depth() -> Self {
+        Self::new(Self::DEPTH, "_depth")
+    }
+}
+
+/// Captured pixels of one `ExportOutput`. The format decides the file type.
+pub enum OutputPixels {
+    /// Export-sized RGBA8, saved as PNG (or whatever the template's extension says)
+    Rgba8(Vec<u8>),
+    /// Single-channel float data at the buffer's own size, saved as 32-bit float EXR with
+    /// the value in R, G and B
+    Float {
+        width: u32,
+        height: u32,
+        data: Vec<f32>,
+    },
+}
+
 #[derive(Debug, Clone)]
 pub struct ExportSettings {
     pub export_path: PathBuf,
@@ -28,6 +72,20 @@ pub struct ExportSettings {
     pub total_time: f32,
     pub fps: u32,
     pub is_exporting: bool,
+    /// Keep the shader's alpha channel: capture clears to transparent and the PNG keeps alpha.
+    /// When false, frames are written fully opaque.
+    pub preserve_alpha: bool,
+    /// File name of each frame, relative to `export_path`. Tokens:
+    /// `{frame}` / `{frame:06}` (zero padded), `{time}` / `{time:.3}` (seconds, precision)
+    /// and `{shader}` (`shader_name`). `.png` is appended unless the name ends in an image
+    /// extension (`.500` from `{time:.3}` isn't one), and a
+    /// template without `{frame}` gets `_{frame:05}` before the extension so frames of a
+    /// sequence never overwrite each other.
+    pub filename_template: String,
+    pub shader_name: String,
+    /// Images written per frame, color only by default. Each output needs a distinct
+    /// `suffix`. Outputs the shader doesn't have are skipped with a warning.
+    pub outputs: Vec<ExportOutput>,
 }
 
 impl Default for ExportSettings {
@@ -40,9 +98,110 @@ impl Default for ExportSettings {
             total_time: 5.0,
             fps: 60,
             is_exporting: false,
+            preserve_alpha: false,
+            filename_template: ExportSettings::DEFAULT_FILENAME_TEMPLATE.to_string(),
+            shader_name: "cuneus".to_string(),
+            outputs: vec![ExportOutput::color()],
+        }
+    }
+}
+
+impl ExportSettings {
+    pub const DEFAULT_FILENAME_TEMPLATE: &'static str = "frame_{frame:05}.png";
+
+    /// Shader time of an export frame
+    pub fn frame_time(&self, frame: u32) -> f32 {
+        self.start_time + frame as f32 / self.fps.max(1) as f32
+    }
+
+    /// Check `filename_template`, replacing it with the default (and a warning) if invalid
+    pub fn validate_filename_template(&mut self) -> bool {
+        match expand_filename_template(&self.filename_template, 0, 0.0, &self.shader_name) {
+            Ok(_) => true,
+            Err(e) => {
+                log::warn!(
+                    "Invalid export filename template {:?} ({e}), using {:?}",
+                    self.filename_template,
+                    Self::DEFAULT_FILENAME_TEMPLATE
+                );
+                self.filename_template = Self::DEFAULT_FILENAME_TEMPLATE.to_string();
+                false
+            }
         }
     }
 }
+
+/// Expand the `ExportSettings::filename_template` tokens
+pub fn expand_filename_template(
+    template: &str,
+    frame: u32,
+    time: f32,
+    shader: &str,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len() + 8);
+    let mut rest = template;
+    let mut has_frame = false;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err("unclosed '{'".to_string());
+        };
+        let token = &rest[start + 1..start + end];
+        let (name, spec) = token.split_once(':').unwrap_or((token, ""));
+        match name {
+            "frame" => {
+                has_frame = true;
+                if spec.is_empty() {
+                    out.push_str(&frame.to_string());
+                } else {
+                    let width: usize = spec
+                        .strip_prefix('0')
+                        .and_then(|w| w.parse().ok())
+                        .ok_or_else(|| format!("bad frame format '{spec}', expected e.g. 06"))?;
+                    out.push_str(&format!("{frame:0width$}"));
+                }
+            }
+            "time" => {
+                if spec.is_empty() {
+                    out.push_str(&time.to_string());
+                } else {
+                    let precision: usize = spec
+                        .strip_prefix('.')
+                        .and_then(|p| p.parse().ok())
+                        .ok_or_else(|| format!("bad time format '{spec}', expected e.g. .3"))?;
+                    out.push_str(&format!("{time:.precision$}"));
+                }
+            }
+            "shader" if spec.is_empty() => out.push_str(shader),
+            _ => return Err(format!("unknown token '{{{token}}}'")),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    if rest.contains('}') {
+        return Err("unmatched '}'".to_string());
+    }
+    out.push_str(rest);
+
+    if out.is_empty() {
+        return Err("empty file name".to_string());
+    }
+    // Only image formats count as extensions, `{time:.3}` decimals look like one too
+    let extension_len = std::path::Path::new(&out)
+        .extension()
+        .filter(|e| image::ImageFormat::from_extension(e).is_some())
+        .map(|e| e.len());
+    if !has_frame {
+        let number = format!("_{frame:05}");
+        match extension_len {
+            Some(len) => out.insert_str(out.len() - len - 1, &number),
+            None => out.push_str(&number),
+        }
+    }
+    if extension_len.is_none() {
+        out.push_str(".png");
+    }
+    Ok(out)
+}
 #[derive(Clone)]
 pub struct ExportUiRequest {
     pub width: u32,
@@ -52,6 +211,7 @@ pub struct ExportUiRequest {
     pub fps: u32,
     pub path: PathBuf,
     pub is_exporting: bool,
+    pub preserve_alpha: bool,
 }
 #[derive(Default)]
 pub struct ExportUiState {
@@ -63,12 +223,20 @@ pub struct ExportUiState {
     pub temp_fps: u32,
 }
 
+/// Called with every file an export wrote, see `ExportManager::set_on_complete`
+type ExportCallback = Box<dyn FnMut(&[PathBuf])>;
+
 /// Manages the export process and UI state
 pub struct ExportManager {
     settings: ExportSettings,
     export_channel: Option<mpsc::Receiver<(u32, f32)>>,
     ui_state: ExportUiState,
     temp_state: TempExportState,
+    written_files: Vec<PathBuf>,
+    completed_files: Vec<PathBuf>,
+    on_complete: Option<ExportCallback>,
+    /// Next frame of a video-synced export, see `start_video_synced_export`
+    video_sync_frame: Option<u32>,
 }
 
 #[derive(Clone)]
@@ -79,6 +247,7 @@ struct TempExportState {
     total_time: f32,
     fps: u32,
     path: PathBuf,
+    preserve_alpha: bool,
 }
 
 impl Default for ExportManager {
@@ -98,6 +267,7 @@ impl ExportManager {
             total_time: settings.total_time,
             fps: settings.fps,
             path: settings.export_path.clone(),
+            preserve_alpha: settings.preserve_alpha,
         };
 
         Self {
@@ -105,6 +275,10 @@ impl ExportManager {
             export_channel: None,
             ui_state,
             temp_state,
+            written_files: Vec::new(),
+            completed_files: Vec::new(),
+            on_complete: None,
+            video_sync_frame: None,
         }
     }
     pub fn get_ui_request(&self) -> ExportUiRequest {
@@ -116,6 +290,7 @@ impl ExportManager {
             fps: self.temp_state.fps,
             path: self.temp_state.path.clone(),
             is_exporting: self.settings.is_exporting,
+            preserve_alpha: self.temp_state.preserve_alpha,
         }
     }
     pub fn apply_ui_request(&mut self, request: ExportUiRequest) {
@@ -125,6 +300,7 @@ impl ExportManager {
         self.temp_state.total_time = request.total_time;
         self.temp_state.fps = request.fps;
         self.temp_state.path = request.path;
+        self.temp_state.preserve_alpha = request.preserve_alpha;
     }
     /// Returns a reference to the current export settings
     pub fn settings(&self) -> &ExportSettings {
@@ -140,6 +316,11 @@ impl ExportManager {
     }
     /// Attempts to get the next frame for export
     pub fn try_get_next_frame(&mut self) -> Option<(u32, f32)> {
+        if let Some(frame) = self.video_sync_frame {
+            // Runs until the video ends, the caller completes the export on EOS
+            self.video_sync_frame = Some(frame + 1);
+            return Some((frame, self.settings.frame_time(frame)));
+        }
         self.export_channel.as_ref()?.try_recv().ok()
     }
 
@@ -148,16 +329,11 @@ impl ExportManager {
             return;
         }
 
-        // Apply the temporary state to settings before starting export
-        self.settings.width = self.temp_state.width;
-        self.settings.height = self.temp_state.height;
-        self.settings.start_time = self.temp_state.start_time;
-        self.settings.total_time = self.temp_state.total_time;
-        self.settings.fps = self.temp_state.fps;
-        self.settings.export_path = self.temp_state.path.clone();
+        self.apply_temp_state();
 
         // Then start the export process
         self.settings.is_exporting = true;
+        self.written_files.clear();
         let settings = self.settings.clone();
         let (tx, rx) = mpsc::channel();
 
@@ -165,7 +341,7 @@ impl ExportManager {
             let total_frames = (settings.total_time * settings.fps as f32) as u32;
 
             for frame in 0..total_frames {
-                let time = settings.start_time + (frame as f32 / settings.fps as f32);
+                let time = settings.frame_time(frame);
                 if tx.send((frame, time)).is_err() {
                     break;
                 }
@@ -175,10 +351,91 @@ impl ExportManager {
         self.export_channel = Some(rx);
     }
 
+    /// Export one frame per decoded frame of `video`, in lockstep, until the video ends.
+    ///
+    /// Uses the video's frame rate and duration instead of the UI's `fps` and `total_time`
+    /// (the start time is 0). The video must be stepped by whoever handles the export:
+    /// `RenderKit::start_video_synced_export` puts its video in step mode and
+    /// `ComputeShader::handle_export` then advances it before every capture, using the
+    /// frame's timestamp as shader time.
+    #[cfg(feature = "media")]
+    pub fn start_video_synced_export(&mut self, video: &crate::gst::video::VideoTextureManager) {
+        if self.settings.is_exporting {
+            return;
+        }
+        if let Some((numer, denom)) = video.framerate().filter(|(_, denom)| *denom > 0) {
+            self.temp_state.fps = ((numer as f32 / denom as f32).round() as u32).max(1);
+        }
+        if let Some(duration) = video.duration() {
+            self.temp_state.total_time = duration.mseconds() as f32 / 1000.0;
+        }
+        self.temp_state.start_time = 0.0;
+        self.apply_temp_state();
+
+        self.settings.is_exporting = true;
+        self.written_files.clear();
+        self.export_channel = None;
+        self.video_sync_frame = Some(0);
+    }
+
+    /// True while a `start_video_synced_export` export is running
+    pub fn is_video_synced(&self) -> bool {
+        self.video_sync_frame.is_some()
+    }
+
+    fn apply_temp_state(&mut self) {
+        self.settings.width = self.temp_state.width;
+        self.settings.height = self.temp_state.height;
+        self.settings.start_time = self.temp_state.start_time;
+        self.settings.total_time = self.temp_state.total_time;
+        self.settings.fps = self.temp_state.fps;
+        self.settings.export_path = self.temp_state.path.clone();
+        self.settings.preserve_alpha = self.temp_state.preserve_alpha;
+        self.settings.validate_filename_template();
+    }
+
     /// Completes the export process
     pub fn complete_export(&mut self) {
+        let was_exporting = self.settings.is_exporting;
         self.settings.is_exporting = false;
         self.export_channel = None;
+        self.video_sync_frame = None;
+
+        if was_exporting {
+            self.completed_files = std::mem::take(&mut self.written_files);
+            if let Some(on_complete) = &mut self.on_complete {
+                on_complete(&self.completed_files);
+            }
+        }
+    }
+
+    /// Save a captured frame with `save_frame` and remember its path for `take_completed_files`
+    pub fn save_export_frame(&mut self, data: Vec<u8>, frame: u32) -> Result<PathBuf, ExportError> {
+        let path = save_frame(data, frame, &self.settings)?;
+        self.written_files.push(path.clone());
+        Ok(path)
+    }
+
+    /// Save one output of a captured frame with `save_output`
+    pub fn save_export_output(
+        &mut self,
+        output: &ExportOutput,
+        pixels: OutputPixels,
+        frame: u32,
+    ) -> Result<PathBuf, ExportError> {
+        let path = save_output(pixels, frame, output, &self.settings)?;
+        self.written_files.push(path.clone());
+        Ok(path)
+    }
+
+    /// Files written by the last finished export, in frame order. Empties the list.
+    pub fn take_completed_files(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.completed_files)
+    }
+
+    /// Called with the written files whenever an export finishes
+    pub fn set_on_complete(&mut self, callback: impl FnMut(&[PathBuf]) + 'static) {
+        self.on_complete = Some(Box::new(callback));
     }
 
     /// Returns references to both UI state and settings for the UI to use
@@ -245,6 +502,7 @@ impl ExportManager {
                                 .color(egui::Color32::from_rgb(150, 150, 150)),
                         ));
                     });
+                    ui.checkbox(&mut request.preserve_alpha, "Preserve Alpha");
                     if !request.path.exists() {
                         ui.horizontal(|ui| {
                             ui.label(
@@ -273,8 +531,7 @@ impl ExportManager {
         if let Some((frame, time)) = self.try_get_next_frame() {
             match capture_fn(frame, time) {
                 Ok(data) => {
-                    let settings = self.settings();
-                    if let Err(e) = save_frame(data, frame, settings) {
+                    if let Err(e) = self.save_export_frame(data, frame) {
                         eprintln!("Error saving frame: {e:?}");
                     }
                 }
@@ -287,14 +544,102 @@ impl ExportManager {
         }
     }
 }
-#[allow(unused_mut)]
+/// Path of an exported frame: `export_path` joined with the expanded `filename_template`
+/// (`frame_00042.png` by default). Bad templates fall back to the default name.
+pub fn frame_path(settings: &ExportSettings, frame: u32) -> PathBuf {
+    let time = settings.frame_time(frame);
+    let file_name = expand_filename_template(
+        &settings.filename_template,
+        frame,
+        time,
+        &settings.shader_name,
+    )
+    .unwrap_or_else(|e| {
+        log::warn!("Invalid export filename template: {e}");
+        format!("frame_{frame:05}.png")
+    });
+    settings.export_path.join(file_name)
+}
+
+/// `frame_path` with `suffix` appended to the file stem and, if given, a new extension
+pub fn output_path(
+    settings: &ExportSettings,
+    frame: u32,
+    suffix: &str,
+    extension: Option<&str>,
+) -> PathBuf {
+    let mut path = frame_path(settings, frame);
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = extension
+        .map(str::to_string)
+        .or_else(|| path.extension().map(|e| e.to_string_lossy().into_owned()));
+    let file_name = match extension {
+        Some(extension) => format!("{stem}{suffix}.{extension}"),
+        None => format!("{stem}{suffix}"),
+    };
+    path.set_file_name(file_name);
+    path
+}
+
+/// Save one output of a frame, returns the written path (see `output_path`). RGBA8 goes
+/// through `save_frame` with the template's extension, float data always becomes `.exr`.
+pub fn save_output(
+    pixels: OutputPixels,
+    frame: u32,
+    output: &ExportOutput,
+    settings: &ExportSettings,
+) -> Result<PathBuf, ExportError> {
+    match pixels {
+        OutputPixels::Rgba8(data) => {
+            let path = output_path(settings, frame, &output.suffix, None);
+            save_rgba8(data, &path, settings)?;
+            Ok(path)
+        }
+        OutputPixels::Float {
+            width,
+            height,
+            data,
+        } => {
+            let path = output_path(settings, frame, &output.suffix, Some("exr"));
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let rgba: Vec<f32> = data.iter().flat_map(|&v| [v, v, v, 1.0]).collect();
+            let image =
+                image::ImageBuffer::<image::Rgba<f32>, Vec<f32>>::from_raw(width, height, rgba)
+                    .ok_or_else(buffer_error)?;
+            image::DynamicImage::ImageRgba32F(image).save(&path)?;
+            Ok(path)
+        }
+    }
+}
+
+fn buffer_error() -> ImageError {
+    ImageError::Parameter(image::error::ParameterError::from_kind(
+        image::error::ParameterErrorKind::Generic("Failed to create image buffer".to_string()),
+    ))
+}
+
+/// Save one RGBA8 frame as PNG, returns the written path (see `frame_path`)
 pub fn save_frame(
-    mut data: Vec<u8>,
+    data: Vec<u8>,
     frame: u32,
     settings: &ExportSettings,
-) -> Result<(), ExportError> {
-    let frame_path = settings.export_path.join(format!("frame_{frame:05}.png"));
+) -> Result<PathBuf, ExportError> {
+    let frame_path = frame_path(settings, frame);
+    save_rgba8(data, &frame_path, settings)?;
+    Ok(frame_path)
+}
 
+#[allow(unused_mut)]
+fn save_rgba8(
+    mut data: Vec<u8>,
+    frame_path: &std::path::Path,
+    settings: &ExportSettings,
+) -> Result<(), ExportError> {
     if let Some(parent) = frame_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
@@ -304,17 +649,162 @@ pub fn save_frame(
             chunk.swap(0, 2);
         }
     }
+    if !settings.preserve_alpha {
+        for chunk in data.chunks_mut(4) {
+            chunk[3] = 255;
+        }
+    }
     let image = image::ImageBuffer::<image::Rgba<u8>, Vec<u8>>::from_raw(
         settings.width,
         settings.height,
         data,
     )
-    .ok_or_else(|| {
-        ImageError::Parameter(image::error::ParameterError::from_kind(
-            image::error::ParameterErrorKind::Generic("Failed to create image buffer".to_string()),
-        ))
-    })?;
+    .ok_or_else(buffer_error)?;
 
-    image.save(&frame_path)?;
+    image.save(frame_path)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_frame_time_and_shader_tokens() {
+        let name = expand_filename_template("{shader}_{frame:06}_{time:.3}.png", 42, 1.5, "fire");
+        assert_eq!(name.unwrap(), "fire_000042_1.500.png");
+        assert_eq!(
+            expand_filename_template("{frame}", 7, 0.0, "x").unwrap(),
+            "7.png"
+        );
+    }
+
+    #[test]
+    fn time_decimals_are_not_an_extension() {
+        assert_eq!(
+            expand_filename_template("shot_{frame}_{time:.3}", 3, 1.5, "x").unwrap(),
+            "shot_3_1.500.png"
+        );
+        assert_eq!(
+            expand_filename_template("shot_{time:.3}", 3, 1.5, "x").unwrap(),
+            "shot_1.500_00003.png"
+        );
+        assert_eq!(
+            expand_filename_template("shot_{time:.3}.JPG", 3, 1.5, "x").unwrap(),
+            "shot_1.500_00003.JPG"
+        );
+    }
+
+    #[test]
+    fn default_template_pads_to_five_digits() {
+        let name =
+            expand_filename_template(ExportSettings::DEFAULT_FILENAME_TEMPLATE, 42, 0.0, "x");
+        assert_eq!(name.unwrap(), "frame_00042.png");
+    }
+
+    #[test]
+    fn template_without_frame_gets_numbered() {
+        assert_eq!(
+            expand_filename_template("{shader}.png", 3, 0.0, "fire").unwrap(),
+            "fire_00003.png"
+        );
+        assert_eq!(
+            expand_filename_template("still", 3, 0.0, "fire").unwrap(),
+            "still_00003.png"
+        );
+        let first = expand_filename_template("{time:.1}.exr", 0, 0.0, "x").unwrap();
+        let second = expand_filename_template("{time:.1}.exr", 1, 0.01, "x").unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn rejects_bad_tokens() {
+        for template in [
+            "{frame",
+            "frame}.png",
+            "{frame:6}",
+            "{time:3}",
+            "{shader:02}",
+            "{name}",
+            "",
+        ] {
+            assert!(
+                expand_filename_template(template, 0, 0.0, "x").is_err(),
+                "{template:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_template_falls_back_to_default() {
+        let mut settings = ExportSettings {
+            filename_template: "{oops}.png".to_string(),
+            ..Default::default()
+        };
+        assert!(!settings.validate_filename_template());
+        assert_eq!(
+            settings.filename_template,
+            ExportSettings::DEFAULT_FILENAME_TEMPLATE
+        );
+    }
+
+    /// Red ramps left to right at a constant 50% straight alpha
+    const HALF_ALPHA_GRADIENT_WGSL: &str = r#"
+@group(1) @binding(0) var output: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(16, 16, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    let red = f32(id.x) / f32(size.x - 1u);
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(red, 0.0, 0.0, 0.5));
+}
+"#;
+
+    #[test]
+    fn preserve_alpha_round_trips_a_half_alpha_gradient() {
+        let Some(core) = crate::testing::test_core() else {
+            return;
+        };
+        let config = crate::compute::ComputeShader::builder()
+            .with_texture_format(crate::compute::COMPUTE_TEXTURE_FORMAT_RGBA8)
+            .with_label("Alpha Export Test")
+            .build();
+        let mut shader =
+            crate::compute::ComputeShader::from_builder(core, HALF_ALPHA_GRADIENT_WGSL, config);
+        let mut render_kit = crate::RenderKit::new_with_standard_layout(core);
+        let captured = crate::testing::TestHarness::new(1, 0.0)
+            .with_alpha()
+            .run(core, &mut shader, &mut render_kit)
+            .unwrap();
+
+        let settings = ExportSettings {
+            export_path: std::env::temp_dir()
+                .join(format!("cuneus-alpha-test-{}", std::process::id())),
+            width: captured.width(),
+            height: captured.height(),
+            preserve_alpha: true,
+            ..Default::default()
+        };
+        let path = save_frame(captured.into_raw(), 0, &settings).unwrap();
+        let written = image::open(&path).unwrap();
+        std::fs::remove_dir_all(&settings.export_path).ok();
+
+        assert!(written.color().has_alpha());
+        let written = written.to_rgba8();
+        for pixel in written.pixels() {
+            assert!(pixel[3].abs_diff(128) <= 1, "alpha {}", pixel[3]);
+        }
+        let y = written.height() / 2;
+        // `save_frame` swaps red and blue on macOS
+        let red = |x| {
+            let pixel = written.get_pixel(x, y);
+            pixel[0].max(pixel[2])
+        };
+        let (left, right) = (red(0), red(written.width() - 1));
+        // Straight alpha: the color is not darkened by the 50% alpha
+        assert!(left < 8 && right > 247, "{left} .. {right}");
+    }
+}
@@ -0,0 +1,623 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Where and why an expression failed to parse
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionError {
+    /// Byte offset into the source
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Fract,
+    Round,
+    Sqrt,
+    Exp,
+    Ln,
+    Pow,
+    Min,
+    Max,
+    Clamp,
+    Mix,
+    Step,
+    Smoothstep,
+    Mod,
+}
+
+impl Func {
+    fn lookup(name: &str) -> Option<(Func, usize)> {
+        Some(match name {
+            "sin" => (Func::Sin, 1),
+            "cos" => (Func::Cos, 1),
+            "tan" => (Func::Tan, 1),
+            "asin" => (Func::Asin, 1),
+            "acos" => (Func::Acos, 1),
+            "atan" => (Func::Atan, 1),
+            "atan2" => (Func::Atan2, 2),
+            "abs" => (Func::Abs, 1),
+            "sign" => (Func::Sign, 1),
+            "floor" => (Func::Floor, 1),
+            "ceil" => (Func::Ceil, 1),
+            "fract" => (Func::Fract, 1),
+            "round" => (Func::Round, 1),
+            "sqrt" => (Func::Sqrt, 1),
+            "exp" => (Func::Exp, 1),
+            "ln" | "log" => (Func::Ln, 1),
+            "pow" => (Func::Pow, 2),
+            "min" => (Func::Min, 2),
+            "max" => (Func::Max, 2),
+            "clamp" => (Func::Clamp, 3),
+            "mix" => (Func::Mix, 3),
+            "step" => (Func::Step, 2),
+            "smoothstep" => (Func::Smoothstep, 3),
+            "mod" => (Func::Mod, 2),
+            _ => return None,
+        })
+    }
+
+    fn apply(self, a: &[f32]) -> f32 {
+        match self {
+            Func::Sin => a[0].sin(),
+            Func::Cos => a[0].cos(),
+            Func::Tan => a[0].tan(),
+            Func::Asin => a[0].asin(),
+            Func::Acos => a[0].acos(),
+            Func::Atan => a[0].atan(),
+            Func::Atan2 => a[0].atan2(a[1]),
+            Func::Abs => a[0].abs(),
+            Func::Sign => {
+                if a[0] == 0.0 {
+                    0.0
+                } else {
+                    a[0].signum()
+                }
+            }
+            Func::Floor => a[0].floor(),
+            Func::Ceil => a[0].ceil(),
+            Func::Fract => a[0] - a[0].floor(),
+            Func::Round => a[0].round(),
+            Func::Sqrt => a[0].sqrt(),
+            Func::Exp => a[0].exp(),
+            Func::Ln => a[0].ln(),
+            Func::Pow => a[0].powf(a[1]),
+            Func::Min => a[0].min(a[1]),
+            Func::Max => a[0].max(a[1]),
+            Func::Clamp => a[0].max(a[1]).min(a[2]),
+            Func::Mix => a[0] + (a[1] - a[0]) * a[2],
+            Func::Step => {
+                if a[1] < a[0] {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Func::Smoothstep => {
+                let t = ((a[2] - a[0]) / (a[1] - a[0])).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+            // GLSL mod: result has the sign of the divisor
+            Func::Mod => a[0] - a[1] * (a[0] / a[1]).floor(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Number(f32),
+    Variable(String),
+    Negate(Box<Node>),
+    Binary(Op, Box<Node>, Box<Node>),
+    Call(Func, Vec<Node>),
+}
+
+impl Node {
+    fn eval(&self, vars: &ExpressionVariables) -> f32 {
+        match self {
+            Node::Number(value) => *value,
+            Node::Variable(name) => vars.get(name).unwrap_or(0.0),
+            Node::Negate(node) => -node.eval(vars),
+            Node::Binary(op, a, b) => {
+                let (a, b) = (a.eval(vars), b.eval(vars));
+                match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Rem => a % b,
+                    Op::Pow => a.powf(b),
+                }
+            }
+            Node::Call(func, args) => {
+                let values: Vec<f32> = args.iter().map(|arg| arg.eval(vars)).collect();
+                func.apply(&values)
+            }
+        }
+    }
+
+    fn collect_variables<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Node::Number(_) => {}
+            Node::Variable(name) => {
+                if !out.contains(&name.as_str()) {
+                    out.push(name);
+                }
+            }
+            Node::Negate(node) => node.collect_variables(out),
+            Node::Binary(_, a, b) => {
+                a.collect_variables(out);
+                b.collect_variables(out);
+            }
+            Node::Call(_, args) => args.iter().for_each(|arg| arg.collect_variables(out)),
+        }
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ExpressionError> {
+        Err(ExpressionError {
+            position: self.pos,
+            message: message.into(),
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.source[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> &'a str {
+        let rest = &self.source[self.pos..];
+        let len = rest.find(|c: char| !accept(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn expr(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                Op::Add
+            } else if self.eat('-') {
+                Op::Sub
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(op, Box::new(node), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                Op::Mul
+            } else if self.eat('/') {
+                Op::Div
+            } else if self.eat('%') {
+                Op::Rem
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, ExpressionError> {
+        if self.eat('-') {
+            return Ok(Node::Negate(Box::new(self.unary()?)));
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        let base = self.primary()?;
+        // Right associative and binding tighter than unary minus: -2^2 == -4
+        if self.eat('^') {
+            return Ok(Node::Binary(
+                Op::Pow,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Node, ExpressionError> {
+        let start = self.pos;
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let node = self.expr()?;
+                if !self.eat(')') {
+                    return self.error("expected `)`");
+                }
+                Ok(node)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let text = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                match text.parse::<f32>() {
+                    Ok(value) => Ok(Node::Number(value)),
+                    Err(_) => {
+                        self.pos = start;
+                        self.error(format!("invalid number `{text}`"))
+                    }
+                }
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '.');
+                if !self.eat('(') {
+                    return Ok(Node::Variable(name.to_string()));
+                }
+                let Some((func, arity)) = Func::lookup(name) else {
+                    self.pos = start;
+                    return self.error(format!("unknown function `{name}`"));
+                };
+                let mut args = Vec::new();
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        if !self.eat(',') {
+                            return self.error("expected `,` or `)`");
+                        }
+                    }
+                }
+                if args.len() != arity {
+                    self.pos = start;
+                    return self.error(format!(
+                        "`{name}` takes {arity} argument{}, got {}",
+                        if arity == 1 { "" } else { "s" },
+                        args.len()
+                    ));
+                }
+                Ok(Node::Call(func, args))
+            }
+            Some(c) => self.error(format!("unexpected `{c}`")),
+            None => self.error("unexpected end of expression"),
+        }
+    }
+}
+
+/// A parsed arithmetic expression over named f32 variables.
+///
+/// Syntax: numbers, variables (`time`, `mouse.x`), `+ - * / %`, `^` (power), parentheses
+/// and the functions `sin cos tan asin acos atan atan2 abs sign floor ceil fract round
+/// sqrt exp ln pow min max clamp mix step smoothstep mod` with their GLSL meaning.
+/// Variables that aren't set evaluate to 0.
+#[derive(Debug, Clone)]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+        let mut parser = Parser { source, pos: 0 };
+        let root = parser.expr()?;
+        if parser.peek().is_some() {
+            return parser.error("unexpected trailing input");
+        }
+        Ok(Self {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    pub fn eval(&self, vars: &ExpressionVariables) -> f32 {
+        self.root.eval(vars)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Variables the expression reads, in order of first use
+    pub fn variables(&self) -> Vec<&str> {
+        let mut out = Vec::new();
+        self.root.collect_variables(&mut out);
+        out
+    }
+}
+
+/// Values expressions can read, see `RenderKit::expression_variables` for the standard set
+#[derive(Debug, Clone)]
+pub struct ExpressionVariables {
+    values: HashMap<String, f32>,
+}
+
+impl Default for ExpressionVariables {
+    fn default() -> Self {
+        let mut values = HashMap::new();
+        values.insert("pi".to_string(), std::f32::consts::PI);
+        values.insert("tau".to_string(), std::f32::consts::TAU);
+        Self { values }
+    }
+}
+
+impl ExpressionVariables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, name: &str, value: f32) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<f32> {
+        self.values.get(name).copied()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+}
+
+/// Drives one uniform field from an expression instead of a slider.
+///
+/// Call `apply` every frame before writing the uniforms; it does nothing while the
+/// binding is switched off, so the slider value stays in charge. `ui` draws the toggle
+/// between the two.
+///
+/// ```ignore
+/// let mut zoom = ExpressionBinding::new("zoom", "1.0 + bass * 0.5")?;
+/// // each frame
+/// let vars = self.base.expression_variables();
+/// zoom.apply(&mut params.zoom, &vars);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExpressionBinding {
+    field: String,
+    text: String,
+    expression: Option<Expression>,
+    error: Option<ExpressionError>,
+    /// Whether the expression (true) or the slider (false) sets the field
+    pub enabled: bool,
+}
+
+impl ExpressionBinding {
+    /// An enabled binding of `field` to `expr`
+    pub fn new(field: &str, expr: &str) -> Result<Self, ExpressionError> {
+        Ok(Self {
+            field: field.to_string(),
+            text: expr.to_string(),
+            expression: Some(Expression::parse(expr)?),
+            error: None,
+            enabled: true,
+        })
+    }
+
+    /// A switched-off binding with no expression yet, for fields that start as sliders
+    pub fn slider(field: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            text: String::new(),
+            expression: None,
+            error: None,
+            enabled: false,
+        }
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Last parse error of the edited text, the previous valid expression stays in use
+    pub fn error(&self) -> Option<&ExpressionError> {
+        self.error.as_ref()
+    }
+
+    /// Replace the expression. On error the previous one is kept.
+    pub fn set_expression(&mut self, expr: &str) -> Result<(), ExpressionError> {
+        self.text = expr.to_string();
+        match Expression::parse(expr) {
+            Ok(expression) => {
+                self.expression = Some(expression);
+                self.error = None;
+                Ok(())
+            }
+            Err(e) => {
+                self.error = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    /// Current expression value, `None` while switched off or without a valid expression
+    pub fn eval(&self, vars: &ExpressionVariables) -> Option<f32> {
+        if !self.enabled {
+            return None;
+        }
+        let value = self.expression.as_ref()?.eval(vars);
+        value.is_finite().then_some(value)
+    }
+
+    /// Overwrite `value` with the expression result. Returns true if it changed.
+    pub fn apply(&self, value: &mut f32, vars: &ExpressionVariables) -> bool {
+        match self.eval(vars) {
+            Some(new) if new != *value => {
+                *value = new;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Slider for `value`, or the expression editor when switched on (the `ƒ` toggle).
+    /// Returns true if the slider moved or the binding changed.
+    pub fn ui(&mut self, ui: &mut egui::Ui, value: &mut f32, range: RangeInclusive<f32>) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            changed |= ui
+                .toggle_value(&mut self.enabled, "ƒ")
+                .on_hover_text("Drive this value with an expression")
+                .changed();
+            if self.enabled {
+                let mut text = self.text.clone();
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut text)
+                        .desired_width(160.0)
+                        .hint_text("sin(time) * bass"),
+                );
+                if response.changed() {
+                    let _ = self.set_expression(&text);
+                    changed = true;
+                }
+                ui.label(format!("{} = {:.3}", self.field, value));
+            } else {
+                changed |= ui
+                    .add(egui::Slider::new(value, range).text(&self.field))
+                    .changed();
+            }
+        });
+        if self.enabled {
+            if let Some(error) = &self.error {
+                ui.colored_label(egui::Color32::LIGHT_RED, error.to_string());
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str) -> f32 {
+        Expression::parse(source)
+            .unwrap()
+            .eval(&ExpressionVariables::new())
+    }
+
+    fn error_at(source: &str) -> usize {
+        Expression::parse(source).unwrap_err().position
+    }
+
+    #[test]
+    fn operator_precedence() {
+        assert_eq!(eval("1+2*3"), 7.0);
+        assert_eq!(eval("(1+2)*3"), 9.0);
+        assert_eq!(eval("10-4-3"), 3.0);
+        assert_eq!(eval("8/4/2"), 1.0);
+        assert_eq!(eval("7%4*2"), 6.0);
+        assert_eq!(eval("-2^2"), -4.0);
+        assert_eq!(eval("2^3^2"), 512.0);
+        assert_eq!(eval("2^-1"), 0.5);
+        assert_eq!(eval("2*-3"), -6.0);
+    }
+
+    #[test]
+    fn functions_and_constants() {
+        assert_eq!(eval("max(1, 3)"), 3.0);
+        assert_eq!(eval("clamp(5, 0, 2)"), 2.0);
+        assert_eq!(eval("mix(0, 10, 0.25)"), 2.5);
+        assert_eq!(eval("sqrt(abs(-16))"), 4.0);
+        assert_eq!(eval("floor(-1.5) + fract(2.25)"), -1.75);
+        assert!((eval("sin(pi / 2)") - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn function_arity_is_checked() {
+        let error = Expression::parse("1 + min(1)").unwrap_err();
+        assert_eq!(error.position, 4);
+        assert_eq!(error.message, "`min` takes 2 arguments, got 1");
+        let error = Expression::parse("sin(1, 2)").unwrap_err();
+        assert_eq!(error.message, "`sin` takes 1 argument, got 2");
+        assert!(Expression::parse("sin()").is_err());
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        assert_eq!(error_at("1 + foo(2)"), 4);
+        assert_eq!(error_at("(1 + 2"), 6);
+        assert_eq!(error_at("1 +"), 3);
+        assert_eq!(error_at("1 2"), 2);
+        assert_eq!(error_at("2 * 1..5"), 4);
+        assert_eq!(error_at("max(1; 2)"), 5);
+        assert_eq!(
+            Expression::parse("1 + )").unwrap_err().to_string(),
+            "unexpected `)` at column 5"
+        );
+    }
+
+    #[test]
+    fn variables_read_their_value_or_zero() {
+        let expression = Expression::parse("time * 2 + mouse.x + missing").unwrap();
+        assert_eq!(expression.variables(), ["time", "mouse.x", "missing"]);
+
+        let mut vars = ExpressionVariables::new();
+        assert_eq!(expression.eval(&vars), 0.0);
+        vars.set("time", 1.5);
+        vars.set("mouse.x", 0.25);
+        assert_eq!(expression.eval(&vars), 3.25);
+    }
+
+    #[test]
+    fn binding_keeps_the_last_valid_expression() {
+        let mut binding = ExpressionBinding::new("zoom", "2").unwrap();
+        assert!(binding.set_expression("2 +").is_err());
+        assert!(binding.error().is_some());
+
+        let mut value = 1.0;
+        let vars = ExpressionVariables::new();
+        assert!(binding.apply(&mut value, &vars));
+        assert_eq!(value, 2.0);
+        binding.enabled = false;
+        assert_eq!(binding.eval(&vars), None);
+    }
+}
@@ -11,6 +11,8 @@ pub struct FontUniforms {
     pub char_size: [f32; 2],
     pub screen_size: [f32; 2],
     pub grid_size: [f32; 2],
+    /// SDF atlas parameters: `[spread_px, smoothing, is_sdf, 0]`, all zero for bitmap atlases
+    pub sdf_params: [f32; 4],
 }
 
 impl UniformProvider for FontUniforms {
@@ -24,6 +26,8 @@ pub struct CharInfo {
     pub uv_min: [f32; 2],
     pub uv_max: [f32; 2],
     pub char_code: u8,
+    /// Distance covered by the field from the glyph edge to 0/1, in UV units (0 for bitmap)
+    pub sdf_range: f32,
 }
 
 pub struct FontSystem {
@@ -35,16 +39,48 @@ pub struct FontSystem {
     pub atlas_height: u32,
     pub grid_size: u32,
     pub char_size: u32,
+    /// Distance field spread in pixels, `None` for the plain bitmap atlas
+    pub sdf_spread: Option<u32>,
 }
 
 impl FontSystem {
     pub fn new(core: &Core) -> Self {
+        Self::create(core, None)
+    }
+
+    /// Font system with a signed distance field atlas, smooth at any scale.
+    /// The field is stored in every channel: 0.5 is the glyph edge, values above are inside,
+    /// and `spread` pixels away from the edge it reaches 0 or 1.
+    ///
+    /// The atlas is bound without a sampler, so filter it by hand in WGSL:
+    ///
+    /// ```wgsl
+    /// fn sdf_sample(uv: vec2<f32>) -> f32 {
+    ///     let p = uv * font_texture_uniform.atlas_size - 0.5;
+    ///     let i = vec2<i32>(floor(p));
+    ///     let f = fract(p);
+    ///     let a = textureLoad(t_font_texture_atlas, i, 0).r;
+    ///     let b = textureLoad(t_font_texture_atlas, i + vec2<i32>(1, 0), 0).r;
+    ///     let c = textureLoad(t_font_texture_atlas, i + vec2<i32>(0, 1), 0).r;
+    ///     let d = textureLoad(t_font_texture_atlas, i + vec2<i32>(1, 1), 0).r;
+    ///     return mix(mix(a, b, f.x), mix(c, d, f.x), f.y);
+    /// }
+    ///
+    /// // scale = on-screen glyph size / char_size
+    /// let smoothing = font_texture_uniform.sdf_params.y / scale;
+    /// let alpha = smoothstep(0.5 - smoothing, 0.5 + smoothing, sdf_sample(glyph_uv));
+    /// ```
+    pub fn new_sdf(core: &Core, spread: u32) -> Self {
+        Self::create(core, Some(spread.max(1)))
+    }
+
+    fn create(core: &Core, sdf_spread: Option<u32>) -> Self {
         //note that: I always use following:
         // _ATLAS_SIZE: u32 = 1024;
         // _CELL_SIZE: u32 = 64;
         // _GRID_SIZE: u32 = 16;
         let font_texture_bytes = include_bytes!("../assets/fonts/fonttexture.png");
-        let font_image = image::load_from_memory(font_texture_bytes)
+        let mut font_image = image::load_from_memory(font_texture_bytes)
             .expect("Failed to load font texture")
             .into_rgba8();
 
@@ -53,6 +89,10 @@ impl FontSystem {
         let grid_size = 16u32;
         let char_size = atlas_width / grid_size;
 
+        if let Some(spread) = sdf_spread {
+            Self::convert_to_sdf(&mut font_image, char_size, spread);
+        }
+
         let font_bind_group_layout =
             core.device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -86,6 +126,10 @@ impl FontSystem {
             char_size: [char_size as f32, char_size as f32],
             screen_size: [core.size.width as f32, core.size.height as f32],
             grid_size: [grid_size as f32, grid_size as f32],
+            sdf_params: match sdf_spread {
+                Some(spread) => [spread as f32, 0.5 / spread as f32, 1.0, 0.0],
+                None => [0.0; 4],
+            },
         };
 
         let font_uniform_layout =
@@ -113,7 +157,8 @@ impl FontSystem {
         );
 
         let atlas_texture = Self::create_font_texture(core, &font_image);
-        let char_map = Self::generate_character_map(grid_size);
+        let sdf_range = sdf_spread.map_or(0.0, |spread| spread as f32 / atlas_width as f32);
+        let char_map = Self::generate_character_map(grid_size, sdf_range);
 
         Self {
             atlas_texture,
@@ -124,6 +169,108 @@ impl FontSystem {
             atlas_height,
             grid_size,
             char_size,
+            sdf_spread,
+        }
+    }
+
+    /// Replace the glyph coverage with a signed distance field, cell by cell so
+    /// neighbouring glyphs don't bleed into each other
+    fn convert_to_sdf(image: &mut image::RgbaImage, cell_size: u32, spread: u32) {
+        // The atlas either stores glyphs in alpha or as white on opaque black
+        let uses_alpha = image.pixels().any(|p| p[3] < 255);
+        let cells_x = image.width() / cell_size;
+        let cells_y = image.height() / cell_size;
+        let n = cell_size as usize;
+
+        let mut inside = vec![false; n * n];
+        for cell_y in 0..cells_y {
+            for cell_x in 0..cells_x {
+                let (x0, y0) = (cell_x * cell_size, cell_y * cell_size);
+                for y in 0..cell_size {
+                    for x in 0..cell_size {
+                        let p = image.get_pixel(x0 + x, y0 + y);
+                        let coverage = if uses_alpha { p[3] } else { p[0] };
+                        inside[(y * cell_size + x) as usize] = coverage > 127;
+                    }
+                }
+
+                let to_inside = Self::distance_field(&inside, n, true);
+                let to_outside = Self::distance_field(&inside, n, false);
+                for y in 0..cell_size {
+                    for x in 0..cell_size {
+                        let i = (y * cell_size + x) as usize;
+                        // Positive inside the glyph
+                        let signed = to_outside[i].sqrt() - to_inside[i].sqrt();
+                        let value = (0.5 + signed / (2.0 * spread as f32)).clamp(0.0, 1.0);
+                        let v = (value * 255.0).round() as u8;
+                        image.put_pixel(x0 + x, y0 + y, image::Rgba([v, v, v, v]));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Squared euclidean distance from every pixel to the nearest pixel where
+    /// `inside == target` (Felzenszwalb & Huttenlocher, separable)
+    fn distance_field(inside: &[bool], n: usize, target: bool) -> Vec<f32> {
+        const FAR: f32 = 1e20;
+        let mut grid: Vec<f32> = inside
+            .iter()
+            .map(|&v| if v == target { 0.0 } else { FAR })
+            .collect();
+
+        let mut line = vec![0.0; n];
+        let mut out = vec![0.0; n];
+        let mut v = vec![0usize; n];
+        let mut z = vec![0.0; n + 1];
+        for x in 0..n {
+            for y in 0..n {
+                line[y] = grid[y * n + x];
+            }
+            Self::distance_1d(&line, &mut out, &mut v, &mut z);
+            for y in 0..n {
+                grid[y * n + x] = out[y];
+            }
+        }
+        for y in 0..n {
+            line.copy_from_slice(&grid[y * n..(y + 1) * n]);
+            Self::distance_1d(&line, &mut out, &mut v, &mut z);
+            grid[y * n..(y + 1) * n].copy_from_slice(&out);
+        }
+        grid
+    }
+
+    fn distance_1d(f: &[f32], d: &mut [f32], v: &mut [usize], z: &mut [f32]) {
+        let n = f.len();
+        let mut k = 0;
+        v[0] = 0;
+        z[0] = f32::NEG_INFINITY;
+        z[1] = f32::INFINITY;
+        for q in 1..n {
+            let qf = q as f32;
+            let parabola_intersection = |p: usize| {
+                let pf = p as f32;
+                ((f[q] + qf * qf) - (f[p] + pf * pf)) / (2.0 * qf - 2.0 * pf)
+            };
+            // z[0] is -inf so this always stops at k = 0
+            let mut s = parabola_intersection(v[k]);
+            while s <= z[k] {
+                k -= 1;
+                s = parabola_intersection(v[k]);
+            }
+            k += 1;
+            v[k] = q;
+            z[k] = s;
+            z[k + 1] = f32::INFINITY;
+        }
+        k = 0;
+        for (q, d) in d.iter_mut().enumerate() {
+            let qf = q as f32;
+            while z[k + 1] < qf {
+                k += 1;
+            }
+            let p = v[k] as f32;
+            *d = (qf - p) * (qf - p) + f[v[k]];
         }
     }
 
@@ -220,10 +367,11 @@ impl FontSystem {
             view,
             sampler,
             bind_group,
+            view_formats: Vec::new(),
         }
     }
 
-    fn generate_character_map(grid_size: u32) -> HashMap<char, CharInfo> {
+    fn generate_character_map(grid_size: u32, sdf_range: f32) -> HashMap<char, CharInfo> {
         let mut char_map = HashMap::new();
 
         for ascii_code in 32..127 {
@@ -247,6 +395,7 @@ impl FontSystem {
                     (grid_y + 1) as f32 / grid_size as f32,
                 ],
                 char_code: ascii_code as u8,
+                sdf_range,
             };
 
             char_map.insert(char, char_info);
@@ -260,6 +409,19 @@ impl FontSystem {
         self.font_uniforms.update(queue);
     }
 
+    /// Edge softness for SDF atlases, in distance field units (default `0.5 / spread`)
+    pub fn set_sdf_smoothing(&mut self, smoothing: f32, queue: &wgpu::Queue) {
+        if self.sdf_spread.is_none() {
+            return;
+        }
+        self.font_uniforms.data.sdf_params[1] = smoothing.max(0.0);
+        self.font_uniforms.update(queue);
+    }
+
+    pub fn is_sdf(&self) -> bool {
+        self.sdf_spread.is_some()
+    }
+
     pub fn get_char_info(&self, ch: char) -> Option<&CharInfo> {
         self.char_map.get(&ch)
     }
@@ -1,9 +1,14 @@
 use std::collections::VecDeque;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 pub struct FpsTracker {
     last_frame_time: Instant,
     frame_times: VecDeque<f32>,
     current_fps: f32,
+    /// Latest accepted frame time in seconds
+    last_frame: f32,
+    /// Exponential moving average of the frame time in seconds, 0 until the first frame
+    smoothed_frame: f32,
+    smoothing_alpha: f32,
 }
 
 impl Default for FpsTracker {
@@ -18,9 +23,15 @@ impl FpsTracker {
             last_frame_time: Instant::now(),
             frame_times: VecDeque::with_capacity(60),
             current_fps: 0.0,
+            last_frame: 0.0,
+            smoothed_frame: 0.0,
+            smoothing_alpha: Self::DEFAULT_SMOOTHING_ALPHA,
         }
     }
 
+    /// Weight of the newest frame in `smoothed_frame_time_ms`
+    pub const DEFAULT_SMOOTHING_ALPHA: f32 = 0.1;
+
     pub fn update(&mut self) {
         let now = Instant::now();
         let frame_time = now.duration_since(self.last_frame_time).as_secs_f32();
@@ -28,6 +39,12 @@ impl FpsTracker {
 
         // lets filter out unreasonable frame times to avoid spikes
         if frame_time > 0.0 && frame_time < 1.0 {
+            self.last_frame = frame_time;
+            self.smoothed_frame = if self.smoothed_frame == 0.0 {
+                frame_time
+            } else {
+                self.smoothed_frame + self.smoothing_alpha * (frame_time - self.smoothed_frame)
+            };
             self.frame_times.push_back(frame_time);
             if self.frame_times.len() > 30 {
                 self.frame_times.pop_front();
@@ -45,4 +62,295 @@ impl FpsTracker {
     pub fn fps(&self) -> f32 {
         self.current_fps
     }
+
+    /// Duration of the last frame in milliseconds, unsmoothed
+    pub fn frame_time_ms(&self) -> f32 {
+        self.last_frame * 1000.0
+    }
+
+    /// Exponentially smoothed frame time in milliseconds, for frame budget decisions
+    pub fn smoothed_frame_time_ms(&self) -> f32 {
+        self.smoothed_frame * 1000.0
+    }
+
+    /// Weight of each new frame in the smoothed frame time, clamped to 0.01..=1.
+    /// Higher reacts faster, lower is steadier; 1 disables smoothing.
+    pub fn set_smoothing_alpha(&mut self, alpha: f32) {
+        self.smoothing_alpha = alpha.clamp(0.01, 1.0);
+    }
+
+    pub fn smoothing_alpha(&self) -> f32 {
+        self.smoothing_alpha
+    }
+}
+
+/// Caps the frame rate by telling the event loop when the next frame is due.
+///
+/// This is a cooperative cap: the loop waits with `ControlFlow::WaitUntil`, so the actual
+/// rate depends on the OS timer resolution and can dip under the limit when a frame takes
+/// longer than its budget. It's meant to save power, not for real-time guarantees.
+pub struct FrameLimiter {
+    limit: Option<u32>,
+    next_frame: Instant,
+}
+
+impl Default for FrameLimiter {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl FrameLimiter {
+    pub fn new(limit: Option<u32>) -> Self {
+        Self {
+            limit: limit.filter(|fps| *fps > 0),
+            next_frame: Instant::now(),
+        }
+    }
+
+    /// `None` (or 0) removes the cap
+    pub fn set_limit(&mut self, limit: Option<u32>) {
+        self.limit = limit.filter(|fps| *fps > 0);
+        self.next_frame = Instant::now();
+    }
+
+    pub fn limit(&self) -> Option<u32> {
+        self.limit
+    }
+
+    /// Time budget per frame, `None` when unlimited
+    pub fn frame_budget(&self) -> Option<Duration> {
+        self.limit
+            .map(|fps| Duration::from_secs_f64(1.0 / fps as f64))
+    }
+
+    /// `None` if a frame can start now, otherwise when it can
+    pub fn wait_until(&self) -> Option<Instant> {
+        self.limit?;
+        let now = Instant::now();
+        (now < self.next_frame).then_some(self.next_frame)
+    }
+
+    /// Mark the start of a frame and schedule the next one
+    pub fn frame_started(&mut self) {
+        let Some(budget) = self.frame_budget() else {
+            return;
+        };
+        let now = Instant::now();
+        self.next_frame += budget;
+        // Don't try to catch up after a slow frame, that would just burst frames
+        if self.next_frame < now {
+            self.next_frame = now + budget;
+        }
+    }
+}
+
+/// Opt-in dynamic resolution: picks a render scale that keeps frame time under a budget.
+///
+/// Once per `interval` the average frame time of that interval is compared to the target:
+/// - above the target: scale down right away, by `sqrt(target / average)` since cost
+///   follows the pixel count (at most halving per step)
+/// - below `target_ms * (1 - hysteresis)`: scale up by `step`, slowly, so a scale that
+///   just fits isn't immediately raised back into overshooting
+/// - in between: keep the scale
+///
+/// The frame time is the wall-clock interval between frames, which includes waiting for
+/// vsync: with a vsynced present mode it never drops below the refresh interval, so a
+/// 16.6ms target on a 60Hz display would read as always over budget. Pass the display's
+/// refresh rate with `with_refresh_rate` (or `with_window_refresh_rate`) when presenting
+/// with vsync. The target is then clamped to `VSYNC_MARGIN` refresh intervals, so only
+/// frames that miss a refresh count as over budget, and since headroom below the refresh
+/// interval can't be seen, frames that keep up with the refresh rate count as room to
+/// scale up once `upscale_delay` intervals passed since the last scale-down.
+///
+/// Scales are kept within `min_scale..=max_scale` and rounded to `step`. The crate has no
+/// render scale of its own, so apply the value `update` returns yourself, e.g. by resizing
+/// the compute shader to `scaled_size` and dispatching with `dispatch_with_count`; the
+/// output is stretched over the window when displayed.
+pub struct DynamicResolution {
+    pub target_ms: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// Headroom under the target required before scaling up, as a fraction of the target
+    pub hysteresis: f32,
+    /// Scale increment when scaling up, and the rounding granularity
+    pub step: f32,
+    pub interval: Duration,
+    /// Intervals to wait after scaling down before scaling up again
+    pub upscale_delay: u32,
+    /// Display refresh interval in ms when presenting with vsync
+    refresh_ms: Option<f32>,
+    calm_intervals: u32,
+    scale: f32,
+    window_start: Instant,
+    total_ms: f32,
+    samples: u32,
+}
+
+impl DynamicResolution {
+    /// Over-budget threshold under vsync, in refresh intervals. A missed refresh takes two.
+    pub const VSYNC_MARGIN: f32 = 1.25;
+    /// Tolerance for frame time jitter when checking that frames keep up with the refresh
+    const VSYNC_JITTER: f32 = 0.05;
+
+    pub fn new(target_ms: f32) -> Self {
+        Self {
+            target_ms,
+            min_scale: 0.5,
+            max_scale: 1.0,
+            hysteresis: 0.2,
+            step: 0.05,
+            interval: Duration::from_secs(1),
+            upscale_delay: 3,
+            refresh_ms: None,
+            calm_intervals: 0,
+            scale: 1.0,
+            window_start: Instant::now(),
+            total_ms: 0.0,
+            samples: 0,
+        }
+    }
+
+    pub fn with_scale_range(mut self, min_scale: f32, max_scale: f32) -> Self {
+        self.min_scale = min_scale.max(0.05);
+        self.max_scale = max_scale.max(self.min_scale);
+        self.scale = self.scale.clamp(self.min_scale, self.max_scale);
+        self
+    }
+
+    /// Refresh rate in Hz of the display when presenting with vsync, see the type docs
+    pub fn with_refresh_rate(mut self, hz: f32) -> Self {
+        self.refresh_ms = (hz > 0.0).then(|| 1000.0 / hz);
+        self
+    }
+
+    /// `with_refresh_rate` with the refresh rate of the monitor the window is on, if known
+    pub fn with_window_refresh_rate(self, window: &winit::window::Window) -> Self {
+        let hz = window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+            .map_or(0.0, |millihertz| millihertz as f32 / 1000.0);
+        self.with_refresh_rate(hz)
+    }
+
+    /// Frame time above which the scale goes down: `target_ms`, or under vsync at least
+    /// `VSYNC_MARGIN` refresh intervals
+    pub fn effective_target_ms(&self) -> f32 {
+        match self.refresh_ms {
+            Some(refresh_ms) => self.target_ms.max(refresh_ms * Self::VSYNC_MARGIN),
+            None => self.target_ms,
+        }
+    }
+
+    /// Current scale, 1.0 is native resolution
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// `width`/`height` multiplied by the current scale, at least 1x1
+    pub fn scaled_size(&self, width: u32, height: u32) -> (u32, u32) {
+        (
+            ((width as f32 * self.scale).round() as u32).max(1),
+            ((height as f32 * self.scale).round() as u32).max(1),
+        )
+    }
+
+    /// Call once per frame after `FpsTracker::update`. Returns the new scale when it changed.
+    pub fn update(&mut self, fps: &FpsTracker) -> Option<f32> {
+        let frame_ms = fps.frame_time_ms();
+        if frame_ms > 0.0 {
+            self.total_ms += frame_ms;
+            self.samples += 1;
+        }
+        if self.window_start.elapsed() < self.interval || self.samples == 0 {
+            return None;
+        }
+        let average = self.total_ms / self.samples as f32;
+        self.window_start = Instant::now();
+        self.total_ms = 0.0;
+        self.samples = 0;
+        self.evaluate(average)
+    }
+
+    /// Pick the scale for an interval with the given average frame time
+    fn evaluate(&mut self, average: f32) -> Option<f32> {
+        let budget = self.effective_target_ms();
+        let over_budget = average > budget;
+        let has_headroom = match self.refresh_ms {
+            // Vsync hides any headroom below the refresh interval, keeping up is the best sign
+            Some(refresh_ms) if budget > self.target_ms => {
+                average <= refresh_ms * (1.0 + Self::VSYNC_JITTER)
+            }
+            _ => average < budget * (1.0 - self.hysteresis),
+        };
+        if over_budget {
+            self.calm_intervals = 0;
+        } else {
+            self.calm_intervals = self.calm_intervals.saturating_add(1);
+        }
+
+        let target = if over_budget {
+            self.scale * (budget / average).sqrt().max(0.5)
+        } else if has_headroom && self.calm_intervals > self.upscale_delay {
+            self.scale + self.step
+        } else {
+            return None;
+        };
+        let step = self.step.max(0.01);
+        let mut scale = ((target / step).round() * step).clamp(self.min_scale, self.max_scale);
+        // Rounding may undo a small decrease, still take one step down when over budget
+        if over_budget && scale >= self.scale {
+            scale = (self.scale - step).max(self.min_scale);
+        }
+        if (scale - self.scale).abs() < f32::EPSILON {
+            return None;
+        }
+        self.scale = scale;
+        Some(scale)
+    }
+
+    /// Back to `max_scale` and a fresh measurement window, e.g. after a shader change
+    pub fn reset(&mut self) {
+        self.scale = self.max_scale;
+        self.calm_intervals = 0;
+        self.window_start = Instant::now();
+        self.total_ms = 0.0;
+        self.samples = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vsync_bound_frames_keep_the_scale() {
+        let mut resolution = DynamicResolution::new(16.6).with_refresh_rate(60.0);
+        for _ in 0..20 {
+            assert_eq!(resolution.evaluate(16.7), None);
+        }
+        assert_eq!(resolution.scale(), 1.0);
+    }
+
+    #[test]
+    fn missed_refreshes_scale_down_and_recover() {
+        let mut resolution = DynamicResolution::new(16.6).with_refresh_rate(60.0);
+        let lowered = resolution
+            .evaluate(33.3)
+            .expect("missed refreshes are over budget");
+        assert!(lowered < 1.0);
+        // Keeping up with the refresh rate only raises the scale after the delay
+        for _ in 0..resolution.upscale_delay {
+            assert_eq!(resolution.evaluate(16.7), None);
+        }
+        assert!(resolution
+            .evaluate(16.7)
+            .is_some_and(|scale| scale > lowered));
+    }
+
+    #[test]
+    fn without_vsync_the_target_is_used_as_is() {
+        let mut resolution = DynamicResolution::new(16.6);
+        assert!(resolution.evaluate(16.7).is_some_and(|scale| scale < 1.0));
+    }
 }
@@ -0,0 +1,60 @@
+use crate::UniformProvider;
+
+/// Global color grading applied by the display blit, see `RenderKit::set_color_grading`.
+///
+/// Works on the linear shader output in this order: `exposure` multiplies, `contrast`
+/// scales around mid-grey (0.18), `saturation` mixes with Rec. 709 luma (0 is greyscale)
+/// and `gamma` raises to `1 / gamma` (above 1 brightens midtones). The default is neutral.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ColorGrading {
+    pub exposure: f32,
+    pub gamma: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        Self::NEUTRAL
+    }
+}
+
+impl UniformProvider for ColorGrading {
+    fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+impl ColorGrading {
+    pub const NEUTRAL: Self = Self {
+        exposure: 1.0,
+        gamma: 1.0,
+        contrast: 1.0,
+        saturation: 1.0,
+    };
+
+    pub fn is_neutral(&self) -> bool {
+        *self == Self::NEUTRAL
+    }
+
+    /// Sliders for all four values plus a reset button. Returns true if anything changed.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let before = *self;
+        ui.add(
+            egui::Slider::new(&mut self.exposure, 0.0..=8.0)
+                .logarithmic(true)
+                .text("Exposure"),
+        );
+        ui.add(egui::Slider::new(&mut self.gamma, 0.2..=3.0).text("Gamma"));
+        ui.add(egui::Slider::new(&mut self.contrast, 0.0..=3.0).text("Contrast"));
+        ui.add(egui::Slider::new(&mut self.saturation, 0.0..=3.0).text("Saturation"));
+        if ui
+            .add_enabled(!self.is_neutral(), egui::Button::new("Reset"))
+            .clicked()
+        {
+            *self = Self::NEUTRAL;
+        }
+        *self != before
+    }
+}
@@ -1,3 +1,4 @@
+use super::waveform::WaveformTap;
 use anyhow::{anyhow, Result};
 use gst::glib::ControlFlow;
 use gst::prelude::*;
@@ -221,6 +222,8 @@ pub struct AudioSynthManager {
     default_envelope: EnvelopeConfig,
     update_interval: std::time::Duration,
     last_envelope_update: Instant,
+    /// Raw sample capture, see `enable_waveform`
+    waveform: Option<WaveformTap>,
 }
 
 impl AudioSynthManager {
@@ -317,6 +320,7 @@ impl AudioSynthManager {
             default_envelope: EnvelopeConfig::default(),
             update_interval: std::time::Duration::from_millis(5),
             last_envelope_update: Instant::now(),
+            waveform: None,
         })
     }
 
@@ -698,6 +702,27 @@ impl AudioSynthManager {
         self.sample_rate
     }
 
+    /// Capture the last `length` mixed samples before the master volume (off by default).
+    /// See `WaveformTap` for the format.
+    pub fn enable_waveform(&mut self, length: usize) -> Result<()> {
+        let pad = self
+            .pipeline
+            .by_name("master_volume")
+            .and_then(|volume| volume.static_pad("sink"))
+            .ok_or_else(|| anyhow!("Master volume element not found in pipeline"))?;
+        self.waveform = Some(WaveformTap::attach(pad, length)?);
+        Ok(())
+    }
+
+    pub fn disable_waveform(&mut self) {
+        self.waveform = None;
+    }
+
+    /// Latest raw samples, `None` unless `enable_waveform` succeeded
+    pub fn waveform_samples(&self) -> Option<Vec<f32>> {
+        self.waveform.as_ref().map(|tap| tap.samples())
+    }
+
     /// Get the current envelope level for a voice (useful for visualization)
     pub fn get_voice_level(&self, voice_id: usize) -> f32 {
         if voice_id < self.voices.len() {
@@ -1125,6 +1150,24 @@ impl SynthesisManager {
     pub fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
+
+    /// Raw synth output for oscilloscope shaders, see `AudioSynthManager::enable_waveform`
+    pub fn enable_waveform(&mut self, length: usize) -> anyhow::Result<()> {
+        match &mut self.audio_manager {
+            Some(manager) => manager.enable_waveform(length),
+            None => Err(anyhow!("No audio manager")),
+        }
+    }
+
+    pub fn disable_waveform(&mut self) {
+        if let Some(ref mut manager) = self.audio_manager {
+            manager.disable_waveform();
+        }
+    }
+
+    pub fn waveform_samples(&self) -> Option<Vec<f32>> {
+        self.audio_manager.as_ref()?.waveform_samples()
+    }
 }
 
 #[repr(C)]
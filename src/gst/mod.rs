@@ -3,6 +3,8 @@ pub mod audio;
 #[cfg(feature = "media")]
 pub mod video;
 #[cfg(feature = "media")]
+pub mod waveform;
+#[cfg(feature = "media")]
 pub mod webcam;
 use log::info;
 
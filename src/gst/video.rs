@@ -1,3 +1,4 @@
+use super::waveform::WaveformTap;
 use crate::texture::TextureManager;
 use anyhow::{anyhow, Result};
 use gst::glib::ControlFlow;
@@ -5,7 +6,7 @@ use gst::prelude::*;
 use gstreamer as gst;
 use gstreamer_app as gst_app;
 use gstreamer_video as gst_video;
-use log::{debug, error, info, warn};
+use log::{debug, error, info, trace, warn};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
@@ -42,6 +43,11 @@ pub struct VideoTextureManager {
     pipeline: gst::Pipeline,
     /// The AppSink element that receives decoded frames
     appsink: gst_app::AppSink,
+    /// Optional queue in front of the appsink, see `VideoBufferConfig`
+    frame_queue: Option<gst::Element>,
+    buffer_config: VideoBufferConfig,
+    /// Frames are pulled one at a time with `step_frame` instead of played in real time
+    step_mode: bool,
     /// Whether the video has an audio track
     has_audio: bool,
     /// Audio volume (0.0 to 1.0)
@@ -76,12 +82,111 @@ pub struct VideoTextureManager {
     spectrum_bands: usize,
     /// Threshold in dB for spectrum analysis
     spectrum_threshold: i32,
+    /// Whether the spectrum element also posts phase values
+    spectrum_phase_enabled: bool,
     /// Spectrum data from the most recent analysis
     spectrum_data: Arc<Mutex<SpectrumData>>,
     /// Audio level data (RMS, peak) for normalization
     audio_level: Arc<Mutex<AudioLevel>>,
     /// bpm
     bpm_value: Arc<Mutex<f32>>,
+    /// Raw sample capture, see `enable_waveform`
+    waveform: Option<WaveformTap>,
+}
+
+/// Decoded frame buffering between the decoder and the texture upload.
+///
+/// Deeper buffers absorb decode jitter (and a busy GPU delaying `update_texture`) at the
+/// cost of latency, roughly one frame per buffer, and memory, one decoded RGBA frame per
+/// buffer (~8 MB at 1080p). The defaults favor low latency: 2 buffers, drop when full,
+/// no queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoBufferConfig {
+    /// appsink `max-buffers`
+    pub max_buffers: u32,
+    /// Drop the oldest frame when the appsink is full instead of blocking the decoder
+    pub drop: bool,
+    /// Insert a `queue` of this many frames before the appsink to smooth decode jitter
+    pub queue_buffers: Option<u32>,
+}
+
+impl Default for VideoBufferConfig {
+    fn default() -> Self {
+        Self {
+            max_buffers: 2,
+            drop: true,
+            queue_buffers: None,
+        }
+    }
+}
+
+/// Copy a packed RGBA frame into a tightly packed image, row by row.
+///
+/// `stride` may be larger than `width * 4` (padding) and is clamped if it's smaller. A frame
+/// shorter than `offset + height * stride` (common for odd sizes, where the last row has no
+/// padding) still gets its last row copied as far as the data goes; rows with no data at
+/// all stay black and trip a debug assertion.
+pub(crate) fn copy_rgba_frame(
+    frame_data: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    offset: usize,
+) -> image::RgbaImage {
+    let mut rgba_image = image::RgbaImage::new(width as u32, height as u32);
+    let row_bytes = width * 4;
+    let copy_len = row_bytes.min(stride);
+    let dst_buffer: &mut [u8] = rgba_image.as_mut();
+
+    for y in 0..height {
+        let src_start = offset + y * stride;
+        let available = frame_data.len().saturating_sub(src_start).min(copy_len);
+        debug_assert!(
+            available > 0,
+            "video row {y} of {height} is past the end of the frame data"
+        );
+        if available == 0 {
+            warn!("Video frame truncated: {y} of {height} rows present");
+            break;
+        }
+        let dst_start = y * row_bytes;
+        dst_buffer[dst_start..dst_start + available]
+            .copy_from_slice(&frame_data[src_start..src_start + available]);
+    }
+    rgba_image
+}
+
+/// Copy a decoded appsink sample into an `RgbaImage`
+fn sample_to_rgba(sample: &gst::Sample) -> Result<image::RgbaImage, gst::FlowError> {
+    let buffer = sample.buffer().ok_or(gst::FlowError::Error)?;
+    let caps = sample.caps().ok_or(gst::FlowError::Error)?;
+    let video_info = gst_video::VideoInfo::from_caps(caps).map_err(|_| gst::FlowError::Error)?;
+    let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
+
+    // We need to copy the data because buffer will be unmapped after this function
+    Ok(copy_rgba_frame(
+        map.as_slice(),
+        video_info.width() as usize,
+        video_info.height() as usize,
+        video_info.stride()[0] as usize,
+        video_info.offset()[0],
+    ))
+}
+
+/// Appsink callbacks used during normal playback: keep only the newest decoded frame
+fn frame_callbacks(
+    current_frame: Arc<Mutex<Option<image::RgbaImage>>>,
+) -> gst_app::AppSinkCallbacks {
+    gst_app::AppSinkCallbacks::builder()
+        .new_sample(move |sink| {
+            let sample = sink.pull_sample().map_err(|_| gst::FlowError::Eos)?;
+            let rgba_image = sample_to_rgba(&sample)?;
+            if let Ok(mut frame_lock) = current_frame.lock() {
+                *frame_lock = Some(rgba_image);
+            }
+            Ok(gst::FlowSuccess::Ok)
+        })
+        .build()
 }
 
 impl VideoTextureManager {
@@ -90,6 +195,23 @@ impl VideoTextureManager {
         queue: &wgpu::Queue,
         bind_group_layout: &wgpu::BindGroupLayout,
         video_path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        Self::new_with_buffering(
+            device,
+            queue,
+            bind_group_layout,
+            video_path,
+            VideoBufferConfig::default(),
+        )
+    }
+
+    /// Like `new`, with custom frame buffering for smoother playback
+    pub fn new_with_buffering(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        video_path: impl AsRef<Path>,
+        buffer_config: VideoBufferConfig,
     ) -> Result<Self> {
         // Create a default 1x1 texture initially, note that, this going to be replaced with first video frame
         let default_image = image::RgbaImage::new(1, 1);
@@ -148,11 +270,25 @@ impl VideoTextureManager {
                 .field("format", gst_video::VideoFormat::Rgba.to_str())
                 .build(),
         ));
-        appsink.set_max_buffers(2);
+        appsink.set_max_buffers(buffer_config.max_buffers.max(1));
         // Drop old buffers when full
-        appsink.set_drop(true);
+        appsink.set_drop(buffer_config.drop);
         appsink.set_sync(true);
 
+        // Optional decode-ahead queue, only limited by frame count
+        let frame_queue = match buffer_config.queue_buffers {
+            Some(buffers) => Some(
+                gst::ElementFactory::make("queue")
+                    .name("frame_queue")
+                    .property("max-size-buffers", buffers.max(1))
+                    .property("max-size-bytes", 0u32)
+                    .property("max-size-time", 0u64)
+                    .build()
+                    .map_err(|_| anyhow!("Failed to create queue element"))?,
+            ),
+            None => None,
+        };
+
         // video elements goes to the pipeline
         pipeline
             .add_many([
@@ -166,8 +302,27 @@ impl VideoTextureManager {
             .map_err(|_| anyhow!("Failed to add video elements to pipeline"))?;
 
         // Link elements that can be linked statically
-        gst::Element::link_many([&videorate, &videoconvert, &capsfilter, appsink.upcast_ref()])
-            .map_err(|_| anyhow!("Failed to link video elements"))?;
+        match &frame_queue {
+            Some(frame_queue) => {
+                pipeline
+                    .add(frame_queue)
+                    .map_err(|_| anyhow!("Failed to add queue to pipeline"))?;
+                gst::Element::link_many([
+                    &videorate,
+                    &videoconvert,
+                    &capsfilter,
+                    frame_queue,
+                    appsink.upcast_ref(),
+                ])
+            }
+            None => gst::Element::link_many([
+                &videorate,
+                &videoconvert,
+                &capsfilter,
+                appsink.upcast_ref(),
+            ]),
+        }
+        .map_err(|_| anyhow!("Failed to link video elements"))?;
 
         gst::Element::link_many([&filesrc, &decodebin])
             .map_err(|_| anyhow!("Failed to link filesrc to decodebin"))?;
@@ -195,12 +350,12 @@ impl VideoTextureManager {
             match message.view() {
                 gst::MessageView::Element(element) => {
                     if let Some(structure) = element.structure() {
-                        info!("Element message structure name: '{}'", structure.name());
+                        trace!("Element message structure name: '{}'", structure.name());
 
                         // Explicitly check for spectrum messages
                         if structure.name() == "spectrum" {
-                            info!("SPECTRUM MESSAGE DETECTED ");
-                            info!("Full structure: {structure}");
+                            trace!("SPECTRUM MESSAGE DETECTED ");
+                            trace!("Full structure: {structure}");
 
                             // Try ALL possible ways to extract spectrum data
                             let mut magnitude_values = Vec::new();
@@ -211,11 +366,11 @@ impl VideoTextureManager {
                                 let field_name = format!("magnitude[{i}]");
                                 match structure.get::<f32>(&field_name) {
                                     Ok(value) => {
-                                        info!("Method 1 - Band {i}: {value} dB");
+                                        trace!("Method 1 - Band {i}: {value} dB");
                                         magnitude_values.push(value);
                                     }
                                     Err(e) => {
-                                        info!("Method 1 failed: {e:?}");
+                                        trace!("Method 1 failed: {e:?}");
                                         break;
                                     }
                                 }
@@ -223,14 +378,14 @@ impl VideoTextureManager {
 
                             // Method 2: Try to access magnitude as array field
                             if structure.has_field("magnitude") {
-                                info!("Structure has 'magnitude' field");
+                                trace!("Structure has 'magnitude' field");
                             } else {
-                                info!("Structure does NOT have 'magnitude' field");
+                                trace!("Structure does NOT have 'magnitude' field");
                             }
 
                             // Method 3: Parse from structure string
                             let struct_str = structure.to_string();
-                            info!("Structure string: {struct_str}");
+                            trace!("Structure string: {struct_str}");
 
                             // If we found magnitude values through any method, process them
                             if !magnitude_values.is_empty() {
@@ -247,12 +402,12 @@ impl VideoTextureManager {
                                 }
 
                                 // Log summary of extracted data
-                                info!("Extracted {} magnitude values", magnitude_values.len());
+                                trace!("Extracted {} magnitude values", magnitude_values.len());
 
                                 // Calculate average magnitude
                                 let avg_magnitude = magnitude_values.iter().sum::<f32>()
                                     / magnitude_values.len() as f32;
-                                info!("Average magnitude: {avg_magnitude:.2} dB");
+                                trace!("Average magnitude: {avg_magnitude:.2} dB");
 
                                 // Find peak frequency
                                 if let Some((peak_idx, &peak_val)) = magnitude_values
@@ -262,7 +417,7 @@ impl VideoTextureManager {
                                         a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)
                                     })
                                 {
-                                    info!("Peak frequency: band {peak_idx} at {peak_val:.2} dB");
+                                    trace!("Peak frequency: band {peak_idx} at {peak_val:.2} dB");
                                 }
 
                                 // Update spectrum data
@@ -283,8 +438,8 @@ impl VideoTextureManager {
 
                         // Handle level messages for RMS/peak/decay
                         if structure.name() == "level" {
-                            info!("LEVEL MESSAGE DETECTED");
-                            info!("Level structure: {structure}");
+                            trace!("LEVEL MESSAGE DETECTED");
+                            trace!("Level structure: {structure}");
 
                             // Extract RMS, peak, and decay values
                             if let Ok(rms_list) = structure.get::<gst::glib::ValueArray>("rms") {
@@ -295,7 +450,7 @@ impl VideoTextureManager {
                                     }
                                 }
                                 if !rms_values.is_empty() {
-                                    info!("Level RMS values: {rms_values:?}");
+                                    trace!("Level RMS values: {rms_values:?}");
                                 }
                             }
                         }
@@ -307,7 +462,7 @@ impl VideoTextureManager {
                     let tags = tag.tags();
                     // Note: BPM tags are ignored - we use bpmdetect messages with octave correction instead
                     if let Some(bpm) = tags.get::<gst::tags::BeatsPerMinute>() {
-                        info!("BPM tag detected (ignored): {:.1}", bpm.get());
+                        trace!("BPM tag detected (ignored): {:.1}", bpm.get());
                     }
                 }
                 gst::MessageView::Error(err) => {
@@ -539,75 +694,16 @@ impl VideoTextureManager {
         let is_muted = Arc::new(Mutex::new(false));
 
         // Setup callbacks to receive frames
-        appsink.set_callbacks(
-            gst_app::AppSinkCallbacks::builder()
-                .new_sample(move |sink| {
-                    let sample = match sink.pull_sample() {
-                        Ok(sample) => sample,
-                        Err(_) => return Err(gst::FlowError::Eos),
-                    };
-
-                    let buffer = match sample.buffer() {
-                        Some(buffer) => buffer,
-                        _ => return Err(gst::FlowError::Error),
-                    };
-
-                    let caps = match sample.caps() {
-                        Some(caps) => caps,
-                        _ => return Err(gst::FlowError::Error),
-                    };
-
-                    let video_info = match gst_video::VideoInfo::from_caps(caps) {
-                        Ok(info) => info,
-                        Err(_) => return Err(gst::FlowError::Error),
-                    };
-
-                    let map = match buffer.map_readable() {
-                        Ok(map) => map,
-                        Err(_) => return Err(gst::FlowError::Error),
-                    };
-
-                    // Access the raw frame data
-                    let frame_data = map.as_slice();
-                    let width = video_info.width() as usize;
-                    let height = video_info.height() as usize;
-
-                    // Create an RgbaImage from the frame data
-                    // (We need to copy the data because buffer will be unmapped after this function)
-                    let mut rgba_image = image::RgbaImage::new(width as u32, height as u32);
-
-                    // Stride might be larger than width * 4
-                    let stride = video_info.stride()[0] as usize;
-
-                    for y in 0..height {
-                        let src_start = y * stride;
-                        let src_end = src_start + width * 4;
-                        let dst_start = y * width * 4;
-                        let dst_end = dst_start + width * 4;
-
-                        // Copy row by row to handle stride correctly
-                        let dst_buffer = rgba_image.as_mut();
-                        if src_end <= frame_data.len() && dst_end <= dst_buffer.len() {
-                            dst_buffer[dst_start..dst_end]
-                                .copy_from_slice(&frame_data[src_start..src_end]);
-                        }
-                    }
-
-                    // Store the frame
-                    if let Ok(mut frame_lock) = current_frame_clone.lock() {
-                        *frame_lock = Some(rgba_image);
-                    }
-
-                    Ok(gst::FlowSuccess::Ok)
-                })
-                .build(),
-        );
+        appsink.set_callbacks(frame_callbacks(current_frame_clone));
 
         // init the object
         let mut video_texture = Self {
             texture_manager,
             pipeline,
             appsink,
+            frame_queue,
+            buffer_config,
+            step_mode: false,
             has_audio: false,
             volume: volume_val,
             is_muted,
@@ -625,9 +721,11 @@ impl VideoTextureManager {
             spectrum_enabled,
             spectrum_bands,
             spectrum_threshold,
+            spectrum_phase_enabled: false,
             spectrum_data,
             audio_level,
             bpm_value: Arc::new(Mutex::new(0.0)),
+            waveform: None,
         };
         // Start pipeline in paused state to get video info
         if video_texture
@@ -784,7 +882,7 @@ impl VideoTextureManager {
                                                         }
                                                     }
 
-                                                    info!(
+                                                    trace!(
                                                         "Extracted {} magnitude values from string",
                                                         magnitude_values.len()
                                                     );
@@ -805,7 +903,7 @@ impl VideoTextureManager {
                                             }
 
                                             if !magnitude_values.is_empty() {
-                                                info!(
+                                                trace!(
                                                     "Extracted {} magnitude values by field access",
                                                     magnitude_values.len()
                                                 );
@@ -873,20 +971,27 @@ impl VideoTextureManager {
                                                 // Rough estimate of frequency based on band index
                                                 let peak_freq =
                                                     (peak_idx as f32 / bands as f32) * 20000.0;
-                                                info!(
+                                                trace!(
                                                     "Peak freq: ~{peak_freq:.0} Hz (band {peak_idx}, val: {peak_val:.2})"
                                                 );
                                             }
 
                                             // Log energy metrics
-                                            info!("Audio energy - Bass: {bass_energy:.2}, Mid: {mid_energy:.2}, High: {high_energy:.2}");
+                                            trace!("Audio energy - Bass: {bass_energy:.2}, Mid: {mid_energy:.2}, High: {high_energy:.2}");
+
+                                            let phases = if self.spectrum_phase_enabled {
+                                                let values = float_list_field(structure, "phase");
+                                                (!values.is_empty()).then_some(values)
+                                            } else {
+                                                None
+                                            };
 
                                             // Update spectrum data
                                             if let Ok(mut data) = self.spectrum_data.lock() {
                                                 *data = SpectrumData {
                                                     bands,
                                                     magnitudes: magnitude_values,
-                                                    phases: None,
+                                                    phases,
                                                     timestamp: structure.get("timestamp").ok(),
                                                 };
                                             }
@@ -964,11 +1069,11 @@ impl VideoTextureManager {
                                                         && corrected_bpm * 2.0 < 140.0
                                                     {
                                                         corrected_bpm *= 2.0;
-                                                        info!("Initial BPM doubled: {bpm_val:.1} → {corrected_bpm:.1}");
+                                                        trace!("Initial BPM doubled: {bpm_val:.1} → {corrected_bpm:.1}");
                                                     } else if halving_count > 0 {
-                                                        info!("Initial BPM halved {halving_count}x: {bpm_val:.1} → {corrected_bpm:.1}");
+                                                        trace!("Initial BPM halved {halving_count}x: {bpm_val:.1} → {corrected_bpm:.1}");
                                                     } else {
-                                                        info!("Initial BPM in range: {corrected_bpm:.1}");
+                                                        trace!("Initial BPM in range: {corrected_bpm:.1}");
                                                     }
 
                                                     *bpm_lock = corrected_bpm;
@@ -982,14 +1087,14 @@ impl VideoTextureManager {
                                                         let halved = new_bpm / 2.0;
                                                         if (60.0..=110.0).contains(&halved) {
                                                             new_bpm = halved;
-                                                            info!("New BPM halved: {bpm_val:.1} → {new_bpm:.1}");
+                                                            trace!("New BPM halved: {bpm_val:.1} → {new_bpm:.1}");
                                                         }
                                                     } else if new_bpm < 50.0 {
                                                         // If new detection is low, try doubling
                                                         let doubled = new_bpm * 2.0;
                                                         if (60.0..=110.0).contains(&doubled) {
                                                             new_bpm = doubled;
-                                                            info!("New BPM doubled: {bpm_val:.1} → {new_bpm:.1}");
+                                                            trace!("New BPM doubled: {bpm_val:.1} → {new_bpm:.1}");
                                                         }
                                                     }
 
@@ -997,10 +1102,10 @@ impl VideoTextureManager {
                                                     let smoothed =
                                                         current_bpm * 0.9 + new_bpm * 0.1;
                                                     *bpm_lock = smoothed;
-                                                    info!("   Final BPM: {smoothed:.1} (smoothed from {new_bpm:.1})");
+                                                    trace!("   Final BPM: {smoothed:.1} (smoothed from {new_bpm:.1})");
                                                 }
 
-                                                info!("BPM set to: {:.1}", *bpm_lock);
+                                                trace!("BPM set to: {:.1}", *bpm_lock);
                                             }
                                         }
                                     }
@@ -1033,11 +1138,11 @@ impl VideoTextureManager {
                                                 {
                                                     corrected_bpm *= 2.0;
                                                 } else if halving_count > 0 {
-                                                    info!("BPM octave corrected: {bpm_val:.1} → {corrected_bpm:.1}");
+                                                    trace!("BPM octave corrected: {bpm_val:.1} → {corrected_bpm:.1}");
                                                 }
 
                                                 *bpm_lock = corrected_bpm;
-                                                info!("BPM initialized: {corrected_bpm:.1}");
+                                                trace!("BPM initialized: {corrected_bpm:.1}");
                                             } else if bpm_val > 0.0 {
                                                 // Subsequent detections
                                                 let mut new_bpm = bpm_val;
@@ -1074,7 +1179,7 @@ impl VideoTextureManager {
                 || self.frame_count <= 3;
 
             if should_recreate {
-                info!("Creating new texture with dimensions: {width}x{height}");
+                debug!("Creating new texture with dimensions: {width}x{height}");
 
                 // Create a completely new texture with the frame's dimensions
                 let new_texture_manager =
@@ -1087,8 +1192,12 @@ impl VideoTextureManager {
                 self.texture_manager.update(queue, &frame);
             }
 
-            // Get current position
-            if let Some(position) = self.pipeline.query_position::<gst::ClockTime>() {
+            // Get current position. Step mode tracks it per frame and ends on EOS instead
+            if let Some(position) = self
+                .pipeline
+                .query_position::<gst::ClockTime>()
+                .filter(|_| !self.step_mode)
+            {
                 *self.position.lock().unwrap() = position;
 
                 // Check if we reached the end of the video
@@ -1174,6 +1283,94 @@ impl VideoTextureManager {
         info!("Video loop set to: {should_loop}");
     }
 
+    /// Switch between real-time playback and lockstep decoding for offline processing.
+    ///
+    /// In step mode the appsink stops syncing to the clock and holds a single frame without
+    /// dropping, so the decoder waits until `step_frame` pulls each frame: every decoded
+    /// frame is seen exactly once. Enabling rewinds to the start and disables looping.
+    /// Audio keeps playing in real time and should be muted.
+    pub fn set_step_mode(&mut self, enabled: bool) -> Result<()> {
+        if enabled == self.step_mode {
+            return Ok(());
+        }
+        self.step_mode = enabled;
+        if enabled {
+            self.appsink
+                .set_callbacks(gst_app::AppSinkCallbacks::builder().build());
+            self.appsink.set_sync(false);
+            self.appsink.set_drop(false);
+            self.appsink.set_max_buffers(1);
+            self.set_loop(false);
+            self.play()?;
+            self.seek(gst::ClockTime::ZERO)?;
+        } else {
+            self.appsink.set_sync(true);
+            self.appsink.set_drop(self.buffer_config.drop);
+            self.appsink.set_max_buffers(self.buffer_config.max_buffers);
+            self.appsink
+                .set_callbacks(frame_callbacks(self.current_frame.clone()));
+        }
+        info!("Video step mode: {enabled}");
+        Ok(())
+    }
+
+    pub fn is_step_mode(&self) -> bool {
+        self.step_mode
+    }
+
+    /// Decode the next frame and upload it (step mode only). Returns the frame's timestamp,
+    /// or `None` once the video has ended.
+    pub fn step_frame(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Result<Option<gst::ClockTime>> {
+        if !self.step_mode {
+            return Err(anyhow!("step_frame needs set_step_mode(true)"));
+        }
+        let Some(sample) = self
+            .appsink
+            .try_pull_sample(gst::ClockTime::from_seconds(5))
+        else {
+            if self.appsink.is_eos() {
+                return Ok(None);
+            }
+            return Err(anyhow!("Timed out waiting for the next video frame"));
+        };
+        let pts = sample
+            .buffer()
+            .and_then(|buffer| buffer.pts())
+            .unwrap_or(gst::ClockTime::ZERO);
+        let frame = sample_to_rgba(&sample).map_err(|e| anyhow!("Bad video sample: {e:?}"))?;
+        *self.current_frame.lock().unwrap() = Some(frame);
+        *self.position.lock().unwrap() = pts;
+        self.update_texture(device, queue, bind_group_layout)?;
+        Ok(Some(pts))
+    }
+
+    /// Change how many decoded frames are buffered while playing (see `VideoBufferConfig`).
+    /// Applies to the appsink and, if the video was created with one, the queue.
+    pub fn set_buffer_depth(&mut self, buffers: u32) {
+        let buffers = buffers.max(1);
+        self.appsink.set_max_buffers(buffers);
+        self.buffer_config.max_buffers = buffers;
+        if let Some(frame_queue) = &self.frame_queue {
+            frame_queue.set_property("max-size-buffers", buffers);
+            self.buffer_config.queue_buffers = Some(buffers);
+        }
+    }
+
+    /// Drop frames when the buffer is full (low latency) or block the decoder (no skips)
+    pub fn set_drop_frames(&mut self, drop: bool) {
+        self.appsink.set_drop(drop);
+        self.buffer_config.drop = drop;
+    }
+
+    pub fn buffer_config(&self) -> VideoBufferConfig {
+        self.buffer_config
+    }
+
     /// audio volume (between 0.0 and 1.0)
     pub fn set_volume(&mut self, volume: f64) -> Result<()> {
         if !self.has_audio {
@@ -1304,6 +1501,55 @@ impl VideoTextureManager {
         }
     }
 
+    /// Enable or disable phase values in spectrum messages (off by default).
+    /// When enabled, `SpectrumData::phases` holds one phase (radians) per band.
+    pub fn enable_phase(&mut self, enabled: bool) -> Result<()> {
+        if !self.has_audio {
+            debug!("Ignoring spectrum phase request - video has no audio");
+            return Ok(());
+        }
+
+        self.spectrum_phase_enabled = enabled;
+
+        if let Some(spectrum_elem) = self.pipeline.by_name("spectrum") {
+            spectrum_elem.set_property("message-phase", enabled);
+            info!(
+                "Spectrum phase {}",
+                if enabled { "enabled" } else { "disabled" }
+            );
+            Ok(())
+        } else {
+            warn!("Spectrum element not found in pipeline");
+            Ok(()) // Don't fail if element not found
+        }
+    }
+
+    /// Capture the last `length` raw audio samples (off by default, it costs a copy of every
+    /// audio buffer). Samples are taken before the volume, so muting doesn't flatten them.
+    /// See `WaveformTap` for the format.
+    pub fn enable_waveform(&mut self, length: usize) -> Result<()> {
+        if !self.has_audio {
+            debug!("Ignoring waveform request - video has no audio");
+            return Ok(());
+        }
+        let pad = self
+            .pipeline
+            .by_name("volume")
+            .and_then(|volume| volume.static_pad("sink"))
+            .ok_or_else(|| anyhow!("Volume element not found in pipeline"))?;
+        self.waveform = Some(WaveformTap::attach(pad, length)?);
+        Ok(())
+    }
+
+    pub fn disable_waveform(&mut self) {
+        self.waveform = None;
+    }
+
+    /// Latest raw samples, `None` unless `enable_waveform` succeeded
+    pub fn waveform_samples(&self) -> Option<Vec<f32>> {
+        self.waveform.as_ref().map(|tap| tap.samples())
+    }
+
     /// Get current spectrum data
     pub fn spectrum_data(&self) -> SpectrumData {
         match self.spectrum_data.lock() {
@@ -1349,6 +1595,25 @@ impl VideoTextureManager {
     }
 }
 
+/// Read a float list field (e.g. "magnitude" or "phase") from a spectrum message.
+/// Mono streams post a flat list, multichannel streams post a list per channel; we use the first channel.
+fn float_list_field(structure: &gst::StructureRef, field: &str) -> Vec<f32> {
+    let Ok(list) = structure.get::<gst::List>(field) else {
+        return Vec::new();
+    };
+
+    let mut values = Vec::with_capacity(list.len());
+    for value in list.iter() {
+        if let Ok(v) = value.get::<f32>() {
+            values.push(v);
+        } else if let Ok(channel) = value.get::<gst::Array>() {
+            // multichannel: first channel only
+            return channel.iter().filter_map(|v| v.get::<f32>().ok()).collect();
+        }
+    }
+    values
+}
+
 impl Drop for VideoTextureManager {
     fn drop(&mut self) {
         info!("Shutting down video pipeline");
@@ -0,0 +1,122 @@
+use anyhow::{anyhow, Result};
+use gst::prelude::*;
+use gstreamer as gst;
+use log::debug;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Samples kept by default, ~23ms at 44.1kHz
+pub const DEFAULT_WAVEFORM_LENGTH: usize = 1024;
+
+/// Raw PCM tap on an audio pad for oscilloscope-style shaders.
+///
+/// A buffer probe copies every buffer passing the pad into a ring of the last `length`
+/// samples. Channels are averaged to mono and samples converted to f32 in -1..1, at the
+/// stream's sample rate. Interleaved F32LE, F64LE, S16LE and S32LE are understood, other
+/// formats are skipped. The probe is removed when the tap is dropped, so it only costs CPU
+/// while enabled.
+pub struct WaveformTap {
+    pad: gst::Pad,
+    probe: Option<gst::PadProbeId>,
+    samples: Arc<Mutex<VecDeque<f32>>>,
+    length: usize,
+}
+
+impl WaveformTap {
+    pub fn attach(pad: gst::Pad, length: usize) -> Result<Self> {
+        let length = length.max(1);
+        let samples = Arc::new(Mutex::new(VecDeque::with_capacity(length)));
+        let samples_clone = samples.clone();
+        let probe = pad
+            .add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+                if let Some(gst::PadProbeData::Buffer(buffer)) = &info.data {
+                    if let (Some(caps), Ok(map)) = (pad.current_caps(), buffer.map_readable()) {
+                        if let Ok(mut ring) = samples_clone.lock() {
+                            push_mono_samples(&caps, map.as_slice(), &mut ring, length);
+                        }
+                    }
+                }
+                gst::PadProbeReturn::Ok
+            })
+            .ok_or_else(|| anyhow!("Failed to add waveform probe"))?;
+        debug!("Waveform tap attached with {length} samples");
+
+        Ok(Self {
+            pad,
+            probe: Some(probe),
+            samples,
+            length,
+        })
+    }
+
+    /// The last `length` samples, oldest first. Zero-padded at the start until enough
+    /// audio has played.
+    pub fn samples(&self) -> Vec<f32> {
+        let mut out = vec![0.0; self.length];
+        if let Ok(ring) = self.samples.lock() {
+            let start = self.length - ring.len();
+            for (dst, src) in out[start..].iter_mut().zip(ring.iter()) {
+                *dst = *src;
+            }
+        }
+        out
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+}
+
+impl Drop for WaveformTap {
+    fn drop(&mut self) {
+        if let Some(probe) = self.probe.take() {
+            self.pad.remove_probe(probe);
+        }
+    }
+}
+
+/// Decode interleaved PCM described by `caps`, downmix to mono and append to `ring`
+fn push_mono_samples(caps: &gst::CapsRef, data: &[u8], ring: &mut VecDeque<f32>, length: usize) {
+    let Some(structure) = caps.structure(0) else {
+        return;
+    };
+    if structure
+        .get::<&str>("layout")
+        .is_ok_and(|l| l != "interleaved")
+    {
+        return;
+    }
+    let channels = structure.get::<i32>("channels").unwrap_or(1).max(1) as usize;
+    let (sample_size, read): (usize, fn(&[u8]) -> f32) =
+        match structure.get::<&str>("format").unwrap_or("") {
+            "F32LE" => (4, read_f32le),
+            "F64LE" => (8, read_f64le),
+            "S16LE" => (2, read_s16le),
+            "S32LE" => (4, read_s32le),
+            _ => return,
+        };
+
+    for frame in data.chunks_exact(sample_size * channels) {
+        let sum: f32 = frame.chunks_exact(sample_size).map(read).sum();
+        ring.push_back(sum / channels as f32);
+    }
+    while ring.len() > length {
+        ring.pop_front();
+    }
+}
+
+fn read_f32le(b: &[u8]) -> f32 {
+    f32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+fn read_f64le(b: &[u8]) -> f32 {
+    f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32
+}
+
+fn read_s16le(b: &[u8]) -> f32 {
+    i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0
+}
+
+fn read_s32le(b: &[u8]) -> f32 {
+    i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0
+}
@@ -183,23 +183,13 @@ impl WebcamTextureManager {
                     let height = video_info.height() as usize;
 
                     // Create an RgbaImage from the frame data
-                    let mut rgba_image = image::RgbaImage::new(width as u32, height as u32);
-
-                    // Stride might be larger than width * 4
-                    let stride = video_info.stride()[0] as usize;
-
-                    for y in 0..height {
-                        let src_start = y * stride;
-                        let src_end = src_start + width * 4;
-                        let dst_start = y * width * 4;
-                        let dst_end = dst_start + width * 4;
-
-                        let dst_buffer = rgba_image.as_mut();
-                        if src_end <= frame_data.len() && dst_end <= dst_buffer.len() {
-                            dst_buffer[dst_start..dst_end]
-                                .copy_from_slice(&frame_data[src_start..src_end]);
-                        }
-                    }
+                    let rgba_image = super::video::copy_rgba_frame(
+                        frame_data,
+                        width,
+                        height,
+                        video_info.stride()[0] as usize,
+                        video_info.offset()[0],
+                    );
 
                     if let Ok(mut frame_lock) = current_frame_clone.lock() {
                         *frame_lock = Some(rgba_image);
@@ -313,7 +303,7 @@ impl WebcamTextureManager {
                 || self.frame_count <= 3;
 
             if should_recreate {
-                info!("Creating new webcam texture with dimensions: {width}x{height}");
+                debug!("Creating new webcam texture with dimensions: {width}x{height}");
 
                 // Create a completely new texture with the frame's dimensions
                 let new_texture_manager =
@@ -1,7 +1,13 @@
-use crate::TextureManager;
+use crate::compute::{create_bind_group_layout, BindGroupLayoutType};
+use crate::{Core, TextureManager};
 use image::codecs::hdr::HdrDecoder;
-use image::{ImageDecoder, RgbaImage};
+use image::RgbaImage;
 use std::io::Cursor;
+use wgpu::util::DeviceExt;
+
+/// WGSL `sampleEnv` / `envPdf` over an `ImportanceMap` buffer, see the file header for
+/// the binding it expects. Needs the compute stdlib for the equirectangular helpers.
+pub const ENV_SAMPLING_WGSL: &str = include_str!("../shaders/env_sampling.wgsl");
 
 #[derive(Clone, Debug, Copy)]
 pub struct HdriMetadata {
@@ -29,12 +35,23 @@ pub fn load_hdri_texture(
     layout: &wgpu::BindGroupLayout,
     exposure: f32,
 ) -> Result<(TextureManager, HdriMetadata), String> {
-    let format = detect_format(data)?;
+    let (format, decoded) = decode(data)?;
+    Ok(upload_hdri(
+        device, queue, format, &decoded, layout, exposure,
+    ))
+}
+
+/// Tone map a decoded HDRI and upload it with a fresh texture, sampler and bind group
+fn upload_hdri(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    format: HdriFormat,
+    decoded: &image::DynamicImage,
+    layout: &wgpu::BindGroupLayout,
+    exposure: f32,
+) -> (TextureManager, HdriMetadata) {
     let gamma = 2.2;
-    let hdri_image = match format {
-        HdriFormat::Hdr => hdr_to_rgba8(data, exposure, Some(gamma))?,
-        HdriFormat::Exr => exr_to_rgba8(data, exposure, Some(gamma))?,
-    };
+    let hdri_image = to_rgba8(format, decoded, exposure, Some(gamma));
     let dimensions = hdri_image.dimensions();
     let texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("HDRI Texture"),
@@ -101,15 +118,178 @@ pub fn load_hdri_texture(
         gamma,
     };
 
-    Ok((
+    (
         TextureManager {
             texture,
             view,
             sampler,
             bind_group,
+            view_formats: Vec::new(),
         },
         metadata,
-    ))
+    )
+}
+
+/// Linear float pixels of an HDRI, before exposure and gamma, for CPU-side work such as
+/// building a luminance CDF for importance sampling.
+#[derive(Clone, Debug)]
+pub struct HdriImage {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA, row-major from the top-left, 4 floats per pixel
+    pub data: Vec<f32>,
+}
+
+impl HdriImage {
+    /// Decode a `.hdr` or `.exr` file
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        let (_, decoded) = decode(data)?;
+        Ok(Self::from_decoded(&decoded))
+    }
+
+    fn from_decoded(decoded: &image::DynamicImage) -> Self {
+        let rgba = decoded.to_rgba32f();
+        Self {
+            width: rgba.width(),
+            height: rgba.height(),
+            data: rgba.into_raw(),
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [f32; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ]
+    }
+
+    /// Rec. 709 luminance of a pixel
+    pub fn luminance(&self, x: u32, y: u32) -> f32 {
+        let [r, g, b, _] = self.pixel(x, y);
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+}
+
+/// `load_hdri_texture` that also keeps the decoded float pixels. The plain function stays
+/// the default so the float copy is only held when asked for.
+pub fn load_hdri_texture_with_data(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    data: &[u8],
+    layout: &wgpu::BindGroupLayout,
+    exposure: f32,
+) -> Result<(TextureManager, HdriMetadata, HdriImage), String> {
+    let (format, decoded) = decode(data)?;
+    let (texture, metadata) = upload_hdri(device, queue, format, &decoded, layout, exposure);
+    Ok((texture, metadata, HdriImage::from_decoded(&decoded)))
+}
+
+/// 2D CDF for drawing environment directions proportional to radiance.
+///
+/// Assumes an equirectangular map: row `y` spans polar angle `pi * y / height` from the top,
+/// so each pixel's weight is its luminance times `sin(theta)` to account for the smaller
+/// solid angle of rows near the poles. `data` packs a `[width, height, total, 0]` header,
+/// the marginal CDF over rows and one conditional CDF per row, which `ENV_SAMPLING_WGSL`
+/// reads from a single read-only storage buffer.
+pub struct ImportanceMap {
+    pub width: u32,
+    pub height: u32,
+    /// Sum of all weights, 0 for a black map
+    pub total: f32,
+    pub data: Vec<f32>,
+    pub buffer: wgpu::Buffer,
+    pub layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl ImportanceMap {
+    pub fn size_bytes(&self) -> u64 {
+        (self.data.len() * std::mem::size_of::<f32>()) as u64
+    }
+
+    /// Upload `data` into a buffer of at least `size_bytes`, e.g. a group 3 storage buffer
+    /// of a `ComputeShader`
+    pub fn write_to(&self, queue: &wgpu::Queue, buffer: &wgpu::Buffer) {
+        queue.write_buffer(buffer, 0, bytemuck::cast_slice(&self.data));
+    }
+}
+
+/// Build the CDFs on the CPU and upload them, see `ImportanceMap`
+pub fn build_importance_map(core: &Core, image: &HdriImage) -> ImportanceMap {
+    let (width, height) = (image.width as usize, image.height as usize);
+    let mut data = Vec::with_capacity(4 + (height + 1) + height * (width + 1));
+    data.extend_from_slice(&[width as f32, height as f32, 0.0, 0.0]);
+    let mut conditional = Vec::with_capacity(height * (width + 1));
+    let mut row_sums = Vec::with_capacity(height);
+
+    for y in 0..height {
+        let sin_theta = (std::f32::consts::PI * (y as f32 + 0.5) / height as f32).sin();
+        let start = conditional.len();
+        let mut sum = 0.0f32;
+        conditional.push(0.0);
+        for x in 0..width {
+            sum += image.luminance(x as u32, y as u32).max(0.0) * sin_theta;
+            conditional.push(sum);
+        }
+        let row = &mut conditional[start..];
+        for (x, value) in row.iter_mut().enumerate() {
+            // A black row is never picked by the marginal, keep it well-formed anyway
+            *value = if sum > 0.0 {
+                *value / sum
+            } else {
+                x as f32 / width as f32
+            };
+        }
+        row_sums.push(sum);
+    }
+
+    let total: f32 = row_sums.iter().sum();
+    let mut acc = 0.0f32;
+    data.push(0.0);
+    for (y, row_sum) in row_sums.iter().enumerate() {
+        acc += row_sum;
+        data.push(if total > 0.0 {
+            acc / total
+        } else {
+            (y + 1) as f32 / height as f32
+        });
+    }
+    data[2] = total;
+    data.extend_from_slice(&conditional);
+
+    let buffer = core
+        .device
+        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("HDRI Importance Map"),
+            contents: bytemuck::cast_slice(&data),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+    let layout = create_bind_group_layout(
+        &core.device,
+        BindGroupLayoutType::StorageBuffer { read_only: true },
+        "HDRI Importance Map",
+    );
+    let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("HDRI Importance Map"),
+        layout: &layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }],
+    });
+
+    ImportanceMap {
+        width: image.width,
+        height: image.height,
+        total,
+        data,
+        buffer,
+        layout,
+        bind_group,
+    }
 }
 
 enum HdriFormat {
@@ -130,13 +310,40 @@ fn detect_format(data: &[u8]) -> Result<HdriFormat, String> {
     Ok(HdriFormat::Hdr)
 }
 
-fn hdr_to_rgba8(hdr_data: &[u8], exposure: f32, gamma: Option<f32>) -> Result<RgbaImage, String> {
-    let cursor = Cursor::new(hdr_data);
-    let decoder = HdrDecoder::new(cursor).map_err(|e| e.to_string())?;
-    let metadata = decoder.metadata();
-    let dynamic_img = image::DynamicImage::from_decoder(decoder)
-        .map_err(|e| format!("Failed to decode HDR: {e}"))?;
-    let mut rgba8_image = RgbaImage::new(metadata.width, metadata.height);
+/// Detect the format of an `.hdr` or `.exr` file and decode it
+fn decode(data: &[u8]) -> Result<(HdriFormat, image::DynamicImage), String> {
+    let format = detect_format(data)?;
+    let cursor = Cursor::new(data);
+    let decoded = match format {
+        HdriFormat::Hdr => {
+            let decoder = HdrDecoder::new(cursor).map_err(|e| e.to_string())?;
+            image::DynamicImage::from_decoder(decoder)
+                .map_err(|e| format!("Failed to decode HDR: {e}"))?
+        }
+        HdriFormat::Exr => {
+            let decoder = image::codecs::openexr::OpenExrDecoder::new(cursor)
+                .map_err(|e| format!("Failed to decode EXR: {e}"))?;
+            image::DynamicImage::from_decoder(decoder)
+                .map_err(|e| format!("Failed to create DynamicImage from EXR: {e}"))?
+        }
+    };
+    Ok((format, decoded))
+}
+
+fn to_rgba8(
+    format: HdriFormat,
+    decoded: &image::DynamicImage,
+    exposure: f32,
+    gamma: Option<f32>,
+) -> RgbaImage {
+    match format {
+        HdriFormat::Hdr => hdr_to_rgba8(decoded, exposure, gamma),
+        HdriFormat::Exr => exr_to_rgba8(decoded, exposure, gamma),
+    }
+}
+
+fn hdr_to_rgba8(dynamic_img: &image::DynamicImage, exposure: f32, gamma: Option<f32>) -> RgbaImage {
+    let mut rgba8_image = RgbaImage::new(dynamic_img.width(), dynamic_img.height());
     let rgb8_image = dynamic_img.to_rgb8();
     let gamma_value = gamma.unwrap_or(2.2);
     let gamma_correction = 1.0 / gamma_value;
@@ -149,19 +356,12 @@ fn hdr_to_rgba8(hdr_data: &[u8], exposure: f32, gamma: Option<f32>) -> Result<Rg
         let b = ((b_linear.powf(gamma_correction)).min(1.0) * 255.0) as u8;
         rgba8_image.put_pixel(x, y, image::Rgba([r, g, b, 255]));
     }
-    Ok(rgba8_image)
+    rgba8_image
 }
 
-fn exr_to_rgba8(exr_data: &[u8], exposure: f32, gamma: Option<f32>) -> Result<RgbaImage, String> {
-    use image::codecs::openexr::OpenExrDecoder;
-
-    let cursor = Cursor::new(exr_data);
-    let decoder = OpenExrDecoder::new(cursor).map_err(|e| format!("Failed to decode EXR: {e}"))?;
-    let (width, height) = decoder.dimensions();
-    let dynamic_img = image::DynamicImage::from_decoder(decoder)
-        .map_err(|e| format!("Failed to create DynamicImage from EXR: {e}"))?;
+fn exr_to_rgba8(dynamic_img: &image::DynamicImage, exposure: f32, gamma: Option<f32>) -> RgbaImage {
     let rgba_float = dynamic_img.to_rgba32f();
-    let mut rgba8_image = RgbaImage::new(width, height);
+    let mut rgba8_image = RgbaImage::new(dynamic_img.width(), dynamic_img.height());
     let gamma_value = gamma.unwrap_or(2.2);
     let gamma_correction = 1.0 / gamma_value;
     for (x, y, pixel) in rgba_float.enumerate_pixels() {
@@ -178,7 +378,7 @@ fn exr_to_rgba8(exr_data: &[u8], exposure: f32, gamma: Option<f32>) -> Result<Rg
         rgba8_image.put_pixel(x, y, image::Rgba([r, g, b, a]));
     }
 
-    Ok(rgba8_image)
+    rgba8_image
 }
 
 pub fn update_hdri_exposure(
@@ -190,11 +390,8 @@ pub fn update_hdri_exposure(
     new_exposure: f32,
     gamma: Option<f32>,
 ) -> Result<(), String> {
-    let format = detect_format(data)?;
-    let rgba_image = match format {
-        HdriFormat::Hdr => hdr_to_rgba8(data, new_exposure, gamma)?,
-        HdriFormat::Exr => exr_to_rgba8(data, new_exposure, gamma)?,
-    };
+    let (format, decoded) = decode(data)?;
+    let rgba_image = to_rgba8(format, &decoded, new_exposure, gamma);
     texture_manager.update(queue, &rgba_image);
     Ok(())
 }
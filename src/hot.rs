@@ -1,11 +1,56 @@
 use notify::{Event, EventKind, RecursiveMode, Watcher};
-use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+/// Why hot reload couldn't be set up. Every variant names the shader file involved.
+#[derive(Debug)]
+pub enum HotReloadError {
+    /// The shader file doesn't exist (paths are relative to the working directory)
+    FileNotFound(PathBuf),
+    /// The file watcher couldn't be created or couldn't watch the file's directory
+    WatchFailed {
+        path: PathBuf,
+        source: notify::Error,
+    },
+    /// The shader on disk couldn't be read or doesn't compile
+    InitialCompile { path: PathBuf, message: String },
+    /// Hot reload was requested before a compute shader exists
+    NoComputeShader,
+}
+
+impl std::fmt::Display for HotReloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HotReloadError::FileNotFound(path) => {
+                write!(f, "Shader file not found: {}", path.display())
+            }
+            HotReloadError::WatchFailed { path, source } => {
+                write!(f, "Could not watch {}: {source}", path.display())
+            }
+            HotReloadError::InitialCompile { path, message } => {
+                write!(f, "Shader {} failed to compile: {message}", path.display())
+            }
+            HotReloadError::NoComputeShader => write!(f, "No compute shader initialized"),
+        }
+    }
+}
+
+impl std::error::Error for HotReloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HotReloadError::WatchFailed { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Rewrites compute source read from disk before compiling, see
+/// `ShaderHotReload::new_compute_with_preprocessor`
+pub type SourcePreprocessor = Box<dyn Fn(&str) -> String>;
+
 pub enum ShaderType {
     RenderPair, // Vertex + Fragment
     Compute,    // Compute
@@ -24,60 +69,39 @@ pub struct ShaderHotReload {
     watcher: notify::RecommendedWatcher,
     rx: Receiver<notify::Event>,
     _watcher_tx: std::sync::mpsc::Sender<notify::Event>,
-    last_update_times: HashMap<PathBuf, Instant>,
+    /// Time of the latest change event not yet reloaded
+    pending_since: Option<Instant>,
+    /// Reads of an empty/unreadable file for the pending change so far
+    read_attempts: u32,
     debounce_duration: Duration,
     shader_type: ShaderType,
     entry_point: Option<String>,
+    source_suffix: String,
+    preprocessor: Option<SourcePreprocessor>,
 }
 
 impl ShaderHotReload {
+    pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+    const MAX_READ_ATTEMPTS: u32 = 5;
+
     pub fn new(
         device: Arc<wgpu::Device>,
         shader_paths: Vec<PathBuf>,
         vs_module: wgpu::ShaderModule,
         fs_module: wgpu::ShaderModule,
-    ) -> notify::Result<Self> {
+    ) -> Result<Self, HotReloadError> {
         let (tx, rx) = channel();
         let watcher_tx = tx.clone();
 
-        let mut watcher = notify::recommended_watcher(move |res: Result<Event, _>| {
-            if let Ok(event) = res {
-                match event.kind {
-                    EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) => {
-                        tx.send(event).unwrap_or_default();
-                    }
-                    _ => {}
-                }
-            }
-        })?;
-
         //normalize for Windows
         let normalized_paths: Vec<PathBuf> = shader_paths
             .iter()
             .map(|path| Self::normalize_path(path))
             .collect();
 
+        let mut watcher = Self::create_watcher(tx, &normalized_paths[0])?;
         for path in &normalized_paths {
-            if let Some(parent) = path.parent() {
-                if !parent.exists() {
-                    fs::create_dir_all(parent).unwrap_or_else(|e| {
-                        println!("Failed to create shader directory: {e}");
-                    });
-                }
-
-                if let Err(e) = watcher.watch(parent, RecursiveMode::Recursive) {
-                    println!(
-                        "Warning: Could not watch shader directory {}: {}",
-                        parent.display(),
-                        e
-                    );
-                    if cfg!(windows) {
-                        if let Err(e) = watcher.watch(parent, RecursiveMode::NonRecursive) {
-                            println!("Fallback watch failed: {e}");
-                        }
-                    }
-                }
-            }
+            Self::watch_file(&mut watcher, path)?;
         }
 
         let last_vs_content = fs::read_to_string(&normalized_paths[0]).unwrap_or_default();
@@ -95,10 +119,13 @@ impl ShaderHotReload {
             watcher,
             rx,
             _watcher_tx: watcher_tx,
-            last_update_times: HashMap::new(),
-            debounce_duration: Duration::from_millis(100),
+            pending_since: None,
+            read_attempts: 0,
+            debounce_duration: Self::DEFAULT_DEBOUNCE,
             shader_type: ShaderType::RenderPair,
             entry_point: None,
+            source_suffix: String::new(),
+            preprocessor: None,
         })
     }
     pub fn new_compute(
@@ -106,52 +133,66 @@ impl ShaderHotReload {
         shader_path: PathBuf,
         compute_module: wgpu::ShaderModule,
         entry_point: &str,
-    ) -> notify::Result<Self> {
+    ) -> Result<Self, HotReloadError> {
+        Self::new_compute_with_suffix(device, shader_path, compute_module, entry_point, "")
+    }
+
+    /// Like `new_compute`, with code appended to the source on every reload (see
+    /// `set_source_suffix`). The file is compiled once with the suffix to catch errors
+    /// up front.
+    pub fn new_compute_with_suffix(
+        device: Arc<wgpu::Device>,
+        shader_path: PathBuf,
+        compute_module: wgpu::ShaderModule,
+        entry_point: &str,
+        suffix: &str,
+    ) -> Result<Self, HotReloadError> {
+        Self::new_compute_with_preprocessor(
+            device,
+            shader_path,
+            compute_module,
+            entry_point,
+            suffix,
+            None,
+        )
+    }
+
+    /// Like `new_compute_with_suffix`, with `preprocessor` applied to the file contents
+    /// before the suffix is appended, e.g. the placeholder substitution the shader was
+    /// built with, so reloads compile the same kind of source as the original build.
+    pub fn new_compute_with_preprocessor(
+        device: Arc<wgpu::Device>,
+        shader_path: PathBuf,
+        compute_module: wgpu::ShaderModule,
+        entry_point: &str,
+        suffix: &str,
+        preprocessor: Option<SourcePreprocessor>,
+    ) -> Result<Self, HotReloadError> {
         let (tx, rx) = channel();
         let watcher_tx = tx.clone();
 
-        let mut watcher = notify::recommended_watcher(move |res: Result<Event, _>| {
-            if let Ok(event) = res {
-                match event.kind {
-                    EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) => {
-                        tx.send(event).unwrap_or_default();
-                    }
-                    _ => {}
-                }
-            }
-        })?;
-
         let normalized_path = Self::normalize_path(&shader_path);
         let shader_paths = vec![normalized_path.clone()];
 
-        if let Some(parent) = normalized_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent).unwrap_or_else(|e| {
-                    println!("Failed to create shader directory: {e}");
-                });
-            }
+        let mut watcher = Self::create_watcher(tx, &normalized_path)?;
+        Self::watch_file(&mut watcher, &normalized_path)?;
 
-            if let Err(e) = watcher.watch(parent, RecursiveMode::Recursive) {
-                println!(
-                    "Warning: Could not watch shader directory {}: {}",
-                    parent.display(),
-                    e
-                );
-                if cfg!(windows) {
-                    if let Err(e) = watcher.watch(parent, RecursiveMode::NonRecursive) {
-                        println!("Fallback watch failed: {e}");
-                    }
-                }
-            }
-        }
-
-        let last_compute_content = fs::read_to_string(&normalized_path).unwrap_or_default();
+        let last_compute_content =
+            fs::read_to_string(&normalized_path).map_err(|e| HotReloadError::InitialCompile {
+                path: normalized_path.clone(),
+                message: e.to_string(),
+            })?;
+        let source_suffix = if suffix.is_empty() {
+            String::new()
+        } else {
+            format!("\n{suffix}")
+        };
         let dummy_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Dummy Shader Module"),
             source: wgpu::ShaderSource::Wgsl("".into()),
         });
 
-        Ok(Self {
+        let hot_reload = Self {
             vs_module: dummy_shader.clone(),
             fs_module: dummy_shader,
             compute_module: Some(compute_module),
@@ -163,11 +204,88 @@ impl ShaderHotReload {
             watcher,
             rx,
             _watcher_tx: watcher_tx,
-            last_update_times: HashMap::new(),
-            debounce_duration: Duration::from_millis(100),
+            pending_since: None,
+            read_attempts: 0,
+            debounce_duration: Self::DEFAULT_DEBOUNCE,
             shader_type: ShaderType::Compute,
             entry_point: Some(entry_point.to_string()),
+            source_suffix,
+            preprocessor,
+        };
+
+        // Compile what reloads will compile, so working shaders aren't rejected up front
+        let source = hot_reload.compiled_source(hot_reload.compute_source().unwrap_or_default());
+        let device = &hot_reload.device;
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let _ = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Hot Reload Initial Check"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(HotReloadError::InitialCompile {
+                path: normalized_path,
+                message: error.to_string(),
+            });
+        }
+        Ok(hot_reload)
+    }
+
+    fn create_watcher(
+        tx: std::sync::mpsc::Sender<notify::Event>,
+        path: &Path,
+    ) -> Result<notify::RecommendedWatcher, HotReloadError> {
+        notify::recommended_watcher(move |res: Result<Event, _>| {
+            if let Ok(event) = res {
+                match event.kind {
+                    EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) => {
+                        tx.send(event).unwrap_or_default();
+                    }
+                    _ => {}
+                }
+            }
         })
+        .map_err(|source| HotReloadError::WatchFailed {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Watch the directory of `path`, which must exist
+    fn watch_file(
+        watcher: &mut notify::RecommendedWatcher,
+        path: &Path,
+    ) -> Result<(), HotReloadError> {
+        if !path.is_file() {
+            return Err(HotReloadError::FileNotFound(path.to_path_buf()));
+        }
+        let Some(parent) = path.parent() else {
+            return Ok(());
+        };
+        // `parent` is empty for bare file names, meaning the working directory
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        if let Err(e) = watcher.watch(parent, RecursiveMode::Recursive) {
+            if !cfg!(windows) {
+                return Err(HotReloadError::WatchFailed {
+                    path: path.to_path_buf(),
+                    source: e,
+                });
+            }
+            println!(
+                "Warning: Could not watch shader directory {}: {e}",
+                parent.display()
+            );
+            watcher
+                .watch(parent, RecursiveMode::NonRecursive)
+                .map_err(|source| HotReloadError::WatchFailed {
+                    path: path.to_path_buf(),
+                    source,
+                })?;
+        }
+        Ok(())
     }
 
     fn normalize_path(path: &Path) -> PathBuf {
@@ -192,43 +310,13 @@ impl ShaderHotReload {
     }
 
     fn reload_render_shaders(&mut self) -> Option<(&wgpu::ShaderModule, &wgpu::ShaderModule)> {
-        let mut should_reload = false;
-
-        // Process all pending events
-        while let Ok(event) = self.rx.try_recv() {
-            for path in event.paths {
-                let now = Instant::now();
-
-                if let Some(last_update) = self.last_update_times.get(&path) {
-                    if now.duration_since(*last_update) < self.debounce_duration {
-                        continue;
-                    }
-                }
-
-                self.last_update_times.insert(path.clone(), now);
-                should_reload = true;
-            }
-        }
-
-        if !should_reload {
+        if !self.poll_events() {
             return None;
         }
 
-        let vs_content = match fs::read_to_string(&self.shader_paths[0]) {
-            Ok(content) => content,
-            Err(e) => {
-                eprintln!("Failed to read vertex shader: {e}");
-                return None;
-            }
-        };
-
-        let fs_content = match fs::read_to_string(&self.shader_paths[1]) {
-            Ok(content) => content,
-            Err(e) => {
-                eprintln!("Failed to read fragment shader: {e}");
-                return None;
-            }
-        };
+        let vs_content = self.read_complete(0)?;
+        let fs_content = self.read_complete(1)?;
+        self.clear_pending();
 
         if vs_content == self.last_vs_content && fs_content == self.last_fs_content {
             return None;
@@ -245,33 +333,12 @@ impl ShaderHotReload {
         Some((&self.vs_module, &self.fs_module))
     }
     pub fn reload_compute_shader(&mut self) -> Option<&wgpu::ShaderModule> {
-        let mut should_reload = false;
-        while let Ok(event) = self.rx.try_recv() {
-            for path in event.paths {
-                let now = Instant::now();
-
-                if let Some(last_update) = self.last_update_times.get(&path) {
-                    if now.duration_since(*last_update) < self.debounce_duration {
-                        continue;
-                    }
-                }
-
-                self.last_update_times.insert(path.clone(), now);
-                should_reload = true;
-            }
-        }
-
-        if !should_reload {
+        if !self.poll_events() {
             return None;
         }
 
-        let compute_content = match fs::read_to_string(&self.shader_paths[0]) {
-            Ok(content) => content,
-            Err(e) => {
-                eprintln!("Failed to read compute shader: {e}");
-                return None;
-            }
-        };
+        let compute_content = self.read_complete(0)?;
+        self.clear_pending();
 
         if let Some(ref last_content) = self.last_compute_content {
             if compute_content == *last_content {
@@ -279,7 +346,8 @@ impl ShaderHotReload {
             }
         }
 
-        let new_compute = self.create_shader_module(&compute_content, "Compute Shader")?;
+        let source = self.compiled_source(&compute_content);
+        let new_compute = self.create_shader_module(&source, "Compute Shader")?;
 
         self.last_compute_content = Some(compute_content);
         self.compute_module = Some(new_compute);
@@ -287,6 +355,14 @@ impl ShaderHotReload {
         self.compute_module.as_ref()
     }
 
+    /// Compute source as compiled: preprocessed, then the suffix appended
+    fn compiled_source(&self, content: &str) -> String {
+        match &self.preprocessor {
+            Some(preprocess) => format!("{}{}", preprocess(content), self.source_suffix),
+            None => format!("{content}{}", self.source_suffix),
+        }
+    }
+
     fn create_shader_module(&self, source: &str, label: &str) -> Option<wgpu::ShaderModule> {
         let desc = wgpu::ShaderModuleDescriptor {
             label: Some(label),
@@ -310,6 +386,68 @@ impl ShaderHotReload {
         }
     }
 
+    /// Drain watcher events and report whether a reload is due: true once no event for a
+    /// watched file arrived during the debounce window. Editors often save in several
+    /// writes (temp file + rename, truncate + write), so reloading on the first event
+    /// would compile a partial file and then skip the final one.
+    fn poll_events(&mut self) -> bool {
+        while let Ok(event) = self.rx.try_recv() {
+            if event.paths.iter().any(|path| self.is_watched(path)) {
+                self.pending_since = Some(Instant::now());
+                self.read_attempts = 0;
+            }
+        }
+        self.pending_since
+            .is_some_and(|since| since.elapsed() >= self.debounce_duration)
+    }
+
+    /// Events come with absolute paths for the whole watched directory
+    fn is_watched(&self, path: &Path) -> bool {
+        self.shader_paths
+            .iter()
+            .any(|watched| watched == path || watched.file_name() == path.file_name())
+    }
+
+    /// Read shader `index` for a pending change. An empty or unreadable file is usually a
+    /// save in progress, so the change stays pending and the read is retried after another
+    /// debounce window, giving up after `MAX_READ_ATTEMPTS`.
+    fn read_complete(&mut self, index: usize) -> Option<String> {
+        let path = &self.shader_paths[index];
+        let error = match fs::read_to_string(path) {
+            Ok(content) if !content.trim().is_empty() => return Some(content),
+            Ok(_) => "file is empty".to_string(),
+            Err(e) => e.to_string(),
+        };
+        self.read_attempts += 1;
+        if self.read_attempts >= Self::MAX_READ_ATTEMPTS {
+            eprintln!("Failed to read shader {}: {error}", path.display());
+            self.clear_pending();
+        } else {
+            self.pending_since = Some(Instant::now());
+        }
+        None
+    }
+
+    fn clear_pending(&mut self) {
+        self.pending_since = None;
+        self.read_attempts = 0;
+    }
+
+    /// Quiet time after the last change event before reloading, 100ms by default. Longer
+    /// windows help with slow network drives or editors that save in several steps.
+    pub fn set_debounce(&mut self, duration: Duration) {
+        self.debounce_duration = duration;
+    }
+
+    pub fn debounce(&self) -> Duration {
+        self.debounce_duration
+    }
+
+    /// Code appended to the compute source on every reload, e.g. `compute::STDLIB_WGSL`
+    pub fn set_source_suffix(&mut self, suffix: &str) {
+        self.source_suffix = format!("\n{suffix}");
+    }
+
     pub fn entry_point(&self) -> Option<&str> {
         self.entry_point.as_deref()
     }
@@ -317,4 +455,9 @@ impl ShaderHotReload {
     pub fn get_compute_module(&self) -> Option<&wgpu::ShaderModule> {
         self.compute_module.as_ref()
     }
+
+    /// Compute source as last read from disk, without the suffix
+    pub fn compute_source(&self) -> Option<&str> {
+        self.last_compute_content.as_deref()
+    }
 }
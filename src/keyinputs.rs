@@ -20,8 +20,13 @@ impl KeyInputHandler {
         }
     }
     pub fn handle_keyboard_input(&mut self, window: &Window, event: &KeyEvent) -> bool {
-        if event.state == ElementState::Pressed && !event.repeat {
-            if let Key::Character(ch) = &event.logical_key {
+        !event.repeat && self.handle_key(window, &event.logical_key, event.state)
+    }
+    /// Same as `handle_keyboard_input` for a key without its `KeyEvent`, e.g. one replayed
+    /// by `InputRecorder`
+    pub fn handle_key(&mut self, window: &Window, key: &Key, state: ElementState) -> bool {
+        if state == ElementState::Pressed {
+            if let Key::Character(ch) = key {
                 match ch.as_str() {
                     "f" | "F" => {
                         self.toggle_fullscreen(window);
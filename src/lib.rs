@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use winit::window::Window;
 
 pub use anyhow;
@@ -12,37 +12,71 @@ pub use bytemuck::{Pod, Zeroable};
 pub use wgpu::SurfaceError;
 pub use winit::event::WindowEvent;
 
+mod animated;
 mod app;
 mod atomic;
+mod batch;
+mod camera;
+mod channels;
 pub mod compute;
 mod controls;
+mod egui_target;
 mod export;
+mod expression;
 mod font;
 mod fps;
+mod grading;
 #[cfg(feature = "media")]
 pub mod gst;
 pub mod hdri;
 mod hot;
 mod keyinputs;
 mod mouse;
+pub mod normalmap;
+#[cfg(feature = "osc")]
+mod osc;
+mod recorder;
 mod renderer;
 mod renderkit;
 mod shader;
 mod spectrum;
+mod switcher;
+mod testing;
 mod texture;
 mod uniforms;
+pub use animated::AnimatedTexture;
 pub use app::*;
 pub use atomic::AtomicBuffer;
-pub use controls::{ControlsRequest, ShaderControls};
-pub use export::{save_frame, ExportError, ExportManager, ExportSettings, ExportUiState};
+pub use batch::{BatchRenderer, BatchVariant};
+pub use camera::{Camera, CameraInput, CameraMode, CameraUniform};
+pub use channels::{parse_channel_directives, ChannelInputs, ChannelSource};
+pub use compute::{list_entry_points, EntryPointInfo};
+pub use controls::{
+    ActionButton, CompositionGuides, ControlsRequest, GuideAspect, PassGraph, PassSummary,
+    ShaderControls, SpectrumSettings,
+};
+pub use export::{
+    frame_path, output_path, save_frame, save_output, ExportError, ExportManager, ExportOutput,
+    ExportSettings, ExportUiState, OutputPixels,
+};
+pub use expression::{Expression, ExpressionBinding, ExpressionError, ExpressionVariables};
 pub use font::{CharInfo, FontSystem, FontUniforms};
+pub use fps::{DynamicResolution, FpsTracker, FrameLimiter};
+pub use grading::ColorGrading;
 pub use hdri::*;
-pub use hot::ShaderHotReload;
+pub use hot::{HotReloadError, ShaderHotReload, SourcePreprocessor};
 pub use keyinputs::KeyInputHandler;
 pub use mouse::*;
+pub use normalmap::*;
+#[cfg(feature = "osc")]
+pub use osc::{OscArg, OscMessage, OscReceiver};
+pub use recorder::{InputRecorder, RecordedEvent, RecordedInput};
 pub use renderer::*;
 pub use renderkit::*;
 pub use shader::*;
+pub use spectrum::{FrequencyScale, Spectrogram, SpectrumWeighting, WaveformTexture};
+pub use switcher::ShaderSwitcher;
+pub use testing::{compare_images, ImageDiff, TestHarness};
 pub use texture::*;
 pub use uniforms::*;
 
@@ -89,16 +123,64 @@ pub mod prelude {
     pub use winit::{dpi::PhysicalSize, event_loop::EventLoop, window::Window};
 }
 
+/// Install an `env_logger` logger with cuneus logging at `level`.
+///
+/// Other crates keep the `RUST_LOG` filter, which can also set cuneus alone
+/// (`RUST_LOG=cuneus=debug`). Per-frame messages are at `trace`, so `info` only shows
+/// lifecycle events like media loading and shader reloads. Call it instead of
+/// `env_logger::init()`, it fails if a logger is already installed.
+pub fn set_log_level(level: log::LevelFilter) -> Result<(), log::SetLoggerError> {
+    env_logger::Builder::from_default_env()
+        .filter_module("cuneus", level)
+        .try_init()
+}
+
+/// Per-user cache directory for the pipeline cache, see `Core::pipeline_cache_path`
+fn user_cache_dir() -> Option<std::path::PathBuf> {
+    let from_env = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(std::path::PathBuf::from)
+    };
+    if cfg!(target_os = "windows") {
+        from_env("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        from_env("HOME").map(|home| home.join("Library").join("Caches"))
+    } else {
+        from_env("XDG_CACHE_HOME").or_else(|| from_env("HOME").map(|home| home.join(".cache")))
+    }
+}
+
 pub struct Core {
     pub surface: wgpu::Surface<'static>,
     pub device: Arc<wgpu::Device>,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
     pub size: winit::dpi::PhysicalSize<u32>,
-    pub window: Window,
+    /// Shared with the surface, which keeps its own clone alive for its `'static` lifetime
+    pub window: Arc<Window>,
+    /// Driver pipeline cache, only on backends with `Features::PIPELINE_CACHE` (Vulkan)
+    pipeline_cache: Option<wgpu::PipelineCache>,
+    pipeline_cache_path: Option<std::path::PathBuf>,
+    anisotropic_filtering: bool,
+    /// Formats with `STORAGE_READ_WRITE` beyond the always-supported R32 ones
+    read_write_storage_formats: Vec<wgpu::TextureFormat>,
+    /// Size `set_window_size` got applied with right away, winit sends no `Resized` for it
+    pending_resize: Mutex<Option<winit::dpi::PhysicalSize<u32>>>,
 }
 impl Core {
-    pub async fn new(window: Window) -> Self {
+    /// Accepts a `Window` or an `Arc<Window>` the caller keeps a handle to
+    pub async fn new(window: impl Into<Arc<Window>>) -> Self {
+        Self::new_with_requirements(window, false).await
+    }
+
+    /// Create the core, optionally forcing wgpu's software fallback adapter
+    /// (llvmpipe/lavapipe/WARP). Useful for CI machines without a GPU.
+    pub async fn new_with_requirements(
+        window: impl Into<Arc<Window>>,
+        force_fallback: bool,
+    ) -> Self {
+        let window = window.into();
         let size = window.inner_size();
         let instance_desc = wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -106,10 +188,7 @@ impl Core {
             ..Default::default()
         };
         let instance = wgpu::Instance::new(&instance_desc);
-        let window_box = Box::new(window);
-        let window_ptr = Box::into_raw(window_box);
-        // SAFETY: window_ptr is valid as we just created it
-        let surface = unsafe { instance.create_surface(&*window_ptr) }.unwrap();
+        let surface = instance.create_surface(window.clone()).unwrap();
         let power_preference = instance
             .enumerate_adapters(wgpu::Backends::all())
             .iter()
@@ -120,14 +199,46 @@ impl Core {
             .request_adapter(&wgpu::RequestAdapterOptions {
                 power_preference,
                 compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
+                force_fallback_adapter: force_fallback,
             })
             .await
-            .unwrap();
+            .unwrap_or_else(|e| {
+                if force_fallback {
+                    panic!("No software fallback adapter available: {e}");
+                }
+                panic!("Failed to find a suitable GPU adapter: {e}");
+            });
+        let adapter_info = adapter.get_info();
+        if force_fallback || adapter_info.device_type == wgpu::DeviceType::Cpu {
+            log::warn!(
+                "Running on software adapter: {} ({:?})",
+                adapter_info.name,
+                adapter_info.backend
+            );
+        } else {
+            log::info!(
+                "Using adapter: {} ({:?})",
+                adapter_info.name,
+                adapter_info.backend
+            );
+        }
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 label: None,
-                required_features: wgpu::Features::empty(),
+                // 16-bit normalized formats are used for high bit depth images when available
+                // BGRA8 storage lets compute shaders write straight into BGRA swapchains
+                // Compressed formats are enabled for KTX2 textures (`TextureManager::from_ktx2`)
+                // CLEAR_TEXTURE zeroes non-persistent passes, `MultiPassManager` copies from a
+                // zeroed buffer without it
+                required_features: adapter.features()
+                    & (wgpu::Features::TEXTURE_FORMAT_16BIT_NORM
+                        | wgpu::Features::BGRA8UNORM_STORAGE
+                        | wgpu::Features::TEXTURE_COMPRESSION_BC
+                        | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+                        | wgpu::Features::TEXTURE_COMPRESSION_ASTC
+                        | wgpu::Features::PIPELINE_CACHE
+                        | wgpu::Features::CLEAR_TEXTURE
+                        | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES),
                 required_limits: wgpu::Limits::default(),
                 memory_hints: Default::default(),
                 experimental_features: Default::default(),
@@ -136,6 +247,52 @@ impl Core {
             .await
             .unwrap();
         let device = Arc::new(device);
+        let pipeline_cache_path = wgpu::util::pipeline_cache_key(&adapter_info)
+            .zip(user_cache_dir())
+            .map(|(key, dir)| dir.join("cuneus").join(key));
+        let pipeline_cache = pipeline_cache_path
+            .as_ref()
+            .filter(|_| device.features().contains(wgpu::Features::PIPELINE_CACHE))
+            .map(|path| {
+                let data = std::fs::read(path).ok();
+                // SAFETY: the file lives in the user's own cache directory, which other users
+                // can't write, and is only written there by `save_pipeline_cache` from a
+                // cache's `get_data`. Its name is keyed to this adapter and driver, and
+                // `fallback` makes wgpu start empty if the header doesn't match.
+                unsafe {
+                    device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                        label: Some("Pipeline Cache"),
+                        data: data.as_deref(),
+                        fallback: true,
+                    })
+                }
+            });
+        let anisotropic_filtering = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING);
+        // Read-write storage beyond R32 is an adapter-specific format feature
+        let read_write_storage_formats = if device
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        {
+            [
+                wgpu::TextureFormat::Rgba8Unorm,
+                wgpu::TextureFormat::Bgra8Unorm,
+                wgpu::TextureFormat::Rgba16Float,
+                wgpu::TextureFormat::Rgba32Float,
+            ]
+            .into_iter()
+            .filter(|format| {
+                adapter
+                    .get_texture_format_features(*format)
+                    .flags
+                    .contains(wgpu::TextureFormatFeatureFlags::STORAGE_READ_WRITE)
+            })
+            .collect()
+        } else {
+            Vec::new()
+        };
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
             .formats
@@ -143,19 +300,35 @@ impl Core {
             .copied()
             .find(|f| f.is_srgb() && *f == CAPTURE_FORMAT)
             .unwrap_or(surface_caps.formats[0]);
+        // Storage usage + a linear view format enable `ComputeShaderBuilder::direct_present`
+        let storage_format = surface_format.remove_srgb_suffix();
+        let storage_supported = surface_caps
+            .usages
+            .contains(wgpu::TextureUsages::STORAGE_BINDING)
+            && Self::is_storage_format(storage_format, device.features());
+        let (usage, view_formats) = if storage_supported {
+            (
+                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::STORAGE_BINDING,
+                if storage_format != surface_format {
+                    vec![storage_format]
+                } else {
+                    vec![]
+                },
+            )
+        } else {
+            (wgpu::TextureUsages::RENDER_ATTACHMENT, vec![])
+        };
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width: size.width,
             height: size.height,
             present_mode: wgpu::PresentMode::Fifo,
             alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
+            view_formats,
+            desired_maximum_frame_latency: Self::DEFAULT_FRAME_LATENCY,
         };
         surface.configure(&device, &config);
-        // SAFETY: window_ptr is still valid and we're taking back ownership
-        let window = unsafe { *Box::from_raw(window_ptr) };
         Self {
             surface,
             device,
@@ -163,11 +336,156 @@ impl Core {
             config,
             size,
             window,
+            pipeline_cache,
+            pipeline_cache_path,
+            anisotropic_filtering,
+            read_write_storage_formats,
+            pending_resize: Mutex::new(None),
         }
     }
     pub fn window(&self) -> &Window {
         &self.window
     }
+    pub fn set_title(&self, title: &str) {
+        self.window.set_title(title);
+    }
+    pub fn title(&self) -> String {
+        self.window.title()
+    }
+    /// Confine or lock the cursor to the window. `Locked` isn't available everywhere
+    /// (X11 only confines, macOS only locks), so check the result and fall back.
+    pub fn set_cursor_grab(
+        &self,
+        mode: winit::window::CursorGrabMode,
+    ) -> Result<(), winit::error::ExternalError> {
+        self.window.set_cursor_grab(mode)
+    }
+    pub fn set_cursor_visible(&self, visible: bool) {
+        self.window.set_cursor_visible(visible);
+    }
+    /// Ask for a new window size, e.g. to match an export resolution. The surface and the
+    /// shader are resized through the usual `ShaderManager::resize` path once the size
+    /// applies, usually before the next frame. The OS or window manager may pick a
+    /// different size (tiling WMs, screen limits, fullscreen) or ignore the request, so
+    /// read `size` afterwards instead of assuming the requested one.
+    pub fn set_window_size(&self, size: winit::dpi::PhysicalSize<u32>) {
+        if let Some(applied) = self.window.request_inner_size(size) {
+            *self.pending_resize.lock().unwrap() = Some(applied);
+        }
+    }
+    /// Enter or leave fullscreen, `None` for windowed. The resize that follows goes
+    /// through the usual resize path; not every platform supports `Exclusive`.
+    pub fn set_fullscreen(&self, fullscreen: Option<winit::window::Fullscreen>) {
+        self.window.set_fullscreen(fullscreen);
+    }
+    pub fn fullscreen(&self) -> Option<winit::window::Fullscreen> {
+        self.window.fullscreen()
+    }
+    /// Size applied synchronously by `set_window_size`, for the app to resize to
+    pub(crate) fn take_pending_resize(&self) -> Option<winit::dpi::PhysicalSize<u32>> {
+        self.pending_resize.lock().unwrap().take()
+    }
+    pub const MIN_FRAME_LATENCY: u32 = 1;
+    pub const MAX_FRAME_LATENCY: u32 = 3;
+    pub const DEFAULT_FRAME_LATENCY: u32 = 2;
+
+    /// Frames the GPU may queue ahead: 1 for lowest input latency, 3 for smoother throughput.
+    /// Values are clamped to 1..=3, the range wgpu honors on all backends. Returns the applied value.
+    /// The app applies the Max Frame Latency slider of the controls panel with this.
+    pub fn set_max_frame_latency(&mut self, latency: u32) -> u32 {
+        let clamped = latency.clamp(Self::MIN_FRAME_LATENCY, Self::MAX_FRAME_LATENCY);
+        if clamped != latency {
+            log::warn!("Frame latency {latency} out of range, using {clamped}");
+        }
+        if self.config.desired_maximum_frame_latency != clamped {
+            self.config.desired_maximum_frame_latency = clamped;
+            self.surface.configure(&self.device, &self.config);
+        }
+        clamped
+    }
+
+    /// Format compute shaders can write the swapchain with, `None` if the surface can't be
+    /// bound as a storage texture. It's the surface format without sRGB: `Rgba8Unorm` works
+    /// everywhere storage surfaces do, `Bgra8Unorm` needs `Features::BGRA8UNORM_STORAGE`.
+    /// Values are written as-is, without the sRGB encoding a render pass would apply.
+    pub fn direct_present_format(&self) -> Option<wgpu::TextureFormat> {
+        self.config
+            .usage
+            .contains(wgpu::TextureUsages::STORAGE_BINDING)
+            .then(|| self.config.format.remove_srgb_suffix())
+    }
+
+    fn is_storage_format(format: wgpu::TextureFormat, features: wgpu::Features) -> bool {
+        match format {
+            wgpu::TextureFormat::Rgba8Unorm
+            | wgpu::TextureFormat::Rgba16Float
+            | wgpu::TextureFormat::Rgba32Float => true,
+            wgpu::TextureFormat::Bgra8Unorm => {
+                features.contains(wgpu::Features::BGRA8UNORM_STORAGE)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether compute shaders can bind `format` as `texture_storage_2d<.., read_write>`.
+    /// `R32Float`, `R32Uint` and `R32Sint` always can; other formats only where the adapter
+    /// reports it (`Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`), commonly
+    /// `Rgba16Float`/`Rgba32Float` on desktop Vulkan, DX12 and Metal, rarely on GL or WebGPU.
+    pub fn supports_read_write_storage(&self, format: wgpu::TextureFormat) -> bool {
+        matches!(
+            format,
+            wgpu::TextureFormat::R32Float
+                | wgpu::TextureFormat::R32Uint
+                | wgpu::TextureFormat::R32Sint
+        ) || self.read_write_storage_formats.contains(&format)
+    }
+
+    /// Pass as `cache` when creating pipelines. `None` where the backend has no pipeline
+    /// cache (everything but Vulkan), where drivers keep their own.
+    pub fn pipeline_cache(&self) -> Option<&wgpu::PipelineCache> {
+        self.pipeline_cache.as_ref()
+    }
+
+    /// Whether samplers honor `anisotropy_clamp`. Without it (WebGL, some GLES drivers)
+    /// wgpu samples with a clamp of 1.
+    pub fn supports_anisotropic_filtering(&self) -> bool {
+        self.anisotropic_filtering
+    }
+
+    /// Where `save_pipeline_cache` writes, or `None` when there is no cache to persist.
+    /// The user cache directory is `$XDG_CACHE_HOME` or `~/.cache` on Linux,
+    /// `~/Library/Caches` on macOS and `%LOCALAPPDATA%` on Windows; without one the cache
+    /// only lives for the current run.
+    pub fn pipeline_cache_path(&self) -> Option<&std::path::Path> {
+        self.pipeline_cache
+            .as_ref()
+            .and(self.pipeline_cache_path.as_deref())
+    }
+
+    /// Write the pipeline cache to `<user cache dir>/cuneus/<adapter key>` so the next run skips
+    /// shader compilation. The key (`wgpu::util::pipeline_cache_key`) covers the adapter,
+    /// driver and wgpu version, so a driver update simply starts a new file; delete the
+    /// directory to drop stale ones. Does nothing without a cache.
+    pub fn save_pipeline_cache(&self) -> std::io::Result<()> {
+        let (Some(cache), Some(path)) = (&self.pipeline_cache, &self.pipeline_cache_path) else {
+            return Ok(());
+        };
+        let Some(data) = cache.get_data() else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write then rename so a crash never leaves a half-written cache behind
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, data)?;
+        std::fs::rename(&temp_path, path)
+    }
+
+    pub fn max_frame_latency(&self) -> u32 {
+        self.config.desired_maximum_frame_latency
+    }
+
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         println!("Core resize called with size: {new_size:?}");
         if new_size.width > 0 && new_size.height > 0 {
@@ -1,6 +1,23 @@
 use crate::UniformProvider;
-use winit::event::WindowEvent;
+use winit::event::{DeviceEvent, WindowEvent};
 
+/// Mouse state for shaders. All coordinates have their origin at the top-left of the
+/// window with y pointing down, flip y (`1.0 - pos_norm.y`) for bottom-left conventions.
+///
+/// WGSL layout (the first four fields are enough for older shaders):
+/// ```wgsl
+/// struct MouseUniform {
+///     position: vec2<f32>,       // normalized 0..1
+///     click_position: vec2<f32>, // normalized, last button-down
+///     wheel: vec2<f32>,
+///     buttons: vec2<u32>,
+///     pos_pixels: vec2<f32>,
+///     pos_norm: vec2<f32>,       // same as position
+///     delta: vec2<f32>,          // pixels moved since `MouseTracker::end_frame`
+///     drag_start: vec2<f32>,     // pixels, last button-down
+///     look: vec2<f32>,           // yaw, pitch in radians, accumulated while captured
+/// }
+/// ```
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct MouseUniform {
@@ -8,6 +25,11 @@ pub struct MouseUniform {
     pub click_position: [f32; 2],
     pub wheel: [f32; 2],
     pub buttons: [u32; 2],
+    pub pos_pixels: [f32; 2],
+    pub pos_norm: [f32; 2],
+    pub delta: [f32; 2],
+    pub drag_start: [f32; 2],
+    pub look: [f32; 2],
 }
 
 impl Default for MouseUniform {
@@ -17,6 +39,11 @@ impl Default for MouseUniform {
             click_position: [0.0, 0.0],
             wheel: [0.0, 0.0],
             buttons: [0, 0],
+            pos_pixels: [0.0, 0.0],
+            pos_norm: [0.0, 0.0],
+            delta: [0.0, 0.0],
+            drag_start: [0.0, 0.0],
+            look: [0.0, 0.0],
         }
     }
 }
@@ -31,6 +58,11 @@ pub struct MouseTracker {
     pub uniform: MouseUniform,
     pub raw_position: [f32; 2],
     pub is_inside_window: bool,
+    /// Radians of look rotation per pixel of raw mouse motion
+    pub look_sensitivity: f32,
+    /// `raw_position` is current, so the next move can produce a delta
+    tracking_delta: bool,
+    captured: bool,
 }
 
 impl Default for MouseTracker {
@@ -39,6 +71,9 @@ impl Default for MouseTracker {
             uniform: MouseUniform::default(),
             raw_position: [0.0, 0.0],
             is_inside_window: false,
+            look_sensitivity: 0.003,
+            tracking_delta: false,
+            captured: false,
         }
     }
 }
@@ -63,10 +98,19 @@ impl MouseTracker {
             WindowEvent::CursorMoved { position, .. } => {
                 let x = position.x as f32;
                 let y = position.y as f32;
+                // The first move after entering would jump from a stale position. While
+                // captured the delta comes from `handle_device_event` instead.
+                if self.tracking_delta && !self.captured {
+                    self.uniform.delta[0] += x - self.raw_position[0];
+                    self.uniform.delta[1] += y - self.raw_position[1];
+                }
                 self.raw_position = [x, y];
+                self.tracking_delta = true;
 
                 self.uniform.position[0] = x / window_size[0];
                 self.uniform.position[1] = y / window_size[1];
+                self.uniform.pos_pixels = [x, y];
+                self.uniform.pos_norm = self.uniform.position;
                 true
             }
             WindowEvent::MouseInput { state, button, .. } => {
@@ -91,6 +135,7 @@ impl MouseTracker {
                 if pressed {
                     self.uniform.buttons[0] |= bit_mask;
                     self.uniform.click_position = self.uniform.position;
+                    self.uniform.drag_start = self.uniform.pos_pixels;
                 } else {
                     self.uniform.buttons[0] &= !bit_mask;
                 }
@@ -113,6 +158,7 @@ impl MouseTracker {
             }
             WindowEvent::CursorLeft { .. } => {
                 self.is_inside_window = false;
+                self.tracking_delta = false;
                 true
             }
             WindowEvent::CursorEntered { .. } => {
@@ -123,7 +169,61 @@ impl MouseTracker {
         }
     }
 
+    /// Raw mouse motion, only used while captured since a locked cursor sends no
+    /// `CursorMoved`. Feeds `delta` and the `look` angles (pitch clamped short of the poles).
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) -> bool {
+        let DeviceEvent::MouseMotion { delta } = event else {
+            return false;
+        };
+        if !self.captured {
+            return false;
+        }
+        let (dx, dy) = (delta.0 as f32, delta.1 as f32);
+        self.uniform.delta[0] += dx;
+        self.uniform.delta[1] += dy;
+
+        let max_pitch = std::f32::consts::FRAC_PI_2 - 0.001;
+        self.uniform.look[0] += dx * self.look_sensitivity;
+        self.uniform.look[1] =
+            (self.uniform.look[1] - dy * self.look_sensitivity).clamp(-max_pitch, max_pitch);
+        true
+    }
+
+    /// Mark the pointer as captured (see `RenderKit::set_mouse_capture`, which also grabs
+    /// and hides the cursor). Look angles are kept across captures.
+    pub fn set_captured(&mut self, captured: bool) {
+        self.captured = captured;
+        // The cursor may have moved while locked
+        self.tracking_delta = false;
+    }
+
+    pub fn is_captured(&self) -> bool {
+        self.captured
+    }
+
+    pub fn reset_look(&mut self) {
+        self.uniform.look = [0.0, 0.0];
+    }
+
     pub fn reset_wheel(&mut self) {
         self.uniform.wheel = [0.0, 0.0];
     }
+
+    /// Start a new `delta` accumulation. Call once per frame after uploading the uniform;
+    /// `RenderKit::update_mouse_uniform` does it for you, and the app loop does it after
+    /// every frame for shaders exposing their kit through `ShaderManager::render_kit_mut`.
+    pub fn end_frame(&mut self) {
+        self.uniform.delta = [0.0, 0.0];
+    }
+
+    /// Pixel offset from where the current drag started, zero when no button is held
+    pub fn drag_offset(&self) -> [f32; 2] {
+        if self.uniform.buttons[0] == 0 {
+            return [0.0, 0.0];
+        }
+        [
+            self.uniform.pos_pixels[0] - self.uniform.drag_start[0],
+            self.uniform.pos_pixels[1] - self.uniform.drag_start[1],
+        ]
+    }
 }
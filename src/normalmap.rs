@@ -0,0 +1,252 @@
+use crate::compute::ComputeShader;
+use crate::{Core, TextureManager};
+use wgpu::util::DeviceExt;
+
+const NORMAL_MAP_SHADER: &str = include_str!("../shaders/normalmap.wgsl");
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct NormalMapParams {
+    strength: f32,
+    tangent_space: u32,
+    _padding: [f32; 2],
+}
+
+#[derive(Clone, Debug, Copy)]
+pub struct NormalMapSettings {
+    /// Multiplier on the height gradient, higher means bumpier
+    pub strength: f32,
+    /// true: tangent space (Z-up), false: Y-up world space heightfield normals
+    pub tangent_space: bool,
+    /// `Rgba8Unorm` or `Rgba16Float`
+    pub format: wgpu::TextureFormat,
+}
+
+impl Default for NormalMapSettings {
+    fn default() -> Self {
+        Self {
+            strength: 1.0,
+            tangent_space: true,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+}
+
+/// Derive a tangent space normal map from a heightmap (red channel) on the GPU.
+/// Normals are encoded as `n * 0.5 + 0.5`.
+pub fn generate_normal_map(
+    core: &Core,
+    height_map: &TextureManager,
+    strength: f32,
+) -> TextureManager {
+    generate_normal_map_with(
+        core,
+        height_map,
+        &NormalMapSettings {
+            strength,
+            ..Default::default()
+        },
+    )
+}
+
+pub fn generate_normal_map_with(
+    core: &Core,
+    height_map: &TextureManager,
+    settings: &NormalMapSettings,
+) -> TextureManager {
+    let device = &core.device;
+    let format_name = match settings.format {
+        wgpu::TextureFormat::Rgba16Float => "rgba16float",
+        wgpu::TextureFormat::Rgba8Unorm => "rgba8unorm",
+        other => {
+            log::warn!("Unsupported normal map format {other:?}, using Rgba8Unorm");
+            "rgba8unorm"
+        }
+    };
+    let format = if format_name == "rgba16float" {
+        wgpu::TextureFormat::Rgba16Float
+    } else {
+        wgpu::TextureFormat::Rgba8Unorm
+    };
+
+    let size = height_map.texture.size();
+    let output = ComputeShader::create_output_texture(
+        device,
+        size.width,
+        size.height,
+        format,
+        "Normal Map Texture",
+    );
+
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Normal Map Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            },
+        ],
+    });
+
+    let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Normal Map Params"),
+        contents: bytemuck::bytes_of(&NormalMapParams {
+            strength: settings.strength,
+            tangent_space: settings.tangent_space as u32,
+            _padding: [0.0; 2],
+        }),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Normal Map Bind Group"),
+        layout: &layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&height_map.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: params.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(&output.view),
+            },
+        ],
+    });
+
+    let source = NORMAL_MAP_SHADER.replace("OUTPUT_FORMAT", format_name);
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Normal Map Shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Normal Map Pipeline Layout"),
+        bind_group_layouts: &[&layout],
+        push_constant_ranges: &[],
+    });
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Normal Map Pipeline"),
+        layout: Some(&pipeline_layout),
+        module: &module,
+        entry_point: Some("main"),
+        compilation_options: Default::default(),
+        cache: core.pipeline_cache(),
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Normal Map Encoder"),
+    });
+    {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Normal Map Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(size.width.div_ceil(16), size.height.div_ceil(16), 1);
+    }
+    core.queue.submit(Some(encoder.finish()));
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::PendingReadback;
+    use crate::testing::test_core;
+
+    fn read_rgba8(core: &Core, texture: &wgpu::Texture) -> Vec<u8> {
+        let size = texture.size();
+        let padded_bytes_per_row = (size.width * 4).div_ceil(256) * 256;
+        let buffer = core.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Normal Map Test Readback"),
+            size: (padded_bytes_per_row * size.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        core.queue.submit(Some(encoder.finish()));
+        let len = buffer.size();
+        PendingReadback::new(buffer, len)
+            .with_row_padding(padded_bytes_per_row, size.width * 4)
+            .wait(&core.device)
+            .unwrap()
+    }
+
+    #[test]
+    fn ramp_tilts_normals_against_the_slope() {
+        let Some(core) = test_core() else {
+            return;
+        };
+        // Height rises by 8/255 per pixel to the right and is constant along y
+        let ramp = image::GrayImage::from_fn(32, 32, |x, _| image::Luma([(x * 8) as u8]));
+        let layout = crate::RenderKit::create_standard_texture_layout(&core.device);
+        let height_map = TextureManager::from_image(
+            &core.device,
+            &core.queue,
+            &image::DynamicImage::ImageLuma8(ramp),
+            &layout,
+            false,
+        );
+        let normals = generate_normal_map(core, &height_map, 1.0);
+        let pixels = read_rgba8(core, &normals.texture);
+
+        // Sobel sums the 2-pixel difference over weights 1, 2, 1: dx = 8 * 8/255
+        let dx = 64.0f32 / 255.0;
+        let length = (dx * dx + 1.0).sqrt();
+        let expected = [-dx / length, 0.0, 1.0 / length].map(|n| (n * 0.5 + 0.5) * 255.0);
+        // Interior pixel, away from the clamped edges
+        let offset = ((16 * 32 + 16) * 4) as usize;
+        for (channel, expected) in expected.iter().enumerate() {
+            let actual = pixels[offset + channel] as f32;
+            assert!(
+                (actual - expected).abs() <= 1.5,
+                "channel {channel}: {actual} vs {expected}"
+            );
+        }
+    }
+}
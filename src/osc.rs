@@ -0,0 +1,430 @@
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Largest UDP payload, OSC packets don't get fragmented across datagrams
+const MAX_PACKET: usize = 65536;
+/// How often the listener thread checks whether it should stop
+const STOP_POLL: Duration = Duration::from_millis(100);
+/// Bundles can nest, a hostile packet shouldn't be able to recurse forever
+const MAX_BUNDLE_DEPTH: usize = 8;
+
+/// One OSC argument. Only the OSC 1.0 types plus `T`/`F` are understood, messages with
+/// other type tags are dropped as malformed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    String(String),
+    Blob(Vec<u8>),
+    Bool(bool),
+}
+
+impl OscArg {
+    /// Numeric value, booleans as 0/1
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            OscArg::Int(v) => Some(*v as f32),
+            OscArg::Float(v) => Some(*v),
+            OscArg::Long(v) => Some(*v as f32),
+            OscArg::Double(v) => Some(*v as f32),
+            OscArg::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+            OscArg::String(_) | OscArg::Blob(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+/// Receives OSC over UDP (TouchOSC, Ableton, ...) and turns mapped addresses into named
+/// values for shader uniforms.
+///
+/// A background thread owns the socket and forwards every valid message; call `poll`
+/// once per frame on the render thread, then copy `value(field)` into your params.
+/// Malformed packets are logged at debug level and skipped. The thread stops when the
+/// receiver is dropped.
+///
+/// Mapping format (`parse_mapping`), one `address field` pair per line, `#` comments:
+///
+/// ```text
+/// # TouchOSC simple layout
+/// /1/fader1  speed
+/// /1/fader2  zoom
+/// /1/toggle1 invert
+/// ```
+///
+/// The first numeric argument of a message becomes the field value (ints, doubles and
+/// booleans are converted to f32). Several addresses may feed the same field.
+pub struct OscReceiver {
+    messages: mpsc::Receiver<OscMessage>,
+    local_addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    mapping: HashMap<String, String>,
+    values: HashMap<String, f32>,
+    unmapped: VecDeque<OscMessage>,
+    unmapped_limit: usize,
+}
+
+impl OscReceiver {
+    /// Listen on `addr`, e.g. `"0.0.0.0:9000"` for messages from other devices
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(STOP_POLL))?;
+        let local_addr = socket.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, messages) = mpsc::channel();
+
+        let thread_stop = Arc::clone(&stop);
+        let thread = std::thread::Builder::new()
+            .name("cuneus-osc".to_string())
+            .spawn(move || listen(socket, sender, thread_stop))?;
+        log::info!("Listening for OSC on {local_addr}");
+
+        Ok(Self {
+            messages,
+            local_addr,
+            stop,
+            thread: Some(thread),
+            mapping: HashMap::new(),
+            values: HashMap::new(),
+            unmapped: VecDeque::new(),
+            unmapped_limit: 0,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Route `address` to `field`
+    pub fn map(&mut self, address: &str, field: &str) {
+        self.mapping.insert(address.to_string(), field.to_string());
+    }
+
+    /// Builder-style `map`
+    pub fn with_mapping(mut self, address: &str, field: &str) -> Self {
+        self.map(address, field);
+        self
+    }
+
+    /// Keep up to `limit` of the latest messages for unmapped addresses for
+    /// `take_unmapped`. Off (0) by default, such messages are dropped.
+    pub fn with_unmapped_limit(mut self, limit: usize) -> Self {
+        self.unmapped_limit = limit;
+        self
+    }
+
+    /// Add the `address field` lines of `text` (see the type docs), skipping lines that
+    /// aren't a pair with a warning
+    pub fn parse_mapping(&mut self, text: &str) {
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(address), Some(field), None) if address.starts_with('/') => {
+                    self.map(address, field);
+                }
+                _ => log::warn!("OSC mapping line {}: expected `/address field`", number + 1),
+            }
+        }
+    }
+
+    /// Apply the messages received since the last call. Returns true if a mapped value
+    /// changed. Messages for unmapped addresses are kept for `take_unmapped` if enabled
+    /// with `with_unmapped_limit`, dropping the oldest ones past the limit.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(message) = self.messages.try_recv() {
+            let Some(field) = self.mapping.get(&message.address) else {
+                if self.unmapped_limit > 0 {
+                    while self.unmapped.len() >= self.unmapped_limit {
+                        self.unmapped.pop_front();
+                    }
+                    self.unmapped.push_back(message);
+                }
+                continue;
+            };
+            let Some(value) = message.args.iter().find_map(OscArg::as_f32) else {
+                log::debug!("OSC {} has no numeric argument", message.address);
+                continue;
+            };
+            if self.values.insert(field.clone(), value) != Some(value) {
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Latest value of a mapped field, `None` until a message for it arrived
+    pub fn value(&self, field: &str) -> Option<f32> {
+        self.values.get(field).copied()
+    }
+
+    pub fn values(&self) -> &HashMap<String, f32> {
+        &self.values
+    }
+
+    /// Messages `poll` kept for addresses without a mapping, oldest first, for custom
+    /// handling. Always empty unless `with_unmapped_limit` was set.
+    pub fn take_unmapped(&mut self) -> Vec<OscMessage> {
+        self.unmapped.drain(..).collect()
+    }
+}
+
+impl Drop for OscReceiver {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn listen(socket: UdpSocket, sender: mpsc::Sender<OscMessage>, stop: Arc<AtomicBool>) {
+    let mut buffer = vec![0u8; MAX_PACKET];
+    let mut messages = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        let len = match socket.recv_from(&mut buffer) {
+            Ok((len, _)) => len,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue;
+            }
+            Err(e) => {
+                log::warn!("OSC receive failed: {e}");
+                continue;
+            }
+        };
+        messages.clear();
+        if parse_packet(&buffer[..len], 0, &mut messages).is_none() {
+            log::debug!("Dropping malformed OSC packet ({len} bytes)");
+            continue;
+        }
+        for message in messages.drain(..) {
+            // The receiver is gone, nobody left to deliver to
+            if sender.send(message).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Parse a message or bundle into `out`. `None` if anything in it is malformed.
+fn parse_packet(data: &[u8], depth: usize, out: &mut Vec<OscMessage>) -> Option<()> {
+    if data.starts_with(b"#bundle\0") {
+        if depth >= MAX_BUNDLE_DEPTH {
+            return None;
+        }
+        // Skip the 8 byte time tag, elements are applied immediately
+        let mut pos = 16;
+        while pos < data.len() {
+            let size = read_i32(data, &mut pos)?;
+            let size = usize::try_from(size).ok()?;
+            let element = data.get(pos..pos.checked_add(size)?)?;
+            parse_packet(element, depth + 1, out)?;
+            pos += size;
+        }
+        return Some(());
+    }
+
+    let mut pos = 0;
+    let address = read_string(data, &mut pos)?;
+    if !address.starts_with('/') {
+        return None;
+    }
+    // Very old senders omit the type tags, such a message has no arguments
+    let tags = if pos < data.len() {
+        read_string(data, &mut pos)?
+    } else {
+        ",".to_string()
+    };
+    let tags = tags.strip_prefix(',')?;
+
+    let mut args = Vec::with_capacity(tags.len());
+    for tag in tags.chars() {
+        let arg = match tag {
+            'i' => OscArg::Int(read_i32(data, &mut pos)?),
+            'f' => OscArg::Float(f32::from_bits(read_i32(data, &mut pos)? as u32)),
+            'h' => OscArg::Long(read_i64(data, &mut pos)?),
+            'd' => OscArg::Double(f64::from_bits(read_i64(data, &mut pos)? as u64)),
+            's' | 'S' => OscArg::String(read_string(data, &mut pos)?),
+            'b' => {
+                let size = usize::try_from(read_i32(data, &mut pos)?).ok()?;
+                let blob = data.get(pos..pos.checked_add(size)?)?.to_vec();
+                pos += size.next_multiple_of(4);
+                OscArg::Blob(blob)
+            }
+            'T' => OscArg::Bool(true),
+            'F' => OscArg::Bool(false),
+            _ => return None,
+        };
+        args.push(arg);
+    }
+    out.push(OscMessage { address, args });
+    Some(())
+}
+
+/// Null-terminated string padded to 4 bytes
+fn read_string(data: &[u8], pos: &mut usize) -> Option<String> {
+    let rest = data.get(*pos..)?;
+    let end = rest.iter().position(|&b| b == 0)?;
+    let text = std::str::from_utf8(&rest[..end]).ok()?.to_string();
+    *pos += (end + 1).next_multiple_of(4);
+    Some(text)
+}
+
+fn read_i32(data: &[u8], pos: &mut usize) -> Option<i32> {
+    let bytes = data.get(*pos..*pos + 4)?;
+    *pos += 4;
+    Some(i32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn read_i64(data: &[u8], pos: &mut usize) -> Option<i64> {
+    let bytes = data.get(*pos..*pos + 8)?;
+    *pos += 8;
+    Some(i64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn padded_string(text: &str) -> Vec<u8> {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.push(0);
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+        bytes
+    }
+
+    fn message(address: &str, tags: &str, args: &[u8]) -> Vec<u8> {
+        let mut bytes = padded_string(address);
+        bytes.extend(padded_string(tags));
+        bytes.extend_from_slice(args);
+        bytes
+    }
+
+    fn bundle(elements: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = b"#bundle\0".to_vec();
+        bytes.extend_from_slice(&1u64.to_be_bytes());
+        for element in elements {
+            bytes.extend_from_slice(&(element.len() as i32).to_be_bytes());
+            bytes.extend_from_slice(element);
+        }
+        bytes
+    }
+
+    fn parse(data: &[u8]) -> Option<Vec<OscMessage>> {
+        let mut out = Vec::new();
+        parse_packet(data, 0, &mut out).map(|()| out)
+    }
+
+    #[test]
+    fn parses_message_arguments() {
+        let mut args = 7i32.to_be_bytes().to_vec();
+        args.extend_from_slice(&0.5f32.to_be_bytes());
+        args.extend(padded_string("hello"));
+        args.extend_from_slice(&3i32.to_be_bytes());
+        args.extend_from_slice(&[1, 2, 3, 0]);
+        args.extend_from_slice(&2.5f64.to_be_bytes());
+        let data = message("/1/fader1", ",ifsbdTF", &args);
+
+        let messages = parse(&data).unwrap();
+        assert_eq!(
+            messages,
+            vec![OscMessage {
+                address: "/1/fader1".to_string(),
+                args: vec![
+                    OscArg::Int(7),
+                    OscArg::Float(0.5),
+                    OscArg::String("hello".to_string()),
+                    OscArg::Blob(vec![1, 2, 3]),
+                    OscArg::Double(2.5),
+                    OscArg::Bool(true),
+                    OscArg::Bool(false),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn message_without_type_tags_has_no_arguments() {
+        let messages = parse(&padded_string("/ping")).unwrap();
+        assert_eq!(messages[0].address, "/ping");
+        assert!(messages[0].args.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        assert!(parse(&padded_string("no/slash")).is_none());
+        assert!(parse(&message("/x", ",q", &[])).is_none());
+        // Missing argument data
+        assert!(parse(&message("/x", ",i", &[0, 0])).is_none());
+    }
+
+    #[test]
+    fn reads_padded_strings() {
+        let data = [b'a', b'b', b'c', 0, b'd', 0, 0, 0];
+        let mut pos = 0;
+        assert_eq!(read_string(&data, &mut pos).as_deref(), Some("abc"));
+        assert_eq!(pos, 4);
+        assert_eq!(read_string(&data, &mut pos).as_deref(), Some("d"));
+        assert_eq!(pos, 8);
+        assert_eq!(read_string(&data, &mut pos), None);
+    }
+
+    #[test]
+    fn rejects_truncated_strings_and_blobs() {
+        // No terminator
+        assert!(parse(b"/abc").is_none());
+        assert!(parse(&message("/x", ",s", b"abcd")).is_none());
+        // Blob size past the end of the packet
+        let mut args = 8i32.to_be_bytes().to_vec();
+        args.extend_from_slice(&[1, 2, 3, 4]);
+        assert!(parse(&message("/x", ",b", &args)).is_none());
+        assert!(parse(&message("/x", ",b", &(-1i32).to_be_bytes())).is_none());
+    }
+
+    #[test]
+    fn parses_nested_bundles_in_order() {
+        let first = message("/a", ",i", &1i32.to_be_bytes());
+        let second = message("/b", ",i", &2i32.to_be_bytes());
+        let data = bundle(&[first, bundle(&[second])]);
+
+        let messages = parse(&data).unwrap();
+        let addresses: Vec<_> = messages.iter().map(|m| m.address.as_str()).collect();
+        assert_eq!(addresses, ["/a", "/b"]);
+    }
+
+    #[test]
+    fn rejects_truncated_bundle_elements() {
+        let mut data = bundle(&[message("/a", ",", &[])]);
+        data.pop();
+        assert!(parse(&data).is_none());
+    }
+
+    #[test]
+    fn limits_bundle_depth() {
+        let nested =
+            |depth: usize| (0..depth).fold(message("/deep", ",", &[]), |inner, _| bundle(&[inner]));
+        assert_eq!(parse(&nested(MAX_BUNDLE_DEPTH)).map(|m| m.len()), Some(1));
+        assert!(parse(&nested(MAX_BUNDLE_DEPTH + 1)).is_none());
+    }
+}
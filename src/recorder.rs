@@ -0,0 +1,573 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use winit::dpi::PhysicalPosition;
+use winit::event::{
+    DeviceId, ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
+};
+use winit::keyboard::{Key, NamedKey, SmolStr};
+
+const HEADER: &str = "# cuneus input recording v1";
+
+/// One recorded input, without the platform specific bits winit attaches to events
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedInput {
+    CursorMoved { x: f64, y: f64 },
+    MouseInput { button: MouseButton, pressed: bool },
+    MouseWheel { delta: MouseScrollDelta },
+    Key { key: Key, pressed: bool },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    /// Number of frames rendered before the event arrived
+    pub frame: u64,
+    /// Seconds since recording started
+    pub time: f32,
+    pub input: RecordedInput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecorderMode {
+    Idle,
+    Recording,
+    Replaying,
+}
+
+/// Records mouse/keyboard `WindowEvent`s and frame times to a file and plays them back.
+///
+/// The file is plain text, one entry per line, tab separated:
+///
+/// ```text
+/// # cuneus input recording v1
+/// <frame> <time> frame                      time the frame was rendered at
+/// <frame> <time> cursor  <x> <y>            physical pixels
+/// <frame> <time> button  <button> <pressed|released>
+/// <frame> <time> wheel   <line|pixel> <dx> <dy>
+/// <frame> <time> key     <pressed|released> <char:<text>|named:<NamedKey>>
+/// ```
+///
+/// `button` is `left`, `right`, `middle`, `back`, `forward` or `other:<n>`.
+/// On replay, events tagged with frame `N` are delivered right before frame `N` is rendered,
+/// and frame `N` uses the recorded time, so playback doesn't depend on the machine speed.
+/// Lines starting with `#` are ignored.
+pub struct InputRecorder {
+    mode: RecorderMode,
+    path: Option<PathBuf>,
+    start: Instant,
+    frame: u64,
+    events: Vec<RecordedEvent>,
+    frame_times: Vec<f32>,
+    replay_cursor: usize,
+}
+
+impl Default for InputRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self {
+            mode: RecorderMode::Idle,
+            path: None,
+            start: Instant::now(),
+            frame: 0,
+            events: Vec::new(),
+            frame_times: Vec::new(),
+            replay_cursor: 0,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.mode == RecorderMode::Recording
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.mode == RecorderMode::Replaying
+    }
+
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Start recording, the file is written by `stop_recording`
+    pub fn start_recording(&mut self, path: impl Into<PathBuf>) {
+        self.mode = RecorderMode::Recording;
+        self.path = Some(path.into());
+        self.start = Instant::now();
+        self.frame = 0;
+        self.events.clear();
+        self.frame_times.clear();
+        log::info!("Input recording started");
+    }
+
+    /// Stop recording and write the file
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        if self.mode != RecorderMode::Recording {
+            return Ok(());
+        }
+        self.mode = RecorderMode::Idle;
+        if let Some(path) = &self.path {
+            self.save(path)?;
+            log::info!(
+                "Input recording saved to {} ({} events, {} frames)",
+                path.display(),
+                self.events.len(),
+                self.frame_times.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// Start or stop recording to `path`
+    pub fn toggle_recording(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        if self.is_recording() {
+            self.stop_recording()
+        } else {
+            self.start_recording(path);
+            Ok(())
+        }
+    }
+
+    /// Load a recording and switch to replay mode
+    pub fn start_replay(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.load(path.as_ref())?;
+        self.mode = RecorderMode::Replaying;
+        self.frame = 0;
+        self.replay_cursor = 0;
+        log::info!(
+            "Replaying {} input events over {} frames from {}",
+            self.events.len(),
+            self.frame_times.len(),
+            path.as_ref().display()
+        );
+        Ok(())
+    }
+
+    /// Log `event` if it's a mouse/keyboard event and we're recording
+    pub fn record_event(&mut self, event: &WindowEvent) {
+        if self.mode != RecorderMode::Recording {
+            return;
+        }
+        if let Some(input) = Self::input_from_event(event) {
+            self.events.push(RecordedEvent {
+                frame: self.frame,
+                time: self.start.elapsed().as_secs_f32(),
+                input,
+            });
+        }
+    }
+
+    /// Mark a rendered frame while recording
+    pub fn record_frame(&mut self) {
+        if self.mode != RecorderMode::Recording {
+            return;
+        }
+        self.frame_times.push(self.start.elapsed().as_secs_f32());
+        self.frame += 1;
+    }
+
+    /// Advance the replay by one frame. Returns the inputs to deliver before rendering
+    /// and the recorded frame time, or `None` once the recording is exhausted.
+    pub fn next_replay_frame(&mut self) -> Option<(Vec<RecordedInput>, f32)> {
+        if self.mode != RecorderMode::Replaying {
+            return None;
+        }
+        let Some(&time) = self.frame_times.get(self.frame as usize) else {
+            self.mode = RecorderMode::Idle;
+            log::info!("Input replay finished");
+            return None;
+        };
+
+        let mut inputs = Vec::new();
+        while let Some(event) = self.events.get(self.replay_cursor) {
+            if event.frame > self.frame {
+                break;
+            }
+            inputs.push(event.input.clone());
+            self.replay_cursor += 1;
+        }
+        self.frame += 1;
+        Some((inputs, time))
+    }
+
+    /// Whether `event` is one of the kinds the recorder captures
+    pub fn is_input_event(event: &WindowEvent) -> bool {
+        matches!(
+            event,
+            WindowEvent::CursorMoved { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::KeyboardInput { .. }
+        )
+    }
+
+    fn input_from_event(event: &WindowEvent) -> Option<RecordedInput> {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => Some(RecordedInput::CursorMoved {
+                x: position.x,
+                y: position.y,
+            }),
+            WindowEvent::MouseInput { state, button, .. } => Some(RecordedInput::MouseInput {
+                button: *button,
+                pressed: state.is_pressed(),
+            }),
+            WindowEvent::MouseWheel { delta, .. } => {
+                Some(RecordedInput::MouseWheel { delta: *delta })
+            }
+            WindowEvent::KeyboardInput { event, .. } if !event.repeat => Some(RecordedInput::Key {
+                key: event.logical_key.clone(),
+                pressed: event.state.is_pressed(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Rebuild a `WindowEvent` for mouse inputs. Keys return `None`: winit's `KeyEvent`
+    /// can't be constructed outside winit, they go through `ShaderManager::handle_replayed_key`.
+    pub fn to_window_event(input: &RecordedInput) -> Option<WindowEvent> {
+        let device_id = DeviceId::dummy();
+        match input {
+            RecordedInput::CursorMoved { x, y } => Some(WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(*x, *y),
+            }),
+            RecordedInput::MouseInput { button, pressed } => Some(WindowEvent::MouseInput {
+                device_id,
+                state: Self::element_state(*pressed),
+                button: *button,
+            }),
+            RecordedInput::MouseWheel { delta } => Some(WindowEvent::MouseWheel {
+                device_id,
+                delta: *delta,
+                phase: TouchPhase::Moved,
+            }),
+            RecordedInput::Key { .. } => None,
+        }
+    }
+
+    pub fn element_state(pressed: bool) -> ElementState {
+        if pressed {
+            ElementState::Pressed
+        } else {
+            ElementState::Released
+        }
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = io::BufWriter::new(File::create(path)?);
+        writeln!(file, "{HEADER}")?;
+
+        // Merge frames and events in order
+        let mut events = self.events.iter().peekable();
+        for (frame, time) in self.frame_times.iter().enumerate() {
+            while let Some(event) = events.next_if(|e| e.frame <= frame as u64) {
+                writeln!(file, "{}", Self::format_event(event))?;
+            }
+            writeln!(file, "{frame}\t{time}\tframe")?;
+        }
+        for event in events {
+            writeln!(file, "{}", Self::format_event(event))?;
+        }
+        file.flush()
+    }
+
+    fn load(&mut self, path: &Path) -> io::Result<()> {
+        let reader = BufReader::new(File::open(path)?);
+        self.events.clear();
+        self.frame_times.clear();
+
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 3 {
+                log::warn!("Skipping malformed recording line {}", line_number + 1);
+                continue;
+            }
+            let (Ok(frame), Ok(time)) = (fields[0].parse::<u64>(), fields[1].parse::<f32>()) else {
+                log::warn!("Skipping malformed recording line {}", line_number + 1);
+                continue;
+            };
+
+            if fields[2] == "frame" {
+                self.frame_times.push(time);
+                continue;
+            }
+            match Self::parse_input(&fields[2..]) {
+                Some(input) => self.events.push(RecordedEvent { frame, time, input }),
+                None => log::warn!(
+                    "Skipping unsupported recording line {}: {line}",
+                    line_number + 1
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    fn format_event(event: &RecordedEvent) -> String {
+        let body = match &event.input {
+            RecordedInput::CursorMoved { x, y } => format!("cursor\t{x}\t{y}"),
+            RecordedInput::MouseInput { button, pressed } => format!(
+                "button\t{}\t{}",
+                Self::format_button(*button),
+                Self::format_pressed(*pressed)
+            ),
+            RecordedInput::MouseWheel { delta } => match delta {
+                MouseScrollDelta::LineDelta(x, y) => format!("wheel\tline\t{x}\t{y}"),
+                MouseScrollDelta::PixelDelta(p) => format!("wheel\tpixel\t{}\t{}", p.x, p.y),
+            },
+            RecordedInput::Key { key, pressed } => {
+                let key = match key {
+                    Key::Character(text) => format!("char:{text}"),
+                    Key::Named(named) => format!("named:{named:?}"),
+                    other => format!("unknown:{other:?}"),
+                };
+                format!("key\t{}\t{key}", Self::format_pressed(*pressed))
+            }
+        };
+        format!("{}\t{}\t{body}", event.frame, event.time)
+    }
+
+    fn parse_input(fields: &[&str]) -> Option<RecordedInput> {
+        match fields {
+            ["cursor", x, y] => Some(RecordedInput::CursorMoved {
+                x: x.parse().ok()?,
+                y: y.parse().ok()?,
+            }),
+            ["button", button, state] => Some(RecordedInput::MouseInput {
+                button: Self::parse_button(button)?,
+                pressed: Self::parse_pressed(state)?,
+            }),
+            ["wheel", "line", x, y] => Some(RecordedInput::MouseWheel {
+                delta: MouseScrollDelta::LineDelta(x.parse().ok()?, y.parse().ok()?),
+            }),
+            ["wheel", "pixel", x, y] => Some(RecordedInput::MouseWheel {
+                delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(
+                    x.parse().ok()?,
+                    y.parse().ok()?,
+                )),
+            }),
+            ["key", state, key] => Some(RecordedInput::Key {
+                key: Self::parse_key(key)?,
+                pressed: Self::parse_pressed(state)?,
+            }),
+            _ => None,
+        }
+    }
+
+    fn format_pressed(pressed: bool) -> &'static str {
+        if pressed {
+            "pressed"
+        } else {
+            "released"
+        }
+    }
+
+    fn parse_pressed(state: &str) -> Option<bool> {
+        match state {
+            "pressed" => Some(true),
+            "released" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn format_button(button: MouseButton) -> String {
+        match button {
+            MouseButton::Left => "left".to_string(),
+            MouseButton::Right => "right".to_string(),
+            MouseButton::Middle => "middle".to_string(),
+            MouseButton::Back => "back".to_string(),
+            MouseButton::Forward => "forward".to_string(),
+            MouseButton::Other(n) => format!("other:{n}"),
+        }
+    }
+
+    fn parse_button(button: &str) -> Option<MouseButton> {
+        match button {
+            "left" => Some(MouseButton::Left),
+            "right" => Some(MouseButton::Right),
+            "middle" => Some(MouseButton::Middle),
+            "back" => Some(MouseButton::Back),
+            "forward" => Some(MouseButton::Forward),
+            other => other
+                .strip_prefix("other:")
+                .and_then(|n| n.parse().ok())
+                .map(MouseButton::Other),
+        }
+    }
+
+    /// Characters round-trip exactly, named keys by their `NamedKey` variant name
+    fn parse_key(key: &str) -> Option<Key> {
+        if let Some(text) = key.strip_prefix("char:") {
+            return Some(Key::Character(SmolStr::new(text)));
+        }
+        parse_named_key(key.strip_prefix("named:")?).map(Key::Named)
+    }
+}
+
+/// `NamedKey` from the variant name its `Debug` output writes to recordings
+macro_rules! named_keys {
+    ($($name:ident)*) => {
+        fn parse_named_key(name: &str) -> Option<NamedKey> {
+            match name {
+                $(stringify!($name) => Some(NamedKey::$name),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+named_keys! {
+    Alt AltGraph CapsLock Control Fn FnLock NumLock ScrollLock Shift Symbol SymbolLock Meta
+    Hyper Super Enter Tab Space ArrowDown ArrowLeft ArrowRight ArrowUp End Home PageDown PageUp
+    Backspace Clear Copy CrSel Cut Delete EraseEof ExSel Insert Paste Redo Undo Accept Again
+    Attn Cancel ContextMenu Escape Execute Find Help Pause Play Props Select ZoomIn ZoomOut
+    BrightnessDown BrightnessUp Eject LogOff Power PowerOff PrintScreen Hibernate Standby WakeUp
+    AllCandidates Alphanumeric CodeInput Compose Convert FinalMode GroupFirst GroupLast
+    GroupNext GroupPrevious ModeChange NextCandidate NonConvert PreviousCandidate Process
+    SingleCandidate HangulMode HanjaMode JunjaMode Eisu Hankaku Hiragana HiraganaKatakana
+    KanaMode KanjiMode Katakana Romaji Zenkaku ZenkakuHankaku Soft1 Soft2 Soft3 Soft4
+    ChannelDown ChannelUp Close MailForward MailReply MailSend MediaClose MediaFastForward
+    MediaPause MediaPlay MediaPlayPause MediaRecord MediaRewind MediaStop MediaTrackNext
+    MediaTrackPrevious New Open Print Save SpellCheck Key11 Key12 AudioBalanceLeft
+    AudioBalanceRight AudioBassBoostDown AudioBassBoostToggle AudioBassBoostUp AudioFaderFront
+    AudioFaderRear AudioSurroundModeNext AudioTrebleDown AudioTrebleUp AudioVolumeDown
+    AudioVolumeUp AudioVolumeMute MicrophoneToggle MicrophoneVolumeDown MicrophoneVolumeUp
+    MicrophoneVolumeMute SpeechCorrectionList SpeechInputToggle LaunchApplication1
+    LaunchApplication2 LaunchCalendar LaunchContacts LaunchMail LaunchMediaPlayer
+    LaunchMusicPlayer LaunchPhone LaunchScreenSaver LaunchSpreadsheet LaunchWebBrowser
+    LaunchWebCam LaunchWordProcessor BrowserBack BrowserFavorites BrowserForward BrowserHome
+    BrowserRefresh BrowserSearch BrowserStop AppSwitch Call Camera CameraFocus EndCall GoBack
+    GoHome HeadsetHook LastNumberRedial Notification MannerMode VoiceDial TV TV3DMode
+    TVAntennaCable TVAudioDescription TVAudioDescriptionMixDown TVAudioDescriptionMixUp
+    TVContentsMenu TVDataService TVInput TVInputComponent1 TVInputComponent2 TVInputComposite1
+    TVInputComposite2 TVInputHDMI1 TVInputHDMI2 TVInputHDMI3 TVInputHDMI4 TVInputVGA1
+    TVMediaContext TVNetwork TVNumberEntry TVPower TVRadioService TVSatellite TVSatelliteBS
+    TVSatelliteCS TVSatelliteToggle TVTerrestrialAnalog TVTerrestrialDigital TVTimer AVRInput
+    AVRPower ColorF0Red ColorF1Green ColorF2Yellow ColorF3Blue ColorF4Grey ColorF5Brown
+    ClosedCaptionToggle Dimmer DisplaySwap DVR Exit FavoriteClear0 FavoriteClear1 FavoriteClear2
+    FavoriteClear3 FavoriteRecall0 FavoriteRecall1 FavoriteRecall2 FavoriteRecall3
+    FavoriteStore0 FavoriteStore1 FavoriteStore2 FavoriteStore3 Guide GuideNextDay
+    GuidePreviousDay Info InstantReplay Link ListProgram LiveContent Lock MediaApps
+    MediaAudioTrack MediaLast MediaSkipBackward MediaSkipForward MediaStepBackward
+    MediaStepForward MediaTopMenu NavigateIn NavigateNext NavigateOut NavigatePrevious
+    NextFavoriteChannel NextUserProfile OnDemand Pairing PinPDown PinPMove PinPToggle PinPUp
+    PlaySpeedDown PlaySpeedReset PlaySpeedUp RandomToggle RcLowBattery RecordSpeedNext RfBypass
+    ScanChannelsToggle ScreenModeNext Settings SplitScreenToggle STBInput STBPower Subtitle
+    Teletext VideoModeNext Wink ZoomToggle F1 F2 F3 F4 F5 F6 F7 F8 F9 F10 F11 F12 F13 F14 F15
+    F16 F17 F18 F19 F20 F21 F22 F23 F24 F25 F26 F27 F28 F29 F30 F31 F32 F33 F34 F35
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(frame: u64, time: f32, input: RecordedInput) -> RecordedEvent {
+        RecordedEvent { frame, time, input }
+    }
+
+    #[test]
+    fn recording_round_trips_through_the_file() {
+        let mut recorder = InputRecorder::new();
+        recorder.frame_times = vec![0.0, 0.016, 0.033];
+        recorder.events = vec![
+            event(0, 0.001, RecordedInput::CursorMoved { x: 10.5, y: 20.0 }),
+            event(
+                0,
+                0.002,
+                RecordedInput::MouseInput {
+                    button: MouseButton::Other(7),
+                    pressed: true,
+                },
+            ),
+            event(
+                1,
+                0.02,
+                RecordedInput::MouseWheel {
+                    delta: MouseScrollDelta::LineDelta(0.0, -1.0),
+                },
+            ),
+            event(
+                1,
+                0.021,
+                RecordedInput::MouseWheel {
+                    delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(3.0, 4.5)),
+                },
+            ),
+            event(
+                2,
+                0.03,
+                RecordedInput::Key {
+                    key: Key::Character(SmolStr::new("h")),
+                    pressed: true,
+                },
+            ),
+            event(
+                2,
+                0.031,
+                RecordedInput::Key {
+                    key: Key::Named(NamedKey::F5),
+                    pressed: false,
+                },
+            ),
+            event(
+                2,
+                0.032,
+                RecordedInput::Key {
+                    key: Key::Named(NamedKey::MediaPlayPause),
+                    pressed: true,
+                },
+            ),
+        ];
+
+        let path =
+            std::env::temp_dir().join(format!("cuneus_recording_test_{}.txt", std::process::id()));
+        recorder.save(&path).unwrap();
+        let mut replay = InputRecorder::new();
+        let loaded = replay.start_replay(&path);
+        std::fs::remove_file(&path).unwrap();
+        loaded.unwrap();
+
+        assert_eq!(replay.events(), recorder.events());
+        assert_eq!(replay.frame_times, recorder.frame_times);
+
+        let (inputs, time) = replay.next_replay_frame().unwrap();
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(time, 0.0);
+        assert_eq!(replay.next_replay_frame().unwrap().0.len(), 2);
+        assert_eq!(replay.next_replay_frame().unwrap().1, 0.033);
+        assert!(replay.next_replay_frame().is_none());
+        assert!(!replay.is_replaying());
+    }
+
+    #[test]
+    fn named_keys_parse_from_their_debug_name() {
+        for named in [
+            NamedKey::Space,
+            NamedKey::F12,
+            NamedKey::PageDown,
+            NamedKey::Super,
+        ] {
+            let key = Key::Named(named);
+            let line = InputRecorder::format_event(&event(
+                0,
+                0.0,
+                RecordedInput::Key {
+                    key: key.clone(),
+                    pressed: true,
+                },
+            ));
+            let fields: Vec<&str> = line.split('\t').collect();
+            assert_eq!(
+                InputRecorder::parse_input(&fields[2..]),
+                Some(RecordedInput::Key { key, pressed: true })
+            );
+        }
+    }
+}
@@ -29,11 +29,45 @@ impl<'a> RenderPassWrapper<'a> {
         self.render_pass
     }
 }
+/// Pipeline settings for `Renderer::new_with_blend`
+pub struct RendererDescriptor<'a> {
+    pub vs_module: &'a wgpu::ShaderModule,
+    pub fs_module: &'a wgpu::ShaderModule,
+    pub format: wgpu::TextureFormat,
+    pub layout: &'a wgpu::PipelineLayout,
+    /// Fragment entry point, `fs_main` when `None`
+    pub fragment_entry: Option<&'a str>,
+    pub blend: Option<wgpu::BlendState>,
+    /// See `Core::pipeline_cache`
+    pub cache: Option<&'a wgpu::PipelineCache>,
+}
 pub struct Renderer {
     pub render_pipeline: wgpu::RenderPipeline,
     pub vertex_buffer: wgpu::Buffer,
 }
 impl Renderer {
+    /// Opaque blend state used by default. REPLACE also keeps straight (non-premultiplied)
+    /// alpha intact, which the alpha-preserving export relies on.
+    pub const DEFAULT_BLEND: wgpu::BlendState = wgpu::BlendState {
+        color: wgpu::BlendComponent::REPLACE,
+        alpha: wgpu::BlendComponent::REPLACE,
+    };
+
+    /// Adds the source color weighted by its alpha, for glow and light layers
+    pub const ADDITIVE_BLEND: wgpu::BlendState = wgpu::BlendState {
+        color: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::SrcAlpha,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        },
+    };
+
+    /// Pipeline with `DEFAULT_BLEND`, pass `Core::pipeline_cache` as `cache`
     pub fn new(
         device: &wgpu::Device,
         vs_module: &wgpu::ShaderModule,
@@ -41,7 +75,33 @@ impl Renderer {
         format: wgpu::TextureFormat,
         layout: &wgpu::PipelineLayout,
         fragment_entry: Option<&str>,
+        cache: Option<&wgpu::PipelineCache>,
     ) -> Self {
+        Self::new_with_blend(
+            device,
+            &RendererDescriptor {
+                vs_module,
+                fs_module,
+                format,
+                layout,
+                fragment_entry,
+                blend: Some(Self::DEFAULT_BLEND),
+                cache,
+            },
+        )
+    }
+
+    /// Same as `new` but with an explicit blend state for the color target
+    pub fn new_with_blend(device: &wgpu::Device, desc: &RendererDescriptor) -> Self {
+        let RendererDescriptor {
+            vs_module,
+            fs_module,
+            format,
+            layout,
+            fragment_entry,
+            blend,
+            cache,
+        } = *desc;
         const VERTICES: &[Vertex] = &[
             Vertex {
                 position: [-1.0, -1.0],
@@ -64,10 +124,7 @@ impl Renderer {
         });
         let color_target_state = [Some(wgpu::ColorTargetState {
             format,
-            blend: Some(wgpu::BlendState {
-                color: wgpu::BlendComponent::REPLACE,
-                alpha: wgpu::BlendComponent::REPLACE,
-            }),
+            blend,
             write_mask: wgpu::ColorWrites::ALL,
         })];
         println!("Creating render pipeline");
@@ -102,7 +159,7 @@ impl Renderer {
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-            cache: None,
+            cache,
         };
 
         let render_pipeline = device.create_render_pipeline(&pipeline_desc);
@@ -1,4 +1,6 @@
-use crate::compute::ComputeShader;
+use crate::animated::AnimatedTexture;
+use crate::compute::{ComputeShader, DepthResolve};
+use crate::egui_target::LinearUiTarget;
 #[cfg(feature = "media")]
 use crate::gst::video::VideoTextureManager;
 #[cfg(feature = "media")]
@@ -9,14 +11,15 @@ use crate::mouse::MouseUniform;
 use crate::spectrum::SpectrumAnalyzer;
 use crate::HdriMetadata;
 use crate::{
-    fps, ControlsRequest, Core, ExportManager, KeyInputHandler, Renderer, ResolutionUniform,
-    ShaderControls, TextureManager, UniformBinding, UniformProvider,
+    fps, ColorGrading, ControlsRequest, Core, ExportManager, ExpressionVariables, HotReloadError,
+    KeyInputHandler, PassGraph, Renderer, RendererDescriptor, ResolutionUniform, ShaderControls,
+    TextureManager, UniformBinding, UniformProvider,
 };
 use egui::ViewportId;
 use egui_wgpu::ScreenDescriptor;
+use log::{error, info, warn};
 #[cfg(feature = "media")]
-use log::warn;
-use log::{error, info};
+use std::collections::HashMap;
 use std::path::Path;
 use std::time::Instant;
 use winit::event::WindowEvent;
@@ -24,6 +27,9 @@ use winit::event::WindowEvent;
 pub const CAPTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
 #[cfg(not(target_os = "macos"))]
 pub const CAPTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+/// An extra egui panel drawn by `RenderKit::render_ui`
+type UiPanel = Box<dyn FnMut(&egui::Context)>;
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct TimeUniform {
@@ -37,16 +43,34 @@ impl UniformProvider for TimeUniform {
 }
 pub struct RenderKit {
     pub renderer: Renderer,
+    /// Blit with `fs_graded`, used by `draw_output` while grading isn't neutral
+    graded_renderer: Renderer,
+    color_grading: ColorGrading,
+    grading_uniform: UniformBinding<ColorGrading>,
+    /// Uploads grading edits from `apply_control_request`, which has no `Core`
+    queue: wgpu::Queue,
     #[cfg(feature = "media")]
     pub video_texture_manager: Option<VideoTextureManager>,
     #[cfg(feature = "media")]
     pub using_video_texture: bool,
+    /// Videos bound to compute channels, see `load_video_to_channel`
+    #[cfg(feature = "media")]
+    pub channel_videos: HashMap<u32, VideoTextureManager>,
+    /// Texture each channel was last bound with, to rebind only when a video recreates it
+    #[cfg(feature = "media")]
+    channel_video_bindings: HashMap<u32, wgpu::Texture>,
     #[cfg(feature = "media")]
     pub webcam_texture_manager: Option<WebcamTextureManager>,
     #[cfg(feature = "media")]
     pub using_webcam_texture: bool,
     pub texture_manager: Option<TextureManager>,
+    pub animated_texture: Option<AnimatedTexture>,
+    pub using_animated_texture: bool,
+    /// Created by the first `resolve_compute_depth`
+    pub depth_resolve: Option<DepthResolve>,
     pub egui_renderer: egui_wgpu::Renderer,
+    /// Set on linear (float) surfaces, see `LinearUiTarget`
+    linear_ui_target: Option<LinearUiTarget>,
     pub egui_state: egui_winit::State,
     pub context: egui::Context,
     pub texture_bind_group_layout: wgpu::BindGroupLayout,
@@ -54,17 +78,27 @@ pub struct RenderKit {
     pub time_uniform: UniformBinding<TimeUniform>,
     pub resolution_uniform: UniformBinding<ResolutionUniform>,
     pub key_handler: KeyInputHandler,
+    ui_enabled: bool,
+    /// Extra panels drawn by `render_ui`, see `add_ui_panel`
+    ui_panels: Vec<UiPanel>,
+    /// egui asked for another frame right away (animations, hover effects)
+    ui_repaint_requested: bool,
     pub export_manager: ExportManager,
     pub controls: ShaderControls,
     pub spectrum_analyzer: SpectrumAnalyzer,
     pub compute_shader: Option<ComputeShader>,
     pub fps_tracker: fps::FpsTracker,
     pub mouse_tracker: MouseTracker,
+    right_click_capture: bool,
     pub mouse_uniform: Option<UniformBinding<MouseUniform>>,
     pub mouse_bind_group_layout: Option<wgpu::BindGroupLayout>,
     pub using_hdri_texture: bool,
     pub hdri_metadata: Option<HdriMetadata>,
     pub hdri_file_data: Option<Vec<u8>>,
+    /// Keep 16-bit and grayscale images in their own format when loading (see `TextureManager::from_image`)
+    pub preserve_image_format: bool,
+    #[cfg(feature = "media")]
+    paused_by_occlusion: bool,
 }
 
 impl RenderKit {
@@ -103,6 +137,20 @@ impl RenderKit {
     }
 
     pub fn new(core: &Core, layout: &wgpu::BindGroupLayout, fragment_entry: Option<&str>) -> Self {
+        Self::new_with_blend(core, layout, fragment_entry, Some(Renderer::DEFAULT_BLEND))
+    }
+
+    /// Same as `new` but with the blend state of the display pass, e.g.
+    /// `wgpu::BlendState::ALPHA_BLENDING` to layer a transparent compute output over what
+    /// is already in the target. The export capture draws with the same pipeline over a
+    /// cleared target, so with alpha blending and `preserve_alpha` the exported color is
+    /// premultiplied by alpha; keep the opaque default for straight-alpha exports.
+    pub fn new_with_blend(
+        core: &Core,
+        layout: &wgpu::BindGroupLayout,
+        fragment_entry: Option<&str>,
+        blend: Option<wgpu::BlendState>,
+    ) -> Self {
         let bind_group_layouts = &[layout];
         let time_bind_group_layout =
             core.device
@@ -199,13 +247,58 @@ impl RenderKit {
                 bind_group_layouts,
                 push_constant_ranges: &[],
             });
-        let renderer = Renderer::new(
+        let renderer = Renderer::new_with_blend(
+            &core.device,
+            &RendererDescriptor {
+                vs_module: &vs_shader,
+                fs_module: &fs_shader,
+                format: core.config.format,
+                layout: &pipeline_layout,
+                fragment_entry,
+                blend,
+                cache: core.pipeline_cache(),
+            },
+        );
+        let grading_bind_group_layout =
+            core.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                    label: Some("grading_bind_group_layout"),
+                });
+        let grading_uniform = UniformBinding::new(
+            &core.device,
+            "Color Grading Uniform",
+            ColorGrading::NEUTRAL,
+            &grading_bind_group_layout,
+            0,
+        );
+        let graded_pipeline_layout =
+            core.device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Graded Render Pipeline Layout"),
+                    bind_group_layouts: &[layout, &grading_bind_group_layout],
+                    push_constant_ranges: &[],
+                });
+        let graded_renderer = Renderer::new_with_blend(
             &core.device,
-            &vs_shader,
-            &fs_shader,
-            core.config.format,
-            &pipeline_layout,
-            fragment_entry,
+            &RendererDescriptor {
+                vs_module: &vs_shader,
+                fs_module: &fs_shader,
+                format: core.config.format,
+                layout: &graded_pipeline_layout,
+                fragment_entry: Some("fs_graded"),
+                blend,
+                cache: core.pipeline_cache(),
+            },
         );
         let context = egui::Context::default();
         let egui_state = egui_winit::State::new(
@@ -216,11 +309,14 @@ impl RenderKit {
             None,
             None,
         );
+        // egui only converts to linear for sRGB targets, float surfaces go through one
         let egui_renderer = egui_wgpu::Renderer::new(
             &core.device,
-            core.config.format,
+            LinearUiTarget::egui_format(core.config.format),
             egui_wgpu::RendererOptions::default(),
         );
+        let linear_ui_target = LinearUiTarget::is_linear_surface(core.config.format)
+            .then(|| LinearUiTarget::new(core, &vs_shader, &fs_shader));
 
         //  default texture manager
         let texture_manager =
@@ -230,16 +326,28 @@ impl RenderKit {
 
         Self {
             renderer,
+            graded_renderer,
+            color_grading: ColorGrading::NEUTRAL,
+            grading_uniform,
+            queue: core.queue.clone(),
             #[cfg(feature = "media")]
             video_texture_manager: None,
             #[cfg(feature = "media")]
             using_video_texture: false,
             #[cfg(feature = "media")]
+            channel_videos: HashMap::new(),
+            #[cfg(feature = "media")]
+            channel_video_bindings: HashMap::new(),
+            #[cfg(feature = "media")]
             webcam_texture_manager: None,
             #[cfg(feature = "media")]
             using_webcam_texture: false,
             texture_manager: Some(texture_manager),
+            animated_texture: None,
+            using_animated_texture: false,
+            depth_resolve: None,
             egui_renderer,
+            linear_ui_target,
             egui_state,
             context,
             texture_bind_group_layout,
@@ -247,17 +355,24 @@ impl RenderKit {
             time_uniform,
             resolution_uniform,
             key_handler: KeyInputHandler::new(),
+            ui_enabled: true,
+            ui_panels: Vec::new(),
+            ui_repaint_requested: false,
             export_manager: ExportManager::new(),
             controls: ShaderControls::new(),
             spectrum_analyzer: SpectrumAnalyzer::new(),
             compute_shader: None,
             fps_tracker,
             mouse_tracker,
+            right_click_capture: false,
             mouse_uniform: None,
             mouse_bind_group_layout: None,
             using_hdri_texture: false,
             hdri_metadata: None,
             hdri_file_data: None,
+            preserve_image_format: false,
+            #[cfg(feature = "media")]
+            paused_by_occlusion: false,
         }
     }
 
@@ -317,17 +432,153 @@ impl RenderKit {
             view: default_view,
             sampler,
             bind_group,
+            view_formats: Vec::new(),
         }
     }
 
+    /// Turn egui off completely for clean presentation: `render_ui` skips the UI closure and
+    /// `handle_render_output` skips tessellation and the egui pass. Unlike hiding the panels
+    /// with `key_handler.show_ui`, nothing egui-related runs per frame.
+    pub fn set_ui_enabled(&mut self, enabled: bool) {
+        self.ui_enabled = enabled;
+    }
+
+    pub fn ui_enabled(&self) -> bool {
+        self.ui_enabled
+    }
+
+    /// Register a panel drawn on every `render_ui` call, for embedders adding their own
+    /// egui UI next to the shader's. Registered panels run in order before `ui_builder`,
+    /// so their `SidePanel`s claim space before a `CentralPanel` there.
+    pub fn add_ui_panel(&mut self, panel: impl FnMut(&egui::Context) + 'static) {
+        self.ui_panels.push(Box::new(panel));
+    }
+
+    pub fn clear_ui_panels(&mut self) {
+        self.ui_panels.clear();
+    }
+
     pub fn render_ui<F>(&mut self, core: &Core, mut ui_builder: F) -> egui::FullOutput
     where
         F: FnMut(&egui::Context),
     {
+        // Drain the input anyway so events don't pile up while the UI is off
         let raw_input = self.egui_state.take_egui_input(core.window());
-        self.context.run(raw_input, |ctx| ui_builder(ctx))
+        if !self.ui_enabled {
+            return egui::FullOutput::default();
+        }
+        let panels = &mut self.ui_panels;
+        let output = self.context.run(raw_input, |ctx| {
+            for panel in panels.iter_mut() {
+                panel(ctx);
+            }
+            ui_builder(ctx);
+        });
+        self.ui_repaint_requested = output
+            .viewport_output
+            .get(&ViewportId::ROOT)
+            .is_some_and(|viewport| viewport.repaint_delay.is_zero());
+        output
+    }
+
+    /// Standard variables for `ExpressionBinding`s this frame:
+    ///
+    /// - `time`: shader time in seconds (paused and scaled like the controls)
+    /// - `frame`: frame counter of the controls
+    /// - `bass`, `mid`, `high`: average spectrum level 0..1 of the low fifth, the middle
+    ///   and the top two fifths of the buckets, 0 without audio
+    /// - `bpm`: detected tempo, 0 until known
+    /// - `mouse.x`, `mouse.y`: pointer position 0..1, `mouse.down`: 1 while the left button
+    ///   is held
+    /// - `pi`, `tau`
+    pub fn expression_variables(&self) -> ExpressionVariables {
+        let mut vars = ExpressionVariables::new();
+        vars.set("time", self.controls.get_time(&self.start_time));
+        vars.set("frame", self.controls.current_frame() as f32);
+
+        let levels: Vec<f32> = self
+            .resolution_uniform
+            .data
+            .audio_data
+            .iter()
+            .flatten()
+            .copied()
+            .take(crate::spectrum::OUTPUT_BUCKETS)
+            .collect();
+        let band = |from: f32, to: f32| {
+            let (start, end) = (
+                (levels.len() as f32 * from) as usize,
+                (levels.len() as f32 * to) as usize,
+            );
+            let slice = &levels[start..end];
+            if slice.is_empty() {
+                0.0
+            } else {
+                slice.iter().sum::<f32>() / slice.len() as f32
+            }
+        };
+        vars.set("bass", band(0.0, 0.2));
+        vars.set("mid", band(0.2, 0.6));
+        vars.set("high", band(0.6, 1.0));
+        vars.set("bpm", self.resolution_uniform.data.bpm);
+
+        let mouse = &self.mouse_tracker.uniform;
+        vars.set("mouse.x", mouse.pos_norm[0]);
+        vars.set("mouse.y", mouse.pos_norm[1]);
+        vars.set("mouse.down", (mouse.buttons[0] & 1) as f32);
+        vars
+    }
+
+    /// Whether frames are still changing, for `ShaderManager::needs_redraw` in
+    /// `RedrawMode::Reactive`: shader time is running (not paused), an export is in
+    /// progress, or egui wants to animate. A shader that ignores time can return false
+    /// while playing instead.
+    pub fn needs_redraw(&self) -> bool {
+        !self.controls.is_paused()
+            || self.export_manager.is_exporting()
+            || (self.ui_enabled && self.ui_repaint_requested)
     }
 
+    /// Grade everything `draw_output` draws, in the live view and in exports. Shaders
+    /// presenting with `dispatch_to_surface` skip the blit and aren't graded.
+    pub fn set_color_grading(&mut self, queue: &wgpu::Queue, grading: ColorGrading) {
+        if grading != self.color_grading {
+            self.color_grading = grading;
+            self.controls.sync_color_grading(grading);
+            self.grading_uniform.data = grading;
+            self.grading_uniform.update(queue);
+        }
+    }
+
+    pub fn color_grading(&self) -> ColorGrading {
+        self.color_grading
+    }
+
+    /// Blit `bind_group` (a texture + sampler in the standard layout, e.g. a compute
+    /// output's) into `render_pass` with the current color grading. Neutral grading uses
+    /// the plain `renderer` pipeline.
+    pub fn draw_output(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        bind_group: &wgpu::BindGroup,
+    ) {
+        if self.color_grading.is_neutral() {
+            render_pass.set_pipeline(&self.renderer.render_pipeline);
+        } else {
+            render_pass.set_pipeline(&self.graded_renderer.render_pipeline);
+            render_pass.set_bind_group(1, &self.grading_uniform.bind_group, &[]);
+        }
+        render_pass.set_vertex_buffer(0, self.renderer.vertex_buffer.slice(..));
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+    }
+
+    /// Draw the UI onto `view`, which must be the full-size surface view.
+    ///
+    /// Pass order per frame: compute dispatches into the shader's output texture, the output
+    /// is blitted (and scaled, if it's smaller than the window) onto the surface, then egui is
+    /// drawn last at the surface's native resolution. The UI is never drawn into a shader
+    /// texture, so it stays crisp whatever size the shader renders at.
     pub fn handle_render_output(
         &mut self,
         core: &Core,
@@ -335,6 +586,9 @@ impl RenderKit {
         full_output: egui::FullOutput,
         encoder: &mut wgpu::CommandEncoder,
     ) {
+        if !self.ui_enabled {
+            return;
+        }
         let screen_descriptor = ScreenDescriptor {
             size_in_pixels: [core.config.width, core.config.height],
             pixels_per_point: core.window().scale_factor() as f32,
@@ -358,16 +612,26 @@ impl RenderKit {
         );
 
         {
+            let (target, load_op) = match &mut self.linear_ui_target {
+                Some(ui_target) => (
+                    ui_target.view(core),
+                    wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                ),
+                None => (view, wgpu::LoadOp::Load),
+            };
             let render_pass = crate::Renderer::begin_render_pass(
                 encoder,
-                view,
-                wgpu::LoadOp::Load,
+                target,
+                load_op,
                 Some("Egui Render Pass"),
             );
             let mut render_pass = render_pass.into_inner().forget_lifetime();
             self.egui_renderer
                 .render(&mut render_pass, &clipped_primitives, &screen_descriptor);
         }
+        if let Some(ui_target) = &self.linear_ui_target {
+            ui_target.composite(encoder, view);
+        }
         // Cleanup egui textures
         for id in &full_output.textures_delta.free {
             self.egui_renderer.free_texture(id);
@@ -386,15 +650,39 @@ impl RenderKit {
                 if ["png", "jpg", "jpeg", "bmp", "gif", "tiff", "webp"].contains(&ext.as_str()) =>
             {
                 info!("Loading image: {path_ref:?}");
+                if ext == "gif" || ext == "png" {
+                    match AnimatedTexture::load(
+                        &core.device,
+                        &core.queue,
+                        &self.texture_bind_group_layout,
+                        path_ref,
+                    ) {
+                        Ok(Some(animation)) => {
+                            self.animated_texture = Some(animation);
+                            self.using_animated_texture = true;
+                            #[cfg(feature = "media")]
+                            {
+                                self.using_video_texture = false;
+                                self.video_texture_manager = None;
+                                self.using_webcam_texture = false;
+                                self.webcam_texture_manager = None;
+                            }
+                            return Ok(());
+                        }
+                        Ok(None) => {}
+                        Err(e) => warn!("Failed to decode animation, loading first frame: {e}"),
+                    }
+                }
                 if let Ok(img) = image::open(path_ref) {
-                    let rgba_image = img.into_rgba8();
-                    let new_texture_manager = TextureManager::new(
+                    let new_texture_manager = TextureManager::from_image(
                         &core.device,
                         &core.queue,
-                        &rgba_image,
+                        &img,
                         &self.texture_bind_group_layout,
+                        !self.preserve_image_format,
                     );
                     self.texture_manager = Some(new_texture_manager);
+                    self.stop_animation();
                     #[cfg(feature = "media")]
                     {
                         self.using_video_texture = false;
@@ -421,6 +709,7 @@ impl RenderKit {
                 ) {
                     Ok((texture_manager, metadata)) => {
                         self.texture_manager = Some(texture_manager);
+                        self.stop_animation();
                         #[cfg(feature = "media")]
                         {
                             self.using_video_texture = false;
@@ -448,6 +737,7 @@ impl RenderKit {
                     path_ref,
                 ) {
                     Ok(video_manager) => {
+                        self.stop_animation();
                         self.video_texture_manager = Some(video_manager);
                         self.using_video_texture = true;
                         self.using_webcam_texture = false;
@@ -468,6 +758,73 @@ impl RenderKit {
             _ => Err(anyhow::anyhow!("Unsupported media format: {:?}", path_ref)),
         }
     }
+    fn stop_animation(&mut self) {
+        self.animated_texture = None;
+        self.using_animated_texture = false;
+    }
+    /// Advance the animated GIF/APNG, returns true if the texture changed
+    pub fn update_animated_texture(&mut self, queue: &wgpu::Queue) -> bool {
+        if self.using_animated_texture {
+            if let Some(animation) = &mut self.animated_texture {
+                return animation.update_texture(queue);
+            }
+        }
+        false
+    }
+    pub fn play_animation(&mut self) {
+        if let Some(animation) = &mut self.animated_texture {
+            animation.play();
+        }
+    }
+    pub fn pause_animation(&mut self) {
+        if let Some(animation) = &mut self.animated_texture {
+            animation.pause();
+        }
+    }
+    pub fn restart_animation(&mut self, queue: &wgpu::Queue) {
+        if let Some(animation) = &mut self.animated_texture {
+            animation.restart(queue);
+        }
+    }
+    pub fn set_animation_loop(&mut self, should_loop: bool) {
+        if let Some(animation) = &mut self.animated_texture {
+            animation.set_loop(should_loop);
+        }
+    }
+    /// Copy the depth field of a `with_depth_output` shader into a `Depth32Float` texture
+    /// (see `DepthResolve`) and return it for use as a render pass depth attachment with
+    /// `LoadOp::Load`. Call after dispatching the shader, in the same encoder.
+    pub fn resolve_compute_depth(
+        &mut self,
+        core: &Core,
+        encoder: &mut wgpu::CommandEncoder,
+        shader: &ComputeShader,
+    ) -> Option<&wgpu::TextureView> {
+        shader.depth_output.as_ref()?;
+        self.depth_resolve
+            .get_or_insert_with(|| DepthResolve::new(core))
+            .resolve(core, encoder, shader)
+    }
+
+    /// Play/pause/restart/loop requests for animated textures. Also called by
+    /// `handle_video_requests`, so the video controls drive GIFs too.
+    pub fn handle_animation_requests(&mut self, core: &Core, request: &ControlsRequest) {
+        if !self.using_animated_texture {
+            return;
+        }
+        if request.play_video {
+            self.play_animation();
+        }
+        if request.pause_video {
+            self.pause_animation();
+        }
+        if request.restart_video {
+            self.restart_animation(&core.queue);
+        }
+        if let Some(should_loop) = request.set_loop {
+            self.set_animation_loop(should_loop);
+        }
+    }
     #[cfg(feature = "media")]
     pub fn update_video_texture(&mut self, core: &Core, queue: &wgpu::Queue) -> bool {
         if self.using_video_texture {
@@ -483,6 +840,41 @@ impl RenderKit {
         }
         false
     }
+    /// Start a video-synced export (`ExportManager::start_video_synced_export`) of the
+    /// loaded video, switching it to step mode. Muted for the duration of the export.
+    #[cfg(feature = "media")]
+    pub fn start_video_synced_export(&mut self) -> anyhow::Result<()> {
+        let Some(video_manager) = &mut self.video_texture_manager else {
+            anyhow::bail!("No video loaded");
+        };
+        video_manager.set_mute(true)?;
+        video_manager.set_step_mode(true)?;
+        self.export_manager.start_video_synced_export(video_manager);
+        Ok(())
+    }
+
+    /// Advance the video of a synced export by one frame. Returns the frame time in seconds,
+    /// or `None` once the video has ended (or failed), which also leaves step mode.
+    #[cfg(feature = "media")]
+    pub fn step_synced_video(&mut self, core: &Core) -> Option<f32> {
+        let video_manager = self.video_texture_manager.as_mut()?;
+        let step =
+            video_manager.step_frame(&core.device, &core.queue, &self.texture_bind_group_layout);
+        match step {
+            Ok(Some(pts)) => Some(pts.nseconds() as f32 / 1e9),
+            result => {
+                if let Err(e) = result {
+                    log::error!("Video-synced export stopped: {e}");
+                }
+                if let Err(e) = video_manager.set_step_mode(false) {
+                    log::warn!("Failed to leave video step mode: {e}");
+                }
+                let _ = video_manager.set_mute(false);
+                None
+            }
+        }
+    }
+
     #[cfg(feature = "media")]
     pub fn play_video(&mut self) -> anyhow::Result<()> {
         if let Some(video_manager) = &mut self.video_texture_manager {
@@ -497,6 +889,29 @@ impl RenderKit {
         }
         Ok(())
     }
+    /// Pause a playing video while the window is occluded and resume it afterwards.
+    /// Only videos paused here are resumed, so a user pause is left alone.
+    #[cfg(feature = "media")]
+    pub fn handle_occlusion(&mut self, occluded: bool) {
+        if occluded {
+            let playing = self
+                .video_texture_manager
+                .as_ref()
+                .is_some_and(|vm| vm.is_playing());
+            if playing && self.pause_video().is_ok() {
+                info!("Window occluded, pausing video");
+                self.paused_by_occlusion = true;
+            }
+        } else if self.paused_by_occlusion {
+            self.paused_by_occlusion = false;
+            info!("Window visible again, resuming video");
+            if let Err(e) = self.play_video() {
+                warn!("Failed to resume video: {e}");
+            }
+        }
+    }
+    #[cfg(not(feature = "media"))]
+    pub fn handle_occlusion(&mut self, _occluded: bool) {}
     #[cfg(feature = "media")]
     pub fn seek_video(&mut self, position_seconds: f64) -> anyhow::Result<()> {
         if let Some(video_manager) = &mut self.video_texture_manager {
@@ -513,6 +928,102 @@ impl RenderKit {
         }
     }
 
+    /// Load a video for `channel` of a compute shader, independent of the main video, for
+    /// split-screen and A/B shaders. It starts playing and loops; replaces any video already
+    /// on that channel. Call `update_channel_videos` every frame to bind and advance it.
+    #[cfg(feature = "media")]
+    pub fn load_video_to_channel<P: AsRef<Path>>(
+        &mut self,
+        core: &Core,
+        path: P,
+        channel: u32,
+    ) -> anyhow::Result<()> {
+        info!("Loading video {:?} to channel {channel}", path.as_ref());
+        let mut video_manager = VideoTextureManager::new(
+            &core.device,
+            &core.queue,
+            &self.texture_bind_group_layout,
+            path,
+        )?;
+        video_manager.play()?;
+        video_manager.set_loop(true);
+        self.channel_videos.insert(channel, video_manager);
+        self.channel_video_bindings.remove(&channel);
+        Ok(())
+    }
+
+    /// Stop the video of `channel`. The shader keeps its last frame until the channel is rebound.
+    #[cfg(feature = "media")]
+    pub fn remove_channel_video(&mut self, channel: u32) -> Option<VideoTextureManager> {
+        self.channel_video_bindings.remove(&channel);
+        self.channel_videos.remove(&channel)
+    }
+
+    /// Upload new frames of the channel videos and (re)bind their textures to `shader`
+    /// when needed. Returns true when any channel got a new frame.
+    #[cfg(feature = "media")]
+    pub fn update_channel_videos(&mut self, core: &Core, shader: &mut ComputeShader) -> bool {
+        let mut updated_any = false;
+        for (&channel, video_manager) in &mut self.channel_videos {
+            match video_manager.update_texture(
+                &core.device,
+                &core.queue,
+                &self.texture_bind_group_layout,
+            ) {
+                Ok(updated) => updated_any |= updated,
+                Err(e) => warn!("Failed to update video on channel {channel}: {e}"),
+            }
+            let texture_manager = video_manager.texture_manager();
+            if self.channel_video_bindings.get(&channel) != Some(&texture_manager.texture) {
+                shader.update_channel_texture(
+                    channel,
+                    &texture_manager.view,
+                    &texture_manager.sampler,
+                    &core.device,
+                    &core.queue,
+                );
+                self.channel_video_bindings
+                    .insert(channel, texture_manager.texture.clone());
+            }
+        }
+        updated_any
+    }
+
+    #[cfg(feature = "media")]
+    pub fn channel_video(&self, channel: u32) -> Option<&VideoTextureManager> {
+        self.channel_videos.get(&channel)
+    }
+
+    #[cfg(feature = "media")]
+    pub fn play_channel_video(&mut self, channel: u32) -> anyhow::Result<()> {
+        match self.channel_videos.get_mut(&channel) {
+            Some(video_manager) => video_manager.play(),
+            None => anyhow::bail!("No video on channel {channel}"),
+        }
+    }
+
+    #[cfg(feature = "media")]
+    pub fn pause_channel_video(&mut self, channel: u32) -> anyhow::Result<()> {
+        match self.channel_videos.get_mut(&channel) {
+            Some(video_manager) => video_manager.pause(),
+            None => anyhow::bail!("No video on channel {channel}"),
+        }
+    }
+
+    #[cfg(feature = "media")]
+    pub fn seek_channel_video(
+        &mut self,
+        channel: u32,
+        position_seconds: f64,
+    ) -> anyhow::Result<()> {
+        match self.channel_videos.get_mut(&channel) {
+            Some(video_manager) => video_manager.seek(gstreamer::ClockTime::from_nseconds(
+                (position_seconds.max(0.0) * 1e9) as u64,
+            )),
+            None => anyhow::bail!("No video on channel {channel}"),
+        }
+    }
+
     #[cfg(feature = "media")]
     pub fn start_webcam(&mut self, core: &Core, device_index: Option<u32>) -> anyhow::Result<()> {
         info!("Starting webcam");
@@ -531,6 +1042,7 @@ impl RenderKit {
         self.using_video_texture = false;
         self.video_texture_manager = None;
         self.using_hdri_texture = false;
+        self.stop_animation();
 
         Ok(())
     }
@@ -563,14 +1075,15 @@ impl RenderKit {
     }
     pub fn load_image(&mut self, core: &Core, path: std::path::PathBuf) {
         if let Ok(img) = image::open(path) {
-            let rgba_image = img.into_rgba8();
-            let new_texture_manager = TextureManager::new(
+            let new_texture_manager = TextureManager::from_image(
                 &core.device,
                 &core.queue,
-                &rgba_image,
+                &img,
                 &self.texture_bind_group_layout,
+                !self.preserve_image_format,
             );
             self.texture_manager = Some(new_texture_manager);
+            self.stop_animation();
             #[cfg(feature = "media")]
             {
                 self.using_video_texture = false;
@@ -600,25 +1113,56 @@ impl RenderKit {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
+        (
+            capture_texture,
+            Self::create_capture_buffer(device, width, height),
+        )
+    }
+    /// Mappable buffer for a `width`x`height` `CAPTURE_FORMAT` copy, rows padded to 256 bytes
+    pub fn create_capture_buffer(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Buffer {
         let align = 256;
         let unpadded_bytes_per_row = width * 4;
         let padding = (align - unpadded_bytes_per_row % align) % align;
         let padded_bytes_per_row = unpadded_bytes_per_row + padding;
         let buffer_size = padded_bytes_per_row * height;
-        let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Capture Buffer"),
             size: buffer_size as u64,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
             mapped_at_creation: false,
-        });
-        (capture_texture, output_buffer)
+        })
     }
     pub fn apply_control_request(&mut self, request: ControlsRequest) {
         if request.should_reset {
             self.start_time = Instant::now();
         }
+        if let Some(grading) = request.set_color_grading {
+            let queue = self.queue.clone();
+            self.set_color_grading(&queue, grading);
+        }
         self.controls.apply_ui_request(request);
     }
+    /// Mirror `shader`'s state into the controls panel: the passes of multi-pass shaders,
+    /// the estimated GPU memory in the advanced section and the channel thumbnails of the
+    /// media panel. Applies the pass toggles clicked since the last call.
+    /// `ComputeShader::handle_export` calls it every frame.
+    pub fn sync_shader_controls(&mut self, core: &Core, shader: &mut ComputeShader) {
+        for (entry_point, enabled) in self.controls.take_pass_toggles() {
+            shader.set_pass_enabled(&entry_point, enabled);
+        }
+        let multi_pass = shader.entry_points.len() > 1;
+        self.controls.sync_pass_graph(
+            multi_pass.then(|| PassGraph::of(shader, (core.size.width, core.size.height))),
+        );
+        self.controls
+            .sync_gpu_memory(shader.estimated_memory_bytes());
+        self.sync_channel_previews(core, shader);
+    }
+    /// Refresh the channel thumbnails for `ShaderControls::render_channel_previews`
+    pub fn sync_channel_previews(&mut self, core: &Core, shader: &ComputeShader) {
+        self.controls
+            .sync_channel_previews(&mut self.egui_renderer, &core.device, shader);
+    }
     #[cfg(feature = "media")]
     pub fn update_audio_spectrum(&mut self, queue: &wgpu::Queue) {
         self.spectrum_analyzer.update_spectrum(
@@ -627,8 +1171,14 @@ impl RenderKit {
             &self.video_texture_manager,
             self.using_video_texture,
         );
+        self.controls.sync_spectrum(crate::SpectrumSettings {
+            scale: self.spectrum_analyzer.frequency_scale(),
+            smoothing: self.spectrum_analyzer.smoothing(),
+            weighting: self.spectrum_analyzer.weighting(),
+        });
     }
-    #[cfg(feature = "media")]
+    /// Media loading and playback requests. Animated textures are driven without the
+    /// `media` feature too, videos and audio only with it.
     pub fn handle_video_requests(&mut self, core: &Core, request: &ControlsRequest) {
         if let Some(path) = &request.load_media_path {
             if let Err(e) = self.load_media(core, path) {
@@ -636,6 +1186,14 @@ impl RenderKit {
             }
         }
 
+        self.handle_animation_requests(core, request);
+
+        #[cfg(feature = "media")]
+        self.handle_video_playback_requests(request);
+    }
+
+    #[cfg(feature = "media")]
+    fn handle_video_playback_requests(&mut self, request: &ControlsRequest) {
         if request.play_video {
             let _ = self.play_video();
         }
@@ -657,6 +1215,18 @@ impl RenderKit {
             self.set_video_loop(should_loop);
         }
 
+        if let Some(scale) = request.spectrum_scale {
+            self.spectrum_analyzer.set_frequency_scale(scale);
+        }
+
+        if let Some(smoothing) = request.spectrum_smoothing {
+            self.spectrum_analyzer.set_smoothing(smoothing);
+        }
+
+        if let Some(weighting) = request.spectrum_weighting {
+            self.spectrum_analyzer.set_weighting(weighting);
+        }
+
         // Handle audio control requests
         if let Some(volume) = request.set_volume {
             if let Some(vm) = &mut self.video_texture_manager {
@@ -758,9 +1328,10 @@ impl RenderKit {
         &mut self,
         core: &Core,
         shader_path: &Path,
-    ) -> Result<(), notify::Error> {
+    ) -> Result<(), HotReloadError> {
         if let Some(compute_shader) = &mut self.compute_shader {
-            let shader_source = std::fs::read_to_string(shader_path)?;
+            let shader_source = std::fs::read_to_string(shader_path)
+                .map_err(|_| HotReloadError::FileNotFound(shader_path.to_path_buf()))?;
             let shader_module = core
                 .device
                 .create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -779,7 +1350,7 @@ impl RenderKit {
             );
             Ok(())
         } else {
-            Err(notify::Error::generic("No compute shader initialized"))
+            Err(HotReloadError::NoComputeShader)
         }
     }
 
@@ -885,6 +1456,7 @@ impl RenderKit {
             mouse_uniform.data = self.mouse_tracker.uniform;
             mouse_uniform.update(queue);
         }
+        self.mouse_tracker.end_frame();
     }
 
     pub fn handle_mouse_input(
@@ -895,12 +1467,84 @@ impl RenderKit {
     ) -> bool {
         let window_size = [core.size.width as f32, core.size.height as f32];
 
+        if self.right_click_capture && !ui_handled {
+            if let WindowEvent::MouseInput {
+                state: winit::event::ElementState::Pressed,
+                button: winit::event::MouseButton::Right,
+                ..
+            } = event
+            {
+                let captured = self.mouse_tracker.is_captured();
+                self.set_mouse_capture(core, !captured);
+            }
+        }
+
         self.mouse_tracker
             .handle_mouse_input(event, window_size, ui_handled)
     }
 
+    /// Raw mouse motion for the `look` angles while the pointer is captured
+    pub fn handle_device_event(&mut self, event: &winit::event::DeviceEvent) -> bool {
+        self.mouse_tracker.handle_device_event(event)
+    }
+
+    /// Built-in shortcuts (`key_handler`) for a key replayed by `InputRecorder`
+    pub fn handle_replayed_key(
+        &mut self,
+        core: &Core,
+        key: &winit::keyboard::Key,
+        state: winit::event::ElementState,
+    ) -> bool {
+        self.key_handler.handle_key(core.window(), key, state)
+    }
+
+    /// Drive `controls` time from an input replay, `None` to go back to wall time
+    pub fn set_replay_time(&mut self, time: Option<f32>) {
+        self.controls.set_time_override(time);
+    }
+
+    /// Hide the cursor and lock it to the window for first-person cameras; shaders then
+    /// read `MouseUniform::look` and `delta`. Falls back to confining the cursor where locking
+    /// isn't supported. Returns whether the pointer is captured.
+    pub fn set_mouse_capture(&mut self, core: &Core, capture: bool) -> bool {
+        use winit::window::CursorGrabMode;
+
+        if !capture {
+            if let Err(e) = core.set_cursor_grab(CursorGrabMode::None) {
+                warn!("Failed to release cursor: {e}");
+            }
+            core.set_cursor_visible(true);
+            self.mouse_tracker.set_captured(false);
+            return false;
+        }
+        let grabbed = core
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| core.set_cursor_grab(CursorGrabMode::Confined));
+        if let Err(e) = grabbed {
+            warn!("Failed to capture cursor: {e}");
+            return false;
+        }
+        core.set_cursor_visible(false);
+        self.mouse_tracker.set_captured(true);
+        true
+    }
+
+    pub fn is_mouse_captured(&self) -> bool {
+        self.mouse_tracker.is_captured()
+    }
+
+    /// Toggle mouse capture with the right button (off by default)
+    pub fn set_right_click_capture(&mut self, enabled: bool) {
+        self.right_click_capture = enabled;
+    }
+
     /// Get current active texture manager (video, webcam, or static image)
     pub fn get_current_texture_manager(&self) -> Option<&TextureManager> {
+        if self.using_animated_texture {
+            if let Some(animation) = &self.animated_texture {
+                return Some(animation.texture_manager());
+            }
+        }
         #[cfg(feature = "media")]
         {
             if self.using_video_texture {
@@ -919,7 +1563,11 @@ impl RenderKit {
     }
 
     /// Update current active texture and return whether an external texture update is needed
+    #[cfg_attr(not(feature = "media"), allow(unused_variables))]
     pub fn update_current_texture(&mut self, core: &Core, queue: &wgpu::Queue) -> bool {
+        if self.using_animated_texture {
+            return self.update_animated_texture(queue);
+        }
         #[cfg(feature = "media")]
         {
             if self.using_video_texture {
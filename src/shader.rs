@@ -1,5 +1,6 @@
-use crate::Core;
-use winit::event::WindowEvent;
+use crate::{Core, RenderKit, TextureManager};
+use winit::event::{DeviceEvent, ElementState, WindowEvent};
+use winit::keyboard::Key;
 
 pub trait ShaderManager {
     fn init(core: &Core) -> Self
@@ -8,7 +9,54 @@ pub trait ShaderManager {
     fn resize(&mut self, _core: &Core) {}
     fn update(&mut self, _core: &Core) {}
     fn render(&mut self, core: &Core) -> Result<(), wgpu::SurfaceError>;
+    /// Record this frame's work into `encoder` and return the finished image instead of
+    /// presenting it (no UI), for `ShaderSwitcher` transitions. `None`, the default, means
+    /// the shader can only present itself and switching to or from it is a cut. For a
+    /// `ComputeShader` based shader: set the time, `dispatch` and return its
+    /// `get_output_texture()`.
+    fn render_offscreen(
+        &mut self,
+        _core: &Core,
+        _encoder: &mut wgpu::CommandEncoder,
+    ) -> Option<&TextureManager> {
+        None
+    }
+    /// The shader's `RenderKit`, which the default input replay hooks forward to
+    fn render_kit_mut(&mut self) -> Option<&mut RenderKit> {
+        None
+    }
     fn handle_input(&mut self, _core: &Core, _event: &WindowEvent) -> bool {
         false
     }
+    /// Raw device input such as `DeviceEvent::MouseMotion`, the only mouse movement
+    /// reported while the pointer is captured. Forward to `RenderKit::handle_device_event`.
+    fn handle_device_event(&mut self, _core: &Core, _event: &DeviceEvent) -> bool {
+        false
+    }
+    /// Called when the window becomes hidden (minimized/occluded) or visible again.
+    /// Goes to `RenderKit::handle_occlusion` by default to pause media while hidden.
+    fn occlusion_changed(&mut self, _core: &Core, occluded: bool) {
+        if let Some(kit) = self.render_kit_mut() {
+            kit.handle_occlusion(occluded);
+        }
+    }
+    /// Keys replayed by `InputRecorder` (winit key events can't be rebuilt for `handle_input`).
+    /// Goes to `RenderKit::handle_replayed_key` by default; override for the shader's own keys.
+    fn handle_replayed_key(&mut self, core: &Core, key: &Key, state: ElementState) -> bool {
+        self.render_kit_mut()
+            .is_some_and(|kit| kit.handle_replayed_key(core, key, state))
+    }
+    /// Recorded frame time during input replay, `None` when the replay ends.
+    /// Goes to `RenderKit::set_replay_time` by default for deterministic playback.
+    fn set_replay_time(&mut self, _core: &Core, time: Option<f32>) {
+        if let Some(kit) = self.render_kit_mut() {
+            kit.set_replay_time(time);
+        }
+    }
+    /// Asked after each batch of events in `RedrawMode::Reactive`: return true to keep
+    /// drawing frames, e.g. while animating. `RenderKit::needs_redraw` covers time, export
+    /// and egui animations.
+    fn needs_redraw(&self) -> bool {
+        false
+    }
 }
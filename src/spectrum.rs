@@ -7,11 +7,124 @@ use crate::ResolutionUniform;
 #[cfg(feature = "media")]
 use crate::UniformBinding;
 #[cfg(feature = "media")]
-use log::info;
+use log::trace;
+
+use crate::compute::ComputeShader;
+use crate::{Core, TextureManager};
+
+/// How the raw GStreamer bands are bucketed into the 64 values of `audio_data`.
+///
+/// `audio_data` is `[[f32; 4]; 32]`, only the first 16 vec4s are filled: bucket `i`
+/// lands in `audio_data[i / 4][i % 4]`, bucket 0 being the lowest frequency.
+/// - `Linear`: buckets are evenly spaced over the lower half of the analyzed bands (0..~11kHz).
+/// - `Logarithmic`: buckets are evenly spaced in log-frequency from 20Hz to 20kHz,
+///   so each octave gets roughly the same number of buckets.
+/// - `Mel`: buckets are evenly spaced on the mel scale from 20Hz to 20kHz,
+///   close to linear below 1kHz and logarithmic above.
+///
+/// For `Logarithmic` and `Mel` each bucket takes the peak of all source bands it covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrequencyScale {
+    #[default]
+    Linear,
+    Logarithmic,
+    Mel,
+}
+
+impl FrequencyScale {
+    pub const ALL: [FrequencyScale; 3] = [
+        FrequencyScale::Linear,
+        FrequencyScale::Logarithmic,
+        FrequencyScale::Mel,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrequencyScale::Linear => "Linear",
+            FrequencyScale::Logarithmic => "Logarithmic",
+            FrequencyScale::Mel => "Mel",
+        }
+    }
+}
+
+/// Perceptual weighting applied to the band magnitudes before bucketing.
+///
+/// - `Flat`: raw magnitudes.
+/// - `AWeighting`: IEC 61672 A-weighting, which follows human loudness sensitivity at
+///   moderate levels, so bass no longer dominates. Each band is weighted at its center
+///   frequency `f` with the amplitude response
+///   `R_A(f) = 12194² f⁴ / ((f² + 20.6²) √((f² + 107.7²)(f² + 737.9²)) (f² + 12194²))`,
+///   normalized to 1 at 1kHz (`A(f) = 20 log10(R_A(f)) + 2.00` dB). The gain is applied
+///   after the dB to linear conversion and the result converted back to dB, so it's the
+///   same as adding `A(f)` to the band's dB value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpectrumWeighting {
+    #[default]
+    Flat,
+    AWeighting,
+}
+
+impl SpectrumWeighting {
+    pub const ALL: [SpectrumWeighting; 2] =
+        [SpectrumWeighting::Flat, SpectrumWeighting::AWeighting];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpectrumWeighting::Flat => "Flat",
+            SpectrumWeighting::AWeighting => "A-weighting",
+        }
+    }
+
+    /// Linear amplitude gain at `hz`
+    pub fn gain(&self, hz: f32) -> f32 {
+        match self {
+            SpectrumWeighting::Flat => 1.0,
+            SpectrumWeighting::AWeighting => {
+                // 10^(2.00 / 20), normalizes the response to 1 at 1kHz
+                const A_NORMALIZATION: f32 = 1.258_925_4;
+                let f2 = hz * hz;
+                let r_a = (12194.0_f32 * 12194.0 * f2 * f2)
+                    / ((f2 + 20.6 * 20.6)
+                        * ((f2 + 107.7 * 107.7) * (f2 + 737.9 * 737.9)).sqrt()
+                        * (f2 + 12194.0 * 12194.0));
+                r_a * A_NORMALIZATION
+            }
+        }
+    }
+}
 
 pub struct SpectrumAnalyzer {
     #[cfg(feature = "media")]
     prev_audio_data: [[f32; 4]; 32],
+    #[cfg(feature = "media")]
+    frequency_scale: FrequencyScale,
+    #[cfg(feature = "media")]
+    smoothing: f32,
+    #[cfg(feature = "media")]
+    weighting: SpectrumWeighting,
+    /// Per-band gains for the band count they were computed for
+    #[cfg(feature = "media")]
+    band_gains: Vec<f32>,
+}
+
+/// Spectrum buckets written to `audio_data`, the rest stay zero
+pub(crate) const OUTPUT_BUCKETS: usize = 64;
+// GStreamer spectrum bands cover 0..nyquist, we assume 44.1kHz audio
+#[cfg(feature = "media")]
+const NYQUIST_HZ: f32 = 22050.0;
+#[cfg(feature = "media")]
+const MIN_FREQ_HZ: f32 = 20.0;
+#[cfg(feature = "media")]
+const MAX_FREQ_HZ: f32 = 20000.0;
+
+#[cfg(feature = "media")]
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+#[cfg(feature = "media")]
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10.0_f32.powf(mel / 2595.0) - 1.0)
 }
 
 #[cfg(feature = "media")]
@@ -26,9 +139,85 @@ impl SpectrumAnalyzer {
     pub fn new() -> Self {
         Self {
             prev_audio_data: [[0.0; 4]; 32],
+            frequency_scale: FrequencyScale::Linear,
+            smoothing: 1.0,
+            weighting: SpectrumWeighting::Flat,
+            band_gains: Vec::new(),
         }
     }
 
+    pub fn frequency_scale(&self) -> FrequencyScale {
+        self.frequency_scale
+    }
+
+    pub fn set_frequency_scale(&mut self, scale: FrequencyScale) {
+        self.frequency_scale = scale;
+    }
+
+    /// Temporal smoothing amount: 0.0 disables smoothing, 1.0 is the default
+    /// attack/decay, up to 2.0 for slower, smoother motion.
+    pub fn smoothing(&self) -> f32 {
+        self.smoothing
+    }
+
+    pub fn set_smoothing(&mut self, smoothing: f32) {
+        self.smoothing = smoothing.clamp(0.0, 2.0);
+    }
+
+    pub fn weighting(&self) -> SpectrumWeighting {
+        self.weighting
+    }
+
+    pub fn set_weighting(&mut self, weighting: SpectrumWeighting) {
+        self.weighting = weighting;
+        self.band_gains.clear();
+    }
+
+    /// `magnitude` (dB) of band `band` out of `bands`, with the weighting applied
+    fn weighted_magnitude(&mut self, magnitude: f32, band: usize, bands: usize) -> f32 {
+        if self.weighting == SpectrumWeighting::Flat {
+            return magnitude;
+        }
+        if self.band_gains.len() != bands {
+            self.band_gains = (0..bands)
+                .map(|j| {
+                    let hz = (j as f32 + 0.5) * NYQUIST_HZ / bands as f32;
+                    self.weighting.gain(hz)
+                })
+                .collect();
+        }
+        let linear = 10.0_f32.powf(magnitude / 20.0) * self.band_gains[band];
+        20.0 * linear.max(1e-12).log10()
+    }
+
+    /// Source band range `[start, end)` feeding output bucket `i`
+    fn band_range(&self, i: usize, bands: usize) -> (usize, usize) {
+        let t0 = i as f32 / OUTPUT_BUCKETS as f32;
+        let t1 = (i + 1) as f32 / OUTPUT_BUCKETS as f32;
+        let max_hz = MAX_FREQ_HZ.min(NYQUIST_HZ);
+        let (f0, f1) = match self.frequency_scale {
+            FrequencyScale::Linear => {
+                // Original layout: lower half of the bands, one band per bucket
+                let source_idx = (t0 * (bands as f32 / 2.0)) as usize;
+                return (source_idx, (source_idx + 1).min(bands));
+            }
+            FrequencyScale::Logarithmic => {
+                let ratio = max_hz / MIN_FREQ_HZ;
+                (MIN_FREQ_HZ * ratio.powf(t0), MIN_FREQ_HZ * ratio.powf(t1))
+            }
+            FrequencyScale::Mel => {
+                let (m0, m1) = (hz_to_mel(MIN_FREQ_HZ), hz_to_mel(max_hz));
+                (
+                    mel_to_hz(m0 + (m1 - m0) * t0),
+                    mel_to_hz(m0 + (m1 - m0) * t1),
+                )
+            }
+        };
+        let start = ((f0 / NYQUIST_HZ) * bands as f32) as usize;
+        let end = (((f1 / NYQUIST_HZ) * bands as f32).ceil() as usize).max(start + 1);
+        (start, end.min(bands))
+    }
+
     pub fn update_spectrum(
         &mut self,
         queue: &wgpu::Queue,
@@ -73,26 +262,28 @@ impl SpectrumAnalyzer {
                         };
 
                         // sanity: to see RMS normalization values
-                        info!(
+                        trace!(
                             "Audio Level - RMS: {:.2}dB, Peak: {:.3}, Gain: {:.2}x",
-                            current_rms_db, audio_level.peak, adaptive_gain
+                            current_rms_db,
+                            audio_level.peak,
+                            adaptive_gain
                         );
 
-                        // Process only first 64 bands (note that, we actually have 128 but its expensiive)
-                        for i in 0..64 {
-                            let band_percent = i as f32 / 64.0;
-                            // Map to source index with slight emphasis on higher frequencies
-                            let source_idx = (band_percent * (bands as f32 / 2.0)) as usize;
-                            // Use narrow width for all frequencies for accuracy
-                            let width = 1;
-                            let end_idx = (source_idx + width).min(bands);
+                        // Fill 64 buckets (note that, we actually have 128 bands but its expensiive)
+                        for i in 0..OUTPUT_BUCKETS {
+                            let band_percent = i as f32 / OUTPUT_BUCKETS as f32;
+                            let (source_idx, end_idx) = self.band_range(i, bands);
 
                             if source_idx < bands {
                                 // Get peak value in this range
                                 let mut peak: f32 = -120.0;
                                 for j in source_idx..end_idx {
                                     if j < bands {
-                                        let val = spectrum_data.magnitudes[j];
+                                        let val = self.weighted_magnitude(
+                                            spectrum_data.magnitudes[j],
+                                            j,
+                                            bands,
+                                        );
                                         peak = peak.max(val);
                                     }
                                 }
@@ -131,11 +322,15 @@ impl SpectrumAnalyzer {
                                     let decay = if band_percent < 0.6 { 0.3 } else { 0.25 };
 
                                     // Apply smoothing
-                                    let smoothing_factor = if enhanced > prev_value {
+                                    let base_factor: f32 = if enhanced > prev_value {
                                         attack // Rising
                                     } else {
                                         decay // Falling
                                     };
+                                    // Scale how much of the previous value we keep
+                                    let smoothing_factor = (1.0
+                                        - (1.0 - base_factor) * self.smoothing)
+                                        .clamp(0.02, 1.0);
                                     // Calculate smoothed value
                                     let smoothed = prev_value * (1.0 - smoothing_factor)
                                         + enhanced * smoothing_factor;
@@ -201,3 +396,297 @@ impl SpectrumAnalyzer {
         Self {}
     }
 }
+
+/// Scrolling spectrogram for music visualizers: every `push` writes one spectrum as a
+/// column of an `R8Unorm` texture, so x is time and y is frequency with the lowest band at
+/// the bottom row. Columns are written in a ring; sample with
+/// `u = fract(uv.x + scroll_offset)` to get the oldest column on the left and the newest on
+/// the right (the sampler repeats horizontally).
+pub struct Spectrogram {
+    pub texture: TextureManager,
+    /// Magnitudes at or below this map to 0
+    pub min_db: f32,
+    /// Magnitudes at or above this map to 1
+    pub max_db: f32,
+    history: u32,
+    height: u32,
+    /// Column written by the last `push`
+    head: u32,
+    column: Vec<u8>,
+}
+
+impl Spectrogram {
+    /// `history` columns of `height` rows, e.g. 512 x 128. Spectra with a different band
+    /// count are resampled to `height`.
+    pub fn new(core: &Core, history: u32, height: u32) -> Self {
+        let history = history.max(1);
+        let height = height.max(1);
+        let texture = core.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Spectrogram Texture"),
+            size: wgpu::Extent3d {
+                width: history,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = core.device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let layout = crate::RenderKit::create_standard_texture_layout(&core.device);
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("Spectrogram Bind Group"),
+        });
+
+        Self {
+            texture: TextureManager {
+                texture,
+                view,
+                sampler,
+                bind_group,
+                view_formats: Vec::new(),
+            },
+            min_db: -60.0,
+            max_db: 0.0,
+            history,
+            height,
+            head: history - 1,
+            column: vec![0; height as usize],
+        }
+    }
+
+    /// Write `magnitudes` (dB, lowest band first) into the next column
+    pub fn push(&mut self, queue: &wgpu::Queue, magnitudes: &[f32]) {
+        self.head = (self.head + 1) % self.history;
+        self.resample(magnitudes);
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: self.head,
+                    y: 0,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self.column,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(1),
+                rows_per_image: Some(self.height),
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Push the latest spectrum of a video's audio track
+    #[cfg(feature = "media")]
+    pub fn push_spectrum(
+        &mut self,
+        queue: &wgpu::Queue,
+        spectrum: &crate::gst::video::SpectrumData,
+    ) {
+        self.push(queue, &spectrum.magnitudes);
+    }
+
+    /// Fill `column` from `magnitudes`: the peak of the covered bands when shrinking,
+    /// linear interpolation when stretching. An empty spectrum gives a silent column.
+    fn resample(&mut self, magnitudes: &[f32]) {
+        let bands = magnitudes.len();
+        let rows = self.height as usize;
+        let range = (self.max_db - self.min_db).max(f32::EPSILON);
+        for row in 0..rows {
+            let db = if bands == 0 {
+                self.min_db
+            } else if bands >= rows {
+                let start = row * bands / rows;
+                let end = ((row + 1) * bands / rows).max(start + 1);
+                magnitudes[start..end]
+                    .iter()
+                    .copied()
+                    .fold(f32::NEG_INFINITY, f32::max)
+            } else {
+                let pos = ((row as f32 + 0.5) * bands as f32 / rows as f32 - 0.5)
+                    .clamp(0.0, (bands - 1) as f32);
+                let i = pos as usize;
+                let next = (i + 1).min(bands - 1);
+                let t = pos - i as f32;
+                magnitudes[i] * (1.0 - t) + magnitudes[next] * t
+            };
+            let value = ((db - self.min_db) / range).clamp(0.0, 1.0);
+            // Lowest band at the bottom of the texture
+            self.column[rows - 1 - row] = (value * 255.0).round() as u8;
+        }
+    }
+
+    /// Bind the texture as `channel` of `shader`. Only needed once, pushes update it in place.
+    pub fn bind_to_channel(&self, core: &Core, shader: &mut ComputeShader, channel: u32) {
+        shader.update_channel_texture(
+            channel,
+            &self.texture.view,
+            &self.texture.sampler,
+            &core.device,
+            &core.queue,
+        );
+    }
+
+    /// Horizontal texture offset that puts the oldest column at `u = 0`
+    pub fn scroll_offset(&self) -> f32 {
+        ((self.head + 1) % self.history) as f32 / self.history as f32
+    }
+
+    /// Column written by the last `push`
+    pub fn head(&self) -> u32 {
+        self.head
+    }
+
+    pub fn history(&self) -> u32 {
+        self.history
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Zero every column
+    pub fn clear(&mut self, queue: &wgpu::Queue) {
+        let zeros = vec![0u8; (self.history * self.height) as usize];
+        queue.write_texture(
+            self.texture.texture.as_image_copy(),
+            &zeros,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(self.history),
+                rows_per_image: Some(self.height),
+            },
+            self.texture.texture.size(),
+        );
+        self.head = self.history - 1;
+    }
+}
+
+/// Raw audio samples as a `length` x 1 `R16Float` texture for oscilloscope shaders, e.g.
+/// from `VideoTextureManager::waveform_samples` or `SynthesisManager::waveform_samples`.
+/// Texel `i` is sample `i` in -1..1, oldest first; read it with
+/// `textureLoad(channelN, vec2<i32>(i, 0), 0).r` or sample it horizontally.
+pub struct WaveformTexture {
+    pub texture: TextureManager,
+    length: u32,
+    texels: Vec<u16>,
+}
+
+impl WaveformTexture {
+    pub fn new(core: &Core, length: u32) -> Self {
+        let length = length.max(1);
+        let texture = core.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Waveform Texture"),
+            size: wgpu::Extent3d {
+                width: length,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R16Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = core.device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let layout = crate::RenderKit::create_standard_texture_layout(&core.device);
+        let bind_group = core.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("Waveform Bind Group"),
+        });
+
+        Self {
+            texture: TextureManager {
+                texture,
+                view,
+                sampler,
+                bind_group,
+                view_formats: Vec::new(),
+            },
+            length,
+            texels: vec![0; length as usize],
+        }
+    }
+
+    /// Upload `samples`. Extra samples are dropped from the start (keeping the newest),
+    /// missing ones leave the start silent.
+    pub fn update(&mut self, queue: &wgpu::Queue, samples: &[f32]) {
+        let length = self.length as usize;
+        let samples = &samples[samples.len().saturating_sub(length)..];
+        let start = length - samples.len();
+        self.texels[..start].fill(0);
+        for (texel, sample) in self.texels[start..].iter_mut().zip(samples) {
+            *texel = crate::compute::readback::f32_to_f16(*sample);
+        }
+        queue.write_texture(
+            self.texture.texture.as_image_copy(),
+            bytemuck::cast_slice(&self.texels),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(self.length * 2),
+                rows_per_image: Some(1),
+            },
+            self.texture.texture.size(),
+        );
+    }
+
+    /// Bind the texture as `channel` of `shader`. Only needed once, updates happen in place.
+    pub fn bind_to_channel(&self, core: &Core, shader: &mut ComputeShader, channel: u32) {
+        shader.update_channel_texture(
+            channel,
+            &self.texture.view,
+            &self.texture.sampler,
+            &core.device,
+            &core.queue,
+        );
+    }
+
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+}
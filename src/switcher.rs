@@ -0,0 +1,330 @@
+use crate::compute::{Crossfade, TransitionKind};
+use crate::{Core, ShaderManager};
+use std::time::Duration;
+use winit::dpi::PhysicalSize;
+use winit::event::{DeviceEvent, ElementState, WindowEvent};
+use winit::keyboard::{Key, NamedKey};
+
+struct Entry {
+    name: String,
+    shader: Box<dyn ShaderManager>,
+    /// Surface size the shader last saw, inactive shaders are resized when selected
+    size: Option<PhysicalSize<u32>>,
+    /// Added since the last `sync` and not told yet that it starts switched away
+    added: bool,
+}
+
+/// Several shaders sharing one window, for galleries and demo reels.
+///
+/// Only the active shader gets input, updates and renders; Page Down/Page Up switch to
+/// the next/previous one (`with_keys` changes them). Inactive shaders keep all their
+/// state: on switching away a shader gets `occlusion_changed(true)` so its media pauses,
+/// and on switching back it's resized if the window changed meanwhile. Shader time is
+/// wall-clock based, so it has moved on when a shader comes back unless it was paused.
+///
+/// Switches are hard cuts unless `with_transition` is set: then both shaders keep updating
+/// while their outputs are blended with `compute::Crossfade`, and the outgoing one is only
+/// switched away once the transition is over. That needs both shaders to implement
+/// `ShaderManager::render_offscreen`, otherwise the switch is still a cut. The UI isn't
+/// drawn during a transition.
+///
+/// ```ignore
+/// app.run(event_loop, |core| {
+///     ShaderSwitcher::new()
+///         .with_shader("Fluid", Fluid::init(core))
+///         .with_shader("Galaxy", Galaxy::init(core))
+///         .with_transition(Duration::from_millis(800), TransitionKind::Fade)
+/// })
+/// ```
+pub struct ShaderSwitcher {
+    entries: Vec<Entry>,
+    active: usize,
+    /// Shader that was active before the last `select`, not yet told it was switched away
+    switched_from: Option<usize>,
+    transition: Option<(Duration, TransitionKind)>,
+    crossfade: Option<Crossfade>,
+    /// Outgoing shader while a transition runs
+    fading_from: Option<usize>,
+    occluded: bool,
+    next_key: Key,
+    prev_key: Key,
+}
+
+impl Default for ShaderSwitcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShaderSwitcher {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            active: 0,
+            switched_from: None,
+            transition: None,
+            crossfade: None,
+            fading_from: None,
+            occluded: false,
+            next_key: Key::Named(NamedKey::PageDown),
+            prev_key: Key::Named(NamedKey::PageUp),
+        }
+    }
+
+    /// Add a shader, the first one added starts active and the others switched away
+    pub fn with_shader(mut self, name: &str, shader: impl ShaderManager + 'static) -> Self {
+        self.add_shader(name, Box::new(shader));
+        self
+    }
+
+    /// Keys switching to the next and previous shader
+    pub fn with_keys(mut self, next: Key, prev: Key) -> Self {
+        self.next_key = next;
+        self.prev_key = prev;
+        self
+    }
+
+    /// Blend from the outgoing to the incoming shader over `duration` on every switch
+    pub fn with_transition(mut self, duration: Duration, kind: TransitionKind) -> Self {
+        self.transition = (!duration.is_zero()).then_some((duration, kind));
+        self
+    }
+
+    pub fn add_shader(&mut self, name: &str, shader: Box<dyn ShaderManager>) {
+        self.entries.push(Entry {
+            name: name.to_string(),
+            shader,
+            size: None,
+            added: true,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.name.as_str())
+    }
+
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    pub fn active_name(&self) -> Option<&str> {
+        self.entries
+            .get(self.active)
+            .map(|entry| entry.name.as_str())
+    }
+
+    /// Make shader `index` active from the next frame. False if it's out of range or
+    /// already active.
+    pub fn select(&mut self, index: usize) -> bool {
+        if index >= self.entries.len() || index == self.active {
+            return false;
+        }
+        self.switched_from.get_or_insert(self.active);
+        self.active = index;
+        true
+    }
+
+    /// Switch to the next shader, wrapping around
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> bool {
+        if self.entries.is_empty() {
+            return false;
+        }
+        self.select((self.active + 1) % self.entries.len())
+    }
+
+    /// Switch to the previous shader, wrapping around
+    pub fn prev(&mut self) -> bool {
+        if self.entries.is_empty() {
+            return false;
+        }
+        self.select((self.active + self.entries.len() - 1) % self.entries.len())
+    }
+
+    /// Finish a pending switch and bring the shaders on screen up to the window size
+    fn sync(&mut self, core: &Core) {
+        // Shaders added off screen pause their media like ones switched away from
+        for (index, entry) in self.entries.iter_mut().enumerate() {
+            if std::mem::take(&mut entry.added) && index != self.active {
+                entry.shader.occlusion_changed(core, true);
+            }
+        }
+        if let Some(previous) = self.switched_from.take() {
+            if previous != self.active {
+                // A new switch cuts a running transition short
+                self.finish_transition(core);
+                if !self.occluded {
+                    if self.transition.is_none() {
+                        self.entries[previous].shader.occlusion_changed(core, true);
+                    }
+                    self.entries[self.active]
+                        .shader
+                        .occlusion_changed(core, false);
+                }
+                if let Some((duration, kind)) = self.transition {
+                    self.fading_from = Some(previous);
+                    self.crossfade
+                        .get_or_insert_with(|| Crossfade::new(core))
+                        .start_transition(duration, kind);
+                }
+                log::info!("Switched to shader {:?}", self.entries[self.active].name);
+            }
+        }
+        if !self.crossfade.as_ref().is_some_and(Crossfade::is_active) {
+            self.finish_transition(core);
+        }
+        for index in [Some(self.active), self.fading_from].into_iter().flatten() {
+            if let Some(entry) = self.entries.get_mut(index) {
+                if entry.size != Some(core.size) {
+                    entry.size = Some(core.size);
+                    entry.shader.resize(core);
+                }
+            }
+        }
+        if let (Some(crossfade), Some(_)) = (&mut self.crossfade, self.fading_from) {
+            crossfade.resize(core, core.size.width, core.size.height);
+        }
+    }
+
+    /// End the running transition, the outgoing shader is switched away for good
+    fn finish_transition(&mut self, core: &Core) {
+        if let Some(from) = self.fading_from.take() {
+            if from != self.active && !self.occluded {
+                self.entries[from].shader.occlusion_changed(core, true);
+            }
+        }
+    }
+
+    /// Blend the outgoing shader `from` into the active one on the surface. False if
+    /// either of them can't render offscreen.
+    fn render_transition(&mut self, core: &Core, from: usize) -> Result<bool, wgpu::SurfaceError> {
+        let Some(crossfade) = &self.crossfade else {
+            return Ok(false);
+        };
+        let Ok([outgoing, incoming]) = self.entries.get_disjoint_mut([from, self.active]) else {
+            return Ok(false);
+        };
+        let mut encoder = core
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Shader Transition Encoder"),
+            });
+        let (Some(from_texture), Some(to_texture)) = (
+            outgoing.shader.render_offscreen(core, &mut encoder),
+            incoming.shader.render_offscreen(core, &mut encoder),
+        ) else {
+            return Ok(false);
+        };
+        crossfade.blend(core, &mut encoder, from_texture, to_texture);
+
+        let output = core.surface.get_current_texture()?;
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        crossfade.present(&mut encoder, &view);
+        core.queue.submit(Some(encoder.finish()));
+        output.present();
+        Ok(true)
+    }
+
+    fn active_shader(&mut self) -> Option<&mut Box<dyn ShaderManager>> {
+        self.entries
+            .get_mut(self.active)
+            .map(|entry| &mut entry.shader)
+    }
+}
+
+impl ShaderManager for ShaderSwitcher {
+    /// An empty switcher, add shaders with `with_shader`
+    fn init(_core: &Core) -> Self {
+        Self::new()
+    }
+
+    fn resize(&mut self, core: &Core) {
+        self.sync(core);
+    }
+
+    fn update(&mut self, core: &Core) {
+        self.sync(core);
+        if let Some(from) = self.fading_from {
+            self.entries[from].shader.update(core);
+        }
+        if let Some(shader) = self.active_shader() {
+            shader.update(core);
+        }
+    }
+
+    fn render(&mut self, core: &Core) -> Result<(), wgpu::SurfaceError> {
+        self.sync(core);
+        if let Some(from) = self.fading_from {
+            if self.render_transition(core, from)? {
+                return Ok(());
+            }
+            self.finish_transition(core);
+        }
+        match self.active_shader() {
+            Some(shader) => shader.render(core),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_input(&mut self, core: &Core, event: &WindowEvent) -> bool {
+        if let WindowEvent::KeyboardInput { event, .. } = event {
+            if event.state == ElementState::Pressed && !event.repeat {
+                if event.logical_key == self.next_key {
+                    self.next();
+                    return true;
+                }
+                if event.logical_key == self.prev_key {
+                    self.prev();
+                    return true;
+                }
+            }
+        }
+        self.active_shader()
+            .is_some_and(|shader| shader.handle_input(core, event))
+    }
+
+    fn handle_device_event(&mut self, core: &Core, event: &DeviceEvent) -> bool {
+        self.active_shader()
+            .is_some_and(|shader| shader.handle_device_event(core, event))
+    }
+
+    fn occlusion_changed(&mut self, core: &Core, occluded: bool) {
+        self.occluded = occluded;
+        if let Some(from) = self.fading_from {
+            self.entries[from].shader.occlusion_changed(core, occluded);
+        }
+        if let Some(shader) = self.active_shader() {
+            shader.occlusion_changed(core, occluded);
+        }
+    }
+
+    fn handle_replayed_key(&mut self, core: &Core, key: &Key, state: ElementState) -> bool {
+        self.active_shader()
+            .is_some_and(|shader| shader.handle_replayed_key(core, key, state))
+    }
+
+    fn set_replay_time(&mut self, core: &Core, time: Option<f32>) {
+        if let Some(shader) = self.active_shader() {
+            shader.set_replay_time(core, time);
+        }
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.switched_from.is_some()
+            || self.fading_from.is_some()
+            || self
+                .entries
+                .get(self.active)
+                .is_some_and(|entry| entry.shader.needs_redraw())
+    }
+}
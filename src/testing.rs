@@ -0,0 +1,209 @@
+use crate::compute::ComputeShader;
+use crate::{Core, RenderKit};
+use image::RgbaImage;
+
+/// Runs a compute shader for a fixed number of frames with a fixed time step, outside the
+/// event loop, and returns the last frame as an image for golden-image tests.
+///
+/// `Core` still needs a window (create it hidden with `Window::default_attributes()
+/// .with_visible(false)`); nothing is presented, frames go through `capture_export_frame`
+/// at `core.size`. Combine with `Core::new_with_requirements(window, true)` to render on the
+/// software adapter for reproducible CI output.
+pub struct TestHarness {
+    frames: u32,
+    dt: f32,
+    preserve_alpha: bool,
+}
+
+impl TestHarness {
+    /// `frames` is clamped to at least 1, frame `i` sees `time = i * dt`
+    pub fn new(frames: u32, dt: f32) -> Self {
+        Self {
+            frames: frames.max(1),
+            dt,
+            preserve_alpha: false,
+        }
+    }
+
+    /// Keep the shader's alpha instead of forcing it to 255
+    pub fn with_alpha(mut self) -> Self {
+        self.preserve_alpha = true;
+        self
+    }
+
+    /// Start `shader` from a clean state, run every frame and capture the last one
+    pub fn run(
+        &self,
+        core: &Core,
+        shader: &mut ComputeShader,
+        render_kit: &mut RenderKit,
+    ) -> Result<RgbaImage, wgpu::SurfaceError> {
+        self.run_with(core, shader, render_kit, |shader, encoder, core| {
+            shader.dispatch(encoder, core)
+        })
+    }
+
+    /// Like `run` but with a custom per-frame dispatch, e.g. for multi-stage shaders.
+    /// Time is already set when `dispatch` is called.
+    pub fn run_with<F>(
+        &self,
+        core: &Core,
+        shader: &mut ComputeShader,
+        render_kit: &mut RenderKit,
+        mut dispatch: F,
+    ) -> Result<RgbaImage, wgpu::SurfaceError>
+    where
+        F: FnMut(&mut ComputeShader, &mut wgpu::CommandEncoder, &Core),
+    {
+        shader.clear_all_buffers(core);
+
+        for frame in 0..self.frames - 1 {
+            let mut encoder = core
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Test Harness Encoder"),
+                });
+            shader.set_time(frame as f32 * self.dt, self.dt, &core.queue);
+            dispatch(shader, &mut encoder, core);
+            core.queue.submit(Some(encoder.finish()));
+            shader.flip_buffers();
+        }
+
+        let previous_settings = render_kit.export_manager.settings().clone();
+        {
+            let settings = render_kit.export_manager.settings_mut();
+            settings.width = core.size.width;
+            settings.height = core.size.height;
+            settings.preserve_alpha = self.preserve_alpha;
+        }
+
+        // The capture times the frame with the export fps, use the harness step instead
+        let time = (self.frames - 1) as f32 * self.dt;
+        let dt = self.dt;
+        let result = shader.capture_export_frame(
+            core,
+            time,
+            render_kit,
+            Some(
+                |shader: &mut ComputeShader, encoder: &mut wgpu::CommandEncoder, core: &Core| {
+                    shader.set_time(time, dt, &core.queue);
+                    dispatch(shader, encoder, core);
+                },
+            ),
+        );
+        *render_kit.export_manager.settings_mut() = previous_settings;
+
+        let mut data = result?;
+        if crate::CAPTURE_FORMAT == wgpu::TextureFormat::Bgra8UnormSrgb {
+            for chunk in data.chunks_exact_mut(4) {
+                chunk.swap(0, 2);
+            }
+        }
+        if !self.preserve_alpha {
+            for chunk in data.chunks_exact_mut(4) {
+                chunk[3] = 255;
+            }
+        }
+        Ok(RgbaImage::from_raw(core.size.width, core.size.height, data)
+            .expect("capture size matches core.size"))
+    }
+}
+
+/// Result of `compare_images`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageDiff {
+    /// Pixels with any channel differing by more than the tolerance
+    pub mismatched_pixels: u64,
+    pub total_pixels: u64,
+    /// Largest per-channel difference seen anywhere
+    pub max_channel_diff: u8,
+}
+
+impl ImageDiff {
+    pub fn mismatch_fraction(&self) -> f64 {
+        if self.total_pixels == 0 {
+            0.0
+        } else {
+            self.mismatched_pixels as f64 / self.total_pixels as f64
+        }
+    }
+
+    /// True when at most `max_fraction` (0..1) of the pixels are off
+    pub fn within(&self, max_fraction: f64) -> bool {
+        self.mismatch_fraction() <= max_fraction
+    }
+}
+
+/// Compare two images per channel. A per-channel `tolerance` of a few units absorbs the
+/// rounding differences between GPUs and drivers; `None` means the sizes differ.
+pub fn compare_images(
+    actual: &RgbaImage,
+    expected: &RgbaImage,
+    tolerance: u8,
+) -> Option<ImageDiff> {
+    if actual.dimensions() != expected.dimensions() {
+        return None;
+    }
+    let mut diff = ImageDiff {
+        mismatched_pixels: 0,
+        total_pixels: actual.width() as u64 * actual.height() as u64,
+        max_channel_diff: 0,
+    };
+    for (a, e) in actual.pixels().zip(expected.pixels()) {
+        let pixel_max =
+            a.0.iter()
+                .zip(e.0.iter())
+                .map(|(a, e)| a.abs_diff(*e))
+                .max()
+                .unwrap_or(0);
+        diff.max_channel_diff = diff.max_channel_diff.max(pixel_max);
+        if pixel_max > tolerance {
+            diff.mismatched_pixels += 1;
+        }
+    }
+    Some(diff)
+}
+
+/// `Core` shared by the unit tests, on a hidden 64x64 window. `None` when there's no display
+/// or GPU adapter, tests needing the GPU return early then.
+#[cfg(test)]
+pub(crate) fn test_core() -> Option<&'static Core> {
+    static CORE: std::sync::OnceLock<Option<Core>> = std::sync::OnceLock::new();
+    CORE.get_or_init(|| {
+        let window = test_window()?;
+        // `Core` panics when no adapter fits the surface
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pollster::block_on(Core::new_with_requirements(window, false))
+        }))
+        .ok()
+    })
+    .as_ref()
+}
+
+/// Tests run off the main thread, which winit only allows on X11/Wayland and Windows
+#[cfg(all(test, any(target_os = "linux", target_os = "windows")))]
+fn test_window() -> Option<winit::window::Window> {
+    let mut builder = winit::event_loop::EventLoop::builder();
+    #[cfg(target_os = "linux")]
+    winit::platform::x11::EventLoopBuilderExtX11::with_any_thread(&mut builder, true);
+    #[cfg(target_os = "windows")]
+    winit::platform::windows::EventLoopBuilderExtWindows::with_any_thread(&mut builder, true);
+
+    let event_loop = builder.build().ok()?;
+    #[allow(deprecated)]
+    let window = event_loop
+        .create_window(
+            winit::window::Window::default_attributes()
+                .with_visible(false)
+                .with_inner_size(winit::dpi::PhysicalSize::new(64, 64)),
+        )
+        .ok()?;
+    // Only one event loop can exist per process and the window needs it alive
+    std::mem::forget(event_loop);
+    Some(window)
+}
+
+#[cfg(all(test, not(any(target_os = "linux", target_os = "windows"))))]
+fn test_window() -> Option<winit::window::Window> {
+    None
+}
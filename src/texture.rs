@@ -1,8 +1,66 @@
+/// Diagnostic patterns for `TextureManager::test_pattern`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestPattern {
+    /// 8x8 black/white checker with a red top-left and green top-right cell, to spot
+    /// flips and rotations
+    Checker,
+    /// `r = u`, `g = v`, `b = 0`, with (0, 0) at the top-left texel. Bind it with
+    /// `ChannelConfig::data()` so the shader reads the exact UV instead of sRGB-decoded values
+    UvGradient,
+    /// Seven vertical bars: white, yellow, cyan, green, magenta, red, blue
+    ColorBars,
+}
+
+impl TestPattern {
+    /// Generate the pattern on the CPU
+    pub fn generate(self, width: u32, height: u32) -> image::RgbaImage {
+        let (width, height) = (width.max(1), height.max(1));
+        match self {
+            TestPattern::Checker => {
+                let cell_w = width.div_ceil(8);
+                let cell_h = height.div_ceil(8);
+                image::RgbaImage::from_fn(width, height, |x, y| {
+                    let (cx, cy) = (x / cell_w, y / cell_h);
+                    match (cx, cy) {
+                        (0, 0) => image::Rgba([255, 0, 0, 255]),
+                        (7, 0) => image::Rgba([0, 255, 0, 255]),
+                        _ if (cx + cy) % 2 == 0 => image::Rgba([255, 255, 255, 255]),
+                        _ => image::Rgba([0, 0, 0, 255]),
+                    }
+                })
+            }
+            TestPattern::UvGradient => image::RgbaImage::from_fn(width, height, |x, y| {
+                // Texel centers, like the UV a shader samples them at
+                let u = (x as f32 + 0.5) / width as f32;
+                let v = (y as f32 + 0.5) / height as f32;
+                image::Rgba([(u * 255.0).round() as u8, (v * 255.0).round() as u8, 0, 255])
+            }),
+            TestPattern::ColorBars => {
+                const BARS: [[u8; 3]; 7] = [
+                    [255, 255, 255],
+                    [255, 255, 0],
+                    [0, 255, 255],
+                    [0, 255, 0],
+                    [255, 0, 255],
+                    [255, 0, 0],
+                    [0, 0, 255],
+                ];
+                image::RgbaImage::from_fn(width, height, |x, _| {
+                    let [r, g, b] = BARS[(x as usize * BARS.len() / width as usize).min(6)];
+                    image::Rgba([r, g, b, 255])
+                })
+            }
+        }
+    }
+}
+
 pub struct TextureManager {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
     pub sampler: wgpu::Sampler,
     pub bind_group: wgpu::BindGroup,
+    /// Extra formats `texture` may be viewed as (`TextureDescriptor::view_formats`)
+    pub view_formats: Vec<wgpu::TextureFormat>,
 }
 impl TextureManager {
     pub fn new(
@@ -11,23 +69,105 @@ impl TextureManager {
         image: &image::RgbaImage,
         layout: &wgpu::BindGroupLayout,
     ) -> Self {
-        let dimensions = image.dimensions();
+        Self::upload(
+            device,
+            queue,
+            layout,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            // Lets channels sample the same data without sRGB decoding
+            &[wgpu::TextureFormat::Rgba8Unorm],
+            image.dimensions(),
+            image,
+        )
+    }
+    /// Create a texture keeping the image's bit depth and channel count where possible:
+    /// - 8-bit grayscale -> `R8Unorm`
+    /// - 16-bit grayscale -> `R16Unorm`, 16-bit RGB(A) -> `Rgba16Unorm`
+    /// - everything else -> `Rgba8UnormSrgb`, same as `new`
+    ///
+    /// 16-bit formats need `Features::TEXTURE_FORMAT_16BIT_NORM`, without it we fall back to 8-bit.
+    /// Single channel textures sample as `(r, 0, 0, 1)`. Pass `as_rgba8 = true` to always get
+    /// the 8-bit RGBA texture a display shader expects.
+    pub fn from_image(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &image::DynamicImage,
+        layout: &wgpu::BindGroupLayout,
+        as_rgba8: bool,
+    ) -> Self {
+        if as_rgba8 {
+            return Self::new(device, queue, &image.to_rgba8(), layout);
+        }
+        let supports_16bit = device
+            .features()
+            .contains(wgpu::Features::TEXTURE_FORMAT_16BIT_NORM);
+
+        let (format, data): (wgpu::TextureFormat, Vec<u8>) = match image {
+            image::DynamicImage::ImageLuma8(gray) => {
+                (wgpu::TextureFormat::R8Unorm, gray.as_raw().clone())
+            }
+            image::DynamicImage::ImageLuma16(gray) if supports_16bit => (
+                wgpu::TextureFormat::R16Unorm,
+                bytemuck::cast_slice(gray.as_raw()).to_vec(),
+            ),
+            image::DynamicImage::ImageRgb16(_) | image::DynamicImage::ImageRgba16(_)
+                if supports_16bit =>
+            {
+                let rgba16 = image.to_rgba16();
+                (
+                    wgpu::TextureFormat::Rgba16Unorm,
+                    bytemuck::cast_slice(rgba16.as_raw()).to_vec(),
+                )
+            }
+            _ => {
+                let bytes_per_channel =
+                    image.color().bytes_per_pixel() / image.color().channel_count();
+                if bytes_per_channel == 2 {
+                    log::warn!("16-bit textures not supported on this device, converting to 8-bit");
+                }
+                return Self::new(device, queue, &image.to_rgba8(), layout);
+            }
+        };
+
+        Self::upload(
+            device,
+            queue,
+            layout,
+            format,
+            &[],
+            (image.width(), image.height()),
+            &data,
+        )
+    }
 
+    /// Create a single-mip texture in `format` holding `data`, with the linear clamping
+    /// sampler and bind group shared by `new` and `from_image`
+    fn upload(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        format: wgpu::TextureFormat,
+        view_formats: &[wgpu::TextureFormat],
+        (width, height): (u32, u32),
+        data: &[u8],
+    ) -> Self {
+        let bytes_per_pixel = format
+            .block_copy_size(None)
+            .expect("uncompressed color format");
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Shader Texture"),
             size: wgpu::Extent3d {
-                width: dimensions.0,
-                height: dimensions.1,
+                width,
+                height,
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
+            view_formats,
         });
-
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -61,15 +201,15 @@ impl TextureManager {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            image,
+            data,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
+                bytes_per_row: Some(bytes_per_pixel * width),
+                rows_per_image: Some(height),
             },
             wgpu::Extent3d {
-                width: dimensions.0,
-                height: dimensions.1,
+                width,
+                height,
                 depth_or_array_layers: 1,
             },
         );
@@ -79,7 +219,156 @@ impl TextureManager {
             view,
             sampler,
             bind_group,
+            view_formats: view_formats.to_vec(),
+        }
+    }
+    /// Upload a KTX2 container as-is, keeping GPU block compression and all mip levels.
+    ///
+    /// Attempted formats and where they usually work:
+    /// - BC1/BC3/BC4/BC5/BC6H/BC7 (`Features::TEXTURE_COMPRESSION_BC`): desktop Vulkan, DX12,
+    ///   Metal on macOS
+    /// - ETC2 RGB8/RGBA8 (`Features::TEXTURE_COMPRESSION_ETC2`): Android/mobile Vulkan and GLES,
+    ///   Metal on Apple silicon
+    /// - ASTC 4x4..12x12 LDR (`Features::TEXTURE_COMPRESSION_ASTC`): mobile, Apple silicon
+    /// - uncompressed RGBA8 (sRGB or not): everywhere
+    ///
+    /// Only single layer 2D textures are supported. Basis/zstd supercompressed files need
+    /// transcoding first and are rejected, as is any format the device can't sample.
+    pub fn from_ktx2(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        layout: &wgpu::BindGroupLayout,
+    ) -> anyhow::Result<Self> {
+        let reader =
+            ktx2::Reader::new(bytes).map_err(|e| anyhow::anyhow!("Invalid KTX2 file: {e:?}"))?;
+        let header = reader.header();
+
+        if let Some(scheme) = header.supercompression_scheme {
+            anyhow::bail!(
+                "KTX2 supercompression {scheme:?} isn't supported, transcode the file first"
+            );
+        }
+        if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+            anyhow::bail!("Only 2D KTX2 textures are supported (no arrays, cubemaps or 3D)");
+        }
+        let ktx_format = header
+            .format
+            .ok_or_else(|| anyhow::anyhow!("KTX2 file has no Vulkan format (Basis data?)"))?;
+        let format = ktx2_to_wgpu_format(ktx_format)
+            .ok_or_else(|| anyhow::anyhow!("KTX2 format {ktx_format:?} isn't supported"))?;
+
+        let missing = format.required_features() - device.features();
+        if !missing.is_empty() {
+            anyhow::bail!("{format:?} needs {missing:?}, which this device doesn't support");
+        }
+
+        let (block_width, block_height) = format.block_dimensions();
+        let (width, height) = (header.pixel_width, header.pixel_height);
+        if width % block_width != 0 || height % block_height != 0 {
+            anyhow::bail!(
+                "{width}x{height} isn't a multiple of the {block_width}x{block_height} block size"
+            );
+        }
+        let block_size = format
+            .block_copy_size(None)
+            .ok_or_else(|| anyhow::anyhow!("{format:?} can't be copied"))?;
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("KTX2 Texture"),
+            size,
+            mip_level_count: header.level_count.max(1),
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        for (mip_level, level) in reader.levels().enumerate() {
+            let mip_size = size.mip_level_size(mip_level as u32, wgpu::TextureDimension::D2);
+            let physical = mip_size.physical_size(format);
+            let blocks_per_row = physical.width / block_width;
+            let block_rows = physical.height / block_height;
+            let expected_len = (blocks_per_row * block_rows * block_size) as usize;
+            if level.data.len() < expected_len {
+                anyhow::bail!(
+                    "KTX2 mip {mip_level} has {} bytes, expected {expected_len}",
+                    level.data.len()
+                );
+            }
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: mip_level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &level.data[..expected_len],
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(blocks_per_row * block_size),
+                    rows_per_image: Some(block_rows),
+                },
+                physical,
+            );
         }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("KTX2 Texture Bind Group"),
+        });
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+            bind_group,
+            view_formats: Vec::new(),
+        })
+    }
+    /// Known diagnostic texture for checking channel bindings and UV orientation, e.g.
+    /// `shader.update_channel_texture_with_config(0, &pattern, ChannelConfig::data(), ..)`
+    pub fn test_pattern(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        pattern: TestPattern,
+        size: (u32, u32),
+    ) -> Self {
+        Self::new(device, queue, &pattern.generate(size.0, size.1), layout)
+    }
+
+    /// Approximate VRAM used by the texture, see `texture_memory_bytes`
+    pub fn memory_bytes(&self) -> u64 {
+        texture_memory_bytes(&self.texture)
     }
     pub fn update(&self, queue: &wgpu::Queue, image: &image::RgbaImage) {
         let dimensions = image.dimensions();
@@ -104,3 +393,65 @@ impl TextureManager {
         );
     }
 }
+
+/// Bytes a texture occupies summed over mip levels and samples. An estimate from the
+/// descriptor: drivers add alignment padding and may compress internally.
+pub fn texture_memory_bytes(texture: &wgpu::Texture) -> u64 {
+    let format = texture.format();
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap_or(4) as u64;
+    let size = texture.size();
+    (0..texture.mip_level_count())
+        .map(|level| {
+            let mip = size.mip_level_size(level, texture.dimension());
+            mip.width.div_ceil(block_width) as u64
+                * mip.height.div_ceil(block_height) as u64
+                * mip.depth_or_array_layers as u64
+                * block_size
+        })
+        .sum::<u64>()
+        * texture.sample_count() as u64
+}
+
+fn ktx2_to_wgpu_format(format: ktx2::Format) -> Option<wgpu::TextureFormat> {
+    use wgpu::{AstcBlock, AstcChannel, TextureFormat as F};
+    let astc = |block, srgb| F::Astc {
+        block,
+        channel: if srgb {
+            AstcChannel::UnormSrgb
+        } else {
+            AstcChannel::Unorm
+        },
+    };
+    let wgpu_format = match format {
+        ktx2::Format::R8G8B8A8_UNORM => F::Rgba8Unorm,
+        ktx2::Format::R8G8B8A8_SRGB => F::Rgba8UnormSrgb,
+        ktx2::Format::BC1_RGBA_UNORM_BLOCK => F::Bc1RgbaUnorm,
+        ktx2::Format::BC1_RGBA_SRGB_BLOCK => F::Bc1RgbaUnormSrgb,
+        ktx2::Format::BC3_UNORM_BLOCK => F::Bc3RgbaUnorm,
+        ktx2::Format::BC3_SRGB_BLOCK => F::Bc3RgbaUnormSrgb,
+        ktx2::Format::BC4_UNORM_BLOCK => F::Bc4RUnorm,
+        ktx2::Format::BC5_UNORM_BLOCK => F::Bc5RgUnorm,
+        ktx2::Format::BC6H_UFLOAT_BLOCK => F::Bc6hRgbUfloat,
+        ktx2::Format::BC7_UNORM_BLOCK => F::Bc7RgbaUnorm,
+        ktx2::Format::BC7_SRGB_BLOCK => F::Bc7RgbaUnormSrgb,
+        ktx2::Format::ETC2_R8G8B8_UNORM_BLOCK => F::Etc2Rgb8Unorm,
+        ktx2::Format::ETC2_R8G8B8_SRGB_BLOCK => F::Etc2Rgb8UnormSrgb,
+        ktx2::Format::ETC2_R8G8B8A8_UNORM_BLOCK => F::Etc2Rgba8Unorm,
+        ktx2::Format::ETC2_R8G8B8A8_SRGB_BLOCK => F::Etc2Rgba8UnormSrgb,
+        ktx2::Format::ASTC_4x4_UNORM_BLOCK => astc(AstcBlock::B4x4, false),
+        ktx2::Format::ASTC_4x4_SRGB_BLOCK => astc(AstcBlock::B4x4, true),
+        ktx2::Format::ASTC_5x5_UNORM_BLOCK => astc(AstcBlock::B5x5, false),
+        ktx2::Format::ASTC_5x5_SRGB_BLOCK => astc(AstcBlock::B5x5, true),
+        ktx2::Format::ASTC_6x6_UNORM_BLOCK => astc(AstcBlock::B6x6, false),
+        ktx2::Format::ASTC_6x6_SRGB_BLOCK => astc(AstcBlock::B6x6, true),
+        ktx2::Format::ASTC_8x8_UNORM_BLOCK => astc(AstcBlock::B8x8, false),
+        ktx2::Format::ASTC_8x8_SRGB_BLOCK => astc(AstcBlock::B8x8, true),
+        ktx2::Format::ASTC_10x10_UNORM_BLOCK => astc(AstcBlock::B10x10, false),
+        ktx2::Format::ASTC_10x10_SRGB_BLOCK => astc(AstcBlock::B10x10, true),
+        ktx2::Format::ASTC_12x12_UNORM_BLOCK => astc(AstcBlock::B12x12, false),
+        ktx2::Format::ASTC_12x12_SRGB_BLOCK => astc(AstcBlock::B12x12, true),
+        _ => return None,
+    };
+    Some(wgpu_format)
+}
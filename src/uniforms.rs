@@ -53,4 +53,182 @@ impl<T: UniformProvider> UniformBinding<T> {
     pub fn update(&self, queue: &wgpu::Queue) {
         queue.write_buffer(&self.buffer, 0, self.data.as_bytes());
     }
+
+    /// Bind an existing buffer, e.g. one owned by a `SharedUniform`
+    pub fn from_buffer(
+        device: &wgpu::Device,
+        label: &str,
+        buffer: wgpu::Buffer,
+        data: T,
+        layout: &wgpu::BindGroupLayout,
+        binding: u32,
+    ) -> Self {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some(label),
+        });
+        Self {
+            buffer,
+            bind_group,
+            data,
+        }
+    }
+
+    /// Create a uniform array bound with a dynamic offset, see `UniformArrayBinding`
+    pub fn new_array(
+        device: &wgpu::Device,
+        label: &str,
+        data: Vec<T>,
+        layout: &wgpu::BindGroupLayout,
+        binding: u32,
+    ) -> UniformArrayBinding<T> {
+        UniformArrayBinding::new(device, label, data, layout, binding)
+    }
+}
+
+/// One uniform buffer bound by several shaders.
+///
+/// Pass it to `ComputeShaderBuilder::with_shared_time` or `with_shared_params` on each
+/// shader and call `update` once per frame, every shader sees the same bytes. All shaders
+/// must declare the same WGSL struct for it, the layout is sized from `T`.
+/// Cloning is cheap and refers to the same GPU buffer.
+#[derive(Clone)]
+pub struct SharedUniform<T: UniformProvider> {
+    buffer: wgpu::Buffer,
+    pub data: T,
+}
+
+impl<T: UniformProvider> SharedUniform<T> {
+    pub fn new(device: &wgpu::Device, label: &str, data: T) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: data.as_bytes(),
+            usage: wgpu::BufferUsages::UNIFORM
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+        });
+        Self { buffer, data }
+    }
+
+    /// Upload `data` for all shaders bound to this buffer
+    pub fn update(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(&self.buffer, 0, self.data.as_bytes());
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+}
+
+/// N uniform values in one buffer, selected per draw/dispatch with a dynamic offset.
+///
+/// Each element is placed at a multiple of `min_uniform_buffer_offset_alignment`
+/// (256 bytes on most devices), so a 16 byte struct still takes 256 bytes per element.
+/// The layout entry must use `has_dynamic_offset: true`, `layout_entry` builds one.
+///
+/// Binding element `i`: `pass.set_bind_group(group, &binding.bind_group, &[binding.offset(i)])`
+pub struct UniformArrayBinding<T: UniformProvider> {
+    pub buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+    pub data: Vec<T>,
+    stride: u64,
+}
+
+impl<T: UniformProvider> UniformArrayBinding<T> {
+    pub fn new(
+        device: &wgpu::Device,
+        label: &str,
+        data: Vec<T>,
+        layout: &wgpu::BindGroupLayout,
+        binding: u32,
+    ) -> Self {
+        assert!(
+            !data.is_empty(),
+            "UniformArrayBinding needs at least one element"
+        );
+        let element_size = std::mem::size_of::<T>() as u64;
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let stride = element_size.div_ceil(alignment) * alignment;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: stride * data.len() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: true,
+        });
+        {
+            let mut mapped = buffer.slice(..).get_mapped_range_mut();
+            for (i, element) in data.iter().enumerate() {
+                let start = i * stride as usize;
+                let bytes = element.as_bytes();
+                mapped[start..start + bytes.len()].copy_from_slice(bytes);
+            }
+        }
+        buffer.unmap();
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(element_size),
+                }),
+            }],
+            label: Some(label),
+        });
+        Self {
+            buffer,
+            bind_group,
+            data,
+            stride,
+        }
+    }
+
+    /// Layout entry matching this binding (uniform with dynamic offset)
+    pub fn layout_entry(
+        binding: u32,
+        visibility: wgpu::ShaderStages,
+    ) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<T>() as u64),
+            },
+            count: None,
+        }
+    }
+
+    /// Dynamic offset to pass to `set_bind_group` for element `index`
+    pub fn offset(&self, index: usize) -> u32 {
+        (index as u64 * self.stride) as u32
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn update_element(&mut self, index: usize, value: T, queue: &wgpu::Queue) {
+        queue.write_buffer(&self.buffer, self.offset(index) as u64, value.as_bytes());
+        self.data[index] = value;
+    }
+
+    /// Upload all elements
+    pub fn update(&self, queue: &wgpu::Queue) {
+        for (i, element) in self.data.iter().enumerate() {
+            queue.write_buffer(&self.buffer, self.offset(i) as u64, element.as_bytes());
+        }
+    }
 }